@@ -10,21 +10,24 @@ use alloc::vec::Vec;
 use abacus_formulas::{
     add_market_assets::add_market_assets, create_market::create_market,
     update_margin::update_margin, update_market_data::update_market_data,
-    update_quote::update_quote, update_supply::update_supply,
+    update_quote::update_quote_template, update_supply::update_supply,
 };
-use alloy_primitives::U128;
-use common::{labels::Labels, vector::Vector};
+use alloy_primitives::{U128, U32};
+use common::{labels::Labels, uint::read_u128, vector::Vector};
 use common_contracts::{
     contracts::{
         clerk::{ClerkStorage, SCRATCH_1, SCRATCH_2, SCRATCH_3, SCRATCH_4},
-        clerk_util::lazy_init_vendor_quote,
+        clerk_util::{find_labels_range, lazy_init_vendor_quote, lazy_install_program},
         keep::Keep,
         keep_calls::KeepCalls,
     },
-    interfaces::banker::IBanker,
+    interfaces::{banker::IBanker, clerk::IClerk},
 };
 use stylus_sdk::{abi::Bytes, prelude::*, stylus_core};
 
+/// Version of `update_quote_template()` installed in the registry
+const UPDATE_QUOTE_VERSION: u32 = 1;
+
 #[storage]
 #[entrypoint]
 pub struct Banker;
@@ -39,9 +42,9 @@ impl Banker {
     /// Note that the new list must be a superset of current list or call will
     /// fail. Delisting assets is not possible. To support delisting we would
     /// need to have a process in place to first reduce supply and delta for the
-    /// delisted assets to zero, and then check they are zero using JFLT, VMAX,
-    /// and SUB operations, i.e. JFLT delisted assets, VMAX to find any non-zero
-    /// value, and SUB to fail if non-zero value is found.
+    /// delisted assets to zero, and then check they are zero using JFLT and
+    /// ASSERT_ZERO operations, i.e. JFLT delisted assets, and ASSERT_ZERO to
+    /// fail if non-zero value is found.
     ///
     pub fn submit_assets(
         &mut self,
@@ -287,6 +290,10 @@ impl Banker {
     /// willing to accept, i.e. they can adjust prices, slopes and liquidity to
     /// take into account their risk factors.
     ///
+    /// Asset names should be sorted, so that only the range of market vectors
+    /// spanning them is loaded and stored. Unsorted names are accepted, but
+    /// whole market vectors are loaded and stored then.
+    ///
     pub fn submit_market_data(
         &mut self,
         vendor_id: U128,
@@ -329,6 +336,28 @@ impl Banker {
         let asset_slopes_id = SCRATCH_4;
 
         let mut clerk_storage = ClerkStorage::storage();
+
+        // Market vectors can be very long, and so we only touch the range
+        // spanning submitted assets. When submitted names are sorted, the
+        // range is given by the first and the last submitted asset, and
+        // otherwise, or when they aren't found, whole market is used.
+        let market_assets_id = account.assets.get();
+        let market_range = if num_assets == 0 {
+            Some((0, 0))
+        } else if Labels::from_vec(&asset_names).is_sorted_unique() {
+            let label_size = size_of::<u128>();
+            find_labels_range(
+                &clerk_storage,
+                market_assets_id,
+                read_u128(&asset_names),
+                read_u128(&asset_names[(num_assets - 1) * label_size..]),
+            )
+        } else {
+            None
+        };
+        let (market_start, market_len) =
+            market_range.unwrap_or_else(|| (0, clerk_storage.len_vector(market_assets_id)));
+
         clerk_storage.store_bytes(asset_names_id, asset_names);
         clerk_storage.store_bytes(asset_liquidity_id, asset_liquidity);
         clerk_storage.store_bytes(asset_prices_id, asset_prices);
@@ -340,10 +369,12 @@ impl Banker {
             asset_prices_id.to(),
             asset_slopes_id.to(),
             asset_liquidity_id.to(),
-            account.assets.get().to(),
+            market_assets_id.to(),
             account.prices.get().to(),
             account.slopes.get().to(),
             account.liquidity.get().to(),
+            market_start as u128,
+            market_len as u128,
         );
 
         let clerk = storage.clerk.get();
@@ -378,26 +409,55 @@ impl Banker {
 
         let account = storage.accounts.get(vendor_id);
 
-        // Compile VIL program, which we will send to DeVIL for execution
+        let args = vec![
+            vault.assets.get(),
+            vault.weights.get(),
+            vendor_quote_id,
+            account.assets.get(),
+            account.prices.get(),
+            account.slopes.get(),
+            account.liquidity.get(),
+        ];
+
+        // Install VIL template on first use, which we will then execute by
+        // hash binding ids of this Index to its arguments. Hash of installed
+        // template is kept, so that template is compiled and hashed again
+        // only when its version changes.
         //
         // The program:
         //  - updates index's quote, i.e. capacity, price, slope
         //
-        // Note it could be a stored procedure as program is constant for each Vault.
-        //
-        let update = update_quote(
-            vault.assets.get().to(),
-            vault.weights.get().to(),
-            vendor_quote_id.to(),
-            account.assets.get().to(),
-            account.prices.get().to(),
-            account.slopes.get().to(),
-            account.liquidity.get().to(),
-        );
+        let mut program_hash = storage.update_quote_hash.get();
+        let is_current = {
+            let program = storage.programs.get(program_hash);
+            program.is_installed() && program.version.get() == U32::from(UPDATE_QUOTE_VERSION)
+        };
+        if !is_current {
+            let template = update_quote_template()?;
+            let code_id;
+            (program_hash, code_id) = lazy_install_program(
+                &mut storage,
+                &mut clerk_storage,
+                template,
+                UPDATE_QUOTE_VERSION,
+                args.len() as u8,
+            );
+            storage.update_quote_hash.set(program_hash);
+            if let Some(code_id) = code_id {
+                stylus_core::log(
+                    self.vm(),
+                    IClerk::ProgramInstalled {
+                        program_hash,
+                        code_id: code_id.to(),
+                        version: UPDATE_QUOTE_VERSION,
+                    },
+                );
+            }
+        }
 
         let clerk = storage.clerk.get();
         let num_registry = 16;
-        self.update_records(clerk, update?, num_registry)?;
+        self.execute_program(clerk, program_hash, args, num_registry)?;
 
         stylus_core::log(
             self.vm(),
@@ -7,12 +7,29 @@ extern crate alloc;
 
 use alloc::vec::Vec;
 
-use alloy_primitives::U128;
-use common::{abacus::program_error::ErrorCode, labels::Labels, vector::Vector};
-use common_contracts::contracts::clerk::ClerkStorage;
-use stylus_sdk::{abi::Bytes, prelude::*};
+use alloy_primitives::{B256, U128, U256};
+use alloy_sol_types::SolError;
+use common::{
+    abacus::program_error::{ErrorCode, ProgramError},
+    labels::Labels,
+    vector::Vector,
+};
+use common_contracts::{
+    contracts::{clerk::ClerkStorage, clerk_util::lazy_install_program, keep::Keep},
+    interfaces::clerk::IClerk,
+};
+use stylus_sdk::{abi::Bytes, prelude::*, stylus_core};
 
-use abacus_runtime::runtime::{VectorIO, VectorVM};
+use abacus_runtime::{
+    runtime::{VectorIO, VectorVM},
+    verifier::verify,
+};
+
+/// Revert data of failed program, which can be decoded off-chain as
+/// `IClerk::ProgramFailed`
+fn program_failed(err: ProgramError) -> Vec<u8> {
+    IClerk::ProgramFailed::from(&err).abi_encode()
+}
 
 struct ClerkStorageRef<'a>(&'a mut ClerkStorage);
 
@@ -52,6 +69,35 @@ impl<'a> VectorIO for ClerkStorageRef<'a> {
         self.0.store_bytes(key, input.to_vec());
         Ok(())
     }
+
+    fn load_labels_range(&self, id: u128, start: usize, len: usize) -> Result<Labels, ErrorCode> {
+        let key = U128::from(id);
+        let labels = start
+            .checked_mul(size_of::<u128>())
+            .zip(len.checked_mul(size_of::<u128>()))
+            .and_then(|(start, len)| self.0.fetch_bytes_range(key, start, len))
+            .ok_or(ErrorCode::OutOfRange)?;
+        Ok(Labels::from_vec(labels))
+    }
+
+    fn load_vector_range(&self, id: u128, start: usize, len: usize) -> Result<Vector, ErrorCode> {
+        let key = U128::from(id);
+        self.0
+            .fetch_vector_range(key, start, len)
+            .ok_or(ErrorCode::OutOfRange)
+    }
+
+    fn store_vector_range(
+        &mut self,
+        id: u128,
+        start: usize,
+        input: Vector,
+    ) -> Result<(), ErrorCode> {
+        let key = U128::from(id);
+        self.0
+            .store_vector_range(key, start, input)
+            .ok_or(ErrorCode::OutOfRange)
+    }
 }
 
 #[storage]
@@ -61,14 +107,145 @@ pub struct Clerk;
 #[public]
 impl Clerk {
     pub fn update_records(&mut self, code: Bytes, num_registry: u128) -> Result<(), Vec<u8>> {
+        #[cfg(feature = "verify")]
+        {
+            let info = verify(&code).map_err(program_failed)?;
+            if info.num_registers() > num_registry as usize {
+                Err(b"Program uses more registers than supplied")?;
+            }
+        }
+
         let mut storage = ClerkStorage::storage();
 
+        // Program writes storage directly, as failed program reverts the
+        // transaction, which discards its writes, and buffering them would
+        // only copy every vector loaded
         let mut ref_storage = ClerkStorageRef(&mut storage);
         let mut program = VectorVM::new(&mut ref_storage);
         program
             .execute(code.to_vec(), num_registry as usize)
-            .map_err(|err| format!("Program error: {:?}", err))?;
+            .map_err(program_failed)?;
 
         Ok(())
     }
+
+    /// Execute program installed in the registry
+    ///
+    /// Arguments are bound to the slots declared by `ARGS` of the program,
+    /// so that the same template can be stored once and executed with
+    /// different ids.
+    pub fn execute_program(
+        &mut self,
+        program_hash: B256,
+        args: Vec<U128>,
+        num_registry: u128,
+    ) -> Result<(), Vec<u8>> {
+        let storage = Keep::storage();
+        storage.check_version()?;
+
+        let program = storage.programs.get(program_hash);
+        program.only_active()?;
+        if args.len() != program.num_args.get().to::<usize>() {
+            Err(b"Invalid number of program arguments")?;
+        }
+
+        let mut clerk_storage = ClerkStorage::storage();
+        let code = clerk_storage
+            .fetch_bytes(program.code_id.get())
+            .ok_or(b"Program code not found")?;
+        let args = args.into_iter().map(|arg| arg.to()).collect();
+
+        let mut ref_storage = ClerkStorageRef(&mut clerk_storage);
+        let mut program = VectorVM::new(&mut ref_storage);
+        program
+            .execute_with_args(code, num_registry as usize, args)
+            .map_err(program_failed)?;
+
+        Ok(())
+    }
+
+    /// Install program into the registry
+    ///
+    /// Program is identified by Keccak256 hash of its code, and so the same
+    /// code cannot be installed twice, e.g. with different version.
+    ///
+    /// Program is always verified, regardless of `verify` feature, so that
+    /// registry never holds program, which cannot be executed by hash.
+    pub fn install_program(
+        &mut self,
+        code: Bytes,
+        version: u32,
+        num_args: u8,
+    ) -> Result<B256, Vec<u8>> {
+        let info = verify(&code).map_err(program_failed)?;
+        if info.num_args != num_args as usize {
+            Err(b"Program declares different number of arguments")?;
+        }
+
+        let mut storage = Keep::storage();
+        storage.check_version()?;
+
+        let mut clerk_storage = ClerkStorage::storage();
+        let (program_hash, code_id) =
+            lazy_install_program(&mut storage, &mut clerk_storage, &code, version, num_args);
+        let code_id = code_id.ok_or(b"Program already installed")?;
+
+        stylus_core::log(
+            self.vm(),
+            IClerk::ProgramInstalled {
+                program_hash,
+                code_id: code_id.to(),
+                version,
+            },
+        );
+
+        Ok(program_hash)
+    }
+
+    /// Retire program, so that it can no longer be executed by hash
+    ///
+    /// Code remains stored, because other programs may call it by its id.
+    pub fn retire_program(&mut self, program_hash: B256) -> Result<(), Vec<u8>> {
+        let mut storage = Keep::storage();
+        storage.check_version()?;
+
+        let mut program = storage.programs.setter(program_hash);
+        program.only_active()?;
+        program.retired.set(true);
+
+        stylus_core::log(self.vm(), IClerk::ProgramRetired { program_hash });
+
+        Ok(())
+    }
+
+    /// Code id, version, number of arguments and whether program is retired
+    pub fn get_program(&self, program_hash: B256) -> Result<(U128, u32, u8, bool), Vec<u8>> {
+        let storage = Keep::storage();
+        let program = storage.programs.get(program_hash);
+        program.only_installed()?;
+        Ok((
+            program.code_id.get(),
+            program.version.get().to(),
+            program.num_args.get().to(),
+            program.retired.get(),
+        ))
+    }
+
+    pub fn get_program_count(&self) -> U256 {
+        let storage = Keep::storage();
+        U256::from(storage.program_hashes.len())
+    }
+
+    /// Hashes of installed programs, including retired ones
+    pub fn get_programs(&self, start_from: U256, max_len: U256) -> Vec<B256> {
+        let storage = Keep::storage();
+        let start_from: usize = start_from.saturating_to();
+        let end = storage
+            .program_hashes
+            .len()
+            .min(start_from.saturating_add(max_len.saturating_to()));
+        (start_from..end)
+            .filter_map(|index| storage.program_hashes.get(index))
+            .collect()
+    }
 }
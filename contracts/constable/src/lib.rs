@@ -21,9 +21,9 @@ use common_contracts::{
         keep::{Keep, KEEP_VERSION_NUMBER},
     },
     interfaces::{
-        alchemist::IAlchemist, banker::IBanker, castle::ICastle, constable::IConstable,
-        factor::IFactor, guildmaster::IGuildmaster, scribe::IScribe, steward::ISteward,
-        worksman::IWorksman,
+        alchemist::IAlchemist, banker::IBanker, castle::ICastle, clerk::IClerk,
+        constable::IConstable, factor::IFactor, guildmaster::IGuildmaster, scribe::IScribe,
+        steward::ISteward, worksman::IWorksman,
     },
 };
 use stylus_sdk::{prelude::*, stylus_core};
@@ -427,6 +427,24 @@ impl Constable {
             clerk_storage.constructor()?;
         }
 
+        self._create_protected_functions(
+            clerk,
+            vec![
+                IClerk::installProgramCall::SELECTOR.into(),
+                IClerk::retireProgramCall::SELECTOR.into(),
+            ],
+            CASTLE_ADMIN_ROLE.into(),
+        )?;
+
+        self._create_public_functions(
+            clerk,
+            vec![
+                IClerk::getProgramCall::SELECTOR.into(),
+                IClerk::getProgramCountCall::SELECTOR.into(),
+                IClerk::getProgramsCall::SELECTOR.into(),
+            ],
+        )?;
+
         Ok(())
     }
 
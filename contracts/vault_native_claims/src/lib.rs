@@ -112,12 +112,18 @@ impl VaultNativeClaims {
         let collateral_spent = keeper_order.bid_delivered.get();
         let itp_received = keeper_order.bid_received.get();
 
-        let itp_claimed = Amount::from_u128(collateral_amount)
-            .checked_mul(Amount::from_u128(itp_received))
-            .ok_or_else(|| b"MathOverflow")?
-            .checked_div(Amount::from_u128(collateral_spent))
-            .ok_or_else(|| b"MathOverflow")?
-            .to_u128();
+        // Claims are pro-rata rounded down, and the last one takes all that is
+        // left, so that split claims pay out exactly what was received
+        let itp_claimed = if collateral_amount == collateral_spent {
+            itp_received
+        } else {
+            Amount::from_u128(collateral_amount)
+                .checked_mul(Amount::from_u128(itp_received))
+                .ok_or_else(|| b"MathOverflow")?
+                .checked_div(Amount::from_u128(collateral_spent))
+                .ok_or_else(|| b"MathOverflow")?
+                .to_u128()
+        };
 
         // Transfer ITP from keeper to Trader
         self.external_call(
@@ -213,12 +219,18 @@ impl VaultNativeClaims {
         let itp_burned = keeper_order.ask_delivered.get();
         let amount_received = keeper_order.ask_received.get();
 
-        let amount_claimed = Amount::from_u128(itp_amount)
-            .checked_mul(Amount::from_u128(amount_received))
-            .ok_or_else(|| b"MathOverflow")?
-            .checked_div(Amount::from_u128(itp_burned))
-            .ok_or_else(|| b"MathOverflow")?
-            .to_u128();
+        // Claims are pro-rata rounded down, and the last one takes all that is
+        // left, so that split claims pay out exactly what was received
+        let amount_claimed = if itp_amount == itp_burned {
+            amount_received
+        } else {
+            Amount::from_u128(itp_amount)
+                .checked_mul(Amount::from_u128(amount_received))
+                .ok_or_else(|| b"MathOverflow")?
+                .checked_div(Amount::from_u128(itp_burned))
+                .ok_or_else(|| b"MathOverflow")?
+                .to_u128()
+        };
 
         // Tranfer gains from keeper to Trader
         self.external_call(
@@ -1,4 +1,4 @@
-use abacus_macros::abacus;
+use abacus_macros::abacus_compact;
 
 /// Add assets and extend all market vectors
 /// 
@@ -22,7 +22,8 @@ pub fn add_market_assets(
     delta_short_id: u128,
     margin_id: u128,
 ) -> Result<Vec<u8>, Vec<u8>> {
-    abacus! {
+    abacus_compact! {
+        #![stack_comments]
         // ====================================
         // * * * (TRY) COMPUTE NEW VALUES * * *
         // ====================================
@@ -1,4 +1,4 @@
-use abacus_macros::abacus;
+use abacus_macros::abacus_compact;
 
 /// Create market adding initial assets and setting market vectors
 pub fn create_market(
@@ -15,7 +15,8 @@ pub fn create_market(
     delta_short_id: u128,
     margin_id: u128,
 ) -> Result<Vec<u8>, Vec<u8>> {
-    abacus! {
+    abacus_compact! {
+        #![stack_comments]
         // ====================================
         // * * * (TRY) COMPUTE NEW VALUES * * *
         // ====================================
@@ -1,4 +1,4 @@
-use abacus_macros::abacus;
+use abacus_macros::abacus_compact;
 
 /// Execute Buy Index Order
 ///
@@ -24,7 +24,8 @@ pub fn execute_buy_order(
     margin_id: u128,
     solve_quadratic_id: u128,
 ) -> Result<Vec<u8>, Vec<u8>> {
-    abacus! {
+    abacus_compact! {
+        #![stack_comments]
         // ====================================
         // * * * (TRY) COMPUTE NEW VALUES * * *
         // ====================================
@@ -1,4 +1,4 @@
-use abacus_macros::abacus;
+use abacus_macros::abacus_compact;
 
 /// Execute rebalance order
 ///
@@ -19,7 +19,8 @@ pub fn execute_rebalance(
     margin_id: u128,
     asset_liquidity_id: u128,
 ) -> Result<Vec<u8>, Vec<u8>> {
-    abacus! {
+    abacus_compact! {
+        #![stack_comments]
         // ====================================
         // * * * (TRY) COMPUTE NEW VALUES * * *
         // ====================================
@@ -39,7 +40,7 @@ pub fn execute_rebalance(
         LDV         asset_liquidity_id          // Stack: [AN, MAN, fM, AL = AssetLiquidity]
         JFLT        2   3                       // Stack: [AN, MAN, fM, fAL]
         LDV         delta_short_id              // Stack: [AN, MAN, fM, fAL, DS = DeltaShort]
-        JFLT        3   4                       // Stack: [AN, MAN, fM, fDS]
+        JFLT        3   4                       // Stack: [AN, MAN, fM, fAL, fDS]
         LDV         delta_long_id               // Stack: [AN, MAN, fM, fAL, fDS, DL = DeltaLong]
         JFLT        4   5                       // Stack: [AN, MAN, fM, fAL, fDS, fDL]
         LDD         3                           // Stack: [AN, MAN, fM, fAL, fDS, fDL, fM]
@@ -1,4 +1,4 @@
-use abacus_macros::abacus;
+use abacus_macros::abacus_compact;
 
 /// Execute Sell Index Order
 /// 
@@ -24,7 +24,8 @@ pub fn execute_sell_order(
     margin_id: u128,
     solve_quadratic_id: u128,
 ) -> Result<Vec<u8>, Vec<u8>> {
-    abacus! {
+    abacus_compact! {
+        #![stack_comments]
         // Load Weights
         LDV         asset_weights_id            // Stack: [AssetWeights]
         STR         _Weights                    // Stack: []
@@ -103,15 +104,15 @@ pub fn execute_sell_order(
         SSB         1                           // Stack: [CL, M, C, SC, P - SC]
         MUL         2                           // Stack: [CL, M, C, SC, W = C * (P + SC)]
         MIN         3                           // Stack: [CL, M, C, SC, WC = MIN(W, M)]
-        STR         _WithdrawAmount             // Stack: [CL, M, C]
-        POPN        3                           // Stack: []
+        STR         _WithdrawAmount             // Stack: [CL, M, C, SC]
+        POPN        3                           // Stack: [CL]
 
         // Solve Quadratic: -S * Q^2 + P * Q - C = 0
-        LDR         _Slope                      // Stack: [MaxOrderSize, Slope]
-        LDR         _Price                      // Stack: [MaxOrderSize, Slope, Price]
-        LDR         _WithdrawAmount             // Stack: [MaxOrderSize, Slope, Price, WithdrawAmount]
-        B           solve_quadratic_id  3  1  4 // Stack: [MaxOrderSize, CappedIndexQuantity]
-        STR         _CappedIndexQuantity        // Stack: [MaxOrderSize]
+        LDR         _Slope                      // Stack: [CL, Slope]
+        LDR         _Price                      // Stack: [CL, Slope, Price]
+        LDR         _WithdrawAmount             // Stack: [CL, Slope, Price, WithdrawAmount]
+        B           solve_quadratic_id  3  1  4 // Stack: [CL, CappedIndexQuantity]
+        STR         _CappedIndexQuantity        // Stack: [CL]
         POPN        1                           // Stack: []
 
         // Generate Individual Asset Orders (compute asset quantities)
@@ -1,4 +1,4 @@
-use abacus_macros::abacus;
+use abacus_macros::abacus_compact;
 
 /// Execute Transfer
 ///
@@ -8,7 +8,8 @@ pub fn execute_transfer(
     receiver_bid_id: u128,
     amount: u128,
 ) -> Result<Vec<u8>, Vec<u8>> {
-    abacus! {
+    abacus_compact! {
+        #![stack_comments]
         // ====================================
         // * * * (TRY) COMPUTE NEW VALUES * * *
         // ====================================
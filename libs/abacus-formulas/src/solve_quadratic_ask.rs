@@ -1,9 +1,12 @@
-use abacus_macros::abacus;
+use abacus_macros::abacus_compact;
 
 /// Solve Index Quantity Equation: (S, P, C) -> Q = C / (P - S * Q)
 /// 
 pub fn solve_quadratic_ask() -> Result<Vec<u8>, Vec<u8>> {
-    abacus! {
+    abacus_compact! {
+        #![inputs(3)]
+        #![stack_comments]
+
         // 1. Initial Load and Setup (assuming stack starts with [C_vec, P_vec, S_vec])
         STR     _C           // C_vec -> R3, POP C_vec
         STR     _P           // P_vec -> R2, POP P_vec
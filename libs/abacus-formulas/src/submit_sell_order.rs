@@ -1,4 +1,4 @@
-use abacus_macros::abacus;
+use abacus_macros::abacus_compact;
 
 /// Submit Sell Index Order
 ///
@@ -9,7 +9,8 @@ pub fn submit_sell_order(
     collateral_added: u128,
     collateral_removed: u128,
 ) -> Result<Vec<u8>, Vec<u8>> {
-    abacus! {
+    abacus_compact! {
+        #![stack_comments]
         // Load Index Order
         LDV         order_id                    // Stack: [Order = (Collateral, Burned, Withdrawn)]
         LDV         vendor_order_id             // Stack: [Order, Vendor]
@@ -1,7 +1,11 @@
-use abacus_macros::abacus;
+use abacus_macros::abacus_compact;
 
 /// Update Market Data
 ///
+/// Only the range of market vectors from `market_start` spanning
+/// `market_len` assets is loaded and stored, and so it must cover all
+/// updated assets, e.g. as found by `find_labels_range()`.
+///
 pub fn update_market_data(
     asset_names_id: u128,
     asset_prices_id: u128,
@@ -11,33 +15,36 @@ pub fn update_market_data(
     market_asset_prices_id: u128,
     market_asset_slopes_id: u128,
     market_asset_liquidity_id: u128,
+    market_start: u128,
+    market_len: u128,
 ) -> Result<Vec<u8>, Vec<u8>> {
-    abacus! {
+    abacus_compact! {
+        #![stack_comments]
         // ====================================
         // * * * (TRY) COMPUTE NEW VALUES * * *
         // ====================================
 
         // Update Prices & Slopes & Liquidity
         LDL         asset_names_id              // Stack [AN = AssetNames]
-        LDL         market_asset_names_id       // Stack [AN = AssetNames, MAN = MarketAssetNames]
+        LDLR        market_asset_names_id  market_start  market_len       // Stack [AN = AssetNames, MAN = MarketAssetNames]
 
         // Compute MarketAssetPrices j= AssetPrices
         LDV         asset_prices_id             // Stack [AN, MAN, AP]
-        LDV         market_asset_prices_id      // Stack [AN, MAN, AP, MAP]
+        LDVR        market_asset_prices_id  market_start  market_len      // Stack [AN, MAN, AP, MAP]
         JUPD        1   2   3                   // Stack [AN, MAN, AP, MAP_updated = (MAP j= AP)]
         STR         _Prices                     // Stack [AN, MAN, AP]
         POPN        1                           // Stack [AN, MAN]
 
         // Compute MarketAssetSlopes j= AssetSlopes
         LDV         asset_slopes_id             // Stack [AN, MAN, AS]
-        LDV         market_asset_slopes_id      // Stack [AN, MAN, AS, MAS]
+        LDVR        market_asset_slopes_id  market_start  market_len      // Stack [AN, MAN, AS, MAS]
         JUPD        1   2   3                   // Stack [AN, MAN, AS, MAS_updated = (MAS j= AS)]
         STR         _Slopes                     // Stack [AN, MAN, AS]
         POPN        1                           // Stack [AN, MAN]
 
         // Compute MarketAssetLiquidity j= AssetLiquidity
         LDV         asset_liquidity_id          // Stack [AN, MAN, AL]
-        LDV         market_asset_liquidity_id   // Stack [AN, MAN, AL, MAL]
+        LDVR        market_asset_liquidity_id  market_start  market_len   // Stack [AN, MAN, AL, MAL]
         JUPD        1   2   3                   // Stack [AN, MAN, AL, MAL_updated = (MAL J= AL)]
         STR         _Liquidity                  // Stack [AN, MAN, AL]
         POPN        1                           // Stack [AN, MAN]
@@ -49,8 +56,8 @@ pub fn update_market_data(
         LDM         _Prices
         LDM         _Slopes
         LDM         _Liquidity
-        STV         market_asset_liquidity_id
-        STV         market_asset_slopes_id
-        STV         market_asset_prices_id
+        STVR        market_asset_liquidity_id  market_start
+        STVR        market_asset_slopes_id  market_start
+        STVR        market_asset_prices_id  market_start
     }
 }
@@ -1,4 +1,4 @@
-use abacus_macros::abacus;
+use abacus_macros::abacus_compact;
 
 /// Update Index Quote (Capacity, Price, Slope)
 ///
@@ -11,7 +11,8 @@ pub fn update_quote(
     asset_slopes_id: u128,
     asset_liquidity_id: u128,
 ) -> Result<Vec<u8>, Vec<u8>> {
-    abacus! {
+    abacus_compact! {
+        #![stack_comments]
         // ====================================
         // * * * (TRY) COMPUTE NEW VALUES * * *
         // ====================================
@@ -27,17 +28,86 @@ pub fn update_quote(
         LDV         asset_prices_id                 //  [AssetNames, MarketAssetNames, MarketAssetPrices]
         JFLT        1   2                           //  [AssetNames, MarketAssetNames, Flt_MarketAssetPrices]
         LDR         _AssetWeights                   //  [AssetNames, MarketAssetNames, Flt_MarketAssetPrices, AssetWeights]
-        SWAP        1                               //  [AssetNames, MarketAssetNames, AssetWeights, Flt_MarketAssetPrices]
-        MUL         1                               //  [AssetNames, MarketAssetNames, AssetWeights, P_vec = (AssetWeights * Flt_MarketAssetPrices)]
-        VSUM                                        //  [AssetNames, MarketAssetNames, AssetWeights, P = SUM(P_vec[..])]
-        STR         _Price                          //  [AssetNames, MarketAssetNames, AssetWeights]
+        VDOT        1                               //  [AssetNames, MarketAssetNames, Flt_MarketAssetPrices, P = SUM(AssetWeights * Flt_MarketAssetPrices)]
+        STR         _Price                          //  [AssetNames, MarketAssetNames, Flt_MarketAssetPrices]
+        POPN        1                               //  [AssetNames, MarketAssetNames]
+
+        // Compute S = MarketAssetSlopes * AssetWeights^2
+        LDR         _AssetWeights                   //  [AssetNames, MarketAssetNames, AssetWeights]
+        MUL         0                               //  [AssetNames, MarketAssetNames, AssetWeights^2]
+        LDV         asset_slopes_id                 //  [AssetNames, MarketAssetNames, AssetWeights^2, MarketAssetSlopes]
+        JFLT        2   3                           //  [AssetNames, MarketAssetNames, AssetWeights^2, Flt_MarketAssetSlopes]
+        VDOT        1                               //  [AssetNames, MarketAssetNames, AssetWeights^2, S = SUM(Flt_MarketAssetSlopes * AssetWeights^2)]
+        STR         _Slope                          //  [AssetNames, MarketAssetNames, AssetWeights^2]
+        POPN        1                               //  [AssetNames, MarketAssetNames]
+
+        // Compute C = MIN(AssetLiquidity / AssetWeights)
+        //
+        // NOTE: We just put market liquidity based capacity, and then when we execute orders we cap with available margin.
+        //
+        LDR         _AssetWeights                   //  [AN = AssetNames, MAN = MarketAssetNames, W = AssetWeights]
+        LDV         asset_liquidity_id              //  [AN, MAN, W, MAL = MarketAssetLiquidity]
+        JFLT        2   3                           //  [AN, MAN, W, Flt_MAL]
+        DIV         1                               //  [AN, MAN, W, C_vec = (Flt_MAL / W)]
+        VMIN                                        //  [AN, MAN, W, C = MIN(C_vec)]
+        STR         _Capacity                       //  [AN, MAN, W]
+        POPN        3                               //  []
+
+        // =============================
+        // * * * COMMIT NEW VALUES * * *
+        // =============================
+
+        LDM         _Capacity                       //  [Capacity]
+        LDM         _Price                          //  [Capacity, Price]
+        LDM         _Slope                          //  [Capacity, Price, Slope]
+        PKV         3                               //  [(Capacity, Price, Slope)]
+        STV         quote_id
+    }
+}
+
+/// Update Index Quote (Capacity, Price, Slope) as a template
+///
+/// Same program as `update_quote()`, except ids are bound when program is
+/// executed, and so it can be installed once and used for every Index.
+///
+pub fn update_quote_template() -> Result<Vec<u8>, Vec<u8>> {
+    abacus_compact! {
+        #![stack_comments]
+        ARGS [
+            index_asset_names_id,
+            weights_id,
+            quote_id,
+            market_asset_names_id,
+            asset_prices_id,
+            asset_slopes_id,
+            asset_liquidity_id,
+        ]
+
+        // ====================================
+        // * * * (TRY) COMPUTE NEW VALUES * * *
+        // ====================================
+
+        LDV         weights_id                      //  [AssetWeights]
+        STR         _AssetWeights
+
+        // Load AssetNames & MarketAssetNames
+        LDL         index_asset_names_id            //  [AssetNames]
+        LDL         market_asset_names_id           //  [AssetNames, MarketAssetNames]
+
+        // Compute P = MarketAssetPrices * AssetWeights
+        LDV         asset_prices_id                 //  [AssetNames, MarketAssetNames, MarketAssetPrices]
+        JFLT        1   2                           //  [AssetNames, MarketAssetNames, Flt_MarketAssetPrices]
+        LDR         _AssetWeights                   //  [AssetNames, MarketAssetNames, Flt_MarketAssetPrices, AssetWeights]
+        VDOT        1                               //  [AssetNames, MarketAssetNames, Flt_MarketAssetPrices, P = SUM(AssetWeights * Flt_MarketAssetPrices)]
+        STR         _Price                          //  [AssetNames, MarketAssetNames, Flt_MarketAssetPrices]
+        POPN        1                               //  [AssetNames, MarketAssetNames]
 
         // Compute S = MarketAssetSlopes * AssetWeights^2
+        LDR         _AssetWeights                   //  [AssetNames, MarketAssetNames, AssetWeights]
         MUL         0                               //  [AssetNames, MarketAssetNames, AssetWeights^2]
         LDV         asset_slopes_id                 //  [AssetNames, MarketAssetNames, AssetWeights^2, MarketAssetSlopes]
         JFLT        2   3                           //  [AssetNames, MarketAssetNames, AssetWeights^2, Flt_MarketAssetSlopes]
-        MUL         1                               //  [AssetNames, MarketAssetNames, AssetWeights^2, S_vec = (Flt_MarketAssetSlopes * AssetWeights^2)]
-        VSUM                                        //  [AssetNames, MarketAssetNames, AssetWeights^2, S = SUM(S_vec[..])]
+        VDOT        1                               //  [AssetNames, MarketAssetNames, AssetWeights^2, S = SUM(Flt_MarketAssetSlopes * AssetWeights^2)]
         STR         _Slope                          //  [AssetNames, MarketAssetNames, AssetWeights^2]
         POPN        1                               //  [AssetNames, MarketAssetNames]
 
@@ -1,4 +1,4 @@
-use abacus_macros::abacus;
+use abacus_macros::abacus_compact;
 
 /// Compute and update rebalance vectors
 /// 
@@ -13,7 +13,8 @@ pub fn update_rebalance(
     rebalance_weights_long_id: u128,
     rebalance_weights_short_id: u128,
 ) -> Result<Vec<u8>, Vec<u8>> {
-    abacus! {
+    abacus_compact! {
+        #![stack_comments]
         // Compute total supply
         LDV     total_bid_id                    // [T_bid]
         UNPK                                    // [C_bid, S_bid, M_bid]
@@ -0,0 +1,118 @@
+//! Benchmarks of label joins on market-sized vectors
+//!
+//! Run with `cargo bench -p abacus-runtime --bench joins`. Market labels are
+//! joined with every tenth label in three layouts: both sorted (merge-join),
+//! only market labels sorted (binary search), and neither sorted (hashing).
+
+use std::collections::HashMap;
+
+use abacus_macros::abacus;
+use abacus_runtime::runtime::{VectorIO, VectorVM};
+use common::{abacus::program_error::ErrorCode, amount::Amount, labels::Labels, vector::Vector};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+const SIZES: [usize; 3] = [10_000, 100_000, 2_000_000];
+
+const MARKET_NAMES_ID: u128 = 1;
+const ASSET_NAMES_ID: u128 = 2;
+const ASSET_VALUES_ID: u128 = 3;
+
+#[derive(Default)]
+struct BenchVectorIO {
+    labels: HashMap<u128, Vec<u128>>,
+    vectors: HashMap<u128, Vec<Amount>>,
+}
+
+impl VectorIO for BenchVectorIO {
+    fn load_labels(&self, id: u128) -> Result<Labels, ErrorCode> {
+        let data = self.labels.get(&id).ok_or(ErrorCode::NotFound)?.clone();
+        Ok(Labels::from(data))
+    }
+
+    fn load_vector(&self, id: u128) -> Result<Vector, ErrorCode> {
+        let data = self.vectors.get(&id).ok_or(ErrorCode::NotFound)?.clone();
+        Ok(Vector { data })
+    }
+
+    fn load_code(&self, _id: u128) -> Result<Vec<u8>, ErrorCode> {
+        Err(ErrorCode::NotFound)
+    }
+
+    fn store_labels(&mut self, id: u128, input: Labels) -> Result<(), ErrorCode> {
+        self.labels.insert(id, input.into());
+        Ok(())
+    }
+
+    fn store_vector(&mut self, id: u128, input: Vector) -> Result<(), ErrorCode> {
+        self.vectors.insert(id, input.data);
+        Ok(())
+    }
+}
+
+/// Market labels and labels of every tenth asset in given layout
+fn layout(size: usize, layout: &str) -> (Vec<u128>, Vec<u128>) {
+    let mut market: Vec<u128> = (0..size as u128).map(|x| 1000 + x * 3).collect();
+    let mut assets: Vec<u128> = market.iter().step_by(10).copied().collect();
+    match layout {
+        "sorted" => {}
+        "unsorted_assets" => assets.reverse(),
+        "unsorted" => {
+            assets.reverse();
+            // Deterministic permutation, as step is co-prime with size
+            let step = 7919;
+            market = (0..size).map(|i| market[(i * step) % size]).collect();
+        }
+        _ => unreachable!(),
+    }
+    (market, assets)
+}
+
+fn bench_positions(c: &mut Criterion) {
+    let mut group = c.benchmark_group("positions");
+    group.sample_size(10);
+    for size in SIZES {
+        for name in ["sorted", "unsorted_assets", "unsorted"] {
+            let (market, assets) = layout(size, name);
+            // Order is tracked when labels are loaded by the VM
+            let mut market = Labels::from(market);
+            let mut assets = Labels::from(assets);
+            market.track_sorted_unique();
+            assets.track_sorted_unique();
+            group.bench_with_input(BenchmarkId::new(name, size), &size, |b, _| {
+                b.iter(|| market.positions_of(&assets).unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_jupd(c: &mut Criterion) {
+    let code = abacus! {
+        LDL     MARKET_NAMES_ID
+        LDL     ASSET_NAMES_ID
+        LDV     ASSET_VALUES_ID     // [M, A, VA]
+        ZEROS   2                   // [M, A, VA, Z]
+        JUPD    1   3   2           // [M, A, VA, Z <- VA]
+    }
+    .unwrap();
+
+    let mut group = c.benchmark_group("jupd");
+    group.sample_size(10);
+    for size in SIZES {
+        for name in ["sorted", "unsorted_assets", "unsorted"] {
+            let (market, assets) = layout(size, name);
+            let values = vec![Amount::ONE; assets.len()];
+            let mut vio = BenchVectorIO::default();
+            vio.labels.insert(MARKET_NAMES_ID, market);
+            vio.labels.insert(ASSET_NAMES_ID, assets);
+            vio.vectors.insert(ASSET_VALUES_ID, values);
+            group.bench_with_input(BenchmarkId::new(name, size), &size, |b, _| {
+                b.iter(|| VectorVM::new(&mut vio).execute(code.clone(), 0).unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_positions, bench_jupd);
+criterion_main!(benches);
@@ -0,0 +1,76 @@
+//! `Amount` and `SignedAmount` checked arithmetic never panics, and its
+//! results agree with exact big integer arithmetic
+//!
+//! Run with `cargo +nightly fuzz run amount` from `libs/abacus-runtime`.
+#![no_main]
+
+use common::{amount::Amount, signed_amount::SignedAmount};
+use libfuzzer_sys::fuzz_target;
+use num_bigint::{BigInt, BigUint};
+
+fn expected(value: BigUint) -> Option<u128> {
+    u128::try_from(value).ok()
+}
+
+fn expected_signed(value: BigInt) -> Option<i128> {
+    i128::try_from(value).ok()
+}
+
+fuzz_target!(|input: (u128, u128, i128, i128, u32)| {
+    let (a, b, c, d, n) = input;
+    let (x, y) = (Amount(a), Amount(b));
+    let scale = BigUint::from(Amount::SCALE);
+    let big = BigUint::from;
+
+    assert_eq!(x.checked_add(y).map(|r| r.0), a.checked_add(b));
+    assert_eq!(x.checked_sub(y).map(|r| r.0), a.checked_sub(b));
+    assert_eq!(x.saturating_sub(y).map(|r| r.0), Some(a.saturating_sub(b)));
+    assert_eq!(
+        x.checked_mul(y).map(|r| r.0),
+        expected(big(a) * big(b) / &scale)
+    );
+    assert_eq!(
+        x.checked_sq().map(|r| r.0),
+        expected(big(a) * big(a) / &scale)
+    );
+    if b == 0 {
+        assert!(x.checked_div(y).is_none());
+        assert!(x.checked_idiv(y).is_none());
+    } else {
+        assert_eq!(
+            x.checked_div(y).map(|r| r.0),
+            expected(big(a) * &scale / big(b))
+        );
+        assert_eq!(
+            x.checked_idiv(y).map(|r| r.0),
+            expected(big(a / b) * &scale)
+        );
+    }
+    assert_eq!(
+        Amount::checked_dot(&[x, y], &[y, x]).map(|r| r.0),
+        expected(big(a) * big(b) * 2u32 / &scale)
+    );
+    x.checked_sqrt();
+    x.checked_exp();
+    x.checked_ln();
+    x.checked_powi(n);
+    x.checked_pow(y);
+
+    let (s, t) = (SignedAmount(c), SignedAmount(d));
+    let signed_scale = BigInt::from(SignedAmount::SCALE);
+    let big = BigInt::from;
+
+    assert_eq!(s.checked_add(t).map(|r| r.0), c.checked_add(d));
+    assert_eq!(s.checked_sub(t).map(|r| r.0), c.checked_sub(d));
+    assert_eq!(s.checked_neg().map(|r| r.0), c.checked_neg());
+    assert_eq!(
+        s.checked_mul(t).map(|r| r.0),
+        expected_signed(big(c) * big(d) / &signed_scale)
+    );
+    let div = if d == 0 {
+        None
+    } else {
+        expected_signed(big(c) * &signed_scale / big(d))
+    };
+    assert_eq!(s.checked_div(t).map(|r| r.0), div);
+});
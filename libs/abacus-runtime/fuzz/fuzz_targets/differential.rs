@@ -0,0 +1,83 @@
+//! `VectorVM` agrees with reference interpreter on generated programs
+//!
+//! Same property as `vector_vm_agrees_with_reference` in `tests/differential.rs`
+//! with programs generated from fuzzer input. Run with
+//! `cargo +nightly fuzz run differential` from `libs/abacus-runtime`.
+#![no_main]
+
+#[path = "../../tests/reference/mod.rs"]
+mod reference;
+
+use abacus_runtime::{decoder::Encoding, runtime::VectorVM, verifier::verify};
+use common::{abacus::program_error::ErrorCode, amount::Amount};
+use libfuzzer_sys::{
+    arbitrary::{Result, Unstructured},
+    fuzz_target,
+};
+
+use reference::{Outcome, Step};
+
+fn generate(u: &mut Unstructured) -> Result<reference::Program> {
+    let count = u.int_in_range(1..=3)?;
+    let len = u.int_in_range(1..=5)?;
+    let mut inputs = Vec::with_capacity(count);
+    for _ in 0..count {
+        let mut input = Vec::with_capacity(len);
+        for _ in 0..len {
+            input.push(Amount(u.arbitrary()?));
+        }
+        inputs.push(input);
+    }
+    let encoding = if u.arbitrary()? {
+        Encoding::Compact
+    } else {
+        Encoding::Fixed
+    };
+    let mut steps = Vec::new();
+    while !u.is_empty() && steps.len() < 64 {
+        steps.push(Step {
+            selector: u.arbitrary()?,
+            pos: u.arbitrary()?,
+            value: u.arbitrary()?,
+        });
+    }
+    Ok(reference::build(inputs, &steps, encoding))
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(program) = generate(&mut Unstructured::new(data)) else {
+        return;
+    };
+    assert!(verify(&program.code).is_ok());
+
+    let mut vio = reference::MemoryVectorIO::new(&program);
+    let result = VectorVM::new(&mut vio).execute(program.code.clone(), 0);
+
+    match (reference::execute(&program), result) {
+        (Outcome::Success(expected), Ok(())) => {
+            for (computed, expected) in vio.outputs(&program).iter().zip(&expected) {
+                assert_eq!(computed.len(), expected.len());
+                for (x, bounds) in computed.iter().zip(expected) {
+                    assert!(
+                        reference::is_within(*x, bounds),
+                        "Computed {} is not within {} +/- {}",
+                        x.0,
+                        bounds.value,
+                        bounds.error
+                    );
+                }
+            }
+        }
+        (Outcome::Overflow, Err(err)) => {
+            assert!(
+                matches!(err.error_code, ErrorCode::MathOverflow),
+                "{:?}",
+                err
+            );
+        }
+        (Outcome::Ambiguous, _) => {}
+        (expected, result) => {
+            panic!("Expected {:?}, but VM returned {:?}", expected, result);
+        }
+    }
+});
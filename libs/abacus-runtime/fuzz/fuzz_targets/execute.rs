@@ -0,0 +1,26 @@
+//! Arbitrary bytecode is either rejected by verifier, fails or succeeds in
+//! `VectorVM`, but it never panics
+//!
+//! Run with `cargo +nightly fuzz run execute` from `libs/abacus-runtime`.
+#![no_main]
+
+#[path = "../../tests/reference/mod.rs"]
+mod reference;
+
+use abacus_runtime::{metering::ExecutionLimits, runtime::VectorVM, verifier::verify};
+use common::amount::Amount;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|code: &[u8]| {
+    let _ = verify(code);
+
+    let limits = ExecutionLimits {
+        max_instructions: 10_000,
+        max_stack_depth: 256,
+        max_call_depth: 8,
+        max_vector_len: 10_000,
+    };
+    let mut vio = reference::MemoryVectorIO::default();
+    vio.vectors.insert(1, vec![Amount::ONE, Amount::TWO]);
+    let _ = VectorVM::with_limits(&mut vio, limits).execute(code.to_vec(), 16);
+});
@@ -0,0 +1,411 @@
+use alloc::vec::Vec;
+use common::{
+    abacus::{instruction_set::*, program_error::ErrorCode},
+    uint::{read_leb128, read_u128, write_leb128, write_u128, MAX_LEB128_LEN},
+};
+
+/// Maximum number of arguments any instruction takes (`B` and `FOLD`).
+pub const MAX_ARGS: usize = 4;
+
+/// Category of an instruction argument as encoded in bytecode.
+///
+/// Same categories as the ones used by `abacus!` macro to encode arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgType {
+    RegisterId, // <reg>
+    Amount,     // <immediate (scalar)> for IMMS/VPUSH
+    StackPos,   // <pos>, <pos_A>, <pos_B>
+    StorageId,  // <label_id>, <vector_id>, <prg_id>
+    Label,      // <immediate (label)>, <assert_id>, <start>, <len>
+    Size,       // <count>, <N>, <M>, <R>
+    Argument,   // <arg>
+}
+
+impl ArgType {
+    /// Tells whether argument is encoded according to program `Encoding`,
+    /// otherwise it always occupies one byte.
+    pub fn is_wide(&self) -> bool {
+        match self {
+            ArgType::RegisterId | ArgType::StackPos | ArgType::Size | ArgType::Argument => false,
+            ArgType::StorageId | ArgType::Amount | ArgType::Label => true,
+        }
+    }
+}
+
+/// Encoding of wide arguments, i.e. storage ids, labels and immediate scalars
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// 16-byte little-endian u128
+    Fixed,
+
+    /// Unsigned LEB128, selected by `COMPACT_HEADER` at the start of program
+    Compact,
+}
+
+impl Encoding {
+    /// Encoding selected by the first byte of a program
+    pub fn of(code: &[u8]) -> Self {
+        match code.first() {
+            Some(&COMPACT_HEADER) => Encoding::Compact,
+            _ => Encoding::Fixed,
+        }
+    }
+
+    /// Number of bytes of program header, i.e. offset of first instruction
+    pub fn header_len(&self) -> usize {
+        match self {
+            Encoding::Fixed => 0,
+            Encoding::Compact => 1,
+        }
+    }
+
+    /// Start new program with this encoding
+    pub fn write_header(&self, output: &mut Vec<u8>) {
+        if let Encoding::Compact = self {
+            output.push(COMPACT_HEADER);
+        }
+    }
+
+    /// Write wide argument
+    pub fn write(&self, value: u128, output: &mut Vec<u8>) {
+        match self {
+            Encoding::Fixed => write_u128(value, output),
+            Encoding::Compact => write_leb128(value, output),
+        }
+    }
+
+    /// Read wide argument at offset `pos`
+    ///
+    /// Returns value and number of bytes it occupies. Fails with
+    /// `TruncatedInstruction` when code ends before the argument does, and
+    /// with `InvalidInstruction` when compact argument exceeds 128 bits.
+    pub fn read(&self, code: &[u8], pos: usize) -> Result<(u128, usize), ErrorCode> {
+        match self {
+            Encoding::Fixed => {
+                let bytes = code
+                    .get(pos..pos + 16)
+                    .ok_or(ErrorCode::TruncatedInstruction)?;
+                Ok((read_u128(bytes), 16))
+            }
+            Encoding::Compact => {
+                let bytes = code.get(pos..).unwrap_or_default();
+                match read_leb128(bytes) {
+                    Some(result) => Ok(result),
+                    None if bytes.len() >= MAX_LEB128_LEN
+                        || bytes.iter().any(|byte| byte & 0x80 == 0) =>
+                    {
+                        Err(ErrorCode::InvalidInstruction)
+                    }
+                    None => Err(ErrorCode::TruncatedInstruction),
+                }
+            }
+        }
+    }
+}
+
+/// Look up argument types of an op-code
+///
+/// Returns `None` for unknown op-codes.
+pub fn arg_types(op_code: u8) -> Option<&'static [ArgType]> {
+    use ArgType::*;
+    let types: &'static [ArgType] = match op_code {
+        // 1. Data Loading & Stack Access
+        OP_LDL | OP_LDV => &[StorageId],
+        OP_LDLR | OP_LDVR => &[StorageId, Label, Label],
+        OP_LDD => &[StackPos],
+        OP_LDR | OP_LDM => &[RegisterId],
+
+        // 2. Data Storage & Register Access
+        OP_STL | OP_STV => &[StorageId],
+        OP_STVR => &[StorageId, Label],
+        OP_STR => &[RegisterId],
+
+        // 3. Data Structure Manipulation
+        OP_PKV | OP_PKL | OP_T => &[Size],
+        OP_UNPK | OP_VPOP => &[],
+        OP_VPUSH => &[Amount],
+
+        // 4. Labels Manipulation
+        OP_LUNION => &[StackPos],
+        OP_LPUSH => &[Label],
+        OP_LPOP => &[],
+        OP_JUPD | OP_JADD => &[StackPos, StackPos, StackPos],
+        OP_JFLT => &[StackPos, StackPos],
+
+        // 5. Arithmetic & Core Math
+        OP_ADD | OP_SUB | OP_SSB | OP_MUL | OP_DIV | OP_POW => &[StackPos],
+        OP_SQRT | OP_EXP | OP_LN => &[],
+
+        // 6. Logic & Comparison
+        OP_MIN | OP_MAX | OP_EQ | OP_GT | OP_LT => &[StackPos],
+        OP_CLAMP | OP_SEL => &[StackPos, StackPos],
+
+        // 7. Vector Aggregation
+        OP_VSUM | OP_VMIN | OP_VMAX | OP_VCUMSUM | OP_VNORM => &[],
+        OP_VDOT => &[StackPos],
+
+        // 8. Immediate Values & Vector Creation
+        OP_IMMS => &[Amount],
+        OP_IMML => &[Label],
+        OP_ZEROS | OP_ONES => &[StackPos],
+
+        // 9. Stack Control & Program Flow
+        OP_POPN => &[Size],
+        OP_SWAP => &[StackPos],
+        OP_B | OP_FOLD | OP_BZ => &[StorageId, Size, Size, Size],
+
+        // 10. Assertions
+        OP_ASSERT_ZERO => &[Label],
+        OP_ASSERT_LE => &[StackPos, Label],
+
+        // 11. Signed Values
+        OP_NEG | OP_ABS | OP_POS | OP_NEGPART => &[],
+        OP_NET => &[StackPos],
+
+        // 12. Sparse Vectors
+        OP_SPK => &[StackPos],
+        OP_SUNPK => &[],
+        OP_SGET | OP_SADD | OP_SUPD => &[StackPos, StackPos],
+
+        // 13. Program Arguments
+        OP_ARGS => &[Size],
+        OP_LDA | OP_LDLA | OP_LDVA | OP_STLA | OP_STVA => &[Argument],
+
+        // 14. Rounding Modifiers
+        OP_RNDU | OP_RNDE => &[],
+
+        _ => return None,
+    };
+    Some(types)
+}
+
+/// Mnemonic of an op-code, or `None` for unknown op-codes.
+pub fn mnemonic(op_code: u8) -> Option<&'static str> {
+    let name = match op_code {
+        OP_LDL => "LDL",
+        OP_LDV => "LDV",
+        OP_LDLR => "LDLR",
+        OP_LDVR => "LDVR",
+        OP_LDD => "LDD",
+        OP_LDR => "LDR",
+        OP_LDM => "LDM",
+        OP_STL => "STL",
+        OP_STV => "STV",
+        OP_STVR => "STVR",
+        OP_STR => "STR",
+        OP_PKV => "PKV",
+        OP_PKL => "PKL",
+        OP_UNPK => "UNPK",
+        OP_VPUSH => "VPUSH",
+        OP_VPOP => "VPOP",
+        OP_T => "T",
+        OP_LUNION => "LUNION",
+        OP_LPUSH => "LPUSH",
+        OP_LPOP => "LPOP",
+        OP_JUPD => "JUPD",
+        OP_JADD => "JADD",
+        OP_JFLT => "JFLT",
+        OP_ADD => "ADD",
+        OP_SUB => "SUB",
+        OP_SSB => "SSB",
+        OP_MUL => "MUL",
+        OP_DIV => "DIV",
+        OP_SQRT => "SQRT",
+        OP_EXP => "EXP",
+        OP_LN => "LN",
+        OP_POW => "POW",
+        OP_MIN => "MIN",
+        OP_MAX => "MAX",
+        OP_EQ => "EQ",
+        OP_GT => "GT",
+        OP_LT => "LT",
+        OP_CLAMP => "CLAMP",
+        OP_SEL => "SEL",
+        OP_VSUM => "VSUM",
+        OP_VMIN => "VMIN",
+        OP_VMAX => "VMAX",
+        OP_VDOT => "VDOT",
+        OP_VCUMSUM => "VCUMSUM",
+        OP_VNORM => "VNORM",
+        OP_IMMS => "IMMS",
+        OP_IMML => "IMML",
+        OP_ZEROS => "ZEROS",
+        OP_ONES => "ONES",
+        OP_POPN => "POPN",
+        OP_SWAP => "SWAP",
+        OP_B => "B",
+        OP_FOLD => "FOLD",
+        OP_BZ => "BZ",
+        OP_ASSERT_ZERO => "ASSERT_ZERO",
+        OP_ASSERT_LE => "ASSERT_LE",
+        OP_NEG => "NEG",
+        OP_ABS => "ABS",
+        OP_POS => "POS",
+        OP_NEGPART => "NEGPART",
+        OP_NET => "NET",
+        OP_SPK => "SPK",
+        OP_SUNPK => "SUNPK",
+        OP_SGET => "SGET",
+        OP_SADD => "SADD",
+        OP_SUPD => "SUPD",
+        OP_ARGS => "ARGS",
+        OP_LDA => "LDA",
+        OP_LDLA => "LDLA",
+        OP_LDVA => "LDVA",
+        OP_STLA => "STLA",
+        OP_STVA => "STVA",
+        OP_RNDU => "RNDU",
+        OP_RNDE => "RNDE",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Look up op-code by mnemonic (case-insensitive)
+///
+/// Returns `None` for unknown mnemonics.
+pub fn op_code(name: &str) -> Option<u8> {
+    (0..=u8::MAX).find(|&op_code| mnemonic(op_code).is_some_and(|m| m.eq_ignore_ascii_case(name)))
+}
+
+/// Single instruction decoded from bytecode
+///
+/// Arguments are stored in fixed size array so that decoding does not
+/// allocate, which keeps it cheap enough to be used on-chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction {
+    pub op_code: u8,
+    pub offset: usize,
+    pub len: usize,
+    num_args: usize,
+    args: [u128; MAX_ARGS],
+}
+
+impl Instruction {
+    /// Construct instruction to be encoded, e.g. by optimizer
+    ///
+    /// Returns `None` for unknown op-code, or when number of arguments does
+    /// not match its argument types. Offset and length are zero, as they are
+    /// only known once instruction is encoded.
+    pub fn new(op_code: u8, args: &[u128]) -> Option<Self> {
+        let types = arg_types(op_code)?;
+        if types.len() != args.len() {
+            return None;
+        }
+        let mut result = Self {
+            op_code,
+            offset: 0,
+            len: 0,
+            num_args: args.len(),
+            args: [0u128; MAX_ARGS],
+        };
+        result.args[..args.len()].copy_from_slice(args);
+        Some(result)
+    }
+
+    /// Encode instruction writing wide arguments with given `Encoding`
+    pub fn encode(&self, encoding: Encoding, output: &mut Vec<u8>) {
+        output.push(self.op_code);
+        for (arg_type, value) in self.arg_types().iter().zip(self.args()) {
+            if arg_type.is_wide() {
+                encoding.write(*value, output);
+            } else {
+                output.push(*value as u8);
+            }
+        }
+    }
+
+    pub fn args(&self) -> &[u128] {
+        &self.args[..self.num_args]
+    }
+
+    /// Argument as stack position, register index, or size.
+    #[inline]
+    pub fn arg_usize(&self, index: usize) -> usize {
+        self.args[index] as usize
+    }
+
+    pub fn arg_types(&self) -> &'static [ArgType] {
+        // Instruction can only be constructed for known op-codes
+        arg_types(self.op_code).unwrap_or(&[])
+    }
+}
+
+/// Decode single instruction at offset `pc`
+///
+/// Wide arguments are read with the `Encoding` selected by program header,
+/// and so `code` must be the whole program.
+///
+/// Fails with `InvalidInstruction` on unknown op-code, and with
+/// `TruncatedInstruction` when code ends before all arguments are read.
+pub fn decode(code: &[u8], pc: usize) -> Result<Instruction, ErrorCode> {
+    let op_code = *code.get(pc).ok_or(ErrorCode::TruncatedInstruction)?;
+    let types = arg_types(op_code).ok_or(ErrorCode::InvalidInstruction)?;
+
+    let encoding = Encoding::of(code);
+    let mut args = [0u128; MAX_ARGS];
+    let mut pos = pc + 1;
+    for (i, arg_type) in types.iter().enumerate() {
+        let (value, arg_len) = if arg_type.is_wide() {
+            encoding.read(code, pos)?
+        } else {
+            let byte = code.get(pos).ok_or(ErrorCode::TruncatedInstruction)?;
+            (*byte as u128, 1)
+        };
+        args[i] = value;
+        pos += arg_len;
+    }
+
+    Ok(Instruction {
+        op_code,
+        offset: pc,
+        len: pos - pc,
+        num_args: types.len(),
+        args,
+    })
+}
+
+/// Iterator over instructions of a program
+///
+/// Yields decoding error once and then stops.
+pub struct Decoder<'a> {
+    code: &'a [u8],
+    pc: usize,
+    failed: bool,
+}
+
+impl<'a> Decoder<'a> {
+    /// Decode program starting with its first instruction, i.e. after header
+    pub fn new(code: &'a [u8]) -> Self {
+        Self {
+            code,
+            pc: Encoding::of(code).header_len(),
+            failed: false,
+        }
+    }
+
+    /// Offset of the next instruction to decode
+    pub fn program_counter(&self) -> usize {
+        self.pc
+    }
+}
+
+impl Iterator for Decoder<'_> {
+    type Item = Result<Instruction, ErrorCode>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.pc >= self.code.len() {
+            return None;
+        }
+        match decode(self.code, self.pc) {
+            Ok(instruction) => {
+                self.pc += instruction.len;
+                Some(Ok(instruction))
+            }
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use core::fmt::{Debug, Display, Write};
+
+use alloc::{string::String, vec::Vec};
+use common::{abacus::instruction_set::*, amount::Amount};
+
+use crate::decoder::{arg_types, decode, mnemonic, ArgType, Encoding, Instruction};
+
+/// Instruction argument decoded according to its `ArgType`
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DecodedArg {
+    Register(u8),
+    StackPos(u8),
+    Size(u8),
+    Argument(u8),
+    StorageId(u128),
+    Label(u128),
+    Amount(Amount),
+}
+
+impl Display for DecodedArg {
+    /// Arguments are formatted the same way as they are written in `abacus!`,
+    /// except registers, which are named after their index.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DecodedArg::Register(x) => write!(f, "_r{}", x),
+            DecodedArg::StackPos(x) | DecodedArg::Size(x) | DecodedArg::Argument(x) => {
+                write!(f, "{}", x)
+            }
+            DecodedArg::StorageId(x) | DecodedArg::Label(x) => write!(f, "{}", x),
+            DecodedArg::Amount(x) => fmt_amount(x, f),
+        }
+    }
+}
+
+impl Debug for DecodedArg {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DecodedArg::Register(x) => write!(f, "Register({})", x),
+            DecodedArg::StackPos(x) => write!(f, "StackPos({})", x),
+            DecodedArg::Size(x) => write!(f, "Size({})", x),
+            DecodedArg::Argument(x) => write!(f, "Argument({})", x),
+            DecodedArg::StorageId(x) => write!(f, "StorageId({})", x),
+            DecodedArg::Label(x) => write!(f, "Label({})", x),
+            DecodedArg::Amount(_) => write!(f, "Amount({})", self),
+        }
+    }
+}
+
+/// Format amount the same way as `amount!` literal
+///
+/// Note that `Display` of `Amount` is not available in smart-contracts.
+fn fmt_amount(amount: &Amount, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let integral = amount.0 / Amount::SCALE;
+    let fraction = amount.0 % Amount::SCALE;
+    if fraction == 0 {
+        return write!(f, "{}", integral);
+    }
+    let mut digits = Amount::DECIMALS;
+    let mut fraction = fraction;
+    while fraction % 10 == 0 {
+        fraction /= 10;
+        digits -= 1;
+    }
+    write!(f, "{}.{:0>digits$}", integral, fraction, digits = digits)
+}
+
+/// Instruction decoded by `disassemble()`
+///
+/// Disassembly does not fail. Unknown op-code or truncated instruction is
+/// returned as the last instruction covering all remaining bytes, because
+/// offsets of any bytes that follow cannot be trusted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedInstruction {
+    pub offset: usize,
+    pub len: usize,
+    pub op_code: u8,
+
+    /// Mnemonic of the instruction, or `None` for unknown op-code
+    pub mnemonic: Option<&'static str>,
+    pub args: Vec<DecodedArg>,
+
+    /// Code ended before all arguments were read
+    pub truncated: bool,
+}
+
+impl DecodedInstruction {
+    pub fn is_valid(&self) -> bool {
+        self.mnemonic.is_some() && !self.truncated
+    }
+
+    /// Short description of what storage or program instruction refers to
+    pub fn comment(&self) -> Option<&'static str> {
+        if self.truncated {
+            return Some("truncated instruction");
+        }
+        let comment = match self.op_code {
+            OP_LDL => "load labels",
+            OP_LDV => "load vector",
+            OP_STL => "store labels",
+            OP_STV => "store vector",
+            OP_LDLR => "load labels range (start, len)",
+            OP_LDVR => "load vector range (start, len)",
+            OP_STVR => "store vector range (start)",
+            OP_ARGS => "template arguments",
+            OP_LDA => "load argument (label)",
+            OP_LDLA => "load labels (argument)",
+            OP_LDVA => "load vector (argument)",
+            OP_STLA => "store labels (argument)",
+            OP_STVA => "store vector (argument)",
+            OP_RNDU => "round up following MUL/DIV",
+            OP_RNDE => "round half-even following MUL/DIV",
+            OP_B => "call program (N inputs, M outputs, R registers)",
+            OP_FOLD => "fold program (N inputs, M outputs, R registers)",
+            _ if self.mnemonic.is_none() => "unknown op-code",
+            _ => return None,
+        };
+        Some(comment)
+    }
+}
+
+impl Display for DecodedInstruction {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let Some(name) = self.mnemonic else {
+            return write!(f, "{:<7} 0x{:02x}", "???", self.op_code);
+        };
+        if self.args.is_empty() {
+            return write!(f, "{}", name);
+        }
+        write!(f, "{:<7}", name)?;
+        for arg in &self.args {
+            write!(f, " {}", arg)?;
+        }
+        Ok(())
+    }
+}
+
+fn decode_arg(arg_type: ArgType, value: u128) -> DecodedArg {
+    match arg_type {
+        ArgType::RegisterId => DecodedArg::Register(value as u8),
+        ArgType::StackPos => DecodedArg::StackPos(value as u8),
+        ArgType::Size => DecodedArg::Size(value as u8),
+        ArgType::Argument => DecodedArg::Argument(value as u8),
+        ArgType::StorageId => DecodedArg::StorageId(value),
+        ArgType::Label => DecodedArg::Label(value),
+        ArgType::Amount => DecodedArg::Amount(Amount(value)),
+    }
+}
+
+/// Decode arguments of an instruction according to their types
+pub fn decode_args(instruction: &Instruction) -> Vec<DecodedArg> {
+    instruction
+        .arg_types()
+        .iter()
+        .zip(instruction.args())
+        .map(|(arg_type, value)| decode_arg(*arg_type, *value))
+        .collect()
+}
+
+/// Decode all instructions of a program
+///
+/// Program header, if any, is skipped, and so the first instruction of
+/// compact program is at offset 1.
+pub fn disassemble(code: &[u8]) -> Vec<DecodedInstruction> {
+    let mut result = Vec::new();
+    let mut pc = Encoding::of(code).header_len();
+    while pc < code.len() {
+        match decode(code, pc) {
+            Ok(instruction) => {
+                let args = decode_args(&instruction);
+                result.push(DecodedInstruction {
+                    offset: pc,
+                    len: instruction.len,
+                    op_code: instruction.op_code,
+                    mnemonic: mnemonic(instruction.op_code),
+                    args,
+                    truncated: false,
+                });
+                pc += instruction.len;
+            }
+            Err(_) => {
+                let op_code = code[pc];
+                result.push(DecodedInstruction {
+                    offset: pc,
+                    len: code.len() - pc,
+                    op_code,
+                    mnemonic: mnemonic(op_code),
+                    args: Vec::new(),
+                    truncated: arg_types(op_code).is_some(),
+                });
+                break;
+            }
+        }
+    }
+    result
+}
+
+/// Find instruction that failed with `ProgramError::program_counter`
+///
+/// Program counter is advanced past op-code and every argument read before
+/// error occurred, and so it points inside or just past failed instruction.
+pub fn instruction_at(
+    instructions: &[DecodedInstruction],
+    program_counter: usize,
+) -> Option<&DecodedInstruction> {
+    instructions.iter().find(|instruction| {
+        instruction.offset < program_counter
+            && program_counter <= instruction.offset + instruction.len
+    })
+}
+
+/// Format annotated listing of a program
+///
+/// Each line starts with byte offset of the instruction. If `program_counter`
+/// is given, the instruction that failed with it is marked with `>>`. Compact
+/// program starts with a comment line in place of its header.
+pub fn listing(code: &[u8], program_counter: Option<usize>) -> String {
+    let instructions = disassemble(code);
+    let failed = program_counter
+        .and_then(|pc| instruction_at(&instructions, pc))
+        .map(|instruction| instruction.offset);
+
+    let mut out = String::new();
+    if let Encoding::Compact = Encoding::of(code) {
+        let _ = writeln!(out, "   {:>6}  // compact encoding", 0);
+    }
+    for instruction in &instructions {
+        let marker = if failed == Some(instruction.offset) {
+            ">>"
+        } else {
+            "  "
+        };
+        let text = alloc::format!("{}", instruction);
+        // Writing to String never fails
+        let _ = match instruction.comment() {
+            Some(comment) => writeln!(
+                out,
+                "{} {:>6}  {:<40} // {}",
+                marker, instruction.offset, text, comment
+            ),
+            None => writeln!(out, "{} {:>6}  {}", marker, instruction.offset, text),
+        };
+    }
+    out
+}
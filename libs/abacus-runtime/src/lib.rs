@@ -3,7 +3,14 @@
 //#[macro_use]
 extern crate alloc;
 
+pub mod decoder;
+pub mod disassembler;
+pub mod metering;
+pub mod optimizer;
 pub mod runtime;
+pub mod tracer;
+pub mod transaction;
+pub mod verifier;
 
 #[cfg(test)]
 pub mod test;
\ No newline at end of file
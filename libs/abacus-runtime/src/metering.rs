@@ -0,0 +1,202 @@
+use common::abacus::{instruction_set::*, program_error::*};
+
+/// Limits enforced by `VectorVM` during execution
+///
+/// Limits apply to whole execution including all `B` and `FOLD`
+/// sub-routines, except `max_stack_depth`, which applies to each frame
+/// separately as every sub-routine runs with its own stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecutionLimits {
+    /// Maximum number of instructions executed
+    pub max_instructions: usize,
+
+    /// Maximum number of operands on the stack of any frame
+    pub max_stack_depth: usize,
+
+    /// Maximum nesting of `B` and `FOLD` sub-routine calls
+    pub max_call_depth: usize,
+
+    /// Maximum length of any vector or labels produced by an instruction
+    pub max_vector_len: usize,
+}
+
+impl ExecutionLimits {
+    pub const UNLIMITED: Self = Self {
+        max_instructions: usize::MAX,
+        max_stack_depth: usize::MAX,
+        max_call_depth: usize::MAX,
+        max_vector_len: usize::MAX,
+    };
+}
+
+impl Default for ExecutionLimits {
+    fn default() -> Self {
+        Self::UNLIMITED
+    }
+}
+
+/// Cost of an instruction in abstract units
+///
+/// Total cost is `base + per_component * N`, where `N` is the length of
+/// vector or labels operand on top of the stack, before or after instruction
+/// whichever is longer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpCost {
+    pub base: u64,
+    pub per_component: u64,
+}
+
+impl OpCost {
+    const fn new(base: u64, per_component: u64) -> Self {
+        Self {
+            base,
+            per_component,
+        }
+    }
+
+    pub fn units(&self, num_components: usize) -> u64 {
+        self.base
+            .saturating_add(self.per_component.saturating_mul(num_components as u64))
+    }
+}
+
+/// Look up cost of an op-code
+///
+/// Costs are relative, and they reflect that storage access is far more
+/// expensive than operations on the stack. Unknown op-codes cost nothing, as
+/// they fail anyways.
+pub fn op_cost(op_code: u8) -> OpCost {
+    match op_code {
+        // Storage access
+        OP_LDL | OP_LDV | OP_LDLR | OP_LDVR | OP_STL | OP_STV | OP_STVR => OpCost::new(100, 20),
+        OP_LDLA | OP_LDVA | OP_STLA | OP_STVA => OpCost::new(100, 20),
+
+        // Copying operands
+        OP_LDD | OP_LDR | OP_ZEROS | OP_ONES => OpCost::new(1, 1),
+        OP_LDM | OP_STR => OpCost::new(1, 0),
+
+        // Data structure manipulation
+        OP_PKV | OP_PKL | OP_UNPK | OP_T => OpCost::new(2, 1),
+        OP_VPUSH | OP_VPOP | OP_LPUSH | OP_LPOP => OpCost::new(1, 0),
+
+        // Joins need to look up labels
+        OP_LUNION | OP_JUPD | OP_JADD | OP_JFLT => OpCost::new(4, 4),
+
+        // Arithmetic
+        OP_ADD | OP_SUB | OP_SSB | OP_MIN | OP_MAX => OpCost::new(1, 1),
+        OP_MUL | OP_DIV => OpCost::new(1, 2),
+        OP_SQRT => OpCost::new(10, 10),
+        OP_EXP | OP_LN | OP_POW => OpCost::new(10, 20),
+        OP_VSUM | OP_VMIN | OP_VMAX | OP_VCUMSUM => OpCost::new(1, 1),
+        OP_VDOT | OP_VNORM => OpCost::new(1, 2),
+
+        // Comparison & selection
+        OP_EQ | OP_GT | OP_LT => OpCost::new(1, 1),
+        OP_CLAMP | OP_SEL => OpCost::new(1, 2),
+
+        // Immediate values & stack control
+        OP_IMMS | OP_IMML | OP_POPN | OP_SWAP => OpCost::new(1, 0),
+
+        // Program arguments
+        OP_ARGS | OP_LDA | OP_RNDU | OP_RNDE => OpCost::new(1, 0),
+
+        // Sub-routine calls (instructions of sub-routine are counted separately)
+        OP_B | OP_FOLD | OP_BZ => OpCost::new(10, 0),
+
+        // Assertions
+        OP_ASSERT_ZERO | OP_ASSERT_LE => OpCost::new(1, 1),
+
+        // Signed values
+        OP_NEG | OP_ABS | OP_POS | OP_NEGPART | OP_NET => OpCost::new(1, 1),
+
+        // Sparse vectors
+        OP_SPK | OP_SUNPK => OpCost::new(2, 1),
+        OP_SGET | OP_SADD | OP_SUPD => OpCost::new(4, 4),
+
+        _ => OpCost::new(0, 0),
+    }
+}
+
+/// Resources consumed by execution
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecutionMetrics {
+    /// Number of instructions executed including sub-routines
+    pub instructions: usize,
+
+    /// Total cost of executed instructions in units of `op_cost()`
+    pub units: u64,
+
+    /// Maximum number of operands on the stack of any frame
+    pub max_stack_depth: usize,
+
+    /// Maximum nesting of sub-routine calls
+    pub max_call_depth: usize,
+
+    /// Maximum length of any vector or labels produced
+    pub max_vector_len: usize,
+}
+
+/// Tracks consumed resources against limits
+pub(crate) struct Meter {
+    limits: ExecutionLimits,
+    metrics: ExecutionMetrics,
+    call_depth: usize,
+}
+
+impl Meter {
+    pub(crate) fn new(limits: ExecutionLimits) -> Self {
+        Self {
+            limits,
+            metrics: ExecutionMetrics::default(),
+            call_depth: 0,
+        }
+    }
+
+    pub(crate) fn metrics(&self) -> &ExecutionMetrics {
+        &self.metrics
+    }
+
+    /// Account for instruction before it is executed
+    pub(crate) fn begin_instruction(&mut self) -> Result<(), ErrorCode> {
+        if self.metrics.instructions >= self.limits.max_instructions {
+            Err(ErrorCode::LimitExceeded(LimitKind::Instructions))?;
+        }
+        self.metrics.instructions += 1;
+        Ok(())
+    }
+
+    /// Account for effects of executed instruction
+    pub(crate) fn end_instruction(
+        &mut self,
+        op_code: u8,
+        num_components: usize,
+        stack_depth: usize,
+    ) -> Result<(), ErrorCode> {
+        self.metrics.units = self
+            .metrics
+            .units
+            .saturating_add(op_cost(op_code).units(num_components));
+        self.metrics.max_stack_depth = self.metrics.max_stack_depth.max(stack_depth);
+        self.metrics.max_vector_len = self.metrics.max_vector_len.max(num_components);
+        if self.limits.max_stack_depth < stack_depth {
+            Err(ErrorCode::LimitExceeded(LimitKind::StackDepth))?;
+        }
+        if self.limits.max_vector_len < num_components {
+            Err(ErrorCode::LimitExceeded(LimitKind::VectorLength))?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn enter_call(&mut self) -> Result<(), ErrorCode> {
+        if self.call_depth >= self.limits.max_call_depth {
+            Err(ErrorCode::LimitExceeded(LimitKind::CallDepth))?;
+        }
+        self.call_depth += 1;
+        self.metrics.max_call_depth = self.metrics.max_call_depth.max(self.call_depth);
+        Ok(())
+    }
+
+    pub(crate) fn leave_call(&mut self) {
+        self.call_depth -= 1;
+    }
+}
@@ -0,0 +1,445 @@
+use alloc::vec::Vec;
+use common::abacus::{instruction_set::*, program_error::*};
+
+use crate::{
+    decoder::{Decoder, Encoding, Instruction},
+    verifier::min_depth_after,
+};
+
+/// Optimize program by rewriting redundant instruction sequences
+///
+/// Rewrites are repeated until none of them applies:
+/// - operands pushed only to be popped are never computed, when pushing them
+///   cannot fail, and consecutive `POPN` are merged,
+/// - `SWAP` is removed by swapping the loads of its operands, or when it
+///   swaps operand with its copy, undoes previous `SWAP`, or both operands
+///   are popped,
+/// - registers loaded and stored back, or stored and moved back, are left
+///   alone, and register stored again before it is read is not stored,
+///   while register read for the last time before it is stored again is
+///   moved with `LDM` instead of copied,
+/// - labels or vector loaded again while unmodified copy is still on the
+///   stack is copied from the stack with `LDD` instead.
+///
+/// Program is rewritten with its original `Encoding`. Final stack and
+/// registers are kept intact, as sub-routine run by `FOLD` finds registers
+/// as its previous iteration left them.
+///
+/// Only instructions, which cannot fail, are ever removed, and so optimized
+/// program fails whenever original program fails, e.g. load of missing
+/// vector, or product that overflows, are kept even when never used.
+/// Register is assumed to exist only after program used it, and stack depth
+/// is assumed to be only as deep as program is known to make it.
+///
+pub fn optimize(code: &[u8]) -> Result<Vec<u8>, ProgramError> {
+    let mut instructions = decode_all(code)?;
+    loop {
+        let changed = peephole(&mut instructions);
+        let changed = registers(&mut instructions) || changed;
+        let changed = reuse_loads(&mut instructions) || changed;
+        if !changed {
+            break;
+        }
+    }
+
+    let encoding = Encoding::of(code);
+    let mut output = Vec::with_capacity(code.len());
+    encoding.write_header(&mut output);
+    for instruction in &instructions {
+        instruction.encode(encoding, &mut output);
+    }
+    Ok(output)
+}
+
+fn decode_all(code: &[u8]) -> Result<Vec<Instruction>, ProgramError> {
+    let mut result = Vec::new();
+    let mut decoder = Decoder::new(code);
+    loop {
+        let pc = decoder.program_counter();
+        let Some(next) = decoder.next() else {
+            break;
+        };
+        let instruction = next.map_err(|error_code| ProgramError {
+            op_code: code.get(pc).copied(),
+            ..ProgramError::new(error_code, pc, 0)
+        })?;
+        result.push(instruction);
+    }
+    Ok(result)
+}
+
+fn make(op_code: u8, args: &[u128]) -> Instruction {
+    // Optimizer only constructs instructions it knows arguments of
+    Instruction::new(op_code, args).expect("Invalid instruction")
+}
+
+fn popn(count: usize) -> Instruction {
+    make(OP_POPN, &[count as u128])
+}
+
+/// Instruction pushes one operand without modifying stack, registers or
+/// storage
+fn is_pure_push(instruction: &Instruction) -> bool {
+    matches!(
+        instruction.op_code,
+        OP_LDL
+            | OP_LDV
+            | OP_LDLR
+            | OP_LDVR
+            | OP_LDD
+            | OP_LDR
+            | OP_IMMS
+            | OP_IMML
+            | OP_LDA
+            | OP_LDLA
+            | OP_LDVA
+            | OP_ZEROS
+            | OP_ONES
+    )
+}
+
+/// Pure push, which cannot fail when executed with at least `min_depth`
+/// operands on the stack
+fn cannot_fail(instruction: &Instruction, min_depth: usize) -> bool {
+    match instruction.op_code {
+        OP_IMMS | OP_IMML => true,
+        OP_LDD => instruction.arg_usize(0) < min_depth,
+        _ => false,
+    }
+}
+
+/// Lower bounds of stack depth before each instruction, and after the last
+/// one, assuming program starts with unknown stack
+fn min_depths(instructions: &[Instruction]) -> Vec<usize> {
+    let mut depths = Vec::with_capacity(instructions.len() + 1);
+    depths.push(0);
+    extend_min_depths(&mut depths, instructions);
+    depths
+}
+
+/// Extend lower bounds of stack depth to cover all instructions
+fn extend_min_depths(depths: &mut Vec<usize>, instructions: &[Instruction]) {
+    for instruction in &instructions[depths.len() - 1..] {
+        let depth = depths.last().copied().unwrap_or_default();
+        depths.push(min_depth_after(instruction, depth));
+    }
+}
+
+/// Instruction only modifies operand on top of the stack
+fn modifies_top(op_code: u8) -> bool {
+    matches!(
+        op_code,
+        OP_ADD
+            | OP_SUB
+            | OP_SSB
+            | OP_MUL
+            | OP_DIV
+            | OP_POW
+            | OP_SQRT
+            | OP_EXP
+            | OP_LN
+            | OP_MIN
+            | OP_MAX
+            | OP_EQ
+            | OP_GT
+            | OP_LT
+            | OP_NET
+            | OP_CLAMP
+            | OP_SEL
+            | OP_VSUM
+            | OP_VMIN
+            | OP_VMAX
+            | OP_VDOT
+            | OP_VCUMSUM
+            | OP_VNORM
+            | OP_NEG
+            | OP_ABS
+            | OP_POS
+            | OP_NEGPART
+            | OP_JUPD
+            | OP_JADD
+            | OP_JFLT
+            | OP_LUNION
+            | OP_VPUSH
+            | OP_LPUSH
+    )
+}
+
+/// Move pure push by `delta` operands deeper or shallower in the stack
+///
+/// Returns `None` when instruction would need to refer to operand outside of
+/// the stack.
+fn shift(instruction: Instruction, delta: isize) -> Option<Instruction> {
+    match instruction.op_code {
+        OP_LDD | OP_ZEROS | OP_ONES => {
+            let pos = instruction.arg_usize(0).checked_add_signed(delta)?;
+            if pos > u8::MAX as usize {
+                return None;
+            }
+            Some(make(instruction.op_code, &[pos as u128]))
+        }
+        _ => Some(instruction),
+    }
+}
+
+/// Pure pushes in reverse order, or `None` when second one refers to the
+/// operand pushed by the first one
+fn reorder(first: Instruction, second: Instruction) -> Option<(Instruction, Instruction)> {
+    if !is_pure_push(&first) || !is_pure_push(&second) {
+        return None;
+    }
+    Some((shift(second, -1)?, shift(first, 1)?))
+}
+
+fn peephole(instructions: &mut Vec<Instruction>) -> bool {
+    let mut output = Vec::with_capacity(instructions.len());
+    let mut depths = min_depths(&output);
+    let mut changed = false;
+    for instruction in instructions.drain(..) {
+        output.push(instruction);
+        extend_min_depths(&mut depths, &output);
+        while rewrite_tail(&mut output, &depths) {
+            // Rewrites replace at most three last instructions
+            let keep = output.len().min(depths.len().saturating_sub(4));
+            depths.truncate(keep + 1);
+            extend_min_depths(&mut depths, &output);
+            changed = true;
+        }
+    }
+    *instructions = output;
+    changed
+}
+
+/// Rewrite last instructions of the output, so that rewrites cascade as
+/// earlier instructions become last again
+///
+/// Every rewrite makes program shorter, which guarantees termination. Lower
+/// bounds of stack depth before each instruction are given in `depths`.
+fn rewrite_tail(output: &mut Vec<Instruction>, depths: &[usize]) -> bool {
+    let n = output.len();
+    if n < 2 {
+        return false;
+    }
+    let (a, b) = (output[n - 2], output[n - 1]);
+    match (a.op_code, b.op_code) {
+        (OP_SWAP, OP_SWAP)
+            if a.args() == b.args() && a.arg_usize(0) != 0 && a.arg_usize(0) < depths[n - 2] =>
+        {
+            output.truncate(n - 2);
+        }
+        (OP_LDD, OP_SWAP) if a.arg_usize(0) == 0 && b.arg_usize(0) == 1 => {
+            output.pop();
+        }
+        (OP_SWAP, OP_POPN) if a.arg_usize(0) != 0 && a.arg_usize(0) < b.arg_usize(0) => {
+            output.truncate(n - 2);
+            output.push(b);
+        }
+        (OP_POPN, OP_POPN)
+            if a.arg_usize(0) != 0
+                && b.arg_usize(0) != 0
+                && a.arg_usize(0) + b.arg_usize(0) <= u8::MAX as usize =>
+        {
+            output.truncate(n - 2);
+            output.push(popn(a.arg_usize(0) + b.arg_usize(0)));
+        }
+        (_, OP_POPN) if b.arg_usize(0) != 0 && cannot_fail(&a, depths[n - 2]) => {
+            output.truncate(n - 2);
+            if b.arg_usize(0) > 1 {
+                output.push(popn(b.arg_usize(0) - 1));
+            }
+        }
+        (_, OP_SWAP) if n >= 3 && b.arg_usize(0) == 1 => {
+            let Some((second, first)) = reorder(output[n - 3], a) else {
+                return false;
+            };
+            output.truncate(n - 3);
+            output.push(second);
+            output.push(first);
+        }
+        _ => return false,
+    }
+    true
+}
+
+fn uses_register(op_code: u8) -> bool {
+    matches!(op_code, OP_LDR | OP_LDM | OP_STR)
+}
+
+fn registers(instructions: &mut Vec<Instruction>) -> bool {
+    // Whether register used by instruction is not read again before it is
+    // stored, i.e. its value is dead after the instruction. Registers are
+    // live when program ends, as sub-routine may be run again by FOLD.
+    let mut dead = Vec::with_capacity(instructions.len());
+    let mut live = [true; u8::MAX as usize + 1];
+    for instruction in instructions.iter().rev() {
+        let reg = instruction.arg_usize(0);
+        match instruction.op_code {
+            OP_STR => {
+                dead.push(!live[reg]);
+                live[reg] = false;
+            }
+            OP_LDR | OP_LDM => {
+                dead.push(!live[reg]);
+                live[reg] = true;
+            }
+            _ => dead.push(false),
+        }
+    }
+    dead.reverse();
+    let depths = min_depths(instructions);
+
+    // Whether register was used by optimized program already, and so using
+    // it cannot fail
+    let mut known = [false; u8::MAX as usize + 1];
+    let mut output: Vec<Instruction> = Vec::with_capacity(instructions.len());
+    let mut changed = false;
+    let mut i = 0;
+    while i < instructions.len() {
+        let instruction = instructions[i];
+        let next = instructions.get(i + 1);
+        let known_reg = uses_register(instruction.op_code) && known[instruction.arg_usize(0)];
+        i += 1;
+        match instruction.op_code {
+            OP_LDR | OP_LDM
+                if known_reg
+                    && next
+                        .is_some_and(|x| x.op_code == OP_STR && x.args() == instruction.args()) =>
+            {
+                i += 1;
+                changed = true;
+            }
+            OP_LDR | OP_LDM if dead[i - 1] => match next {
+                Some(x) if known_reg && x.op_code == OP_POPN && x.arg_usize(0) != 0 => {
+                    if x.arg_usize(0) > 1 {
+                        output.push(popn(x.arg_usize(0) - 1));
+                    }
+                    i += 1;
+                    changed = true;
+                }
+                _ if instruction.op_code == OP_LDR => {
+                    output.push(make(OP_LDM, instruction.args()));
+                    changed = true;
+                }
+                _ => output.push(instruction),
+            },
+            OP_STR if known_reg && dead[i - 1] => {
+                output.push(popn(1));
+                changed = true;
+            }
+            OP_STR => match next {
+                Some(x)
+                    if known_reg
+                        && matches!(x.op_code, OP_LDR | OP_LDM)
+                        && x.args() == instruction.args()
+                        && dead[i]
+                        && depths[i - 1] != 0 =>
+                {
+                    i += 1;
+                    changed = true;
+                }
+                _ => output.push(instruction),
+            },
+            _ => output.push(instruction),
+        }
+        if let Some(last) = output.last().filter(|x| uses_register(x.op_code)) {
+            known[last.arg_usize(0)] = true;
+        }
+    }
+    *instructions = output;
+    changed
+}
+
+/// Storage, which unmodified operand on the stack was loaded from
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Labels(u128),
+    Vector(u128),
+}
+
+fn reuse_loads(instructions: &mut [Instruction]) -> bool {
+    let mut stack = Vec::new();
+    let mut changed = false;
+    for instruction in instructions.iter_mut() {
+        let source = match instruction.op_code {
+            OP_LDL => Some(Source::Labels(instruction.args()[0])),
+            OP_LDV => Some(Source::Vector(instruction.args()[0])),
+            _ => None,
+        };
+        if source.is_some() {
+            let pos = stack.iter().rev().position(|x| *x == source);
+            if let Some(pos) = pos.filter(|&pos| pos <= u8::MAX as usize) {
+                *instruction = make(OP_LDD, &[pos as u128]);
+                changed = true;
+            }
+        }
+        track(&mut stack, instruction);
+    }
+    changed
+}
+
+/// Follow operands loaded from storage through the stack
+///
+/// Stack holds only operands on top of the actual stack, and operands below
+/// are unknown, so that programs starting with inputs on the stack, or
+/// pushing number of operands that depends on vector lengths, are handled by
+/// forgetting everything known so far.
+fn track(stack: &mut Vec<Option<Source>>, instruction: &Instruction) {
+    let arg = |i| instruction.arg_usize(i);
+    let pop = |stack: &mut Vec<Option<Source>>, count: usize| {
+        stack.truncate(stack.len().saturating_sub(count));
+    };
+    let modify_top = |stack: &mut Vec<Option<Source>>| {
+        if let Some(top) = stack.last_mut() {
+            *top = None;
+        }
+    };
+    let forget = |stack: &mut Vec<Option<Source>>, source: Source| {
+        for x in stack.iter_mut().filter(|x| **x == Some(source)) {
+            *x = None;
+        }
+    };
+    match instruction.op_code {
+        OP_LDL => stack.push(Some(Source::Labels(instruction.args()[0]))),
+        OP_LDV => stack.push(Some(Source::Vector(instruction.args()[0]))),
+        OP_LDD => {
+            let copy = stack
+                .len()
+                .checked_sub(arg(0) + 1)
+                .and_then(|index| stack[index]);
+            stack.push(copy);
+        }
+        OP_LDLR | OP_LDVR | OP_LDR | OP_LDM | OP_IMMS | OP_IMML | OP_LDA | OP_LDLA | OP_LDVA
+        | OP_ZEROS | OP_ONES => stack.push(None),
+        OP_STL => {
+            pop(stack, 1);
+            forget(stack, Source::Labels(instruction.args()[0]));
+        }
+        OP_STV | OP_STVR => {
+            pop(stack, 1);
+            forget(stack, Source::Vector(instruction.args()[0]));
+        }
+        OP_STR | OP_ASSERT_ZERO | OP_ASSERT_LE => pop(stack, 1),
+        OP_POPN if arg(0) != 0 => pop(stack, arg(0)),
+        OP_PKV | OP_PKL if arg(0) != 0 => {
+            pop(stack, arg(0));
+            stack.push(None);
+        }
+        OP_SWAP => {
+            let len = stack.len();
+            if arg(0) < len {
+                stack.swap(len - 1, len - 1 - arg(0));
+            } else {
+                modify_top(stack);
+            }
+        }
+        OP_VPOP | OP_LPOP | OP_SUNPK => {
+            modify_top(stack);
+            stack.push(None);
+        }
+        op_code if modifies_top(op_code) => modify_top(stack),
+        OP_ARGS | OP_RNDU | OP_RNDE => {}
+        // Sub-routines and template arguments may store anything, while
+        // other instructions may push or pop any number of operands
+        _ => stack.clear(),
+    }
+}
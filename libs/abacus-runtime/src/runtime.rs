@@ -3,11 +3,17 @@ use core::mem::swap;
 use alloc::{vec, vec::Vec};
 use common::{
     abacus::{instruction_set::*, program_error::*},
-    amount::Amount,
+    amount::{Amount, Rounding},
     labels::Labels,
     log_msg,
-    uint::read_u128,
-    vector::Vector,
+    signed_amount::SignedAmount,
+    vector::{SignedVector, SparseVector, Vector},
+};
+
+use crate::{
+    decoder::{decode, Encoding, Instruction},
+    metering::{ExecutionLimits, ExecutionMetrics, Meter},
+    tracer::{Step, Tracer},
 };
 
 pub trait VectorIO {
@@ -17,6 +23,92 @@ pub trait VectorIO {
 
     fn store_labels(&mut self, id: u128, input: Labels) -> Result<(), ErrorCode>;
     fn store_vector(&mut self, id: u128, input: Vector) -> Result<(), ErrorCode>;
+
+    /// Load `len` labels starting at `start`
+    ///
+    /// Default implementation loads all labels, and storage supporting
+    /// partial access should override it.
+    fn load_labels_range(&self, id: u128, start: usize, len: usize) -> Result<Labels, ErrorCode> {
+        let labels = self.load_labels(id)?;
+        let end = start.checked_add(len).ok_or(ErrorCode::OutOfRange)?;
+        let data = labels.get(start..end).ok_or(ErrorCode::OutOfRange)?;
+        Ok(Labels::from(data.to_vec()))
+    }
+
+    /// Load `len` vector components starting at `start`
+    ///
+    /// Default implementation loads whole vector, and storage supporting
+    /// partial access should override it.
+    fn load_vector_range(&self, id: u128, start: usize, len: usize) -> Result<Vector, ErrorCode> {
+        let vector = self.load_vector(id)?;
+        let end = start.checked_add(len).ok_or(ErrorCode::OutOfRange)?;
+        let data = vector.data.get(start..end).ok_or(ErrorCode::OutOfRange)?;
+        Ok(Vector {
+            data: data.to_vec(),
+        })
+    }
+
+    /// Overwrite vector components starting at `start`
+    ///
+    /// Range must be within bounds of the stored vector, i.e. its length
+    /// doesn't change. Default implementation loads and stores whole vector,
+    /// and storage supporting partial access should override it.
+    fn store_vector_range(
+        &mut self,
+        id: u128,
+        start: usize,
+        input: Vector,
+    ) -> Result<(), ErrorCode> {
+        let mut vector = self.load_vector(id)?;
+        let end = start
+            .checked_add(input.data.len())
+            .ok_or(ErrorCode::OutOfRange)?;
+        vector
+            .data
+            .get_mut(start..end)
+            .ok_or(ErrorCode::OutOfRange)?
+            .copy_from_slice(&input.data);
+        self.store_vector(id, vector)
+    }
+}
+
+impl<T: VectorIO + ?Sized> VectorIO for &mut T {
+    fn load_labels(&self, id: u128) -> Result<Labels, ErrorCode> {
+        (**self).load_labels(id)
+    }
+
+    fn load_vector(&self, id: u128) -> Result<Vector, ErrorCode> {
+        (**self).load_vector(id)
+    }
+
+    fn load_code(&self, id: u128) -> Result<Vec<u8>, ErrorCode> {
+        (**self).load_code(id)
+    }
+
+    fn store_labels(&mut self, id: u128, input: Labels) -> Result<(), ErrorCode> {
+        (**self).store_labels(id, input)
+    }
+
+    fn store_vector(&mut self, id: u128, input: Vector) -> Result<(), ErrorCode> {
+        (**self).store_vector(id, input)
+    }
+
+    fn load_labels_range(&self, id: u128, start: usize, len: usize) -> Result<Labels, ErrorCode> {
+        (**self).load_labels_range(id, start, len)
+    }
+
+    fn load_vector_range(&self, id: u128, start: usize, len: usize) -> Result<Vector, ErrorCode> {
+        (**self).load_vector_range(id, start, len)
+    }
+
+    fn store_vector_range(
+        &mut self,
+        id: u128,
+        start: usize,
+        input: Vector,
+    ) -> Result<(), ErrorCode> {
+        (**self).store_vector_range(id, start, input)
+    }
 }
 
 pub struct VectorVM<'vio, VIO>
@@ -24,28 +116,78 @@ where
     VIO: VectorIO,
 {
     vio: &'vio mut VIO,
+    meter: Meter,
+    frames: Vec<Frame>,
+    tracer: Option<&'vio mut dyn Tracer>,
+}
+
+/// Failure of instruction, or of sub-routine called by it
+enum Failure {
+    Error(ErrorCode),
+
+    /// Error of sub-routine, which already tells where it occurred
+    Subroutine(ProgramError),
+}
+
+impl From<ErrorCode> for Failure {
+    fn from(error_code: ErrorCode) -> Self {
+        Failure::Error(error_code)
+    }
 }
 
-enum Operand {
+/// Operand on the stack or in a register
+pub enum Operand {
     None,
     Labels(Labels),
     Vector(Vector),
     Scalar(Amount),
     Label(u128),
+    SignedVector(SignedVector),
+    SignedScalar(SignedAmount),
+    Sparse(SparseVector),
+}
+
+impl Operand {
+    fn is_signed(&self) -> bool {
+        matches!(self, Operand::SignedVector(_) | Operand::SignedScalar(_))
+    }
+
+    fn is_sparse(&self) -> bool {
+        matches!(self, Operand::Sparse(_))
+    }
+
+    pub fn operand_type(&self) -> OperandType {
+        match self {
+            Operand::None => OperandType::None,
+            Operand::Labels(_) => OperandType::Labels,
+            Operand::Vector(_) => OperandType::Vector,
+            Operand::Scalar(_) => OperandType::Scalar,
+            Operand::Label(_) => OperandType::Label,
+            Operand::SignedVector(_) => OperandType::SignedVector,
+            Operand::SignedScalar(_) => OperandType::SignedScalar,
+            Operand::Sparse(_) => OperandType::Sparse,
+        }
+    }
 }
 
 impl Clone for Operand {
     fn clone(&self) -> Self {
         match self {
             Operand::None => Operand::None,
-            Operand::Labels(x) => Operand::Labels(Labels {
-                data: x.data.clone(),
-            }),
+            Operand::Labels(x) => Operand::Labels(x.clone()),
             Operand::Vector(x) => Operand::Vector(Vector {
                 data: x.data.clone(),
             }),
             Operand::Scalar(x) => Operand::Scalar(x.clone()),
             Operand::Label(x) => Operand::Label(x.clone()),
+            Operand::SignedVector(x) => Operand::SignedVector(SignedVector {
+                data: x.data.clone(),
+            }),
+            Operand::SignedScalar(x) => Operand::SignedScalar(*x),
+            Operand::Sparse(x) => Operand::Sparse(SparseVector {
+                labels: x.labels.clone(),
+                data: x.data.clone(),
+            }),
         }
     }
 }
@@ -53,14 +195,25 @@ impl Clone for Operand {
 pub(crate) struct Stack {
     stack: Vec<Operand>,
     registry: Vec<Operand>,
+
+    /// Arguments bound to template program, which are not passed on to
+    /// sub-routines, and which program can use only once it declares them
+    /// with `ARGS`
+    args: Vec<u128>,
 }
 
 macro_rules! impl_abacus_binary_op {
     (
         $fn_name:ident,
         $checked_op:ident
+        $(, $signed_op:ident)?
     ) => {
         fn $fn_name(&mut self, pos: usize) -> Result<(), ErrorCode> {
+            $(
+                if self.stack.last().is_some_and(Operand::is_signed) {
+                    return self.signed_binary_op(pos, SignedAmount::$signed_op);
+                }
+            )?
             if pos == 0 {
                 let v1 = self
                     .stack
@@ -91,7 +244,7 @@ macro_rules! impl_abacus_binary_op {
                 match (v1, v2) {
                     (Operand::Vector(ref mut v1), Operand::Vector(ref v2)) => {
                         if v1.data.len() != v2.data.len() {
-                            Err(ErrorCode::NotAligned)?;
+                            Err(ErrorCode::NotAligned(v1.data.len(), v2.data.len()))?;
                         }
                         for (x1, x2) in v1.data.iter_mut().zip(v2.data.iter()) {
                             *x1 = x1.$checked_op(*x2).ok_or_else(|| ErrorCode::MathOverflow)?;
@@ -117,6 +270,92 @@ macro_rules! impl_abacus_binary_op {
     };
 }
 
+macro_rules! impl_abacus_mask_op {
+    (
+        $fn_name:ident,
+        $cmp_op:ident
+    ) => {
+        fn $fn_name(&mut self, pos: usize) -> Result<(), ErrorCode> {
+            let stack_index = self.get_stack_index(pos)?;
+            let (v1, rest) = self
+                .stack
+                .split_last_mut()
+                .ok_or(ErrorCode::StackUnderflow)?;
+            let v2 = rest.get(stack_index).ok_or(ErrorCode::OutOfRange)?;
+            match (v1, v2) {
+                (Operand::Vector(ref mut v1), Operand::Vector(ref v2)) => {
+                    if v1.data.len() != v2.data.len() {
+                        Err(ErrorCode::NotAligned(v1.data.len(), v2.data.len()))?;
+                    }
+                    for (x1, x2) in v1.data.iter_mut().zip(v2.data.iter()) {
+                        *x1 = mask((*x1).$cmp_op(x2));
+                    }
+                }
+                (Operand::Vector(ref mut v1), Operand::Scalar(ref x2)) => {
+                    for x1 in v1.data.iter_mut() {
+                        *x1 = mask((*x1).$cmp_op(x2));
+                    }
+                }
+                (Operand::Scalar(ref mut x1), Operand::Scalar(ref x2)) => {
+                    *x1 = mask((*x1).$cmp_op(x2));
+                }
+                _ => {
+                    Err(ErrorCode::InvalidOperand)?;
+                }
+            }
+            Ok(())
+        }
+    };
+}
+
+/// Mask component, i.e. 1 when condition holds, and 0 otherwise
+fn mask(condition: bool) -> Amount {
+    if condition {
+        Amount::ONE
+    } else {
+        Amount::ZERO
+    }
+}
+
+/// Component of a vector, or the scalar itself when scalar is broadcast
+fn component(operand: &Operand, index: usize) -> Amount {
+    match operand {
+        Operand::Vector(v) => v.data[index],
+        Operand::Scalar(x) => *x,
+        _ => Amount::ZERO,
+    }
+}
+
+/// Convert unsigned value into signed one
+fn to_signed(value: Amount) -> Result<SignedAmount, ErrorCode> {
+    SignedAmount::try_from_amount(value).ok_or(ErrorCode::MathOverflow)
+}
+
+/// Component of a vector as signed value, or the scalar itself when scalar
+/// is broadcast
+fn signed_component(operand: &Operand, index: usize) -> Result<SignedAmount, ErrorCode> {
+    match operand {
+        Operand::SignedVector(v) => Ok(v.data[index]),
+        Operand::SignedScalar(x) => Ok(*x),
+        Operand::Vector(v) => to_signed(v.data[index]),
+        Operand::Scalar(x) => to_signed(*x),
+        _ => Err(ErrorCode::InvalidOperand),
+    }
+}
+
+/// Check operand can be combined component-wise with vector of length `len`
+fn check_broadcast(operand: &Operand, len: usize) -> Result<(), ErrorCode> {
+    match operand {
+        Operand::Vector(v) if v.data.len() != len => Err(ErrorCode::NotAligned(v.data.len(), len)),
+        Operand::SignedVector(v) if v.data.len() != len => {
+            Err(ErrorCode::NotAligned(v.data.len(), len))
+        }
+        Operand::Vector(_) | Operand::Scalar(_) => Ok(()),
+        Operand::SignedVector(_) | Operand::SignedScalar(_) => Ok(()),
+        _ => Err(ErrorCode::InvalidOperand),
+    }
+}
+
 impl Stack {
     pub(crate) fn new(num_registers: usize) -> Self {
         let mut registry = Vec::new();
@@ -124,6 +363,7 @@ impl Stack {
         Self {
             stack: Vec::new(),
             registry,
+            args: Vec::new(),
         }
     }
 
@@ -131,6 +371,27 @@ impl Stack {
         self.stack.len()
     }
 
+    /// Types of operands on top of the stack, the last one is TOS
+    fn top_types(&self) -> [Option<OperandType>; MAX_ERROR_OPERANDS] {
+        let mut types = [None; MAX_ERROR_OPERANDS];
+        let start = self.stack.len().saturating_sub(MAX_ERROR_OPERANDS);
+        for (t, operand) in types.iter_mut().zip(&self.stack[start..]) {
+            *t = Some(operand.operand_type());
+        }
+        types
+    }
+
+    /// Number of components of the operand on top of the stack
+    fn top_len(&self) -> usize {
+        match self.stack.last() {
+            Some(Operand::Labels(x)) => x.len(),
+            Some(Operand::Vector(x)) => x.data.len(),
+            Some(Operand::SignedVector(x)) => x.data.len(),
+            Some(Operand::Sparse(x)) => x.data.len(),
+            _ => 0,
+        }
+    }
+
     fn push(&mut self, operand: Operand) {
         self.stack.push(operand);
     }
@@ -221,7 +482,7 @@ impl Stack {
         for v in self.stack.drain(pos..) {
             match v {
                 Operand::Label(x) => {
-                    res.data.push(x);
+                    res.push(x);
                 }
                 _ => Err(ErrorCode::InvalidOperand)?,
             }
@@ -240,7 +501,7 @@ impl Stack {
                 }
             }
             Operand::Labels(v) => {
-                for x in v.data {
+                for x in Vec::from(v) {
                     exp.push(Operand::Label(x));
                 }
             }
@@ -291,32 +552,424 @@ impl Stack {
         Ok(())
     }
 
-    impl_abacus_binary_op!(add, checked_add);
-    impl_abacus_binary_op!(sub, checked_sub);
+    impl_abacus_binary_op!(dense_add, checked_add, checked_add);
+    impl_abacus_binary_op!(sub, checked_sub, checked_sub);
     impl_abacus_binary_op!(ssb, saturating_sub);
-    impl_abacus_binary_op!(mul, checked_mul);
-    impl_abacus_binary_op!(div, checked_div);
+    impl_abacus_binary_op!(dense_mul, checked_mul, checked_mul);
+    impl_abacus_binary_op!(div, checked_div, checked_div);
+    impl_abacus_binary_op!(pow, checked_pow);
 
-    fn sqrt(&mut self) -> Result<(), ErrorCode> {
-        let v1 = self
+    fn add(&mut self, pos: usize) -> Result<(), ErrorCode> {
+        if self.stack.last().is_some_and(Operand::is_sparse) {
+            return self.sparse_binary_op(pos, SparseVector::checked_add, None);
+        }
+        self.dense_add(pos)
+    }
+
+    fn mul(&mut self, pos: usize) -> Result<(), ErrorCode> {
+        if self.stack.last().is_some_and(Operand::is_sparse) {
+            return self.sparse_binary_op(
+                pos,
+                SparseVector::checked_mul,
+                Some(Amount::checked_mul),
+            );
+        }
+        self.dense_mul(pos)
+    }
+
+    /// Apply binary operation with explicit rounding to unsigned dense TOS
+    ///
+    /// Operand at [T-pos] is either vector of same length or scalar, and
+    /// [T-0] is used when `pos` is zero.
+    fn rounded_binary_op(
+        &mut self,
+        pos: usize,
+        rounding: Rounding,
+        op: fn(Amount, Amount, Rounding) -> Option<Amount>,
+    ) -> Result<(), ErrorCode> {
+        let apply = |x1: &mut Amount, x2| -> Result<(), ErrorCode> {
+            *x1 = op(*x1, x2, rounding).ok_or(ErrorCode::MathOverflow)?;
+            Ok(())
+        };
+        let stack_index = self.get_stack_index(pos)?;
+        let (v1, rest) = self
             .stack
-            .last_mut()
-            .ok_or_else(|| ErrorCode::StackUnderflow)?;
+            .split_last_mut()
+            .ok_or(ErrorCode::StackUnderflow)?;
+        let self_operand;
+        let v2 = if pos == 0 {
+            self_operand = v1.clone();
+            &self_operand
+        } else {
+            rest.get(stack_index).ok_or(ErrorCode::OutOfRange)?
+        };
+        match (v1, v2) {
+            (Operand::Vector(v1), Operand::Vector(v2)) => {
+                if v1.data.len() != v2.data.len() {
+                    Err(ErrorCode::NotAligned(v1.data.len(), v2.data.len()))?;
+                }
+                for (x1, x2) in v1.data.iter_mut().zip(v2.data.iter()) {
+                    apply(x1, *x2)?;
+                }
+            }
+            (Operand::Vector(v1), Operand::Scalar(x2)) => {
+                for x1 in v1.data.iter_mut() {
+                    apply(x1, *x2)?;
+                }
+            }
+            (Operand::Scalar(x1), Operand::Scalar(x2)) => apply(x1, *x2)?,
+            _ => Err(ErrorCode::InvalidOperand)?,
+        }
+        Ok(())
+    }
+
+    /// Apply binary operation to sparse operand on TOS
+    ///
+    /// Operand at [T-pos] can be either sparse, which is combined with TOS
+    /// using `merge`, or scalar, which is combined with each stored value
+    /// using `scalar_op` if operation keeps zeros as zeros.
+    fn sparse_binary_op(
+        &mut self,
+        pos: usize,
+        merge: fn(&SparseVector, &SparseVector) -> Option<SparseVector>,
+        scalar_op: Option<fn(Amount, Amount) -> Option<Amount>>,
+    ) -> Result<(), ErrorCode> {
+        let stack_index = self.get_stack_index(pos)?;
+        let (v1, rest) = self
+            .stack
+            .split_last_mut()
+            .ok_or(ErrorCode::StackUnderflow)?;
+        let self_operand;
+        let v2 = if pos == 0 {
+            self_operand = v1.clone();
+            &self_operand
+        } else {
+            rest.get(stack_index).ok_or(ErrorCode::OutOfRange)?
+        };
+        let Operand::Sparse(ref mut s1) = v1 else {
+            return Err(ErrorCode::InvalidOperand);
+        };
+        match (v2, scalar_op) {
+            (Operand::Sparse(s2), _) => {
+                *s1 = merge(s1, s2).ok_or(ErrorCode::MathOverflow)?;
+            }
+            (Operand::Scalar(x2), Some(op)) => {
+                for x1 in s1.data.iter_mut() {
+                    *x1 = op(*x1, *x2).ok_or(ErrorCode::MathOverflow)?;
+                }
+            }
+            _ => Err(ErrorCode::InvalidOperand)?,
+        }
+        Ok(())
+    }
+
+    /// Apply binary operation to signed operand on TOS
+    ///
+    /// Operand at [T-pos] can be either signed or unsigned.
+    fn signed_binary_op(
+        &mut self,
+        pos: usize,
+        op: fn(SignedAmount, SignedAmount) -> Option<SignedAmount>,
+    ) -> Result<(), ErrorCode> {
+        let apply = |x1: &mut SignedAmount, x2| -> Result<(), ErrorCode> {
+            *x1 = op(*x1, x2).ok_or(ErrorCode::MathOverflow)?;
+            Ok(())
+        };
+        let stack_index = self.get_stack_index(pos)?;
+        let (v1, rest) = self
+            .stack
+            .split_last_mut()
+            .ok_or(ErrorCode::StackUnderflow)?;
+        let self_operand;
+        let v2 = if pos == 0 {
+            self_operand = v1.clone();
+            &self_operand
+        } else {
+            rest.get(stack_index).ok_or(ErrorCode::OutOfRange)?
+        };
+        match v1 {
+            Operand::SignedVector(ref mut v1) => {
+                check_broadcast(v2, v1.data.len())?;
+                for (i, x1) in v1.data.iter_mut().enumerate() {
+                    apply(x1, signed_component(v2, i)?)?;
+                }
+            }
+            Operand::SignedScalar(ref mut x1) => match v2 {
+                Operand::Scalar(_) | Operand::SignedScalar(_) => {
+                    apply(x1, signed_component(v2, 0)?)?;
+                }
+                _ => Err(ErrorCode::InvalidOperand)?,
+            },
+            _ => Err(ErrorCode::InvalidOperand)?,
+        }
+        Ok(())
+    }
+
+    fn neg(&mut self) -> Result<(), ErrorCode> {
+        let v = self.stack.last_mut().ok_or(ErrorCode::StackUnderflow)?;
+        let negate = |x: SignedAmount| x.checked_neg().ok_or(ErrorCode::MathOverflow);
+        *v = match v {
+            Operand::Vector(v) => Operand::SignedVector(SignedVector {
+                data: v
+                    .data
+                    .iter()
+                    .map(|x| negate(to_signed(*x)?))
+                    .collect::<Result<_, _>>()?,
+            }),
+            Operand::Scalar(x) => Operand::SignedScalar(negate(to_signed(*x)?)?),
+            Operand::SignedVector(v) => Operand::SignedVector(SignedVector {
+                data: v
+                    .data
+                    .iter()
+                    .map(|x| negate(*x))
+                    .collect::<Result<_, _>>()?,
+            }),
+            Operand::SignedScalar(x) => Operand::SignedScalar(negate(*x)?),
+            _ => Err(ErrorCode::InvalidOperand)?,
+        };
+        Ok(())
+    }
+
+    /// Replace signed operand on TOS with unsigned one using `part`
+    fn unsigned_part(&mut self, part: fn(&SignedAmount) -> Amount) -> Result<(), ErrorCode> {
+        let v = self.stack.last_mut().ok_or(ErrorCode::StackUnderflow)?;
+        *v = match v {
+            Operand::SignedVector(v) => Operand::Vector(Vector {
+                data: v.data.iter().map(part).collect(),
+            }),
+            Operand::SignedScalar(x) => Operand::Scalar(part(x)),
+            _ => Err(ErrorCode::InvalidOperand)?,
+        };
+        Ok(())
+    }
+
+    fn abs(&mut self) -> Result<(), ErrorCode> {
+        self.unsigned_part(SignedAmount::abs)
+    }
+
+    fn pos(&mut self) -> Result<(), ErrorCode> {
+        self.unsigned_part(SignedAmount::positive_part)
+    }
+
+    fn negpart(&mut self) -> Result<(), ErrorCode> {
+        self.unsigned_part(SignedAmount::negative_part)
+    }
+
+    fn net(&mut self, pos: usize) -> Result<(), ErrorCode> {
+        let stack_index = self.get_stack_index(pos)?;
+        let (v1, rest) = self
+            .stack
+            .split_last_mut()
+            .ok_or(ErrorCode::StackUnderflow)?;
+        let v2 = rest.get(stack_index).ok_or(ErrorCode::OutOfRange)?;
+        *v1 = match (&v1, v2) {
+            (Operand::Vector(long), Operand::Vector(short)) => {
+                if long.data.len() != short.data.len() {
+                    Err(ErrorCode::NotAligned(long.data.len(), short.data.len()))?;
+                }
+                Operand::SignedVector(
+                    SignedVector::from_parts(long, short).ok_or(ErrorCode::MathOverflow)?,
+                )
+            }
+            (Operand::Scalar(long), Operand::Scalar(short)) => Operand::SignedScalar(
+                SignedAmount::from_parts(*long, *short).ok_or(ErrorCode::MathOverflow)?,
+            ),
+            _ => Err(ErrorCode::InvalidOperand)?,
+        };
+        Ok(())
+    }
+
+    fn spk(&mut self, pos: usize) -> Result<(), ErrorCode> {
+        let stack_index = self.get_stack_index(pos)?;
+        let (v1, rest) = self
+            .stack
+            .split_last_mut()
+            .ok_or(ErrorCode::StackUnderflow)?;
+        let labels = rest.get(stack_index).ok_or(ErrorCode::OutOfRange)?;
+        *v1 = match (&v1, labels) {
+            (Operand::Vector(values), Operand::Labels(labels)) => {
+                if values.data.len() != labels.len() {
+                    Err(ErrorCode::NotAligned(values.data.len(), labels.len()))?;
+                }
+                let labels = labels.clone();
+                let values = Vector {
+                    data: values.data.clone(),
+                };
+                Operand::Sparse(
+                    SparseVector::from_parts(labels, values).ok_or(ErrorCode::InvalidOperand)?,
+                )
+            }
+            _ => Err(ErrorCode::InvalidOperand)?,
+        };
+        Ok(())
+    }
+
+    fn sunpk(&mut self) -> Result<(), ErrorCode> {
+        let v = self.stack.pop().ok_or(ErrorCode::StackUnderflow)?;
+        match v {
+            Operand::Sparse(sparse) => {
+                let (labels, values) = sparse.into_parts();
+                self.stack.push(Operand::Labels(labels));
+                self.stack.push(Operand::Vector(values));
+            }
+            _ => Err(ErrorCode::InvalidOperand)?,
+        }
+        Ok(())
+    }
+
+    fn sget(&mut self, pos_vector: usize, pos_labels: usize) -> Result<(), ErrorCode> {
+        let stack_index_vector = self.get_stack_index(pos_vector)?;
+        let stack_index_labels = self.get_stack_index(pos_labels)?;
+        let (v1, rest) = self
+            .stack
+            .split_last_mut()
+            .ok_or(ErrorCode::StackUnderflow)?;
+        let vector = rest.get(stack_index_vector).ok_or(ErrorCode::OutOfRange)?;
+        let labels = rest.get(stack_index_labels).ok_or(ErrorCode::OutOfRange)?;
+        *v1 = match (&v1, vector, labels) {
+            (Operand::Labels(keys), Operand::Vector(vector), Operand::Labels(labels)) => {
+                if vector.data.len() != labels.len() {
+                    Err(ErrorCode::NotAligned(vector.data.len(), labels.len()))?;
+                }
+                Operand::Sparse(
+                    SparseVector::gather(keys, labels, vector).ok_or(ErrorCode::NotFound)?,
+                )
+            }
+            _ => Err(ErrorCode::InvalidOperand)?,
+        };
+        Ok(())
+    }
+
+    /// Combine values of sparse operand at [T-pos_sparse] into vector on TOS
+    /// at positions of their labels within labels at [T-pos_labels]
+    fn scatter(
+        &mut self,
+        pos_sparse: usize,
+        pos_labels: usize,
+        op: fn(Amount, Amount) -> Option<Amount>,
+    ) -> Result<(), ErrorCode> {
+        let stack_index_sparse = self.get_stack_index(pos_sparse)?;
+        let stack_index_labels = self.get_stack_index(pos_labels)?;
+        let (v1, rest) = self
+            .stack
+            .split_last_mut()
+            .ok_or(ErrorCode::StackUnderflow)?;
+        let sparse = rest.get(stack_index_sparse).ok_or(ErrorCode::OutOfRange)?;
+        let labels = rest.get(stack_index_labels).ok_or(ErrorCode::OutOfRange)?;
+        match (v1, sparse, labels) {
+            (Operand::Vector(v1), Operand::Sparse(sparse), Operand::Labels(labels)) => {
+                if v1.data.len() != labels.len() {
+                    Err(ErrorCode::NotAligned(v1.data.len(), labels.len()))?;
+                }
+                let positions = sparse.positions(labels).ok_or(ErrorCode::NotFound)?;
+                for (index, x2) in positions.into_iter().zip(sparse.data.iter()) {
+                    let x1 = &mut v1.data[index];
+                    *x1 = op(*x1, *x2).ok_or(ErrorCode::MathOverflow)?;
+                }
+            }
+            _ => Err(ErrorCode::InvalidOperand)?,
+        }
+        Ok(())
+    }
+
+    fn sadd(&mut self, pos_sparse: usize, pos_labels: usize) -> Result<(), ErrorCode> {
+        self.scatter(pos_sparse, pos_labels, Amount::checked_add)
+    }
+
+    fn supd(&mut self, pos_sparse: usize, pos_labels: usize) -> Result<(), ErrorCode> {
+        self.scatter(pos_sparse, pos_labels, |_, x2| Some(x2))
+    }
+
+    impl_abacus_mask_op!(eq, eq);
+    impl_abacus_mask_op!(gt, gt);
+    impl_abacus_mask_op!(lt, lt);
+
+    fn clamp(&mut self, pos_lo: usize, pos_hi: usize) -> Result<(), ErrorCode> {
+        let stack_index_lo = self.get_stack_index(pos_lo)?;
+        let stack_index_hi = self.get_stack_index(pos_hi)?;
+        let (v1, rest) = self
+            .stack
+            .split_last_mut()
+            .ok_or(ErrorCode::StackUnderflow)?;
+        let lo = rest.get(stack_index_lo).ok_or(ErrorCode::OutOfRange)?;
+        let hi = rest.get(stack_index_hi).ok_or(ErrorCode::OutOfRange)?;
+        match (v1, lo, hi) {
+            (Operand::Vector(ref mut v1), lo, hi) => {
+                check_broadcast(lo, v1.data.len())?;
+                check_broadcast(hi, v1.data.len())?;
+                for (i, x1) in v1.data.iter_mut().enumerate() {
+                    *x1 = (*x1).max(component(lo, i)).min(component(hi, i));
+                }
+            }
+            (Operand::Scalar(ref mut x1), Operand::Scalar(lo), Operand::Scalar(hi)) => {
+                *x1 = (*x1).max(*lo).min(*hi);
+            }
+            _ => {
+                Err(ErrorCode::InvalidOperand)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn sel(&mut self, pos_mask: usize, pos_other: usize) -> Result<(), ErrorCode> {
+        let stack_index_mask = self.get_stack_index(pos_mask)?;
+        let stack_index_other = self.get_stack_index(pos_other)?;
+        let (v1, rest) = self
+            .stack
+            .split_last_mut()
+            .ok_or(ErrorCode::StackUnderflow)?;
+        let mask = rest.get(stack_index_mask).ok_or(ErrorCode::OutOfRange)?;
+        let other = rest.get(stack_index_other).ok_or(ErrorCode::OutOfRange)?;
+        match (v1, mask, other) {
+            (Operand::Vector(ref mut v1), mask, other) => {
+                check_broadcast(mask, v1.data.len())?;
+                check_broadcast(other, v1.data.len())?;
+                for (i, x1) in v1.data.iter_mut().enumerate() {
+                    if component(mask, i).is_zero() {
+                        *x1 = component(other, i);
+                    }
+                }
+            }
+            (Operand::Scalar(ref mut x1), Operand::Scalar(mask), Operand::Scalar(other)) => {
+                if mask.is_zero() {
+                    *x1 = *other;
+                }
+            }
+            _ => {
+                Err(ErrorCode::InvalidOperand)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn unary_op(&mut self, op: fn(Amount) -> Option<Amount>) -> Result<(), ErrorCode> {
+        let v1 = self.stack.last_mut().ok_or(ErrorCode::StackUnderflow)?;
         match v1 {
             Operand::Vector(ref mut v1) => {
-                for i in 0..v1.data.len() {
-                    let x = &mut v1.data[i];
-                    *x = x.checked_sqrt().ok_or_else(|| ErrorCode::MathOverflow)?;
+                for x in v1.data.iter_mut() {
+                    *x = op(*x).ok_or(ErrorCode::MathOverflow)?;
                 }
             }
             Operand::Scalar(ref mut x) => {
-                *x = x.checked_sqrt().ok_or_else(|| ErrorCode::MathOverflow)?;
+                *x = op(*x).ok_or(ErrorCode::MathOverflow)?;
             }
             _ => return Err(ErrorCode::InvalidOperand),
         }
         Ok(())
     }
 
+    fn sqrt(&mut self) -> Result<(), ErrorCode> {
+        self.unary_op(Amount::checked_sqrt)
+    }
+
+    fn exp(&mut self) -> Result<(), ErrorCode> {
+        self.unary_op(Amount::checked_exp)
+    }
+
+    fn ln(&mut self) -> Result<(), ErrorCode> {
+        self.unary_op(Amount::checked_ln)
+    }
+
     fn vsum(&mut self) -> Result<(), ErrorCode> {
         let v = self.stack.pop().ok_or_else(|| ErrorCode::StackUnderflow)?;
         match v {
@@ -328,6 +981,13 @@ impl Stack {
                 }
                 self.stack.push(Operand::Scalar(s));
             }
+            Operand::SignedVector(ref v) => {
+                let mut s = SignedAmount::ZERO;
+                for x in &v.data {
+                    s = s.checked_add(*x).ok_or(ErrorCode::MathOverflow)?;
+                }
+                self.stack.push(Operand::SignedScalar(s));
+            }
             _ => {
                 Err(ErrorCode::InvalidOperand)?;
             }
@@ -371,6 +1031,65 @@ impl Stack {
         Ok(())
     }
 
+    fn vdot(&mut self, pos: usize) -> Result<(), ErrorCode> {
+        let stack_index = self.get_stack_index(pos)?;
+        let (v1, rest) = self
+            .stack
+            .split_last_mut()
+            .ok_or(ErrorCode::StackUnderflow)?;
+        let v2 = if pos == 0 {
+            &*v1
+        } else {
+            rest.get(stack_index).ok_or(ErrorCode::OutOfRange)?
+        };
+        let s = match (&*v1, v2) {
+            (Operand::Vector(a), Operand::Vector(b)) => {
+                if a.data.len() != b.data.len() {
+                    Err(ErrorCode::NotAligned(a.data.len(), b.data.len()))?;
+                }
+                Amount::checked_dot(&a.data, &b.data).ok_or(ErrorCode::MathOverflow)?
+            }
+            _ => Err(ErrorCode::InvalidOperand)?,
+        };
+        *v1 = Operand::Scalar(s);
+        Ok(())
+    }
+
+    fn vcumsum(&mut self) -> Result<(), ErrorCode> {
+        let v = self.stack.last_mut().ok_or(ErrorCode::StackUnderflow)?;
+        match v {
+            Operand::Vector(ref mut v) => {
+                let mut s = Amount::ZERO;
+                for x in v.data.iter_mut() {
+                    s = s.checked_add(*x).ok_or(ErrorCode::MathOverflow)?;
+                    *x = s;
+                }
+            }
+            _ => Err(ErrorCode::InvalidOperand)?,
+        }
+        Ok(())
+    }
+
+    fn vnorm(&mut self) -> Result<(), ErrorCode> {
+        let v = self.stack.last_mut().ok_or(ErrorCode::StackUnderflow)?;
+        match v {
+            Operand::Vector(ref mut v) => {
+                let mut s = Amount::ZERO;
+                for x in &v.data {
+                    s = s.checked_add(*x).ok_or(ErrorCode::MathOverflow)?;
+                }
+                if s.is_zero() {
+                    Err(ErrorCode::MathOverflow)?;
+                }
+                for x in v.data.iter_mut() {
+                    *x = x.checked_div(s).ok_or(ErrorCode::MathOverflow)?;
+                }
+            }
+            _ => Err(ErrorCode::InvalidOperand)?,
+        }
+        Ok(())
+    }
+
     fn min(&mut self, pos: usize) -> Result<(), ErrorCode> {
         let stack_index = self.get_stack_index(pos)?;
         let (v1, rest) = self
@@ -381,7 +1100,7 @@ impl Stack {
         match (v1, v2) {
             (Operand::Vector(ref mut v1), Operand::Vector(ref v2)) => {
                 if v1.data.len() != v2.data.len() {
-                    Err(ErrorCode::NotAligned)?;
+                    Err(ErrorCode::NotAligned(v1.data.len(), v2.data.len()))?;
                 }
                 for i in 0..v1.data.len() {
                     let x1 = &mut v1.data[i];
@@ -409,7 +1128,7 @@ impl Stack {
         match (v1, v2) {
             (Operand::Vector(ref mut v1), Operand::Vector(ref v2)) => {
                 if v1.data.len() != v2.data.len() {
-                    Err(ErrorCode::NotAligned)?;
+                    Err(ErrorCode::NotAligned(v1.data.len(), v2.data.len()))?;
                 }
                 for i in 0..v1.data.len() {
                     let x1 = &mut v1.data[i];
@@ -427,13 +1146,48 @@ impl Stack {
         Ok(())
     }
 
+    fn assert_zero(&mut self, assert_id: u128) -> Result<(), ErrorCode> {
+        let v = self.stack.pop().ok_or(ErrorCode::StackUnderflow)?;
+        let is_zero = match v {
+            Operand::Vector(ref v) => v.data.iter().all(|x| x.is_zero()),
+            Operand::Scalar(ref x) => x.is_zero(),
+            _ => Err(ErrorCode::InvalidOperand)?,
+        };
+        if !is_zero {
+            Err(ErrorCode::AssertionFailed(assert_id))?;
+        }
+        Ok(())
+    }
+
+    fn assert_le(&mut self, pos: usize, assert_id: u128) -> Result<(), ErrorCode> {
+        let stack_index = self.get_stack_index(pos)?;
+        let (v1, rest) = self.stack.split_last().ok_or(ErrorCode::StackUnderflow)?;
+        let v2 = rest.get(stack_index).ok_or(ErrorCode::OutOfRange)?;
+        let is_le = match (v1, v2) {
+            (Operand::Vector(v1), Operand::Vector(v2)) => {
+                if v1.data.len() != v2.data.len() {
+                    Err(ErrorCode::NotAligned(v1.data.len(), v2.data.len()))?;
+                }
+                v1.data.iter().zip(v2.data.iter()).all(|(x1, x2)| x1 <= x2)
+            }
+            (Operand::Vector(v1), Operand::Scalar(x2)) => v1.data.iter().all(|x1| x1 <= x2),
+            (Operand::Scalar(x1), Operand::Scalar(x2)) => x1 <= x2,
+            _ => Err(ErrorCode::InvalidOperand)?,
+        };
+        if !is_le {
+            Err(ErrorCode::AssertionFailed(assert_id))?;
+        }
+        self.stack.pop();
+        Ok(())
+    }
+
     fn zeros(&mut self, pos: usize) -> Result<(), ErrorCode> {
         let stack_index = self.get_stack_index(pos)?;
         let labels = self.stack.get(stack_index).ok_or(ErrorCode::OutOfRange)?;
 
         let num_cols = match labels {
             Operand::Vector(v) => v.data.len(),
-            Operand::Labels(l) => l.data.len(),
+            Operand::Labels(l) => l.len(),
             _ => return Err(ErrorCode::InvalidOperand), // Must be a Labels operand
         };
 
@@ -450,7 +1204,7 @@ impl Stack {
 
         let num_cols = match labels {
             Operand::Vector(v) => v.data.len(),
-            Operand::Labels(l) => l.data.len(),
+            Operand::Labels(l) => l.len(),
             _ => return Err(ErrorCode::InvalidOperand), // Must be a Labels operand
         };
 
@@ -492,7 +1246,7 @@ impl Stack {
             .ok_or_else(|| ErrorCode::StackUnderflow)?;
         match v {
             Operand::Labels(ref mut v) => {
-                v.data.push(value);
+                v.push(value);
             }
             _ => Err(ErrorCode::InvalidOperand)?,
         }
@@ -521,7 +1275,7 @@ impl Stack {
             .ok_or_else(|| ErrorCode::StackUnderflow)?;
         match v {
             Operand::Labels(ref mut v) => {
-                let val = v.data.pop().ok_or_else(|| ErrorCode::OutOfRange)?;
+                let val = v.pop().ok_or_else(|| ErrorCode::OutOfRange)?;
                 self.stack.push(Operand::Label(val));
             }
             _ => Err(ErrorCode::InvalidOperand)?,
@@ -557,14 +1311,18 @@ impl Stack {
 
         match (v1, v2) {
             (Operand::Labels(labels_a), Operand::Labels(labels_b)) => {
+                if let Some(union) = labels_a.union_sorted(labels_b) {
+                    *labels_a = union;
+                    return Ok(());
+                }
                 let mut result = Vec::new();
                 let mut j = 0;
-                for i in 0..labels_a.data.len() {
-                    let label_a = labels_a.data[i];
+                for i in 0..labels_a.len() {
+                    let label_a = labels_a[i];
                     let mut updated = false;
-                    while j < labels_b.data.len() {
+                    while j < labels_b.len() {
                         updated = true;
-                        let label_b = labels_b.data[j];
+                        let label_b = labels_b[j];
                         if label_b < label_a {
                             result.push(label_b);
                             updated = false;
@@ -583,10 +1341,10 @@ impl Stack {
                         result.push(label_a);
                     }
                 }
-                if j < labels_b.data.len() {
-                    result.extend(labels_b.data[j..].iter());
+                if j < labels_b.len() {
+                    result.extend(labels_b[j..].iter());
                 }
-                labels_a.data = result;
+                *labels_a.data_mut() = result;
             }
             _ => Err(ErrorCode::InvalidOperand)?,
         }
@@ -627,23 +1385,17 @@ impl Stack {
                 Operand::Labels(labels_a),
                 Operand::Labels(labels_b),
             ) => {
-                if v1.data.len() != labels_a.data.len() {
-                    Err(ErrorCode::NotAligned)?;
+                if v1.data.len() != labels_a.len() {
+                    Err(ErrorCode::NotAligned(v1.data.len(), labels_a.len()))?;
                 }
-                if v2.data.len() != labels_b.data.len() {
-                    Err(ErrorCode::NotAligned)?;
+                if v2.data.len() != labels_b.len() {
+                    Err(ErrorCode::NotAligned(v2.data.len(), labels_b.len()))?;
                 }
-                let mut i = 0;
-                for j in 0..labels_b.data.len() {
-                    if i < labels_a.data.len() {
-                        if labels_a.data[i] != labels_b.data[j] {
-                            i += labels_a.data[i..]
-                                .binary_search(&labels_b.data[j])
-                                .map_err(|_| ErrorCode::MathUnderflow)?;
-                        }
-                        v1.data[i] = v2.data[j];
-                        i += 1;
-                    }
+                let positions = labels_a
+                    .positions_of(labels_b)
+                    .ok_or(ErrorCode::MathUnderflow)?;
+                for (i, x2) in positions.into_iter().zip(v2.data.iter()) {
+                    v1.data[i] = *x2;
                 }
             }
             _ => Err(ErrorCode::InvalidOperand)?,
@@ -686,26 +1438,18 @@ impl Stack {
                 Operand::Labels(labels_a),
                 Operand::Labels(labels_b),
             ) => {
-                if v1.data.len() != labels_a.data.len() {
-                    Err(ErrorCode::NotAligned)?;
+                if v1.data.len() != labels_a.len() {
+                    Err(ErrorCode::NotAligned(v1.data.len(), labels_a.len()))?;
                 }
-                if v2.data.len() != labels_b.data.len() {
-                    Err(ErrorCode::NotAligned)?;
+                if v2.data.len() != labels_b.len() {
+                    Err(ErrorCode::NotAligned(v2.data.len(), labels_b.len()))?;
                 }
-                let mut i = 0;
-                for j in 0..labels_b.data.len() {
-                    if i < labels_a.data.len() {
-                        if labels_a.data[i] != labels_b.data[j] {
-                            i += labels_a.data[i..]
-                                .binary_search(&labels_b.data[j])
-                                .map_err(|_| ErrorCode::MathUnderflow)?;
-                        }
-                        let x1 = &mut v1.data[i];
-                        *x1 = x1
-                            .checked_add(v2.data[j])
-                            .ok_or_else(|| ErrorCode::MathOverflow)?;
-                        i += 1;
-                    }
+                let positions = labels_a
+                    .positions_of(labels_b)
+                    .ok_or(ErrorCode::MathUnderflow)?;
+                for (i, x2) in positions.into_iter().zip(v2.data.iter()) {
+                    let x1 = &mut v1.data[i];
+                    *x1 = x1.checked_add(*x2).ok_or(ErrorCode::MathOverflow)?;
                 }
             }
             _ => Err(ErrorCode::InvalidOperand)?,
@@ -734,23 +1478,13 @@ impl Stack {
 
         match (v1, labels_a, labels_b) {
             (Operand::Vector(v1), Operand::Labels(labels_a), Operand::Labels(labels_b)) => {
-                if v1.data.len() != labels_a.data.len() {
-                    Err(ErrorCode::NotAligned)?;
-                }
-                let mut result = Vec::with_capacity(labels_b.data.len());
-                let mut i = 0;
-                for j in 0..labels_b.data.len() {
-                    if i < labels_a.data.len() {
-                        if labels_a.data[i] != labels_b.data[j] {
-                            i += labels_a.data[i..]
-                                .binary_search(&labels_b.data[j])
-                                .map_err(|_| ErrorCode::MathUnderflow)?;
-                        }
-                        result.push(v1.data[i]);
-                        i += 1;
-                    }
+                if v1.data.len() != labels_a.len() {
+                    Err(ErrorCode::NotAligned(v1.data.len(), labels_a.len()))?;
                 }
-                v1.data = result;
+                let positions = labels_a
+                    .positions_of(labels_b)
+                    .ok_or(ErrorCode::MathUnderflow)?;
+                v1.data = positions.into_iter().map(|i| v1.data[i]).collect();
             }
             _ => Err(ErrorCode::InvalidOperand)?,
         }
@@ -772,6 +1506,9 @@ pub(crate) fn log_stack_fun(stack: &Stack) {
                 Operand::Vector(vector) => format!("Vector: {:0.5}", *vector),
                 Operand::Scalar(amount) => format!("Scalar: {:0.5}", *amount),
                 Operand::Label(label) => format!("Label: {}", label),
+                Operand::SignedVector(vector) => format!("SignedVector: {:0.5}", *vector),
+                Operand::SignedScalar(amount) => format!("SignedScalar: {:0.5}", *amount),
+                Operand::Sparse(sparse) => format!("Sparse: {:0.5}", *sparse),
             }
         );
     }
@@ -787,6 +1524,9 @@ pub(crate) fn log_stack_fun(stack: &Stack) {
                 Operand::Vector(vector) => format!("Vector: {:0.5}", *vector),
                 Operand::Scalar(amount) => format!("Scalar: {:0.5}", *amount),
                 Operand::Label(label) => format!("Label: {}", label),
+                Operand::SignedVector(vector) => format!("SignedVector: {:0.5}", *vector),
+                Operand::SignedScalar(amount) => format!("SignedScalar: {:0.5}", *amount),
+                Operand::Sparse(sparse) => format!("Sparse: {:0.5}", *sparse),
             }
         );
     }
@@ -796,50 +1536,7 @@ pub(crate) fn log_stack_fun(stack: &Stack) {
 
 #[cfg(test)]
 pub(crate) fn _op_code_str_fun(op_code: u8) -> &'static str {
-    match op_code {
-        OP_LDL => "LDL",
-        OP_LDV => "LDV",
-        OP_STL => "STL",
-        OP_STV => "STV",
-        OP_LDD => "LDD",
-        OP_LDR => "LDR",
-        OP_LDM => "LDM",
-        OP_STR => "STR",
-        OP_PKV => "PKV",
-        OP_PKL => "PKL",
-        OP_UNPK => "UNPK",
-        OP_T => "T",
-        OP_ADD => "ADD",
-        OP_SUB => "SUB",
-        OP_SSB => "SSB",
-        OP_MUL => "MUL",
-        OP_DIV => "DIV",
-        OP_SQRT => "SQRT",
-        OP_VSUM => "VSUM",
-        OP_MIN => "MIN",
-        OP_MAX => "MAX",
-        OP_LUNION => "LUNION",
-        OP_ZEROS => "ZEROS",
-        OP_ONES => "ONES",
-        OP_IMMS => "IMMS",
-        OP_IMML => "IMML",
-        OP_VMIN => "VMIN",
-        OP_VMAX => "VMAX",
-        OP_VPUSH => "VPUSH",
-        OP_LPUSH => "LPUSH",
-        OP_VPOP => "VPOP",
-        OP_LPOP => "LPOP",
-        OP_POPN => "POPN",
-        OP_SWAP => "SWAP",
-        OP_JUPD => "JUPD",
-        OP_JADD => "JADD",
-        OP_JFLT => "JFLT",
-        OP_B => "B",
-        OP_FOLD => "FOLD",
-        _ => {
-            panic!("Unknown op-code");
-        }
-    }
+    crate::decoder::mnemonic(op_code).unwrap_or("???")
 }
 
 #[cfg(not(test))]
@@ -872,12 +1569,56 @@ macro_rules! op_code_str {
     };
 }
 
+#[inline]
+fn fetch_u8(code: &[u8], pc: &mut usize) -> Result<usize, ErrorCode> {
+    let value = code.get(*pc).ok_or(ErrorCode::TruncatedInstruction)?;
+    *pc += 1;
+    Ok(*value as usize)
+}
+
+#[inline]
+fn fetch_u128(code: &[u8], pc: &mut usize, encoding: Encoding) -> Result<u128, ErrorCode> {
+    let (value, len) = encoding.read(code, *pc)?;
+    *pc += len;
+    Ok(value)
+}
+
+#[inline]
+fn fetch_usize(code: &[u8], pc: &mut usize, encoding: Encoding) -> Result<usize, ErrorCode> {
+    usize::try_from(fetch_u128(code, pc, encoding)?).map_err(|_| ErrorCode::OutOfRange)
+}
+
+/// Argument at index `arg` of those declared by `ARGS`
+#[inline]
+fn declared_arg(args: &[u128], arg: usize) -> Result<u128, ErrorCode> {
+    args.get(arg).copied().ok_or(ErrorCode::OutOfRange)
+}
+
 impl<'vio, VIO> VectorVM<'vio, VIO>
 where
     VIO: VectorIO,
 {
     pub fn new(vio: &'vio mut VIO) -> Self {
-        Self { vio }
+        Self::with_limits(vio, ExecutionLimits::UNLIMITED)
+    }
+
+    pub fn with_limits(vio: &'vio mut VIO, limits: ExecutionLimits) -> Self {
+        Self {
+            vio,
+            meter: Meter::new(limits),
+            frames: Vec::new(),
+            tracer: None,
+        }
+    }
+
+    /// Trace every instruction executed from now on
+    pub fn set_tracer(&mut self, tracer: &'vio mut dyn Tracer) {
+        self.tracer = Some(tracer);
+    }
+
+    /// Resources consumed by all programs executed by this VM so far
+    pub fn metrics(&self) -> &ExecutionMetrics {
+        self.meter.metrics()
     }
 
     pub fn execute(&mut self, code: Vec<u8>, num_registers: usize) -> Result<(), ProgramError> {
@@ -885,6 +1626,21 @@ where
         self.execute_with_stack(code, &mut stack)
     }
 
+    /// Execute template program binding its arguments
+    ///
+    /// Arguments are loaded by `LDA`, `LDLA`, `LDVA`, `STLA` and `STVA`, and
+    /// template declares how many it takes with `ARGS`.
+    pub fn execute_with_args(
+        &mut self,
+        code: Vec<u8>,
+        num_registers: usize,
+        args: Vec<u128>,
+    ) -> Result<(), ProgramError> {
+        let mut stack = Stack::new(num_registers);
+        stack.args = args;
+        self.execute_with_stack(code, &mut stack)
+    }
+
     pub(crate) fn execute_with_stack(
         &mut self,
         code: Vec<u8>,
@@ -893,33 +1649,62 @@ where
         log_msg!("\nvvv EXECUTE PROGRAM vvv");
         log_stack!(&stack);
 
-        let mut pc = 0;
-        let mut run = || -> Result<(), ErrorCode> {
+        let encoding = Encoding::of(&code);
+        let mut pc = encoding.header_len();
+        let mut traced = None;
+        let mut failed_op_code = None;
+        let mut operands = [None; MAX_ERROR_OPERANDS];
+        let mut rounding = Rounding::Down;
+        // Arguments declared by `ARGS`, i.e. none unless program is a template
+        let mut args = Vec::new();
+        let mut run = || -> Result<(), Failure> {
             while pc < code.len() {
                 let op_code = code[pc];
+                failed_op_code = Some(op_code);
+                operands = stack.top_types();
                 log_msg!(
                     "PC = {:4}, OpCode = {} {}",
                     pc,
                     op_code,
                     op_code_str!(op_code)
                 );
+                if self.tracer.is_some() {
+                    traced = decode(&code, pc).ok();
+                    self.trace_before(&traced, stack);
+                }
                 pc += 1;
+                self.meter.begin_instruction()?;
+                let len_before = stack.top_len();
                 match op_code {
                     OP_LDL => {
-                        let id = read_u128(&code[pc..pc + 16]);
-                        pc += 16;
-                        let v = self.vio.load_labels(id)?;
+                        let id = fetch_u128(&code, &mut pc, encoding)?;
+                        let mut v = self.vio.load_labels(id)?;
+                        // Joins of loaded labels won't need to check order again
+                        v.track_sorted_unique();
                         stack.push(Operand::Labels(v));
                     }
                     OP_LDV => {
-                        let id = read_u128(&code[pc..pc + 16]);
-                        pc += 16;
+                        let id = fetch_u128(&code, &mut pc, encoding)?;
                         let v = self.vio.load_vector(id)?;
                         stack.push(Operand::Vector(v));
                     }
+                    OP_LDLR => {
+                        let id = fetch_u128(&code, &mut pc, encoding)?;
+                        let start = fetch_usize(&code, &mut pc, encoding)?;
+                        let len = fetch_usize(&code, &mut pc, encoding)?;
+                        let mut v = self.vio.load_labels_range(id, start, len)?;
+                        v.track_sorted_unique();
+                        stack.push(Operand::Labels(v));
+                    }
+                    OP_LDVR => {
+                        let id = fetch_u128(&code, &mut pc, encoding)?;
+                        let start = fetch_usize(&code, &mut pc, encoding)?;
+                        let len = fetch_usize(&code, &mut pc, encoding)?;
+                        let v = self.vio.load_vector_range(id, start, len)?;
+                        stack.push(Operand::Vector(v));
+                    }
                     OP_STL => {
-                        let id = read_u128(&code[pc..pc + 16]);
-                        pc += 16;
+                        let id = fetch_u128(&code, &mut pc, encoding)?;
                         match stack.pop()? {
                             Operand::Labels(v) => {
                                 self.vio.store_labels(id, v)?;
@@ -930,8 +1715,7 @@ where
                         }
                     }
                     OP_STV => {
-                        let id = read_u128(&code[pc..pc + 16]);
-                        pc += 16;
+                        let id = fetch_u128(&code, &mut pc, encoding)?;
                         match stack.pop()? {
                             Operand::Vector(v) => {
                                 self.vio.store_vector(id, v)?;
@@ -941,108 +1725,150 @@ where
                             }
                         }
                     }
+                    OP_STVR => {
+                        let id = fetch_u128(&code, &mut pc, encoding)?;
+                        let start = fetch_usize(&code, &mut pc, encoding)?;
+                        match stack.pop()? {
+                            Operand::Vector(v) => {
+                                self.vio.store_vector_range(id, start, v)?;
+                            }
+                            _ => {
+                                Err(ErrorCode::InvalidOperand)?;
+                            }
+                        }
+                    }
                     OP_LDD => {
-                        let pos = code[pc] as usize;
-                        pc += 1;
+                        let pos = fetch_u8(&code, &mut pc)?;
                         stack.ldd(pos)?;
                     }
                     OP_LDR => {
-                        let reg = code[pc] as usize;
-                        pc += 1;
+                        let reg = fetch_u8(&code, &mut pc)?;
                         stack.ldr(reg)?;
                     }
                     OP_LDM => {
-                        let reg = code[pc] as usize;
-                        pc += 1;
+                        let reg = fetch_u8(&code, &mut pc)?;
                         stack.ldm(reg)?;
                     }
                     OP_STR => {
-                        let reg = code[pc] as usize;
-                        pc += 1;
+                        let reg = fetch_u8(&code, &mut pc)?;
                         stack.op_str(reg)?;
                     }
                     OP_PKV => {
-                        let count = code[pc] as usize;
-                        pc += 1;
+                        let count = fetch_u8(&code, &mut pc)?;
                         stack.pkv(count)?;
                     }
                     OP_PKL => {
-                        let count = code[pc] as usize;
-                        pc += 1;
+                        let count = fetch_u8(&code, &mut pc)?;
                         stack.pkl(count)?;
                     }
                     OP_UNPK => {
                         stack.unpk()?;
                     }
                     OP_T => {
-                        let count = code[pc] as usize;
-                        pc += 1;
+                        let count = fetch_u8(&code, &mut pc)?;
                         stack.transpose(count)?;
                     }
                     OP_ADD => {
-                        let pos = code[pc] as usize;
-                        pc += 1;
+                        let pos = fetch_u8(&code, &mut pc)?;
                         stack.add(pos)?;
                     }
                     OP_SUB => {
-                        let pos = code[pc] as usize;
-                        pc += 1;
+                        let pos = fetch_u8(&code, &mut pc)?;
                         stack.sub(pos)?;
                     }
                     OP_SSB => {
-                        let pos = code[pc] as usize;
-                        pc += 1;
+                        let pos = fetch_u8(&code, &mut pc)?;
                         stack.ssb(pos)?;
                     }
                     OP_MUL => {
-                        let pos = code[pc] as usize;
-                        pc += 1;
-                        stack.mul(pos)?;
+                        let pos = fetch_u8(&code, &mut pc)?;
+                        match core::mem::take(&mut rounding) {
+                            Rounding::Down => stack.mul(pos)?,
+                            r => stack.rounded_binary_op(pos, r, Amount::checked_mul_rounded)?,
+                        }
                     }
                     OP_DIV => {
-                        let pos = code[pc] as usize;
-                        pc += 1;
-                        stack.div(pos)?;
+                        let pos = fetch_u8(&code, &mut pc)?;
+                        match core::mem::take(&mut rounding) {
+                            Rounding::Down => stack.div(pos)?,
+                            r => stack.rounded_binary_op(pos, r, Amount::checked_div_rounded)?,
+                        }
+                    }
+                    OP_RNDU | OP_RNDE => {
+                        // Modifier applies only to immediately following MUL or DIV
+                        if !matches!(code.get(pc), Some(&OP_MUL) | Some(&OP_DIV)) {
+                            Err(ErrorCode::InvalidInstruction)?;
+                        }
+                        rounding = if op_code == OP_RNDU {
+                            Rounding::Up
+                        } else {
+                            Rounding::HalfEven
+                        };
                     }
                     OP_SQRT => {
                         stack.sqrt()?;
                     }
+                    OP_EXP => {
+                        stack.exp()?;
+                    }
+                    OP_LN => {
+                        stack.ln()?;
+                    }
+                    OP_POW => {
+                        let pos = fetch_u8(&code, &mut pc)?;
+                        stack.pow(pos)?;
+                    }
                     OP_VSUM => {
                         stack.vsum()?;
                     }
                     OP_MIN => {
-                        let pos = code[pc] as usize;
-                        pc += 1;
+                        let pos = fetch_u8(&code, &mut pc)?;
                         stack.min(pos)?;
                     }
                     OP_MAX => {
-                        let pos = code[pc] as usize;
-                        pc += 1;
+                        let pos = fetch_u8(&code, &mut pc)?;
                         stack.max(pos)?;
                     }
+                    OP_EQ => {
+                        let pos = fetch_u8(&code, &mut pc)?;
+                        stack.eq(pos)?;
+                    }
+                    OP_GT => {
+                        let pos = fetch_u8(&code, &mut pc)?;
+                        stack.gt(pos)?;
+                    }
+                    OP_LT => {
+                        let pos = fetch_u8(&code, &mut pc)?;
+                        stack.lt(pos)?;
+                    }
+                    OP_CLAMP => {
+                        let pos_lo = fetch_u8(&code, &mut pc)?;
+                        let pos_hi = fetch_u8(&code, &mut pc)?;
+                        stack.clamp(pos_lo, pos_hi)?;
+                    }
+                    OP_SEL => {
+                        let pos_mask = fetch_u8(&code, &mut pc)?;
+                        let pos_other = fetch_u8(&code, &mut pc)?;
+                        stack.sel(pos_mask, pos_other)?;
+                    }
                     OP_LUNION => {
-                        let pos = code[pc] as usize;
-                        pc += 1;
+                        let pos = fetch_u8(&code, &mut pc)?;
                         stack.lunion(pos)?;
                     }
                     OP_ZEROS => {
-                        let pos = code[pc] as usize;
-                        pc += 1;
+                        let pos = fetch_u8(&code, &mut pc)?;
                         stack.zeros(pos)?;
                     }
                     OP_ONES => {
-                        let pos = code[pc] as usize;
-                        pc += 1;
+                        let pos = fetch_u8(&code, &mut pc)?;
                         stack.ones(pos)?;
                     }
                     OP_IMMS => {
-                        let val = read_u128(&code[pc..pc + 16]);
-                        pc += 16;
+                        let val = fetch_u128(&code, &mut pc, encoding)?;
                         stack.imms(val)?;
                     }
                     OP_IMML => {
-                        let val = read_u128(&code[pc..pc + 16]);
-                        pc += 16;
+                        let val = fetch_u128(&code, &mut pc, encoding)?;
                         stack.imml(val)?;
                     }
                     OP_VMIN => {
@@ -1051,14 +1877,22 @@ where
                     OP_VMAX => {
                         stack.vmax()?;
                     }
+                    OP_VDOT => {
+                        let pos = fetch_u8(&code, &mut pc)?;
+                        stack.vdot(pos)?;
+                    }
+                    OP_VCUMSUM => {
+                        stack.vcumsum()?;
+                    }
+                    OP_VNORM => {
+                        stack.vnorm()?;
+                    }
                     OP_VPUSH => {
-                        let val = read_u128(&code[pc..pc + 16]);
-                        pc += 16;
+                        let val = fetch_u128(&code, &mut pc, encoding)?;
                         stack.vpush(val)?;
                     }
                     OP_LPUSH => {
-                        let val = read_u128(&code[pc..pc + 16]);
-                        pc += 16;
+                        let val = fetch_u128(&code, &mut pc, encoding)?;
                         stack.lpush(val)?;
                     }
                     OP_VPOP => {
@@ -1068,86 +1902,63 @@ where
                         stack.lpop()?;
                     }
                     OP_POPN => {
-                        let count = code[pc] as usize;
-                        pc += 1;
+                        let count = fetch_u8(&code, &mut pc)?;
                         stack.op_popn(count)?;
                     }
                     OP_SWAP => {
-                        let pos = code[pc] as usize;
-                        pc += 1;
+                        let pos = fetch_u8(&code, &mut pc)?;
                         stack.swap(pos)?;
                     }
                     OP_JUPD => {
-                        let pos_1 = code[pc] as usize;
-                        pc += 1;
-                        let pos_2 = code[pc] as usize;
-                        pc += 1;
-                        let pos_3 = code[pc] as usize;
-                        pc += 1;
+                        let pos_1 = fetch_u8(&code, &mut pc)?;
+                        let pos_2 = fetch_u8(&code, &mut pc)?;
+                        let pos_3 = fetch_u8(&code, &mut pc)?;
                         stack.jupd(pos_1, pos_2, pos_3)?;
                     }
                     OP_JADD => {
-                        let pos_1 = code[pc] as usize;
-                        pc += 1;
-                        let pos_2 = code[pc] as usize;
-                        pc += 1;
-                        let pos_3 = code[pc] as usize;
-                        pc += 1;
+                        let pos_1 = fetch_u8(&code, &mut pc)?;
+                        let pos_2 = fetch_u8(&code, &mut pc)?;
+                        let pos_3 = fetch_u8(&code, &mut pc)?;
                         stack.jadd(pos_1, pos_2, pos_3)?;
                     }
                     OP_JFLT => {
-                        let pos_1 = code[pc] as usize;
-                        pc += 1;
-                        let pos_2 = code[pc] as usize;
-                        pc += 1;
+                        let pos_1 = fetch_u8(&code, &mut pc)?;
+                        let pos_2 = fetch_u8(&code, &mut pc)?;
                         stack.jflt(pos_1, pos_2)?;
                     }
                     OP_B => {
                         // B <program_id> <num_inputs> <num_outputs> <num_registers>
-                        let code_address = read_u128(&code[pc..pc + 16]);
-                        pc += 16;
-                        let num_inputs = code[pc] as usize;
-                        pc += 1;
-                        let num_outputs = code[pc] as usize;
-                        pc += 1;
-                        let num_regs = code[pc] as usize;
-                        pc += 1;
-                        let mut st = Stack::new(num_regs);
-                        let mut prg = VectorVM::new(self.vio);
-                        let cod = prg.vio.load_code(code_address)?;
-                        let frm = stack
-                            .stack
-                            .len()
-                            .checked_sub(num_inputs)
-                            .ok_or_else(|| ErrorCode::StackUnderflow)?;
-                        st.stack.extend(stack.stack.drain(frm..));
-                        let res = prg.execute_with_stack(cod, &mut st);
-                        if let Err(err) = res {
-                            log_msg!("\n\nError occurred in procedure:");
-                            log_stack!(&st);
-                            log_msg!("^^^ Stack of the procedure\n\n");
-                            return Err(ErrorCode::SubroutineError(err.into()));
+                        let code_address = fetch_u128(&code, &mut pc, encoding)?;
+                        let num_inputs = fetch_u8(&code, &mut pc)?;
+                        let num_outputs = fetch_u8(&code, &mut pc)?;
+                        let num_regs = fetch_u8(&code, &mut pc)?;
+                        self.call(code_address, num_inputs, num_outputs, num_regs, stack)?;
+                    }
+                    OP_BZ => {
+                        // BZ <program_id> <num_inputs> <num_outputs> <num_registers>
+                        let code_address = fetch_u128(&code, &mut pc, encoding)?;
+                        let num_inputs = fetch_u8(&code, &mut pc)?;
+                        let num_outputs = fetch_u8(&code, &mut pc)?;
+                        let num_regs = fetch_u8(&code, &mut pc)?;
+                        if num_inputs != num_outputs {
+                            Err(ErrorCode::InvalidInstruction)?;
+                        }
+                        let is_zero = match stack.pop()? {
+                            Operand::Scalar(x) => x.is_zero(),
+                            _ => Err(ErrorCode::InvalidOperand)?,
+                        };
+                        if is_zero {
+                            self.call(code_address, num_inputs, num_outputs, num_regs, stack)?;
                         }
-                        let frm = st
-                            .stack
-                            .len()
-                            .checked_sub(num_outputs)
-                            .ok_or_else(|| ErrorCode::StackUnderflow)?;
-                        stack.stack.extend(st.stack.drain(frm..));
                     }
                     OP_FOLD => {
                         // FOLD <program_id> <num_inputs> <num_outputs> <num_registers>
-                        let code_address = read_u128(&code[pc..pc + 16]);
-                        pc += 16;
-                        let num_inputs = code[pc] as usize;
-                        pc += 1;
-                        let num_outputs = code[pc] as usize;
-                        pc += 1;
-                        let num_regs = code[pc] as usize;
-                        pc += 1;
+                        let code_address = fetch_u128(&code, &mut pc, encoding)?;
+                        let num_inputs = fetch_u8(&code, &mut pc)?;
+                        let num_outputs = fetch_u8(&code, &mut pc)?;
+                        let num_regs = fetch_u8(&code, &mut pc)?;
                         let mut st = Stack::new(num_regs);
-                        let mut prg = VectorVM::new(self.vio);
-                        let cod = prg.vio.load_code(code_address)?;
+                        let cod = self.vio.load_code(code_address)?;
                         let source = stack.stack.pop().ok_or_else(|| ErrorCode::StackUnderflow)?;
                         let frm = stack
                             .stack
@@ -1155,23 +1966,15 @@ where
                             .checked_sub(num_inputs)
                             .ok_or_else(|| ErrorCode::StackUnderflow)?;
                         st.stack.extend(stack.stack.drain(frm..));
-                        match source {
-                            Operand::Labels(s) => {
-                                for item in s.data {
-                                    st.stack.push(Operand::Label(item));
-                                    prg.execute_with_stack(cod.clone(), &mut st)
-                                        .map_err(|ec| ErrorCode::SubroutineError(ec.into()))?;
-                                }
-                            }
-                            Operand::Vector(s) => {
-                                for item in s.data {
-                                    st.stack.push(Operand::Scalar(item));
-                                    prg.execute_with_stack(cod.clone(), &mut st)
-                                        .map_err(|ec| ErrorCode::SubroutineError(ec.into()))?;
-                                }
-                            }
-                            _ => Err(ErrorCode::InvalidOperand)?,
-                        }
+                        self.meter.enter_call()?;
+                        self.frames.push(Frame {
+                            program_id: code_address,
+                            iteration: Some(0),
+                        });
+                        let res = self.fold(&cod, source, &mut st);
+                        self.frames.pop();
+                        self.meter.leave_call();
+                        res?;
                         let frm = st
                             .stack
                             .len()
@@ -1179,22 +1982,232 @@ where
                             .ok_or_else(|| ErrorCode::StackUnderflow)?;
                         stack.stack.extend(st.stack.drain(frm..));
                     }
+                    OP_ASSERT_ZERO => {
+                        let assert_id = fetch_u128(&code, &mut pc, encoding)?;
+                        stack.assert_zero(assert_id)?;
+                    }
+                    OP_ASSERT_LE => {
+                        let pos = fetch_u8(&code, &mut pc)?;
+                        let assert_id = fetch_u128(&code, &mut pc, encoding)?;
+                        stack.assert_le(pos, assert_id)?;
+                    }
+                    OP_NEG => {
+                        stack.neg()?;
+                    }
+                    OP_ABS => {
+                        stack.abs()?;
+                    }
+                    OP_POS => {
+                        stack.pos()?;
+                    }
+                    OP_NEGPART => {
+                        stack.negpart()?;
+                    }
+                    OP_NET => {
+                        let pos = fetch_u8(&code, &mut pc)?;
+                        stack.net(pos)?;
+                    }
+                    OP_SPK => {
+                        let pos = fetch_u8(&code, &mut pc)?;
+                        stack.spk(pos)?;
+                    }
+                    OP_SUNPK => {
+                        stack.sunpk()?;
+                    }
+                    OP_SGET => {
+                        let pos_vector = fetch_u8(&code, &mut pc)?;
+                        let pos_labels = fetch_u8(&code, &mut pc)?;
+                        stack.sget(pos_vector, pos_labels)?;
+                    }
+                    OP_SADD => {
+                        let pos_sparse = fetch_u8(&code, &mut pc)?;
+                        let pos_labels = fetch_u8(&code, &mut pc)?;
+                        stack.sadd(pos_sparse, pos_labels)?;
+                    }
+                    OP_SUPD => {
+                        let pos_sparse = fetch_u8(&code, &mut pc)?;
+                        let pos_labels = fetch_u8(&code, &mut pc)?;
+                        stack.supd(pos_sparse, pos_labels)?;
+                    }
+                    OP_ARGS => {
+                        // Arguments must be declared before any of them is used
+                        if pc != encoding.header_len() + 1 {
+                            Err(ErrorCode::InvalidInstruction)?;
+                        }
+                        let count = fetch_u8(&code, &mut pc)?;
+                        if stack.args.len() != count {
+                            Err(ErrorCode::OutOfRange)?;
+                        }
+                        args.clone_from(&stack.args);
+                    }
+                    OP_LDA => {
+                        let arg = fetch_u8(&code, &mut pc)?;
+                        let v = declared_arg(&args, arg)?;
+                        stack.push(Operand::Label(v));
+                    }
+                    OP_LDLA => {
+                        let id = declared_arg(&args, fetch_u8(&code, &mut pc)?)?;
+                        let mut v = self.vio.load_labels(id)?;
+                        v.track_sorted_unique();
+                        stack.push(Operand::Labels(v));
+                    }
+                    OP_LDVA => {
+                        let id = declared_arg(&args, fetch_u8(&code, &mut pc)?)?;
+                        let v = self.vio.load_vector(id)?;
+                        stack.push(Operand::Vector(v));
+                    }
+                    OP_STLA => {
+                        let id = declared_arg(&args, fetch_u8(&code, &mut pc)?)?;
+                        match stack.pop()? {
+                            Operand::Labels(v) => {
+                                self.vio.store_labels(id, v)?;
+                            }
+                            _ => {
+                                Err(ErrorCode::InvalidOperand)?;
+                            }
+                        }
+                    }
+                    OP_STVA => {
+                        let id = declared_arg(&args, fetch_u8(&code, &mut pc)?)?;
+                        match stack.pop()? {
+                            Operand::Vector(v) => {
+                                self.vio.store_vector(id, v)?;
+                            }
+                            _ => {
+                                Err(ErrorCode::InvalidOperand)?;
+                            }
+                        }
+                    }
                     _ => {
                         Err(ErrorCode::InvalidInstruction)?;
                     }
                 }
+                let num_components = len_before.max(stack.top_len());
+                self.meter
+                    .end_instruction(op_code, num_components, stack.depth())?;
+                self.trace_after(&traced, stack, None);
             }
             Ok(())
         };
 
-        run().map_err(|ec| ProgramError {
-            error_code: ec,
-            program_counter: pc,
-            stack_depth: stack.depth(),
+        run().map_err(|failure| match failure {
+            Failure::Error(ec) => {
+                self.trace_after(&traced, stack, Some(&ec));
+                ProgramError {
+                    error_code: ec,
+                    program_counter: pc,
+                    stack_depth: stack.depth(),
+                    op_code: failed_op_code,
+                    frames: self.frames.clone(),
+                    operands: operands.into_iter().flatten().collect(),
+                }
+            }
+            Failure::Subroutine(err) => {
+                self.trace_after(&traced, stack, Some(&err.error_code));
+                err
+            }
         })?;
 
         log_stack!(&stack);
         log_msg!("\n^^^ PROGRAM ENDED ^^^");
         Ok(())
     }
+
+    fn call(
+        &mut self,
+        code_address: u128,
+        num_inputs: usize,
+        num_outputs: usize,
+        num_regs: usize,
+        stack: &mut Stack,
+    ) -> Result<(), Failure> {
+        let mut st = Stack::new(num_regs);
+        let cod = self.vio.load_code(code_address)?;
+        let frm = stack
+            .stack
+            .len()
+            .checked_sub(num_inputs)
+            .ok_or_else(|| ErrorCode::StackUnderflow)?;
+        st.stack.extend(stack.stack.drain(frm..));
+        self.meter.enter_call()?;
+        self.frames.push(Frame {
+            program_id: code_address,
+            iteration: None,
+        });
+        let res = self.execute_with_stack(cod, &mut st);
+        self.frames.pop();
+        self.meter.leave_call();
+        if let Err(err) = res {
+            log_msg!("\n\nError occurred in procedure:");
+            log_stack!(&st);
+            log_msg!("^^^ Stack of the procedure\n\n");
+            return Err(Failure::Subroutine(err));
+        }
+        let frm = st
+            .stack
+            .len()
+            .checked_sub(num_outputs)
+            .ok_or_else(|| ErrorCode::StackUnderflow)?;
+        stack.stack.extend(st.stack.drain(frm..));
+        Ok(())
+    }
+
+    fn fold(&mut self, code: &[u8], source: Operand, stack: &mut Stack) -> Result<(), Failure> {
+        match source {
+            Operand::Labels(s) => {
+                for (index, item) in Vec::from(s).into_iter().enumerate() {
+                    self.set_iteration(index);
+                    stack.stack.push(Operand::Label(item));
+                    self.execute_with_stack(code.to_vec(), stack)
+                        .map_err(Failure::Subroutine)?;
+                }
+            }
+            Operand::Vector(s) => {
+                for (index, item) in s.data.into_iter().enumerate() {
+                    self.set_iteration(index);
+                    stack.stack.push(Operand::Scalar(item));
+                    self.execute_with_stack(code.to_vec(), stack)
+                        .map_err(Failure::Subroutine)?;
+                }
+            }
+            _ => Err(ErrorCode::InvalidOperand)?,
+        }
+        Ok(())
+    }
+
+    fn set_iteration(&mut self, index: usize) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.iteration = Some(index);
+        }
+    }
+
+    fn trace_before(&mut self, instruction: &Option<Instruction>, stack: &Stack) {
+        if let (Some(tracer), Some(instruction)) = (self.tracer.as_deref_mut(), instruction) {
+            tracer.before_instruction(&Step {
+                frames: &self.frames,
+                instruction,
+                stack: &stack.stack,
+                registers: &stack.registry,
+            });
+        }
+    }
+
+    fn trace_after(
+        &mut self,
+        instruction: &Option<Instruction>,
+        stack: &Stack,
+        error: Option<&ErrorCode>,
+    ) {
+        if let (Some(tracer), Some(instruction)) = (self.tracer.as_deref_mut(), instruction) {
+            tracer.after_instruction(
+                &Step {
+                    frames: &self.frames,
+                    instruction,
+                    stack: &stack.stack,
+                    registers: &stack.registry,
+                },
+                error,
+            );
+        }
+    }
 }
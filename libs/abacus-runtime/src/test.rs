@@ -13,6 +13,9 @@ use crate::runtime::*; // Use glob import for tidiness
 mod test_utils {
     use common::abacus::program_error::*;
 
+    use crate::optimizer::optimize;
+    use crate::verifier::verify;
+
     use super::*;
 
     pub(super) struct TestVectorIO {
@@ -35,6 +38,59 @@ mod test_utils {
             self.codes.insert(id, input);
             Ok(())
         }
+
+        /// Copy of the storage with every stored sub-routine optimized
+        pub(super) fn optimized_copy(&self) -> Self {
+            Self {
+                labels: self.labels.iter().map(|(id, v)| (*id, v.clone())).collect(),
+                vectors: self
+                    .vectors
+                    .iter()
+                    .map(|(id, v)| {
+                        (
+                            *id,
+                            Vector {
+                                data: v.data.clone(),
+                            },
+                        )
+                    })
+                    .collect(),
+                codes: self
+                    .codes
+                    .iter()
+                    .map(|(id, code)| (*id, optimize(code).unwrap()))
+                    .collect(),
+            }
+        }
+
+        /// Check that both storages hold the same labels and vectors
+        pub(super) fn assert_same_data(&self, other: &Self) {
+            let mut label_ids: Vec<_> = self.labels.keys().collect();
+            let mut vector_ids: Vec<_> = self.vectors.keys().collect();
+            label_ids.sort();
+            vector_ids.sort();
+            let mut other_label_ids: Vec<_> = other.labels.keys().collect();
+            let mut other_vector_ids: Vec<_> = other.vectors.keys().collect();
+            other_label_ids.sort();
+            other_vector_ids.sort();
+            assert_eq!(label_ids, other_label_ids);
+            assert_eq!(vector_ids, other_vector_ids);
+            for id in label_ids {
+                assert_eq!(
+                    self.labels[id].as_slice(),
+                    other.labels[id].as_slice(),
+                    "Labels {}",
+                    id
+                );
+            }
+            for id in vector_ids {
+                assert_eq!(
+                    self.vectors[id].data, other.vectors[id].data,
+                    "Vector {}",
+                    id
+                );
+            }
+        }
     }
 
     impl VectorIO for TestVectorIO {
@@ -44,9 +100,7 @@ mod test_utils {
                 ErrorCode::NotFound
             })?;
             log_msg!("Loaded labels {}: {}", id, v);
-            Ok(Labels {
-                data: v.data.clone(),
-            })
+            Ok(v.clone())
         }
 
         fn load_vector(&self, id: u128) -> Result<Vector, ErrorCode> {
@@ -82,24 +136,73 @@ mod test_utils {
         }
     }
 
+    /// Execute program, and then execute it optimized against copy of the
+    /// storage as it was before, checking that both either fail, or leave the
+    /// same labels and vectors, and that optimized program executes no more
+    /// instructions.
+    pub(super) fn execute_optimized(
+        vio: &mut TestVectorIO,
+        code: Vec<u8>,
+        num_registers: usize,
+        args: Vec<u128>,
+    ) -> Result<(), ProgramError> {
+        let mut optimized_vio = vio.optimized_copy();
+        let optimized_code = optimize(&code).unwrap();
+
+        let num_instructions = verify(&code).unwrap().num_instructions;
+        let optimized_num_instructions = verify(&optimized_code).unwrap().num_instructions;
+        assert!(optimized_num_instructions <= num_instructions);
+
+        let mut program = VectorVM::new(vio);
+        let result = program.execute_with_args(code, num_registers, args.clone());
+        let metrics = program.metrics().clone();
+
+        let mut optimized_program = VectorVM::new(&mut optimized_vio);
+        let optimized_result =
+            optimized_program.execute_with_args(optimized_code, num_registers, args);
+        let optimized_metrics = optimized_program.metrics().clone();
+
+        match (result, optimized_result) {
+            (Ok(()), Ok(())) => {}
+            (Err(err), Err(_)) => return Err(err),
+            (Ok(()), Err(err)) => panic!("Optimized program failed: {:?}", err),
+            (Err(err), Ok(())) => {
+                panic!("Optimized program succeeded, but not original: {:?}", err)
+            }
+        }
+
+        log_msg!(
+            "Optimized {} -> {} instructions, {} -> {} executed, {} -> {} units",
+            num_instructions,
+            optimized_num_instructions,
+            metrics.instructions,
+            optimized_metrics.instructions,
+            metrics.units,
+            optimized_metrics.units
+        );
+        assert!(optimized_metrics.instructions <= metrics.instructions);
+        vio.assert_same_data(&optimized_vio);
+        Ok(())
+    }
+
     pub(super) struct TestProgram<'a> {
-        program: VectorVM<'a, TestVectorIO>,
+        vio: &'a mut TestVectorIO,
     }
 
     impl<'a> TestProgram<'a> {
         pub(super) fn new(vio: &'a mut TestVectorIO) -> Self {
-            Self {
-                program: VectorVM::new(vio),
-            }
+            Self { vio }
+        }
+
+        pub(super) fn execute(&mut self, message: &str, code: Vec<u8>) {
+            self.execute_with_args(message, code, Vec::new());
         }
 
-        pub(super) fn execute(&mut self, _message: &str, code: Vec<u8>) {
+        pub(super) fn execute_with_args(&mut self, _message: &str, code: Vec<u8>, args: Vec<u128>) {
             log_msg!("\nExecute: {}", _message);
             let num_registers = 16;
-            let mut stack = Stack::new(num_registers);
-            let result = self.program.execute_with_stack(code, &mut stack);
+            let result = execute_optimized(self.vio, code, num_registers, args);
             if let Err(err) = result {
-                log_stack!(&stack);
                 panic!("Failed to execute test: {:?}", err);
             }
         }
@@ -120,20 +223,8 @@ mod test_utils {
             },
         )
         .unwrap();
-        vio.store_labels(
-            from_names_id,
-            Labels {
-                data: from_names.data.clone(),
-            },
-        )
-        .unwrap();
-        vio.store_labels(
-            to_names_id,
-            Labels {
-                data: to_names.data.clone(),
-            },
-        )
-        .unwrap();
+        vio.store_labels(from_names_id, from_names.clone()).unwrap();
+        vio.store_labels(to_names_id, to_names.clone()).unwrap();
 
         let code = abacus!(
             LDV     data_id         // D
@@ -174,131 +265,2009 @@ mod unit_tests {
         let data_2 = amount_vec![1.5, 2.5];
         let data_3 = amount_vec![5.5, 6.5, 7.5];
 
-        vio.store_labels(1, names_1).unwrap();
-        vio.store_labels(2, names_2).unwrap();
-        vio.store_labels(3, names_3).unwrap();
+        vio.store_labels(1, names_1).unwrap();
+        vio.store_labels(2, names_2).unwrap();
+        vio.store_labels(3, names_3).unwrap();
+
+        vio.store_vector(4, data_1).unwrap();
+        vio.store_vector(5, data_2).unwrap();
+        vio.store_vector(6, data_3).unwrap();
+
+        let code = abacus! {
+            LDL     1           //  [1]
+            LDL     2           //  [1, 2]
+            LDL     3           //  [1, 2, 3]
+            LDD     0
+            STR     _L3
+            LUNION  1           //  [1, 2, (2 u 3)]
+            LUNION  2           //  [1, 2, U = (1 u (2 u 3))]
+            LDD     0
+            STL     10
+
+            LDV     4
+            LDV     5
+            LDV     6           // [1, 2, U, 4, 5, 6]
+
+            ZEROS   3           // [1, 2, U, 4, 5, 6, Z]
+            JUPD    3   4   6   // [1, 2, U, 4, 5, 6, A = Z <- 4]
+            JUPD    2   4   5   // [1, 2, U, 4, 5, 6, A <- 5]
+            STV     11
+
+            LDM     _L3         // [1, 2, U, 4, 5, 6, 3]
+            ZEROS   4           // [1, 2, U, 4, 5, 6, 3, Z]
+            JUPD    2   5   1
+            STV     12
+        };
+
+        let num_registry = 1;
+        let mut stack = Stack::new(num_registry);
+        let mut program = VectorVM::new(&mut vio);
+
+        if let Err(err) = program.execute_with_stack(code.unwrap(), &mut stack) {
+            log_stack!(&stack);
+            panic!("Failed to execute test: {:?}", err);
+        }
+
+        let result10 = vio.load_labels(10).unwrap();
+        assert_eq!(result10.as_slice(), label_vec![1, 2, 3, 5].as_slice());
+
+        let result11 = vio.load_vector(11).unwrap();
+        assert_eq!(result11.data, amount_vec![1.5, 0, 2.5, 0].data);
+
+        let result12 = vio.load_vector(12).unwrap();
+        assert_eq!(result12.data, amount_vec![0, 5.5, 6.5, 7.5].data);
+    }
+
+    #[test]
+    fn test_unsorted_joins() {
+        use common::abacus::program_error::ErrorCode;
+
+        let run = |names_b: Labels| -> Result<test_utils::TestVectorIO, ErrorCode> {
+            let mut vio = test_utils::TestVectorIO::new();
+            vio.store_labels(1, label_vec![5, 1, 3, 2]).unwrap();
+            vio.store_labels(2, names_b).unwrap();
+            vio.store_vector(3, amount_vec![1.5, 2.5]).unwrap();
+
+            let code = abacus! {
+                LDL     1           // [A]
+                LDL     2           // [A, B]
+                LDV     3           // [A, B, VB]
+                ZEROS   2           // [A, B, VB, Z]
+                JUPD    1   3   2   // [A, B, VB, Z <- VB]
+                JADD    1   3   2   // [A, B, VB, Z + VB]
+                LDD     0
+                STV     10
+                JFLT    3   2       // [A, B, VB, (Z + VB) filtered to B]
+                STV     11
+            }
+            .unwrap();
+            let mut program = VectorVM::new(&mut vio);
+            program.execute(code, 0).map_err(|err| err.error_code)?;
+            Ok(vio)
+        };
+
+        // Labels are joined by hashing when they are not sorted
+        let vio = run(label_vec![3, 5]).unwrap();
+        assert_eq!(
+            vio.load_vector(10).unwrap().data,
+            amount_vec![5, 0, 3, 0].data
+        );
+        assert_eq!(vio.load_vector(11).unwrap().data, amount_vec![3, 5].data);
+
+        // All labels must be found
+        assert!(matches!(
+            run(label_vec![3, 7]),
+            Err(ErrorCode::MathUnderflow)
+        ));
+    }
+
+    #[test]
+    fn test_join_missing_labels() {
+        use common::abacus::program_error::ErrorCode;
+
+        let run = |code: Vec<u8>| -> Result<(), ErrorCode> {
+            let mut vio = test_utils::TestVectorIO::new();
+            vio.store_labels(1, label_vec![1, 2, 3]).unwrap();
+            vio.store_labels(2, label_vec![2, 3, 4]).unwrap();
+            vio.store_vector(3, amount_vec![1.5, 2.5, 3.5]).unwrap();
+            let mut program = VectorVM::new(&mut vio);
+            program.execute(code, 0).map_err(|err| err.error_code)
+        };
+
+        // Label 4 is missing from A, and since labels are joined by position
+        // of every label, rather than by merging until A is exhausted, it is
+        // not skipped, and each of the joins fails
+        let jupd = abacus! {
+            LDL     1           // [A]
+            LDL     2           // [A, B]
+            LDV     3           // [A, B, VB]
+            ZEROS   2           // [A, B, VB, Z]
+            JUPD    1   3   2   // [A, B, VB, Z <- VB]
+        };
+        let jadd = abacus! {
+            LDL     1           // [A]
+            LDL     2           // [A, B]
+            LDV     3           // [A, B, VB]
+            ZEROS   2           // [A, B, VB, Z]
+            JADD    1   3   2   // [A, B, VB, Z + VB]
+        };
+        let jflt = abacus! {
+            LDL     2           // [B]
+            LDL     1           // [B, A]
+            ZEROS   0           // [B, A, Z]
+            JFLT    1   2       // [B, A, Z filtered to B]
+        };
+        for code in [jupd, jadd, jflt] {
+            assert!(matches!(run(code.unwrap()), Err(ErrorCode::MathUnderflow)));
+        }
+    }
+
+    #[test]
+    fn test_transpose() {
+        let mut vio = test_utils::TestVectorIO::new();
+        let num_registers = 8;
+
+        // --- 1. Setup VIO Inputs ---
+        let vector1_id = 100;
+        let vector2_id = 101;
+        let expected1_id = 102; // T1: [1, 4]
+        let expected2_id = 103; // T2: [2, 5]
+        let expected3_id = 104; // T3: [3, 6]
+        let delta_id = 105;
+
+        vio.store_vector(vector1_id, amount_vec![1, 2, 3]).unwrap();
+        vio.store_vector(vector2_id, amount_vec![4, 5, 6]).unwrap();
+        vio.store_vector(expected1_id, amount_vec![1, 4]).unwrap();
+        vio.store_vector(expected2_id, amount_vec![2, 5]).unwrap();
+        vio.store_vector(expected3_id, amount_vec![3, 6]).unwrap();
+
+        // --- 2. VIL Code Execution ---
+        let code = abacus![
+            // 1. Setup Transposition
+            LDV         vector1_id              // Stack: [V1]
+            LDV         vector2_id              // Stack: [V1, V2]
+            T           2                       // Stack: [T1, T2, T3] (3 vectors)
+
+            // 2. Load Expected Vectors for comparison
+            LDV         expected1_id            // [T1, T2, T3, E1]
+            LDV         expected2_id            // [T1, T2, T3, E1, E2]
+            LDV         expected3_id            // [T1, T2, T3, E1, E2, E3] (6 vectors)
+
+            // 3. D3 = T3 - E3
+            SUB         3                       // Stack: [T1, T2, T3, E1, E2, D3]
+
+            // 4. D2 = T2 - E2
+            SWAP        1                       // Stack: [T1, T2, T3, E1, D3, E2]
+            SUB         4                       // Stack: [T1, T2, T3, E1, D3, D2]
+
+            // 5. D1 = T1 - E1
+            SWAP        2                       // Stack: [T1, T2, T3, D2, D3, E1]
+            SUB         5                       // Stack: [T1, T2, T3, D2, D3, D1]
+
+            // 6. Compute total delta - should be zero
+            ADD         1                       // Stack: [T1, T2, T3, D2, D3, D1 + D3]
+            ADD         2                       // Stack: [T1, T2, T3, D2, D3, D1 + D3 + D2]
+
+            // 7. Store the final zero vector
+            STV         delta_id
+        ];
+
+        let mut stack = Stack::new(num_registers);
+        let mut program = VectorVM::new(&mut vio);
+
+        if let Err(err) = program.execute_with_stack(code.unwrap(), &mut stack) {
+            log_stack!(&stack);
+            panic!("Failed to execute test: {:?}", err);
+        }
+
+        // --- 3. Assertion ---
+        let delta = vio.load_vector(delta_id).unwrap();
+
+        assert_eq!(delta.data, amount_vec![0, 0].data);
+    }
+
+    #[test]
+    fn test_verify() {
+        use crate::verifier::{verify, verify_with_inputs};
+        use common::abacus::program_error::ErrorCode;
+
+        let code = abacus! {
+            #![stack_comments]
+            LDV     1           // [V]
+            LDD     0           // [V, V]
+            STR     _A          // [V]
+            LDR     _A          // [V, V]
+            STR     _B          // [V]
+            ADD     0           // [V]
+            STV     2           // []
+        }
+        .unwrap();
+
+        let info = verify(&code).unwrap();
+        assert_eq!(info.num_instructions, 7);
+        assert_eq!(info.max_stack_depth, Some(2));
+        assert_eq!(info.max_register, Some(1));
+        assert_eq!(info.num_registers(), 2);
+
+        // Sub-routine expects 3 inputs on the stack
+        let code = solve_quadratic_bid().unwrap();
+        let err = verify(&code).unwrap_err();
+        assert!(matches!(err.error_code, ErrorCode::StackUnderflow));
+        // First instruction follows compact header
+        assert_eq!(err.program_counter, 1);
+
+        let info = verify_with_inputs(&code, 3).unwrap();
+        assert_eq!(info.num_registers(), 4);
+
+        // Same inputs are declared to abacus!, which checks stack when compiled
+        let code = abacus! {
+            #![inputs(2)]
+            #![stack_comments]
+            ADD     1           // [A, A + B]
+            SWAP    1           // [A + B, A]
+            POPN    1           // [A + B]
+        }
+        .unwrap();
+        let info = verify_with_inputs(&code, 2).unwrap();
+        assert_eq!(info.max_stack_depth, Some(2));
+
+        // Truncated argument of the last instruction
+        let code = abacus! {
+            LDV     1
+            STV     2
+        }
+        .unwrap();
+        let err = verify(&code[..code.len() - 1]).unwrap_err();
+        assert!(matches!(err.error_code, ErrorCode::TruncatedInstruction));
+        assert_eq!(err.program_counter, 17);
+
+        // Unknown op-code
+        let err = verify(&[254]).unwrap_err();
+        assert!(matches!(err.error_code, ErrorCode::InvalidInstruction));
+
+        // Underflow after B consumes more inputs than available
+        let code = abacus! {
+            #![unchecked]
+            LDV     1
+            LDV     2
+            B       10  3   1   0
+        }
+        .unwrap();
+        let err = verify(&code).unwrap_err();
+        assert!(matches!(err.error_code, ErrorCode::StackUnderflow));
+        assert_eq!(err.program_counter, 34);
+
+        // BZ must return as many outputs as it takes inputs
+        let code = abacus! {
+            #![unchecked]
+            LDV     1
+            IMMS    0
+            BZ      10  1   2   0
+        }
+        .unwrap();
+        let err = verify(&code).unwrap_err();
+        assert!(matches!(err.error_code, ErrorCode::InvalidInstruction));
+        assert_eq!(err.program_counter, 34);
+
+        // Stack depth after UNPK is only known at run-time
+        let code = abacus! {
+            LDV     1
+            UNPK
+            POPN    1
+        }
+        .unwrap();
+        let info = verify(&code).unwrap();
+        assert_eq!(info.max_stack_depth, None);
+    }
+
+    #[test]
+    fn test_truncated_program() {
+        use common::abacus::program_error::ErrorCode;
+
+        let mut vio = test_utils::TestVectorIO::new();
+        vio.store_vector(1, amount_vec![1, 2]).unwrap();
+
+        let code = abacus! {
+            LDV     1
+            STV     2
+        }
+        .unwrap();
+
+        let mut program = VectorVM::new(&mut vio);
+        let err = program
+            .execute(code[..code.len() - 8].to_vec(), 0)
+            .unwrap_err();
+
+        assert!(matches!(err.error_code, ErrorCode::TruncatedInstruction));
+    }
+
+    #[test]
+    fn test_disassemble() {
+        use crate::disassembler::{disassemble, instruction_at, listing, DecodedArg};
+        use common::amount::Amount;
+
+        let code = abacus! {
+            LDV     100
+            IMMS    1.5
+            STR     _factor
+            LDR     _factor
+            MUL     1
+            B       7  2  1  4
+            STV     101
+        }
+        .unwrap();
+
+        let instructions = disassemble(&code);
+        let text: Vec<String> = instructions.iter().map(|i| format!("{}", i)).collect();
+        assert_eq!(
+            text,
+            vec![
+                "LDV     100",
+                "IMMS    1.5",
+                "STR     _r0",
+                "LDR     _r0",
+                "MUL     1",
+                "B       7 2 1 4",
+                "STV     101",
+            ]
+        );
+
+        let offsets: Vec<usize> = instructions.iter().map(|i| i.offset).collect();
+        assert_eq!(offsets, vec![0, 17, 34, 36, 38, 40, 60]);
+        assert_eq!(
+            instructions[1].args,
+            vec![DecodedArg::Amount(Amount::from_u128_with_scale(15, 1))]
+        );
+        assert!(instructions.iter().all(|i| i.is_valid()));
+
+        // Failed MUL has read its argument, and program counter points past it
+        assert_eq!(instruction_at(&instructions, 40).unwrap().offset, 38);
+        assert!(listing(&code, Some(40)).contains(">>     38  MUL     1"));
+
+        // Truncated instruction covers all remaining bytes
+        let instructions = disassemble(&code[..code.len() - 8]);
+        let last = instructions.last().unwrap();
+        assert_eq!((last.offset, last.len), (60, 9));
+        assert_eq!(last.mnemonic, Some("STV"));
+        assert!(last.truncated);
+
+        // Unknown op-code stops disassembly
+        let mut code = code;
+        code[17] = 0xff;
+        let instructions = disassemble(&code);
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(instructions[1].mnemonic, None);
+        assert!(!instructions[1].is_valid());
+    }
+
+    #[test]
+    fn test_compact_encoding() {
+        use crate::decoder::{decode, Encoding};
+        use crate::disassembler::{disassemble, listing};
+        use abacus_macros::abacus_compact;
+        use common::abacus::{instruction_set::*, program_error::ErrorCode};
+
+        let fixed = abacus! {
+            B       102  0  1  0    // [2]
+            LDV     100             // [2, V]
+            MUL     1               // [2, 2 * V]
+            STV     101             // [2]
+        }
+        .unwrap();
+
+        let compact = abacus_compact! {
+            B       102  0  1  0
+            LDV     100
+            MUL     1
+            STV     101
+        }
+        .unwrap();
+
+        assert_eq!(Encoding::of(&fixed), Encoding::Fixed);
+        assert_eq!(Encoding::of(&compact), Encoding::Compact);
+        assert_eq!(fixed.len(), 56);
+        assert_eq!(compact.len(), 12);
+
+        // Both encodings disassemble into the same instructions
+        let text = |code: &[u8]| -> Vec<String> {
+            disassemble(code).iter().map(|i| format!("{}", i)).collect()
+        };
+        assert_eq!(text(&fixed), text(&compact));
+        let offsets: Vec<usize> = disassemble(&compact).iter().map(|i| i.offset).collect();
+        assert_eq!(offsets, vec![1, 6, 8, 10]);
+        assert!(listing(&compact, None).starts_with("        0  // compact encoding\n"));
+
+        // Sub-routine has its own encoding, independent of the caller
+        for (code, sub_code) in [
+            (fixed.clone(), abacus_compact! { IMMS 2 }.unwrap()),
+            (compact.clone(), abacus! { IMMS 2 }.unwrap()),
+        ] {
+            let mut vio = test_utils::TestVectorIO::new();
+            vio.store_vector(100, amount_vec![1, 2.5]).unwrap();
+            vio.store_code(102, sub_code).unwrap();
+
+            let mut program = VectorVM::new(&mut vio);
+            program.execute(code, 0).unwrap();
+
+            let result = vio.load_vector(101).unwrap();
+            assert_eq!(result.data, amount_vec![2, 5].data);
+        }
+
+        // Program ends in the middle of LEB128 argument
+        let code = abacus_compact! { LDV 300 }.unwrap();
+        assert_eq!(code, vec![COMPACT_HEADER, OP_LDV, 0xac, 0x02]);
+        let mut vio = test_utils::TestVectorIO::new();
+        let mut program = VectorVM::new(&mut vio);
+        let err = program.execute(code[..3].to_vec(), 0).unwrap_err();
+        assert!(matches!(err.error_code, ErrorCode::TruncatedInstruction));
+
+        // LEB128 argument exceeding 128 bits
+        let mut code = vec![COMPACT_HEADER, OP_LDV];
+        code.extend([0xff; 18]);
+        code.push(0x04);
+        assert!(matches!(
+            decode(&code, 1),
+            Err(ErrorCode::InvalidInstruction)
+        ));
+    }
+
+    #[test]
+    fn test_tracer() {
+        use crate::decoder::mnemonic;
+        use crate::tracer::{Frame, Step, Tracer};
+        use common::abacus::program_error::ErrorCode;
+
+        type Event = (Vec<Frame>, usize, &'static str, usize, Option<String>);
+
+        #[derive(Default)]
+        struct RecordingTracer {
+            events: Vec<Event>,
+        }
+
+        impl Tracer for RecordingTracer {
+            fn before_instruction(&mut self, step: &Step<'_>) {
+                let instruction = step.instruction;
+                assert!(self.events.last().is_none_or(|e| e.4.is_none()));
+                self.events.push((
+                    step.frames.to_vec(),
+                    instruction.offset,
+                    mnemonic(instruction.op_code).unwrap(),
+                    step.stack.len(),
+                    None,
+                ));
+            }
+
+            fn after_instruction(&mut self, step: &Step<'_>, error: Option<&ErrorCode>) {
+                if let Some(error) = error {
+                    self.events.push((
+                        step.frames.to_vec(),
+                        step.instruction.offset,
+                        "!",
+                        step.stack.len(),
+                        Some(format!("{:?}", error)),
+                    ));
+                }
+            }
+        }
+
+        let vector_id = 1;
+        let sum_prg_id = 2;
+
+        let mut vio = test_utils::TestVectorIO::new();
+        vio.store_vector(vector_id, amount_vec![1, 2]).unwrap();
+        vio.store_code(
+            sum_prg_id,
+            abacus! {
+                #![inputs(2)]
+                ADD     1
+                SWAP    1
+                POPN    1
+            }
+            .unwrap(),
+        )
+        .unwrap();
+
+        let code = abacus! {
+            IMMS    0
+            LDV     vector_id
+            FOLD    sum_prg_id  1  1  0
+            SQRT
+            STR     _Sum
+            LDR     _Sum
+            VSUM
+        }
+        .unwrap();
+
+        let mut tracer = RecordingTracer::default();
+        let mut program = VectorVM::new(&mut vio);
+        program.set_tracer(&mut tracer);
+        let err = program.execute(code, 1).unwrap_err();
+        assert!(matches!(err.error_code, ErrorCode::InvalidOperand));
+
+        let frame = |iteration| {
+            vec![Frame {
+                program_id: sum_prg_id,
+                iteration: Some(iteration),
+            }]
+        };
+        assert_eq!(
+            tracer.events,
+            vec![
+                (vec![], 0, "IMMS", 0, None),
+                (vec![], 17, "LDV", 1, None),
+                (vec![], 34, "FOLD", 2, None),
+                (frame(0), 0, "ADD", 2, None),
+                (frame(0), 2, "SWAP", 2, None),
+                (frame(0), 4, "POPN", 2, None),
+                (frame(1), 0, "ADD", 2, None),
+                (frame(1), 2, "SWAP", 2, None),
+                (frame(1), 4, "POPN", 2, None),
+                (vec![], 54, "SQRT", 1, None),
+                (vec![], 55, "STR", 1, None),
+                (vec![], 57, "LDR", 0, None),
+                (vec![], 59, "VSUM", 1, None),
+                (vec![], 59, "!", 0, Some("InvalidOperand".into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_execution_limits() {
+        use crate::metering::{ExecutionLimits, ExecutionMetrics};
+        use common::abacus::program_error::{ErrorCode, LimitKind};
+
+        let vector_id = 1;
+        let sum_prg_id = 2;
+        let result_id = 3;
+
+        let sum_code = abacus! {
+            #![inputs(2)]
+            ADD     1           // Stack: [Sum, Item + Sum]
+            SWAP    1           // Stack: [Item + Sum, Sum]
+            POPN    1           // Stack: [Item + Sum]
+        }
+        .unwrap();
+
+        let code = abacus! {
+            IMMS    0           // Stack: [0]
+            LDV     vector_id   // Stack: [0, V]
+            FOLD    sum_prg_id  1  1  0
+            PKV     1           // Stack: [[Sum]]
+            STV     result_id
+        }
+        .unwrap();
+
+        let run = |limits: ExecutionLimits| -> Result<ExecutionMetrics, ErrorCode> {
+            let mut vio = test_utils::TestVectorIO::new();
+            vio.store_vector(vector_id, amount_vec![1, 2, 3, 4, 5])
+                .unwrap();
+            vio.store_code(sum_prg_id, sum_code.clone()).unwrap();
+
+            let mut program = VectorVM::with_limits(&mut vio, limits);
+            program
+                .execute(code.clone(), 0)
+                .map_err(|err| err.error_code)?;
+            let metrics = program.metrics().clone();
+
+            let result = vio.load_vector(result_id).unwrap();
+            assert_eq!(result.data, amount_vec![15].data);
+            Ok(metrics)
+        };
+
+        let metrics = run(ExecutionLimits::UNLIMITED).unwrap();
+        assert_eq!(metrics.instructions, 5 + 5 * 3);
+        assert_eq!(metrics.max_stack_depth, 2);
+        assert_eq!(metrics.max_call_depth, 1);
+        assert_eq!(metrics.max_vector_len, 5);
+        assert!(metrics.units > 0);
+
+        // Exact limits are fine
+        let limits = ExecutionLimits {
+            max_instructions: metrics.instructions,
+            max_stack_depth: metrics.max_stack_depth,
+            max_call_depth: metrics.max_call_depth,
+            max_vector_len: metrics.max_vector_len,
+        };
+        assert_eq!(run(limits).unwrap(), metrics);
+
+        // Limit exceeded in main program
+        let exceeded = |limits, kind| match run(limits) {
+            Err(ErrorCode::LimitExceeded(k)) => assert_eq!(k, kind),
+            other => panic!("Expected {:?} limit exceeded, got: {:?}", kind, other),
+        };
+        exceeded(
+            ExecutionLimits {
+                max_instructions: metrics.instructions - 1,
+                ..limits
+            },
+            LimitKind::Instructions,
+        );
+        exceeded(
+            ExecutionLimits {
+                max_stack_depth: 1,
+                ..limits
+            },
+            LimitKind::StackDepth,
+        );
+        exceeded(
+            ExecutionLimits {
+                max_call_depth: 0,
+                ..limits
+            },
+            LimitKind::CallDepth,
+        );
+        exceeded(
+            ExecutionLimits {
+                max_vector_len: 4,
+                ..limits
+            },
+            LimitKind::VectorLength,
+        );
+
+        // Limit exceeded in sub-routine
+        exceeded(
+            ExecutionLimits {
+                max_instructions: 10,
+                ..limits
+            },
+            LimitKind::Instructions,
+        );
+    }
+
+    #[test]
+    fn test_program_error() {
+        use common::abacus::{
+            instruction_set::{OP_STL, OP_SUB},
+            program_error::{ErrorCode, Frame, LimitKind, OperandType},
+        };
+
+        let vector_id = 1;
+        let sub_prg_id = 2;
+
+        // Fails once item exceeds 2
+        let sub_code = abacus! {
+            #![inputs(1)]
+            IMMS    2           // Stack: [Item, 2]
+            SUB     1           // Stack: [Item, 2 - Item]
+        }
+        .unwrap();
+
+        let code = abacus! {
+            LDV     vector_id   // Stack: [V]
+            FOLD    sub_prg_id  0  0  0
+        }
+        .unwrap();
+
+        let mut vio = test_utils::TestVectorIO::new();
+        vio.store_vector(vector_id, amount_vec![1, 2, 3]).unwrap();
+        vio.store_code(sub_prg_id, sub_code).unwrap();
+
+        // Error tells where in sub-routine it failed
+        let err = VectorVM::new(&mut vio).execute(code, 0).unwrap_err();
+        assert!(matches!(err.error_code, ErrorCode::MathOverflow));
+        assert_eq!(err.op_code, Some(OP_SUB));
+        assert_eq!(err.program_counter, 19);
+        assert_eq!(err.stack_depth, 6);
+        assert_eq!(
+            err.frames,
+            vec![Frame {
+                program_id: sub_prg_id,
+                iteration: Some(2),
+            }]
+        );
+        assert_eq!(err.operands, vec![OperandType::Scalar; 3]);
+
+        // Operand consumed by failed instruction is reported
+        let code = abacus! {
+            IMML    5           // Stack: [5]
+            LDV     vector_id   // Stack: [5, V]
+            STL     3
+        }
+        .unwrap();
+        let err = VectorVM::new(&mut vio).execute(code, 0).unwrap_err();
+        assert!(matches!(err.error_code, ErrorCode::InvalidOperand));
+        assert_eq!(err.op_code, Some(OP_STL));
+        assert!(err.frames.is_empty());
+        assert_eq!(err.operands, vec![OperandType::Label, OperandType::Vector]);
+
+        // Error codes with their values can be reconstructed
+        for error_code in [
+            ErrorCode::NotAligned(3, 4),
+            ErrorCode::LimitExceeded(LimitKind::CallDepth),
+            ErrorCode::AssertionFailed(7),
+            ErrorCode::OutOfRange,
+        ] {
+            let decoded = ErrorCode::from_code(error_code.code(), &error_code.args()).unwrap();
+            assert_eq!(format!("{:?}", decoded), format!("{:?}", error_code));
+        }
+        assert!(ErrorCode::from_code(8, &[3]).is_none());
+    }
+
+    #[test]
+    fn test_conditions() {
+        use common::abacus::program_error::ErrorCode;
+
+        let asset_names_id = 1;
+        let delisted_names_id = 2;
+        let supply_id = 3;
+        let capacity_id = 4;
+        let mask_id = 5;
+        let double_prg_id = 6;
+        let result_id = 7;
+
+        let double_code = abacus! {
+            #![inputs(1)]
+            ADD     0           // Stack: [2 * V]
+        }
+        .unwrap();
+
+        let run = |delisted, code: Vec<u8>| -> Result<test_utils::TestVectorIO, ErrorCode> {
+            let mut vio = test_utils::TestVectorIO::new();
+            vio.store_labels(asset_names_id, label_vec![1, 2, 3, 4])
+                .unwrap();
+            vio.store_labels(delisted_names_id, delisted).unwrap();
+            vio.store_vector(supply_id, amount_vec![0, 5, 0, 2])
+                .unwrap();
+            vio.store_vector(capacity_id, amount_vec![1, 5, 1, 1])
+                .unwrap();
+            vio.store_code(double_prg_id, double_code.clone()).unwrap();
+
+            let mut program = VectorVM::new(&mut vio);
+            program.execute(code, 0).map_err(|err| err.error_code)?;
+            Ok(vio)
+        };
+
+        // Supply of delisted assets must be zero
+        let code = abacus! {
+            LDL     asset_names_id      // Stack: [AN]
+            LDL     delisted_names_id   // Stack: [AN, DN]
+            LDV     supply_id           // Stack: [AN, DN, S]
+            JFLT    2   1               // Stack: [AN, DN, fS]
+            ASSERT_ZERO 101             // Stack: [AN, DN]
+        }
+        .unwrap();
+        assert!(run(label_vec![1, 3], code.clone()).is_ok());
+        assert!(matches!(
+            run(label_vec![1, 2], code.clone()),
+            Err(ErrorCode::AssertionFailed(101))
+        ));
+
+        // Supply must not exceed capacity
+        let code = abacus! {
+            LDV     capacity_id         // Stack: [C]
+            LDV     supply_id           // Stack: [C, S]
+            ASSERT_LE   1   102         // Stack: [C]
+        }
+        .unwrap();
+        assert!(matches!(
+            run(label_vec![], code),
+            Err(ErrorCode::AssertionFailed(102))
+        ));
+
+        // Mask of components equal to scalar, and scalar upper bound
+        let code = abacus! {
+            IMMS    0                   // Stack: [0]
+            LDV     supply_id           // Stack: [0, S]
+            EQ      1                   // Stack: [0, M]
+            STV     mask_id             // Stack: [0]
+            IMMS    5                   // Stack: [0, 5]
+            LDV     supply_id           // Stack: [0, 5, S]
+            ASSERT_LE   1   103         // Stack: [0, 5]
+        }
+        .unwrap();
+        let vio = run(label_vec![], code).unwrap();
+        assert_eq!(
+            vio.load_vector(mask_id).unwrap().data,
+            amount_vec![1, 0, 1, 0].data
+        );
+
+        // Conditional call is made only when condition is zero
+        let code = abacus! {
+            LDV     supply_id           // Stack: [S]
+            IMMS    0                   // Stack: [S, 0]
+            BZ      double_prg_id  1  1  0  // Stack: [2 * S]
+            IMMS    1                   // Stack: [2 * S, 1]
+            BZ      double_prg_id  1  1  0  // Stack: [2 * S]
+            STV     result_id
+        }
+        .unwrap();
+        let vio = run(label_vec![], code).unwrap();
+        assert_eq!(
+            vio.load_vector(result_id).unwrap().data,
+            amount_vec![0, 10, 0, 4].data
+        );
+
+        // Conditional call must return as many outputs as it takes inputs
+        let code = abacus! {
+            LDV     supply_id
+            IMMS    0
+            BZ      double_prg_id  1  0  0
+        }
+        .unwrap();
+        assert!(matches!(
+            run(label_vec![], code),
+            Err(ErrorCode::InvalidInstruction)
+        ));
+    }
+
+    #[test]
+    fn test_select_and_clamp() {
+        use common::abacus::program_error::ErrorCode;
+
+        let quantity_id = 1;
+        let capacity_id = 2;
+        let clamped_id = 3;
+        let greater_id = 4;
+        let less_id = 5;
+        let selected_id = 6;
+
+        let run = |code: Vec<u8>| -> Result<test_utils::TestVectorIO, ErrorCode> {
+            let mut vio = test_utils::TestVectorIO::new();
+            vio.store_vector(quantity_id, amount_vec![1, 5, 3, 0.5])
+                .unwrap();
+            vio.store_vector(capacity_id, amount_vec![2, 4, 3, 1])
+                .unwrap();
+
+            let mut program = VectorVM::new(&mut vio);
+            program.execute(code, 0).map_err(|err| err.error_code)?;
+            Ok(vio)
+        };
+
+        let code = abacus! {
+            IMMS    1                   // Stack: [1]
+            LDV     capacity_id         // Stack: [1, C]
+            LDV     quantity_id         // Stack: [1, C, Q]
+            LDD     0                   // Stack: [1, C, Q, Q]
+            CLAMP   3   2               // Stack: [1, C, Q, MIN(MAX(Q, 1), C)]
+            STV     clamped_id          // Stack: [1, C, Q]
+            LDD     0                   // Stack: [1, C, Q, Q]
+            GT      2                   // Stack: [1, C, Q, Q > C]
+            STV     greater_id          // Stack: [1, C, Q]
+            LDD     0                   // Stack: [1, C, Q, Q]
+            LT      2                   // Stack: [1, C, Q, Q < C]
+            LDD     1                   // Stack: [1, C, Q, Q < C, Q]
+            SEL     1   3               // Stack: [1, C, Q, Q < C, Q < C ? Q : C]
+            STV     selected_id         // Stack: [1, C, Q, Q < C]
+            STV     less_id             // Stack: [1, C, Q]
+        }
+        .unwrap();
+        let vio = run(code).unwrap();
+        let load = |id| vio.load_vector(id).unwrap().data;
+        assert_eq!(load(clamped_id), amount_vec![1, 4, 3, 1].data);
+        assert_eq!(load(greater_id), amount_vec![0, 1, 0, 0].data);
+        assert_eq!(load(less_id), amount_vec![1, 0, 0, 1].data);
+        assert_eq!(load(selected_id), amount_vec![1, 4, 3, 0.5].data);
+
+        // Scalar operands
+        let code = abacus! {
+            IMMS    0                   // Stack: [0]
+            IMMS    2                   // Stack: [0, 2]
+            IMMS    3                   // Stack: [0, 2, 3]
+            CLAMP   2   1               // Stack: [0, 2, 2]
+            SEL     2   1               // Stack: [0, 2, 2]
+            GT      1                   // Stack: [0, 2, 0]
+            PKV     3
+            STV     selected_id
+        }
+        .unwrap();
+        let vio = run(code).unwrap();
+        assert_eq!(
+            vio.load_vector(selected_id).unwrap().data,
+            amount_vec![0, 2, 0].data
+        );
+
+        // Bounds must be aligned with the vector
+        let code = abacus! {
+            LDV     capacity_id
+            VPOP
+            POPN    1
+            LDV     quantity_id
+            CLAMP   1   1
+        }
+        .unwrap();
+        assert!(matches!(run(code), Err(ErrorCode::NotAligned(3, 4))));
+    }
+
+    #[test]
+    fn test_signed_values() {
+        use common::abacus::program_error::ErrorCode;
+
+        let delta_long_id = 1;
+        let delta_short_id = 2;
+        let prices_id = 3;
+        let exposure_id = 4;
+
+        let run = |code: Vec<u8>| -> Result<test_utils::TestVectorIO, ErrorCode> {
+            let mut vio = test_utils::TestVectorIO::new();
+            vio.store_vector(delta_long_id, amount_vec![3, 0, 2])
+                .unwrap();
+            vio.store_vector(delta_short_id, amount_vec![1, 4, 2])
+                .unwrap();
+            vio.store_vector(prices_id, amount_vec![1, 2, 3]).unwrap();
+
+            let mut program = VectorVM::new(&mut vio);
+            program.execute(code, 0).map_err(|err| err.error_code)?;
+            Ok(vio)
+        };
+
+        let code = abacus! {
+            LDV     delta_short_id      // Stack: [DS]
+            LDV     delta_long_id       // Stack: [DS, DL]
+            NET     1                   // Stack: [DS, D = DL - DS]
+            LDV     prices_id           // Stack: [DS, D, P]
+            LDD     1                   // Stack: [DS, D, P, D]
+            MUL     1                   // Stack: [DS, D, P, D * P]
+            VSUM                        // Stack: [DS, D, P, E = SUM(D * P)]
+            ABS                         // Stack: [DS, D, P, |E|]
+            PKV     1                   // Stack: [DS, D, P, [|E|]]
+            STV     exposure_id         // Stack: [DS, D, P]
+            POPN    1                   // Stack: [DS, D]
+            IMMS    1                   // Stack: [DS, D, 1]
+            NEG                         // Stack: [DS, D, -1]
+            SWAP    1                   // Stack: [DS, -1, D]
+            ADD     1                   // Stack: [DS, -1, D - 1]
+            LDD     0                   // Stack: [DS, -1, D - 1, D - 1]
+            POS                         // Stack: [DS, -1, D - 1, DL_new]
+            STV     delta_long_id       // Stack: [DS, -1, D - 1]
+            NEGPART                     // Stack: [DS, -1, DS_new]
+            STV     delta_short_id      // Stack: [DS, -1]
+        }
+        .unwrap();
+        let vio = run(code).unwrap();
+        let load = |id| vio.load_vector(id).unwrap().data;
+        assert_eq!(load(exposure_id), amount_vec![6].data);
+        assert_eq!(load(delta_long_id), amount_vec![1, 0, 0].data);
+        assert_eq!(load(delta_short_id), amount_vec![0, 5, 1].data);
+
+        // Signed vectors must be split before they are stored
+        let code = abacus! {
+            LDV     delta_short_id
+            LDV     delta_long_id
+            NET     1
+            STV     delta_long_id
+        }
+        .unwrap();
+        assert!(matches!(run(code), Err(ErrorCode::InvalidOperand)));
+
+        // Saturating subtraction is not defined for signed values
+        let code = abacus! {
+            LDV     delta_short_id
+            LDV     delta_long_id
+            NET     1
+            SSB     1
+        }
+        .unwrap();
+        assert!(matches!(run(code), Err(ErrorCode::InvalidOperand)));
+    }
+
+    #[test]
+    fn test_aggregation() {
+        use common::abacus::program_error::ErrorCode;
+
+        let weights_id = 1;
+        let prices_id = 2;
+        let result_id = 3;
+
+        let run = |code: Vec<u8>| -> Result<Vector, ErrorCode> {
+            let mut vio = test_utils::TestVectorIO::new();
+            vio.store_vector(weights_id, amount_vec![1, 3, 0, 4])
+                .unwrap();
+            vio.store_vector(prices_id, amount_vec![0.5, 2, 10, 0.25])
+                .unwrap();
+
+            let mut program = VectorVM::new(&mut vio);
+            program.execute(code, 0).map_err(|err| err.error_code)?;
+            Ok(vio.load_vector(result_id).unwrap())
+        };
+
+        let code = abacus! {
+            LDV     prices_id           // Stack: [P]
+            LDV     weights_id          // Stack: [P, W]
+            VDOT    1                   // Stack: [P, SUM(W * P)]
+            LDV     weights_id          // Stack: [P, SUM(W * P), W]
+            VDOT    0                   // Stack: [P, SUM(W * P), SUM(W * W)]
+            LDV     weights_id          // Stack: [P, SUM(W * P), SUM(W * W), W]
+            VCUMSUM                     // Stack: [P, SUM(W * P), SUM(W * W), [1, 4, 4, 8]]
+            UNPK                        // Stack: [P, SUM(W * P), SUM(W * W), 1, 4, 4, 8]
+            LDV     weights_id          // Stack: [P, SUM(W * P), SUM(W * W), 1, 4, 4, 8, W]
+            VNORM                       // Stack: [P, SUM(W * P), SUM(W * W), 1, 4, 4, 8, W / 8]
+            UNPK                        // Stack: [P, SUM(W * P), SUM(W * W), 1, 4, 4, 8, ...W / 8]
+            PKV     10
+            STV     result_id
+        }
+        .unwrap();
+        assert_eq!(
+            run(code).unwrap().data,
+            amount_vec![7.5, 26, 1, 4, 4, 8, 0.125, 0.375, 0, 0.5].data
+        );
+
+        // Weights summing to zero cannot be normalized
+        let code = abacus! {
+            LDV     weights_id
+            ZEROS   0
+            VNORM
+        }
+        .unwrap();
+        assert!(matches!(run(code), Err(ErrorCode::MathOverflow)));
+    }
+
+    #[test]
+    fn test_exp_ln_pow() {
+        use common::abacus::program_error::ErrorCode;
+
+        let values_id = 1;
+        let result_id = 2;
+
+        let run = |code: Vec<u8>| -> Result<Vector, ErrorCode> {
+            let mut vio = test_utils::TestVectorIO::new();
+            vio.store_vector(values_id, amount_vec![1, 2, 4]).unwrap();
+
+            let mut program = VectorVM::new(&mut vio);
+            program.execute(code, 0).map_err(|err| err.error_code)?;
+            Ok(vio.load_vector(result_id).unwrap())
+        };
+
+        let code = abacus! {
+            IMMS    0.5                 // Stack: [0.5]
+            LDV     values_id           // Stack: [0.5, V]
+            POW     1                   // Stack: [0.5, V^0.5]
+            UNPK                        // Stack: [0.5, ...V^0.5]
+            LDV     values_id           // Stack: [0.5, ...V^0.5, V]
+            LN                          // Stack: [0.5, ...V^0.5, LN(V)]
+            UNPK                        // Stack: [0.5, ...V^0.5, ...LN(V)]
+            IMMS    1                   // Stack: [0.5, ...V^0.5, ...LN(V), 1]
+            EXP                         // Stack: [0.5, ...V^0.5, ...LN(V), e]
+            IMMS    3                   // Stack: [0.5, ...V^0.5, ...LN(V), e, 3]
+            POW     0                   // Stack: [0.5, ...V^0.5, ...LN(V), e, 27]
+            PKV     8
+            STV     result_id
+        }
+        .unwrap();
+        assert_eq!(
+            run(code).unwrap().data,
+            amount_vec![
+                1,
+                1.414213562373095049,
+                2,
+                0,
+                0.693147180559945309,
+                1.386294361119890619,
+                2.718281828459045235,
+                27
+            ]
+            .data
+        );
+
+        // Logarithm of values below one would be negative
+        let code = abacus! {
+            LDV     values_id
+            IMMS    0.5
+            SWAP    1
+            MUL     1
+            LN
+        }
+        .unwrap();
+        assert!(matches!(run(code), Err(ErrorCode::MathOverflow)));
+    }
+
+    #[test]
+    fn test_vector_range() {
+        use common::abacus::program_error::ErrorCode;
+
+        let names_id = 1;
+        let values_id = 2;
+        let result_id = 3;
+
+        let run = |code: Vec<u8>| -> Result<test_utils::TestVectorIO, ErrorCode> {
+            let mut vio = test_utils::TestVectorIO::new();
+            vio.store_labels(names_id, label_vec![10, 20, 30, 40, 50])
+                .unwrap();
+            vio.store_vector(values_id, amount_vec![1, 2, 3, 4, 5])
+                .unwrap();
+
+            let mut program = VectorVM::new(&mut vio);
+            program.execute(code, 0).map_err(|err| err.error_code)?;
+            Ok(vio)
+        };
+
+        let code = abacus! {
+            LDLR    names_id  1  3      // Stack: [20, 30, 40]
+            STL     result_id
+            LDVR    values_id  1  3     // Stack: [2, 3, 4]
+            IMMS    10
+            SWAP    1
+            MUL     1                   // Stack: [10, [20, 30, 40]]
+            STVR    values_id  2        // Values: [1, 2, 20, 30, 40]
+        }
+        .unwrap();
+        let vio = run(code).unwrap();
+        assert_eq!(
+            vio.load_labels(result_id).unwrap().as_slice(),
+            vec![20, 30, 40]
+        );
+        assert_eq!(
+            vio.load_vector(values_id).unwrap().data,
+            amount_vec![1, 2, 20, 30, 40].data
+        );
+
+        // Ranges must be within bounds
+        let code = abacus! {
+            LDVR    values_id  4  2
+        }
+        .unwrap();
+        assert!(matches!(run(code), Err(ErrorCode::OutOfRange)));
+
+        let code = abacus! {
+            LDVR    values_id  0  2
+            STVR    values_id  4
+        }
+        .unwrap();
+        assert!(matches!(run(code), Err(ErrorCode::OutOfRange)));
+    }
+
+    #[test]
+    fn test_sparse() {
+        use crate::verifier::verify;
+        use common::abacus::program_error::ErrorCode;
+
+        let names_id = 1;
+        let values_id = 2;
+        let upd_names_id = 3;
+        let upd_values_id = 4;
+        let other_names_id = 5;
+        let other_values_id = 6;
+        let result_id = 7;
+        let gathered_names_id = 8;
+        let gathered_values_id = 9;
+
+        let run = |code: Vec<u8>| -> Result<test_utils::TestVectorIO, ErrorCode> {
+            let mut vio = test_utils::TestVectorIO::new();
+            vio.store_labels(names_id, label_vec![10, 20, 30, 40, 50])
+                .unwrap();
+            vio.store_vector(values_id, amount_vec![1, 2, 3, 4, 5])
+                .unwrap();
+            vio.store_labels(upd_names_id, label_vec![20, 40]).unwrap();
+            vio.store_vector(upd_values_id, amount_vec![7, 9]).unwrap();
+            vio.store_labels(other_names_id, label_vec![40, 50])
+                .unwrap();
+            vio.store_vector(other_values_id, amount_vec![1, 1])
+                .unwrap();
+
+            let mut program = VectorVM::new(&mut vio);
+            program.execute(code, 0).map_err(|err| err.error_code)?;
+            Ok(vio)
+        };
+
+        let code = abacus! {
+            LDL     names_id
+            LDV     values_id
+            LDL     upd_names_id
+            LDV     upd_values_id
+            SPK     1               // Stack: [L, V, UL, S1 = {20: 7, 40: 9}]
+            LDL     other_names_id
+            LDV     other_values_id
+            SPK     1               // Stack: [L, V, UL, S1, OL, S2 = {40: 1, 50: 1}]
+            ADD     2               // S2 = {20: 7, 40: 10, 50: 1}
+            LDD     4
+            SADD    1  6            // [1, 9, 3, 14, 6]
+            STV     result_id
+            MUL     2               // S2 = {20: 49, 40: 90}
+            LDD     4
+            SUPD    1  6            // [1, 49, 3, 90, 5]
+            STV     values_id
+            LDD     1
+            SGET    5  6            // Stack: [..., {40: 4, 50: 5}]
+            SUNPK
+            STV     gathered_values_id
+            STL     gathered_names_id
+        }
+        .unwrap();
+        verify(&code).unwrap();
+        let vio = run(code).unwrap();
+        assert_eq!(
+            vio.load_vector(result_id).unwrap().data,
+            amount_vec![1, 9, 3, 14, 6].data
+        );
+        assert_eq!(
+            vio.load_vector(values_id).unwrap().data,
+            amount_vec![1, 49, 3, 90, 5].data
+        );
+        assert_eq!(
+            vio.load_labels(gathered_names_id).unwrap().as_slice(),
+            vec![40, 50]
+        );
+        assert_eq!(
+            vio.load_vector(gathered_values_id).unwrap().data,
+            amount_vec![4, 5].data
+        );
+
+        // Sparse vector can be scaled, but not shifted by scalar
+        let code = abacus! {
+            IMMS    2
+            LDL     upd_names_id
+            LDV     upd_values_id
+            SPK     1
+            MUL     2
+            SUNPK
+            STV     result_id
+        }
+        .unwrap();
+        let vio = run(code).unwrap();
+        assert_eq!(
+            vio.load_vector(result_id).unwrap().data,
+            amount_vec![14, 18].data
+        );
+
+        let code = abacus! {
+            IMMS    2
+            LDL     upd_names_id
+            LDV     upd_values_id
+            SPK     1
+            ADD     2
+        }
+        .unwrap();
+        assert!(matches!(run(code), Err(ErrorCode::InvalidOperand)));
+
+        // Labels must be sorted
+        let code = abacus! {
+            LDL     upd_names_id
+            LPUSH   30
+            LDV     other_values_id
+            VPUSH   1
+            SPK     1
+        }
+        .unwrap();
+        assert!(matches!(run(code), Err(ErrorCode::InvalidOperand)));
+
+        // Labels of sparse vector must exist in dense vector
+        let code = abacus! {
+            LDL     names_id
+            LDV     values_id
+            LDL     other_names_id
+            LPUSH   60
+            LDV     other_values_id
+            VPUSH   1
+            SPK     1               // Stack: [L, V, OL, S = {40: 1, 50: 1, 60: 1}]
+            LDD     2
+            SADD    1  4
+        }
+        .unwrap();
+        assert!(matches!(run(code), Err(ErrorCode::NotFound)));
+    }
+
+    #[test]
+    fn test_transactional_vio() {
+        use crate::transaction::TransactionalVectorIO;
+        use common::abacus::program_error::ErrorCode;
+
+        let values_id = 1;
+        let result_id = 2;
+        let copy_id = 3;
+
+        let mut vio = test_utils::TestVectorIO::new();
+        vio.store_vector(values_id, amount_vec![1, 2, 3, 4, 5])
+            .unwrap();
+
+        // Failed program leaves no writes behind
+        let code = abacus! {
+            LDV     values_id
+            STV     result_id
+            LDV     result_id
+            LDV     99
+        }
+        .unwrap();
+        let mut tx = TransactionalVectorIO::new(&mut vio);
+        let err = tx.execute(code, 0).unwrap_err();
+        assert!(matches!(err.error_code, ErrorCode::NotFound));
+        assert!(!tx.is_dirty());
+        assert!(vio.load_vector(result_id).is_err());
+
+        let code = abacus! {
+            LDV     values_id
+            IMMS    2
+            SWAP    1
+            MUL     1
+            STV     result_id           // Result: [2, 4, 6, 8, 10]
+            LDVR    values_id  1  2
+            STVR    values_id  3        // Values: [1, 2, 3, 2, 3]
+            LDV     values_id
+            STV     copy_id             // Loads see pending writes
+        }
+        .unwrap();
+        let mut tx = TransactionalVectorIO::new(&mut vio);
+        let mut program = VectorVM::new(&mut tx);
+        program.execute(code, 0).unwrap();
+
+        assert_eq!(tx.written_vectors().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert!(tx.inner().load_vector(result_id).is_err());
+
+        let write_set = tx.write_set().unwrap();
+        assert!(write_set.labels.is_empty());
+        let values = &write_set.vectors[0];
+        assert_eq!(values.id, values_id);
+        assert_eq!(
+            values.before.as_ref().unwrap().data,
+            amount_vec![1, 2, 3, 4, 5].data
+        );
+        assert_eq!(values.after.data, amount_vec![1, 2, 3, 2, 3].data);
+        let result = &write_set.vectors[1];
+        assert!(result.before.is_none());
+        assert_eq!(result.after.data, amount_vec![2, 4, 6, 8, 10].data);
+
+        tx.commit().unwrap();
+        assert!(!tx.is_dirty());
+        assert_eq!(
+            vio.load_vector(values_id).unwrap().data,
+            amount_vec![1, 2, 3, 2, 3].data
+        );
+        assert_eq!(
+            vio.load_vector(result_id).unwrap().data,
+            amount_vec![2, 4, 6, 8, 10].data
+        );
+        assert_eq!(
+            vio.load_vector(copy_id).unwrap().data,
+            amount_vec![1, 2, 3, 2, 3].data
+        );
+
+        // Range writes must be within bounds
+        let code = abacus! {
+            LDV     values_id
+            STVR    values_id  1
+        }
+        .unwrap();
+        let err = TransactionalVectorIO::new(&mut vio)
+            .execute(code, 0)
+            .unwrap_err();
+        assert!(matches!(err.error_code, ErrorCode::OutOfRange));
+    }
+
+    #[test]
+    fn test_rounding() {
+        use crate::verifier::verify;
+        use common::{abacus::program_error::ErrorCode, amount::Amount};
+
+        let raw_id = 1;
+        let half_id = 2;
+        let values_id = 3;
+        let vector_output_id = 100;
+        let scalar_output_id = 101;
+
+        let run = |code: Vec<u8>| -> Result<test_utils::TestVectorIO, ErrorCode> {
+            let mut vio = test_utils::TestVectorIO::new();
+            let raw = vec![Amount(1), Amount(3), Amount(5)];
+            vio.store_vector(raw_id, Vector { data: raw }).unwrap();
+            vio.store_vector(half_id, amount_vec![0.5]).unwrap();
+            vio.store_vector(values_id, amount_vec![1, 2, 3]).unwrap();
+
+            let mut program = VectorVM::new(&mut vio);
+            program.execute(code, 0).map_err(|err| err.error_code)?;
+            Ok(vio)
+        };
+        let raw = |values: &[u128]| values.iter().copied().map(Amount).collect::<Vec<_>>();
+
+        // Products of dust amounts by one half, i.e. [0.5, 1.5, 2.5]
+        let halves = |code: Vec<u8>| {
+            let vio = run(code).unwrap();
+            vio.load_vector(vector_output_id).unwrap().data
+        };
+        let down = abacus! {
+            LDV     half_id             // [H]
+            VPOP                        // [H, h]
+            LDV     raw_id              // [H, h, R]
+            MUL     1                   // [H, h, R * h]
+            STV     vector_output_id    // [H, h]
+        }
+        .unwrap();
+        let up = abacus! {
+            LDV     half_id
+            VPOP
+            LDV     raw_id
+            RNDU
+            MUL     1
+            STV     vector_output_id
+        }
+        .unwrap();
+        let half_even = abacus! {
+            LDV     half_id
+            VPOP
+            LDV     raw_id
+            RNDE
+            MUL     1
+            STV     vector_output_id
+        }
+        .unwrap();
+        assert_eq!(halves(down), raw(&[0, 1, 2]));
+        assert_eq!(halves(up), raw(&[1, 2, 3]));
+        assert_eq!(halves(half_even), raw(&[0, 2, 2]));
+
+        // Thirds of [1, 2, 3], and of scalar 2
+        let thirds = |code: Vec<u8>| {
+            let vio = run(code).unwrap();
+            let mut result = vio.load_vector(vector_output_id).unwrap().data;
+            result.extend(vio.load_vector(scalar_output_id).unwrap().data);
+            result
+        };
+        let down = abacus! {
+            IMMS    3                   // [3]
+            LDV     values_id           // [3, V]
+            DIV     1                   // [3, V / 3]
+            STV     vector_output_id    // [3]
+            IMMS    2                   // [3, 2]
+            DIV     1                   // [3, 2 / 3]
+            PKV     1                   // [3, [2 / 3]]
+            STV     scalar_output_id    // [3]
+        }
+        .unwrap();
+        let up = abacus! {
+            IMMS    3
+            LDV     values_id
+            RNDU
+            DIV     1
+            STV     vector_output_id
+            IMMS    2
+            RNDU
+            DIV     1
+            PKV     1
+            STV     scalar_output_id
+        }
+        .unwrap();
+        let half_even = abacus! {
+            IMMS    3
+            LDV     values_id
+            RNDE
+            DIV     1
+            STV     vector_output_id
+            IMMS    2
+            RNDE
+            DIV     1
+            PKV     1
+            STV     scalar_output_id
+        }
+        .unwrap();
+        let third = Amount::SCALE / 3;
+        assert_eq!(
+            thirds(down),
+            raw(&[third, 2 * third, Amount::SCALE, 2 * third])
+        );
+        assert_eq!(
+            thirds(up),
+            raw(&[third + 1, 2 * third + 1, Amount::SCALE, 2 * third + 1])
+        );
+        assert_eq!(
+            thirds(half_even),
+            raw(&[third, 2 * third + 1, Amount::SCALE, 2 * third + 1])
+        );
+
+        // Modifier applies only to MUL or DIV immediately following it
+        let code = abacus! {
+            #![unchecked]
+            LDV     values_id
+            LDV     values_id
+            RNDU
+            ADD     1
+        }
+        .unwrap();
+        let err = verify(&code).unwrap_err();
+        assert!(matches!(err.error_code, ErrorCode::InvalidInstruction));
+        assert!(matches!(run(code), Err(ErrorCode::InvalidInstruction)));
+
+        let code = abacus! {
+            #![unchecked]
+            LDV     values_id
+            LDV     values_id
+            MUL     1
+            RNDE
+        }
+        .unwrap();
+        let err = verify(&code).unwrap_err();
+        assert!(matches!(err.error_code, ErrorCode::InvalidInstruction));
+
+        // Rounding is not defined for signed values
+        let code = abacus! {
+            LDV     values_id
+            LDV     values_id
+            NET     1
+            RNDU
+            MUL     0
+        }
+        .unwrap();
+        assert!(matches!(run(code), Err(ErrorCode::InvalidOperand)));
+    }
+
+    #[test]
+    fn test_templates() {
+        use crate::transaction::TransactionalVectorIO;
+        use crate::verifier::verify;
+        use abacus_macros::abacus_compact;
+        use common::abacus::program_error::ErrorCode;
+
+        // Template copying labels and vector, and storing its label argument
+        let template = abacus! {
+            ARGS [labels_id, vector_id, output_id, label]
+            LDL     labels_id       // [L]
+            STL     output_id       // []
+            LDV     vector_id       // [V]
+            STV     output_id       // []
+            IMML    label           // [l]
+            PKL     1               // [[l]]
+            STL     200             // []
+        }
+        .unwrap();
+        let expected = abacus! {
+            ARGS    4
+            LDLA    0
+            STLA    2
+            LDVA    1
+            STVA    2
+            LDA     3
+            PKL     1
+            STL     200
+        }
+        .unwrap();
+        assert_eq!(template, expected);
+        assert_eq!(verify(&template).unwrap().num_args, 4);
+
+        let mut vio = test_utils::TestVectorIO::new();
+        vio.store_labels(1, label_vec![5, 7]).unwrap();
+        vio.store_vector(2, amount_vec![1, 2]).unwrap();
+        TransactionalVectorIO::new(&mut vio)
+            .execute_with_args(template.clone(), 0, vec![1, 2, 100, 42])
+            .unwrap();
+        assert_eq!(vio.load_labels(100).unwrap().as_slice(), vec![5, 7]);
+        assert_eq!(vio.load_vector(100).unwrap().data, amount_vec![1, 2].data);
+        assert_eq!(vio.load_labels(200).unwrap().as_slice(), vec![42]);
 
-        vio.store_vector(4, data_1).unwrap();
-        vio.store_vector(5, data_2).unwrap();
-        vio.store_vector(6, data_3).unwrap();
+        // Wrong number of arguments leaves nothing stored
+        let mut vio = test_utils::TestVectorIO::new();
+        vio.store_labels(1, label_vec![5, 7]).unwrap();
+        let err = TransactionalVectorIO::new(&mut vio)
+            .execute_with_args(template, 0, vec![1, 2, 100])
+            .unwrap_err();
+        assert!(matches!(err.error_code, ErrorCode::OutOfRange));
+        assert!(vio.load_labels(100).is_err());
+
+        // Arguments must be declared first, and used within declared count
+        let err = verify(&abacus! { IMML 1  ARGS 1 }.unwrap()).unwrap_err();
+        assert!(matches!(err.error_code, ErrorCode::InvalidInstruction));
+        let err = verify(&abacus! { ARGS 1  LDA 1 }.unwrap()).unwrap_err();
+        assert!(matches!(err.error_code, ErrorCode::OutOfRange));
+        let err = verify(&abacus! { LDVA 0 }.unwrap()).unwrap_err();
+        assert!(matches!(err.error_code, ErrorCode::OutOfRange));
+
+        // Same is enforced at run-time, as programs aren't always verified
+        let run = |code: Vec<u8>, args: Vec<u128>| {
+            let mut vio = test_utils::TestVectorIO::new();
+            vio.store_vector(7, amount_vec![1, 2]).unwrap();
+            VectorVM::new(&mut vio)
+                .execute_with_args(code, 0, args)
+                .map_err(|err| err.error_code)
+        };
+        let code = abacus! { IMML 1  ARGS 1 }.unwrap();
+        assert!(matches!(
+            run(code, vec![7]),
+            Err(ErrorCode::InvalidInstruction)
+        ));
+        let code = abacus! { LDVA 0  STV 8 }.unwrap();
+        assert!(matches!(run(code, vec![7]), Err(ErrorCode::OutOfRange)));
+        let code = abacus! { ARGS 0  LDA 0  POPN 1 }.unwrap();
+        assert!(matches!(run(code, vec![]), Err(ErrorCode::OutOfRange)));
+        let code = abacus_compact! { ARGS 1  LDVA 0  STV 8 }.unwrap();
+        assert!(run(code, vec![7]).is_ok());
+    }
 
-        let code = abacus! {
-            LDL     1           //  [1]
-            LDL     2           //  [1, 2]
-            LDL     3           //  [1, 2, 3]
-            LDD     0
-            STR     _L3
-            LUNION  1           //  [1, 2, (2 u 3)]
-            LUNION  2           //  [1, 2, U = (1 u (2 u 3))]
-            LDD     0
-            STL     10
+    #[test]
+    fn test_procedures() {
+        use common::abacus::program::{procedure_id, PROCEDURE_ID_FLAG};
+
+        let vector_id = 1;
+        let result_id = 100;
+
+        let program = abacus! {
+            #![stack_comments]
+            PROC sum(2, 1, 0) {
+                ADD     1           // Stack: [Sum, Item + Sum]
+                SWAP    1           // Stack: [Item + Sum, Sum]
+                POPN    1           // Stack: [Item + Sum]
+            }
+            PROC double(1, 1, 0) {
+                ADD     0           // Stack: [2 * X]
+            }
+            IMMS    0               // Stack: [0]
+            LDV     vector_id       // Stack: [0, V]
+            FOLD    sum             // Stack: [Sum]
+            B       double          // Stack: [2 * Sum]
+            PKV     1               // Stack: [[2 * Sum]]
+            STV     result_id       // Stack: []
+        }
+        .unwrap();
 
-            LDV     4
-            LDV     5
-            LDV     6           // [1, 2, U, 4, 5, 6]
+        // Sizes of sub-routine calls are taken from PROC declarations
+        let explicit = abacus! {
+            PROC sum(2, 1, 0) {
+                ADD     1
+                SWAP    1
+                POPN    1
+            }
+            PROC double(1, 1, 0) {
+                ADD     0
+            }
+            IMMS    0
+            LDV     vector_id
+            FOLD    sum     1  1  0
+            B       double  1  1  0
+            PKV     1
+            STV     result_id
+        }
+        .unwrap();
+        assert_eq!(program, explicit);
 
-            ZEROS   3           // [1, 2, U, 4, 5, 6, Z]
-            JUPD    3   4   6   // [1, 2, U, 4, 5, 6, A = Z <- 4]
-            JUPD    2   4   5   // [1, 2, U, 4, 5, 6, A <- 5]
-            STV     11
+        // Procedure ids are derived from their bytecode
+        let double = program.procedure("double").unwrap();
+        let double_code = abacus! {
+            #![inputs(1)]
+            ADD 0
+        }
+        .unwrap();
+        assert_eq!(double.code, double_code);
+        assert_eq!(double.id, procedure_id(&double_code));
+        assert_ne!(double.id & PROCEDURE_ID_FLAG, 0);
+        assert_ne!(double.id, program.procedure("sum").unwrap().id);
 
-            LDM     _L3         // [1, 2, U, 4, 5, 6, 3]
-            ZEROS   4           // [1, 2, U, 4, 5, 6, 3, Z]
-            JUPD    2   5   1
-            STV     12
-        };
+        let mut vio = test_utils::TestVectorIO::new();
+        vio.store_vector(vector_id, amount_vec![1, 2, 3]).unwrap();
+        for procedure in &program.procedures {
+            vio.store_code(procedure.id, procedure.code.clone())
+                .unwrap();
+        }
 
-        let num_registry = 1;
-        let mut stack = Stack::new(num_registry);
         let mut program = VectorVM::new(&mut vio);
+        program.execute(explicit.code, 0).unwrap();
+        assert_eq!(
+            vio.load_vector(result_id).unwrap().data,
+            amount_vec![12].data
+        );
+    }
 
-        if let Err(err) = program.execute_with_stack(code.unwrap(), &mut stack) {
-            log_stack!(&stack);
-            panic!("Failed to execute test: {:?}", err);
+    #[test]
+    fn test_vil_update_quote() {
+        use abacus_formulas::update_quote::update_quote;
+        use abacus_macros::vil;
+
+        let (index_asset_names_id, weights_id, quote_id) = (1, 2, 3);
+        let (market_asset_names_id, asset_prices_id) = (4, 5);
+        let (asset_slopes_id, asset_liquidity_id) = (6, 7);
+
+        let code = vil! {
+            labels assets = index_asset_names_id;
+            labels market = market_asset_names_id;
+            vector weights[assets] = weights_id;
+            vector prices[market] = asset_prices_id;
+            vector slopes[market] = asset_slopes_id;
+            vector liquidity[market] = asset_liquidity_id;
+            vector quote = quote_id;
+
+            let price = vdot(prices[assets], weights);
+            let slope = vdot(slopes[assets], weights * weights);
+            let capacity = vmin(liquidity[assets] / weights);
+            store quote = [capacity, price, slope];
         }
+        .unwrap();
+        let expected = update_quote(
+            index_asset_names_id,
+            weights_id,
+            quote_id,
+            market_asset_names_id,
+            asset_prices_id,
+            asset_slopes_id,
+            asset_liquidity_id,
+        )
+        .unwrap();
 
-        let result10 = vio.load_labels(10).unwrap();
-        assert_eq!(result10.data, label_vec![1, 2, 3, 5].data);
+        let run = |code: Vec<u8>| {
+            let mut vio = test_utils::TestVectorIO::new();
+            vio.store_labels(index_asset_names_id, label_vec![102, 104])
+                .unwrap();
+            vio.store_vector(weights_id, amount_vec![0.5, 2]).unwrap();
+            vio.store_labels(market_asset_names_id, label_vec![101, 102, 103, 104])
+                .unwrap();
+            vio.store_vector(asset_prices_id, amount_vec![1, 10, 100, 1000])
+                .unwrap();
+            vio.store_vector(asset_slopes_id, amount_vec![0.1, 0.2, 0.3, 0.4])
+                .unwrap();
+            vio.store_vector(asset_liquidity_id, amount_vec![5, 30, 50, 80])
+                .unwrap();
+            let mut program = VectorVM::new(&mut vio);
+            program.execute(code, 16).unwrap();
+            vio.load_vector(quote_id).unwrap().data
+        };
 
-        let result11 = vio.load_vector(11).unwrap();
-        assert_eq!(result11.data, amount_vec![1.5, 0, 2.5, 0].data);
-        
-        let result12 = vio.load_vector(12).unwrap();
-        assert_eq!(result12.data, amount_vec![0, 5.5, 6.5, 7.5].data);
+        // Capacity = MIN(30 / 0.5, 80 / 2), Price = 10 * 0.5 + 1000 * 2,
+        // Slope = 0.2 * 0.5^2 + 0.4 * 2^2
+        let quote = run(code.clone());
+        assert_eq!(quote, amount_vec![40, 2005, 1.65].data);
+        assert_eq!(quote, run(expected));
+
+        let optimized = crate::optimizer::optimize(&code).unwrap();
+        assert_eq!(run(optimized), quote);
     }
 
     #[test]
-    fn test_transpose() {
-        let mut vio = test_utils::TestVectorIO::new();
-        let num_registers = 8;
-
-        // --- 1. Setup VIO Inputs ---
-        let vector1_id = 100;
-        let vector2_id = 101;
-        let expected1_id = 102; // T1: [1, 4]
-        let expected2_id = 103; // T2: [2, 5]
-        let expected3_id = 104; // T3: [3, 6]
-        let delta_id = 105;
+    fn test_vil() {
+        use abacus_macros::vil;
+        use common::amount::Amount;
+
+        let (all_id, sub_id, values_id, updates_id) = (1, 2, 3, 4);
+        let (updated_id, scaled_id, selected_id, scalars_id) = (100, 101, 102, 103);
+        let union_id = 104;
+
+        let code = vil! {
+            labels all = all_id;
+            labels sub = sub_id;
+            vector values[all] = values_id;
+            vector updates[sub] = updates_id;
+            vector updated[all] = updated_id;
+            vector scaled = scaled_id;
+            vector selected[all] = selected_id;
+            vector scalars = scalars_id;
+            labels union_labels = union_id;
+
+            // Scalar operand on the left is broadcast to vector
+            let doubled: vector[all] = 2 * values;
+            store scaled = 1 - values / 4;
+
+            // Components with labels of sub are updated and incremented
+            doubled[sub] = updates;
+            doubled[sub] += 1;
+            store updated = doubled;
+            store selected = select(values > 2, values, 0);
+
+            let short = net(values[sub], updates);
+            let maximum = max(values, 2);
+            store scalars = [
+                vsum(maximum),
+                vmax(doubled),
+                round_up(1 / 3),
+                clamp(5, 1, 3),
+                abs(vsum(short)),
+            ];
+            store union_labels = union(sub, all);
+        }
+        .unwrap();
 
-        vio.store_vector(vector1_id, amount_vec![1, 2, 3]).unwrap();
-        vio.store_vector(vector2_id, amount_vec![4, 5, 6]).unwrap();
-        vio.store_vector(expected1_id, amount_vec![1, 4]).unwrap();
-        vio.store_vector(expected2_id, amount_vec![2, 5]).unwrap();
-        vio.store_vector(expected3_id, amount_vec![3, 6]).unwrap();
+        let mut vio = test_utils::TestVectorIO::new();
+        vio.store_labels(all_id, label_vec![1, 2, 3, 4]).unwrap();
+        vio.store_labels(sub_id, label_vec![2, 4]).unwrap();
+        vio.store_vector(values_id, amount_vec![1, 2, 3, 4])
+            .unwrap();
+        vio.store_vector(updates_id, amount_vec![10, 20]).unwrap();
+        let mut program = VectorVM::new(&mut vio);
+        program.execute(code, 16).unwrap();
 
-        // --- 2. VIL Code Execution ---
-        let code = abacus![
-            // 1. Setup Transposition
-            LDV         vector1_id              // Stack: [V1]
-            LDV         vector2_id              // Stack: [V1, V2]
-            T           2                       // Stack: [T1, T2, T3] (3 vectors)
+        let load = |id| vio.load_vector(id).unwrap().data;
+        assert_eq!(load(updated_id), amount_vec![2, 11, 6, 21].data);
+        assert_eq!(load(scaled_id), amount_vec![0.75, 0.5, 0.25, 0].data);
+        assert_eq!(load(selected_id), amount_vec![0, 0, 3, 4].data);
+        assert_eq!(
+            load(scalars_id),
+            vec![
+                amount_vec![11].data[0],
+                amount_vec![21].data[0],
+                Amount::from_u128_raw(333_333_333_333_333_334),
+                amount_vec![3].data[0],
+                amount_vec![24].data[0],
+            ]
+        );
+        assert_eq!(
+            vio.load_labels(union_id).unwrap().as_slice(),
+            vec![1, 2, 3, 4]
+        );
+    }
 
-            // 2. Load Expected Vectors for comparison
-            LDV         expected1_id            // [T1, T2, T3, E1]
-            LDV         expected2_id            // [T1, T2, T3, E1, E2]
-            LDV         expected3_id            // [T1, T2, T3, E1, E2, E3] (6 vectors)
+    #[test]
+    fn test_optimize() {
+        use crate::optimizer::optimize;
+        use abacus_macros::abacus_compact;
+        use common::abacus::{
+            instruction_set::{OP_LDD, OP_LDV, OP_POPN},
+            program_error::ErrorCode,
+        };
 
-            // 3. D3 = T3 - E3
-            SUB         3                       // Stack: [T1, T2, T3, E1, E2, D3]
+        let code = abacus! {
+            IMMS    0               // [0]
+            STR     _Tmp            // []               kept, as register may not exist
+            LDV     1               // [A]
+            LDV     2               // [A, B]
+            SWAP    1               // [B, A]           loads reordered
+            STR     _Tmp            // [B]
+            LDM     _Tmp            // [B, A]           stored and moved back
+            ADD     1               // [B, A + B]
+            LDD     0               // [B, A + B, A + B]
+            SWAP    1               // [B, A + B, A + B] swapped with copy
+            STR     _Tmp            // [B, A + B]       stored again before read
+            STV     3               // [B]
+            LDV     2               // [B, B]           copied with LDD 0
+            MUL     1               // [B, B * B]
+            STV     4               // [B]
+            IMMS    1               // [B, 1]
+            SWAP    1               // [1, B]
+            SWAP    1               // [B, 1]           swapped back
+            SWAP    1               // [1, B]
+            ADD     1               // [1, B + 1]       kept, as it may fail
+            STR     _Tmp            // [1]              kept, as registers are live at exit
+            POPN    1               // []
+            IMMS    2               // [2]              pushed only to be popped
+            POPN    1               // []               merged
+        }
+        .unwrap();
+        let expected = abacus! {
+            IMMS    0
+            STR     _Tmp
+            LDV     2
+            LDV     1
+            ADD     1
+            STV     3
+            LDD     0
+            MUL     1
+            STV     4
+            IMMS    1
+            SWAP    1
+            ADD     1
+            STR     _Tmp
+            POPN    1
+        }
+        .unwrap();
+        let optimized = optimize(&code).unwrap();
+        assert_eq!(optimized, expected);
 
-            // 4. D2 = T2 - E2
-            SWAP        1                       // Stack: [T1, T2, T3, E1, D3, E2]
-            SUB         4                       // Stack: [T1, T2, T3, E1, D3, D2]
+        let mut vio = test_utils::TestVectorIO::new();
+        vio.store_vector(1, amount_vec![1, 2]).unwrap();
+        vio.store_vector(2, amount_vec![3, 4]).unwrap();
+        let mut optimized_vio = vio.optimized_copy();
+        VectorVM::new(&mut vio).execute(code, 2).unwrap();
+        VectorVM::new(&mut optimized_vio)
+            .execute(optimized, 2)
+            .unwrap();
+        vio.assert_same_data(&optimized_vio);
+        assert_eq!(vio.load_vector(3).unwrap().data, amount_vec![4, 6].data);
+        assert_eq!(vio.load_vector(4).unwrap().data, amount_vec![9, 16].data);
 
-            // 5. D1 = T1 - E1
-            SWAP        2                       // Stack: [T1, T2, T3, D2, D3, E1]
-            SUB         5                       // Stack: [T1, T2, T3, D2, D3, D1]
+        // Register read for the last time before it is stored again is moved
+        let code = abacus! {
+            LDV     1
+            STR     _X
+            LDV     2
+            LDR     _X
+            ADD     1
+            STV     3
+            STR     _X
+        };
+        let expected = abacus! {
+            LDV     1
+            STR     _X
+            LDV     2
+            LDM     _X
+            ADD     1
+            STV     3
+            STR     _X
+        };
+        assert_eq!(optimize(&code.unwrap()).unwrap(), expected.unwrap());
 
-            // 6. Compute total delta - should be zero
-            ADD         1                       // Stack: [T1, T2, T3, D2, D3, D1 + D3]
-            ADD         2                       // Stack: [T1, T2, T3, D2, D3, D1 + D3 + D2]
+        // Product computed only to be popped is kept, as it may overflow,
+        // and so is load of missing vector, so that optimized program fails
+        // same as the original
+        let code = abacus! {
+            LDV     1
+            LDV     2
+            RNDU
+            MUL     1
+            POPN    1
+            LDV     9
+            POPN    1
+            STV     3
+        }
+        .unwrap();
+        let expected = optimize(&code).unwrap();
+        assert_eq!(expected, code);
 
-            // 7. Store the final zero vector
-            STV         delta_id
-        ];
+        let mut vio = test_utils::TestVectorIO::new();
+        vio.store_vector(1, amount_vec![1, 2]).unwrap();
+        vio.store_vector(2, amount_vec![3, 4]).unwrap();
+        let mut optimized_vio = vio.optimized_copy();
+        let err = VectorVM::new(&mut vio).execute(code, 1).unwrap_err();
+        let optimized_err = VectorVM::new(&mut optimized_vio)
+            .execute(expected, 1)
+            .unwrap_err();
+        assert!(matches!(err.error_code, ErrorCode::NotFound));
+        assert!(matches!(optimized_err.error_code, ErrorCode::NotFound));
+
+        // Copy of operand, which may not exist, is kept too
+        let code = abacus! {
+            LDV     1
+            LDD     0
+            POPN    1
+            STV     3
+        };
+        let code = [&[OP_LDD, 2, OP_POPN, 1][..], &code.unwrap()].concat();
+        let expected = abacus! {
+            LDV     1
+            STV     3
+        };
+        let expected = [&[OP_LDD, 2, OP_POPN, 1][..], &expected.unwrap()].concat();
+        assert_eq!(optimize(&code).unwrap(), expected);
+
+        // Compact encoding is kept
+        let code = abacus_compact! {
+            LDV     1000
+            LDV     1001
+            SWAP    1
+            STV     1000
+            STV     1001
+        };
+        let expected = abacus_compact! {
+            LDV     1001
+            LDV     1000
+            STV     1000
+            STV     1001
+        };
+        assert_eq!(optimize(&code.unwrap()).unwrap(), expected.unwrap());
 
-        let mut stack = Stack::new(num_registers);
-        let mut program = VectorVM::new(&mut vio);
+        let err = optimize(&[OP_LDV, 1]).unwrap_err();
+        assert!(matches!(err.error_code, ErrorCode::TruncatedInstruction));
+        assert_eq!(err.op_code, Some(OP_LDV));
+    }
 
-        if let Err(err) = program.execute_with_stack(code.unwrap(), &mut stack) {
-            log_stack!(&stack);
-            panic!("Failed to execute test: {:?}", err);
+    #[test]
+    fn test_optimize_fold_registers() {
+        use crate::optimizer::optimize;
+
+        let labels_id = 1;
+        let prev_prg_id = 2;
+        let result_id = 3;
+
+        // Sub-routine carries register over to the next iteration, and so
+        // storing it is kept even though it is never read again. Register is
+        // read before it is written, which is unchecked to avoid warning.
+        let prev = abacus! {
+            #![inputs(1)]
+            #![unchecked]
+            LDM     _Prev
+            SWAP    1
+            STR     _Prev
         }
+        .unwrap();
+        assert_eq!(optimize(&prev).unwrap(), prev);
 
-        // --- 3. Assertion ---
-        let delta = vio.load_vector(delta_id).unwrap();
+        let code = abacus! {
+            LDL     labels_id
+            FOLD    prev_prg_id  0  2  1
+            PKL     2
+            STL     result_id
+        }
+        .unwrap();
 
-        assert_eq!(delta.data, amount_vec![0, 0].data);
+        let mut vio = test_utils::TestVectorIO::new();
+        vio.store_labels(labels_id, label_vec![7, 8, 9]).unwrap();
+        vio.store_code(prev_prg_id, prev).unwrap();
+        let mut program = test_utils::TestProgram::new(&mut vio);
+        program.execute("Fold with register", code);
+        assert_eq!(vio.load_labels(result_id).unwrap().as_slice(), vec![7, 8]);
     }
 }
 
 mod test_scenarios {
     use abacus_formulas::{
-        add_market_assets::add_market_assets, create_market::create_market,
-        execute_rebalance::execute_rebalance, execute_sell_order::execute_sell_order,
-        execute_transfer::execute_transfer, solve_quadratic_ask::solve_quadratic_ask,
-        update_margin::update_margin, update_market_data::update_market_data,
-        update_quote::update_quote, update_rebalance::update_rebalance,
+        add_market_assets::add_market_assets,
+        create_market::create_market,
+        execute_rebalance::execute_rebalance,
+        execute_sell_order::execute_sell_order,
+        execute_transfer::execute_transfer,
+        solve_quadratic_ask::solve_quadratic_ask,
+        submit_buy_order::submit_buy_order,
+        submit_sell_order::submit_sell_order,
+        update_margin::update_margin,
+        update_market_data::update_market_data,
+        update_quote::{update_quote, update_quote_template},
+        update_rebalance::update_rebalance,
         update_supply::update_supply,
     };
     use amount_macros::amount;
@@ -429,12 +2398,10 @@ mod test_scenarios {
 
         let num_registers = 23;
 
-        let mut program = VectorVM::new(&mut vio);
-        let mut stack = Stack::new(num_registers);
-        let result = program.execute_with_stack(code.unwrap(), &mut stack);
+        let result =
+            test_utils::execute_optimized(&mut vio, code.unwrap(), num_registers, Vec::new());
 
         if let Err(err) = result {
-            log_stack!(&stack);
             panic!("Failed to execute test: {:?}", err);
         }
 
@@ -616,12 +2583,10 @@ mod test_scenarios {
 
         let num_registers = 22;
 
-        let mut program = VectorVM::new(&mut vio);
-        let mut stack = Stack::new(num_registers);
-        let result = program.execute_with_stack(code.unwrap(), &mut stack);
+        let result =
+            test_utils::execute_optimized(&mut vio, code.unwrap(), num_registers, Vec::new());
 
         if let Err(err) = result {
-            log_stack!(&stack);
             panic!("Failed to execute test: {:?}", err);
         }
 
@@ -701,12 +2666,10 @@ mod test_scenarios {
 
         let num_registers = 6;
 
-        let mut program = VectorVM::new(&mut vio);
-        let mut stack = Stack::new(num_registers);
-        let result = program.execute_with_stack(code.unwrap(), &mut stack);
+        let result =
+            test_utils::execute_optimized(&mut vio, code.unwrap(), num_registers, Vec::new());
 
         if let Err(err) = result {
-            log_stack!(&stack);
             panic!("Failed to execute test: {:?}", err);
         }
         let sender_bid_after = vio.load_vector(sender_bid_id).unwrap();
@@ -800,6 +2763,7 @@ mod test_scenarios {
 
         let weights_id = 1001;
         let quote_id = 1002;
+        let template_quote_id = 1003;
 
         vio.store_labels(asset_names_id, label_vec![101, 103, 104])
             .unwrap();
@@ -843,6 +2807,8 @@ mod test_scenarios {
                 market_asset_prices_id,
                 market_asset_slopes_id,
                 market_asset_liquidity_id,
+                0, // Market assets range spanning 101..=104
+                4,
             )
             .unwrap(),
         );
@@ -878,6 +2844,20 @@ mod test_scenarios {
             .unwrap(),
         );
 
+        program.execute_with_args(
+            "update quote template",
+            update_quote_template().unwrap(),
+            vec![
+                asset_names_id,
+                weights_id,
+                template_quote_id,
+                market_asset_names_id,
+                market_asset_prices_id,
+                market_asset_slopes_id,
+                market_asset_liquidity_id,
+            ],
+        );
+
         let new_margin = vio.load_vector(margin_id).unwrap();
         assert_eq!(
             new_margin.data,
@@ -987,7 +2967,11 @@ mod test_scenarios {
         assert_eq!(
             new_quote.data,
             amount_vec![1.250000000, 12000.000000000, 1120.000000000].data
-        )
+        );
+
+        // Template computes the same quote as program compiled with ids
+        let template_quote = vio.load_vector(template_quote_id).unwrap();
+        assert_eq!(template_quote.data, new_quote.data);
     }
 
     #[test]
@@ -1017,35 +3001,20 @@ mod test_scenarios {
         vio.store_vector(total_ask_id, amount_vec![0.3, 0.1, 200.0])
             .unwrap();
 
-        vio.store_labels(
-            asset_names_id,
-            Labels {
-                data: old_asset_names.data.clone(),
-            },
-        )
-        .unwrap();
+        vio.store_labels(asset_names_id, old_asset_names.clone())
+            .unwrap();
 
         vio.store_vector(asset_weights_id, amount_vec![0.1, 0.2, 1.0])
             .unwrap();
 
-        vio.store_labels(
-            new_asset_names_id,
-            Labels {
-                data: new_asset_names.data.clone(),
-            },
-        )
-        .unwrap();
+        vio.store_labels(new_asset_names_id, new_asset_names.clone())
+            .unwrap();
 
         vio.store_vector(new_weights_id, amount_vec![0.2, 0.2, 0.8])
             .unwrap();
 
-        vio.store_labels(
-            rebalance_asset_names_id,
-            Labels {
-                data: rebalance_asset_names.data.clone(),
-            },
-        )
-        .unwrap();
+        vio.store_labels(rebalance_asset_names_id, rebalance_asset_names.clone())
+            .unwrap();
 
         vio.store_vector(rebalance_weights_long_id, amount_vec![0.04, 0])
             .unwrap();
@@ -1072,12 +3041,10 @@ mod test_scenarios {
         let rebalance_weights_short_before = vio.load_vector(rebalance_weights_short_id).unwrap();
 
         let num_registers = 8;
-        let mut program = VectorVM::new(&mut vio);
-        let mut stack = Stack::new(num_registers);
-        let result = program.execute_with_stack(code.unwrap(), &mut stack);
+        let result =
+            test_utils::execute_optimized(&mut vio, code.unwrap(), num_registers, Vec::new());
 
         if let Err(err) = result {
-            log_stack!(&stack);
             panic!("Failed to execute test: {:?}", err);
         }
 
@@ -1088,7 +3055,7 @@ mod test_scenarios {
         let new_names = vio.load_labels(asset_names_id).unwrap();
         let new_weights = vio.load_vector(asset_weights_id).unwrap();
 
-        assert_eq!(new_names.data, new_asset_names.data);
+        assert_eq!(new_names.as_slice(), new_asset_names.as_slice());
 
         let all_asset_names = label_vec![51, 52, 53, 54, 55];
         let old_weights = project(&old_weights, &old_asset_names, &all_asset_names);
@@ -1149,8 +3116,8 @@ mod test_scenarios {
         );
 
         assert_eq!(
-            rebalance_asset_names_after.data,
-            label_vec![51, 52, 53, 54, 55].data
+            rebalance_asset_names_after.as_slice(),
+            label_vec![51, 52, 53, 54, 55].as_slice()
         );
 
         assert_eq!(
@@ -1192,21 +3159,11 @@ mod test_scenarios {
 
         let capacity_factor = amount!(0.50);
 
-        vio.store_labels(
-            rebalance_asset_names_id,
-            Labels {
-                data: rebalance_asset_names.data.clone(),
-            },
-        )
-        .unwrap();
+        vio.store_labels(rebalance_asset_names_id, rebalance_asset_names.clone())
+            .unwrap();
 
-        vio.store_labels(
-            market_asset_names_id,
-            Labels {
-                data: market_asset_names.data.clone(),
-            },
-        )
-        .unwrap();
+        vio.store_labels(market_asset_names_id, market_asset_names.clone())
+            .unwrap();
 
         vio.store_vector(rebalance_weights_long_id, amount_vec![0.1, 0, 0.05])
             .unwrap();
@@ -1266,12 +3223,10 @@ mod test_scenarios {
         );
 
         let num_registers = 12;
-        let mut program = VectorVM::new(&mut vio);
-        let mut stack = Stack::new(num_registers);
-        let result = program.execute_with_stack(code.unwrap(), &mut stack);
+        let result =
+            test_utils::execute_optimized(&mut vio, code.unwrap(), num_registers, Vec::new());
 
         if let Err(err) = result {
-            log_stack!(&stack);
             panic!("Failed to execute test: {:?}", err);
         }
 
@@ -1349,4 +3304,183 @@ mod test_scenarios {
 
         assert_eq!(demand_short_after.data, amount_vec![0, 0, 0.54, 0, 0].data);
     }
+
+    #[test]
+    fn test_optimize_formulas() {
+        use crate::optimizer::optimize;
+        use crate::verifier::{verify, verify_with_inputs};
+
+        let check = |_name: &str, code: Result<Vec<u8>, Vec<u8>>, num_inputs: usize| {
+            let code = code.unwrap();
+            let optimized = optimize(&code).unwrap();
+            let info = verify_with_inputs(&code, num_inputs).unwrap();
+            let optimized_info = verify_with_inputs(&optimized, num_inputs).unwrap();
+            log_msg!(
+                "Optimized {}: {} -> {} instructions, {} -> {} bytes",
+                _name,
+                info.num_instructions,
+                optimized_info.num_instructions,
+                code.len(),
+                optimized.len()
+            );
+            assert!(optimized_info.num_instructions <= info.num_instructions);
+            assert!(optimized_info.num_registers() <= info.num_registers());
+            assert_eq!(optimized_info.num_args, info.num_args);
+        };
+
+        check(
+            "execute_buy_order",
+            execute_buy_order(
+                1, 2, 3, 0, 0, 0, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17,
+            ),
+            0,
+        );
+        check(
+            "execute_sell_order",
+            execute_sell_order(
+                1, 2, 3, 0, 0, 0, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17,
+            ),
+            0,
+        );
+        check(
+            "execute_rebalance",
+            execute_rebalance(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14),
+            0,
+        );
+        check("execute_transfer", execute_transfer(1, 2, 3, 0), 0);
+        check(
+            "update_rebalance",
+            update_rebalance(1, 2, 3, 4, 5, 6, 7, 8, 9),
+            0,
+        );
+        check(
+            "create_market",
+            create_market(1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12),
+            0,
+        );
+        check(
+            "add_market_assets",
+            add_market_assets(1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12),
+            0,
+        );
+        check("update_margin", update_margin(1, 2, 3, 4), 0);
+        check(
+            "update_market_data",
+            update_market_data(1, 2, 3, 4, 5, 6, 7, 8, 0, 1),
+            0,
+        );
+        check(
+            "update_supply",
+            update_supply(1, 2, 3, 4, 5, 6, 7, 8, 9, 10),
+            0,
+        );
+        check("update_quote", update_quote(1, 2, 3, 4, 5, 6, 7), 0);
+        check("update_quote_template", update_quote_template(), 0);
+        check("submit_buy_order", submit_buy_order(1, 2, 3, 0, 0), 0);
+        check("submit_sell_order", submit_sell_order(1, 2, 3, 0, 0), 0);
+        check("solve_quadratic_bid", solve_quadratic_bid(), 3);
+        check("solve_quadratic_ask", solve_quadratic_ask(), 3);
+        assert!(verify(&optimize(&update_quote_template().unwrap()).unwrap()).is_ok());
+
+        // Order submission is not part of other scenarios
+        let order_id = 10001;
+        let vendor_order_id = 10002;
+        let total_order_id = 10003;
+        let mut vio = test_utils::TestVectorIO::new();
+        vio.store_vector(order_id, amount_vec![950, 0, 0]).unwrap();
+        vio.store_vector(vendor_order_id, amount_vec![1950, 20000, 2])
+            .unwrap();
+        vio.store_vector(total_order_id, amount_vec![2950, 50000, 5])
+            .unwrap();
+
+        for code in [
+            submit_buy_order(
+                order_id,
+                vendor_order_id,
+                total_order_id,
+                amount!(100).to_u128_raw(),
+                amount!(50).to_u128_raw(),
+            ),
+            submit_sell_order(
+                order_id,
+                vendor_order_id,
+                total_order_id,
+                amount!(0.5).to_u128_raw(),
+                amount!(0.25).to_u128_raw(),
+            ),
+        ] {
+            test_utils::execute_optimized(&mut vio, code.unwrap(), 9, Vec::new()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_verify_formulas() {
+        use crate::verifier::{verify, verify_with_inputs};
+
+        let check = |_name: &str, code: Result<Vec<u8>, Vec<u8>>, num_registers: usize| {
+            let info = verify(&code.unwrap()).unwrap();
+            log_msg!("Verified {}: {:?}", _name, info);
+            assert!(info.num_registers() <= num_registers);
+        };
+
+        check(
+            "execute_buy_order",
+            execute_buy_order(
+                1, 2, 3, 0, 0, 0, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17,
+            ),
+            23,
+        );
+        check(
+            "execute_sell_order",
+            execute_sell_order(
+                1, 2, 3, 0, 0, 0, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17,
+            ),
+            22,
+        );
+        check(
+            "execute_rebalance",
+            execute_rebalance(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14),
+            12,
+        );
+        check("execute_transfer", execute_transfer(1, 2, 3, 0), 6);
+        check(
+            "update_rebalance",
+            update_rebalance(1, 2, 3, 4, 5, 6, 7, 8, 9),
+            8,
+        );
+        check(
+            "create_market",
+            create_market(1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12),
+            16,
+        );
+        check(
+            "add_market_assets",
+            add_market_assets(1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12),
+            16,
+        );
+        check("update_margin", update_margin(1, 2, 3, 4), 16);
+        check(
+            "update_market_data",
+            update_market_data(1, 2, 3, 4, 5, 6, 7, 8, 0, 1),
+            16,
+        );
+        check(
+            "update_supply",
+            update_supply(1, 2, 3, 4, 5, 6, 7, 8, 9, 10),
+            16,
+        );
+        check("update_quote", update_quote(1, 2, 3, 4, 5, 6, 7), 16);
+        check("update_quote_template", update_quote_template(), 16);
+        assert_eq!(
+            verify(&update_quote_template().unwrap()).unwrap().num_args,
+            7
+        );
+        check("submit_buy_order", submit_buy_order(1, 2, 3, 0, 0), 9);
+        check("submit_sell_order", submit_sell_order(1, 2, 3, 0, 0), 9);
+
+        for code in [solve_quadratic_bid(), solve_quadratic_ask()] {
+            let info = verify_with_inputs(&code.unwrap(), 3).unwrap();
+            assert!(info.num_registers() <= 4);
+        }
+    }
 }
@@ -0,0 +1,32 @@
+use common::abacus::program_error::ErrorCode;
+pub use common::abacus::program_error::Frame;
+
+use crate::{decoder::Instruction, runtime::Operand};
+
+/// State of the VM at the instruction being traced
+pub struct Step<'a> {
+    /// Path of sub-routine frames, empty in main program
+    pub frames: &'a [Frame],
+
+    /// Instruction with decoded arguments (`offset` is the program counter)
+    pub instruction: &'a Instruction,
+
+    /// Stack of current frame, top of the stack is the last operand
+    pub stack: &'a [Operand],
+
+    /// Registers of current frame
+    pub registers: &'a [Operand],
+}
+
+/// Hook called by `VectorVM` around every instruction
+///
+/// Instructions of sub-routines are traced too, and `Step::frames` tells
+/// which frame they belong to. Tracer cannot alter execution, however it may
+/// block, e.g. to wait for user in a debugger.
+pub trait Tracer {
+    /// Called before instruction is executed
+    fn before_instruction(&mut self, _step: &Step<'_>) {}
+
+    /// Called after instruction is executed, or when it failed with `error`
+    fn after_instruction(&mut self, _step: &Step<'_>, _error: Option<&ErrorCode>) {}
+}
@@ -0,0 +1,346 @@
+use core::{cell::RefCell, mem::take};
+
+use alloc::{collections::BTreeMap, vec, vec::Vec};
+use common::{
+    abacus::program_error::{ErrorCode, ProgramError},
+    amount::Amount,
+    labels::Labels,
+    vector::Vector,
+};
+
+use crate::runtime::{VectorIO, VectorVM};
+
+/// Labels or vector written by a program, with its value before and after
+pub struct Write<T> {
+    pub id: u128,
+
+    /// Value in underlying storage, or `None` if it wasn't stored before
+    pub before: Option<T>,
+
+    /// Value to be committed
+    pub after: T,
+}
+
+/// Labels and vectors written by a program, ordered by id
+#[derive(Default)]
+pub struct WriteSet {
+    pub labels: Vec<Write<Labels>>,
+    pub vectors: Vec<Write<Vector>>,
+}
+
+/// Vector write waiting for commit
+enum PendingVector {
+    /// Whole vector was stored
+    Full(Vector),
+
+    /// Components were overwritten starting at given offsets, in order of
+    /// writes, and the rest is as in underlying storage
+    Ranges(Vec<(usize, Vector)>),
+}
+
+/// Values read from underlying storage, i.e. never containing pending writes
+#[derive(Default)]
+struct ReadCache {
+    labels: BTreeMap<u128, Labels>,
+    vectors: BTreeMap<u128, Vector>,
+    codes: BTreeMap<u128, Vec<u8>>,
+}
+
+/// `VectorIO` buffering writes in memory until they are committed
+///
+/// Stores are kept in memory and loads see them, so that program executes as
+/// if it was writing to underlying storage directly. Writes reach underlying
+/// storage only on `commit()`, and `rollback()` discards them, so that failed
+/// program leaves no partial writes behind. Reads from underlying storage are
+/// cached, and repeated loads of the same item don't reach storage again.
+///
+/// Writes of partial ranges (`STVR`) are committed as ranges, so that storage
+/// supporting partial access doesn't have to store whole vectors.
+///
+/// Meant for off-chain tools, e.g. `vil-dbg`. On-chain, failed program reverts
+/// the transaction, which discards its writes anyway, while caching reads
+/// would copy every vector loaded.
+pub struct TransactionalVectorIO<V: VectorIO> {
+    inner: V,
+    labels: BTreeMap<u128, Labels>,
+    vectors: BTreeMap<u128, PendingVector>,
+    cache: RefCell<ReadCache>,
+}
+
+fn copy_labels(labels: &Labels) -> Labels {
+    labels.clone()
+}
+
+fn copy_vector(vector: &Vector) -> Vector {
+    Vector {
+        data: vector.data.clone(),
+    }
+}
+
+fn slice<T: Copy>(data: &[T], start: usize, len: usize) -> Result<Vec<T>, ErrorCode> {
+    let end = start.checked_add(len).ok_or(ErrorCode::OutOfRange)?;
+    let data = data.get(start..end).ok_or(ErrorCode::OutOfRange)?;
+    Ok(data.to_vec())
+}
+
+/// Overwrite components of `data`, which starts at `offset` of the vector,
+/// with those of `ranges` overlapping it
+fn apply_ranges(data: &mut [Amount], offset: usize, ranges: &[(usize, Vector)]) {
+    let end = offset + data.len();
+    for (start, input) in ranges {
+        let lo = offset.max(*start);
+        let hi = end.min(start + input.data.len());
+        if lo < hi {
+            data[lo - offset..hi - offset].copy_from_slice(&input.data[lo - start..hi - start]);
+        }
+    }
+}
+
+impl<V: VectorIO> TransactionalVectorIO<V> {
+    pub fn new(inner: V) -> Self {
+        Self {
+            inner,
+            labels: BTreeMap::new(),
+            vectors: BTreeMap::new(),
+            cache: RefCell::new(ReadCache::default()),
+        }
+    }
+
+    /// Underlying storage, which doesn't see pending writes
+    pub fn inner(&self) -> &V {
+        &self.inner
+    }
+
+    /// Underlying storage, and pending writes are discarded
+    pub fn into_inner(self) -> V {
+        self.inner
+    }
+
+    /// Tells whether there are writes waiting for commit
+    pub fn is_dirty(&self) -> bool {
+        !self.labels.is_empty() || !self.vectors.is_empty()
+    }
+
+    /// Ids of labels written since last commit or rollback
+    pub fn written_labels(&self) -> impl Iterator<Item = u128> + '_ {
+        self.labels.keys().copied()
+    }
+
+    /// Ids of vectors written since last commit or rollback
+    pub fn written_vectors(&self) -> impl Iterator<Item = u128> + '_ {
+        self.vectors.keys().copied()
+    }
+
+    /// Values of written labels and vectors before and after pending writes
+    ///
+    /// Values before are loaded from underlying storage unless they were
+    /// already read.
+    pub fn write_set(&self) -> Result<WriteSet, ErrorCode> {
+        let mut write_set = WriteSet::default();
+        for (&id, labels) in &self.labels {
+            write_set.labels.push(Write {
+                id,
+                before: self.read_labels(id).ok(),
+                after: copy_labels(labels),
+            });
+        }
+        for (&id, pending) in &self.vectors {
+            let before = self.read_vector(id).ok();
+            let after = match pending {
+                PendingVector::Full(vector) => copy_vector(vector),
+                PendingVector::Ranges(ranges) => {
+                    let mut vector = copy_vector(before.as_ref().ok_or(ErrorCode::NotFound)?);
+                    apply_ranges(&mut vector.data, 0, ranges);
+                    vector
+                }
+            };
+            write_set.vectors.push(Write { id, before, after });
+        }
+        Ok(write_set)
+    }
+
+    /// Store pending writes into underlying storage
+    ///
+    /// Labels are stored first and then vectors, both in order of their ids.
+    /// Should underlying storage fail, the writes stored before the failure
+    /// remain stored, and the rest is discarded.
+    pub fn commit(&mut self) -> Result<(), ErrorCode> {
+        let labels = take(&mut self.labels);
+        let vectors = take(&mut self.vectors);
+        let cache = self.cache.get_mut();
+        for (id, labels) in labels {
+            self.inner.store_labels(id, copy_labels(&labels))?;
+            cache.labels.insert(id, labels);
+        }
+        for (id, pending) in vectors {
+            match pending {
+                PendingVector::Full(vector) => {
+                    self.inner.store_vector(id, copy_vector(&vector))?;
+                    cache.vectors.insert(id, vector);
+                }
+                PendingVector::Ranges(ranges) => {
+                    if let Some(vector) = cache.vectors.get_mut(&id) {
+                        apply_ranges(&mut vector.data, 0, &ranges);
+                    }
+                    for (start, input) in ranges {
+                        self.inner.store_vector_range(id, start, input)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Discard pending writes
+    pub fn rollback(&mut self) {
+        self.labels.clear();
+        self.vectors.clear();
+    }
+
+    /// Execute program, and commit its writes only if it succeeds
+    ///
+    /// Writes of failed program are rolled back. Use `VectorVM` directly
+    /// followed by `commit()` or `rollback()` to execute program with limits
+    /// or tracer.
+    pub fn execute(&mut self, code: Vec<u8>, num_registers: usize) -> Result<(), ProgramError> {
+        self.execute_with_args(code, num_registers, Vec::new())
+    }
+
+    /// Execute template program binding its arguments, and commit its
+    /// writes only if it succeeds
+    pub fn execute_with_args(
+        &mut self,
+        code: Vec<u8>,
+        num_registers: usize,
+        args: Vec<u128>,
+    ) -> Result<(), ProgramError> {
+        let code_len = code.len();
+        let mut program = VectorVM::new(self);
+        if let Err(err) = program.execute_with_args(code, num_registers, args) {
+            self.rollback();
+            return Err(err);
+        }
+        self.commit()
+            .map_err(|error_code| ProgramError::new(error_code, code_len, 0))
+    }
+
+    fn read_labels(&self, id: u128) -> Result<Labels, ErrorCode> {
+        if let Some(labels) = self.cache.borrow().labels.get(&id) {
+            return Ok(copy_labels(labels));
+        }
+        let labels = self.inner.load_labels(id)?;
+        self.cache
+            .borrow_mut()
+            .labels
+            .insert(id, copy_labels(&labels));
+        Ok(labels)
+    }
+
+    fn read_vector(&self, id: u128) -> Result<Vector, ErrorCode> {
+        if let Some(vector) = self.cache.borrow().vectors.get(&id) {
+            return Ok(copy_vector(vector));
+        }
+        let vector = self.inner.load_vector(id)?;
+        self.cache
+            .borrow_mut()
+            .vectors
+            .insert(id, copy_vector(&vector));
+        Ok(vector)
+    }
+}
+
+impl<V: VectorIO> VectorIO for TransactionalVectorIO<V> {
+    fn load_labels(&self, id: u128) -> Result<Labels, ErrorCode> {
+        match self.labels.get(&id) {
+            Some(labels) => Ok(copy_labels(labels)),
+            None => self.read_labels(id),
+        }
+    }
+
+    fn load_vector(&self, id: u128) -> Result<Vector, ErrorCode> {
+        match self.vectors.get(&id) {
+            Some(PendingVector::Full(vector)) => Ok(copy_vector(vector)),
+            Some(PendingVector::Ranges(ranges)) => {
+                let mut vector = self.read_vector(id)?;
+                apply_ranges(&mut vector.data, 0, ranges);
+                Ok(vector)
+            }
+            None => self.read_vector(id),
+        }
+    }
+
+    fn load_code(&self, id: u128) -> Result<Vec<u8>, ErrorCode> {
+        if let Some(code) = self.cache.borrow().codes.get(&id) {
+            return Ok(code.clone());
+        }
+        let code = self.inner.load_code(id)?;
+        self.cache.borrow_mut().codes.insert(id, code.clone());
+        Ok(code)
+    }
+
+    fn store_labels(&mut self, id: u128, input: Labels) -> Result<(), ErrorCode> {
+        self.labels.insert(id, input);
+        Ok(())
+    }
+
+    fn store_vector(&mut self, id: u128, input: Vector) -> Result<(), ErrorCode> {
+        self.vectors.insert(id, PendingVector::Full(input));
+        Ok(())
+    }
+
+    fn load_labels_range(&self, id: u128, start: usize, len: usize) -> Result<Labels, ErrorCode> {
+        if let Some(labels) = self.labels.get(&id) {
+            let data = slice(labels, start, len)?;
+            return Ok(Labels::from(data));
+        }
+        if let Some(labels) = self.cache.borrow().labels.get(&id) {
+            let data = slice(labels, start, len)?;
+            return Ok(Labels::from(data));
+        }
+        self.inner.load_labels_range(id, start, len)
+    }
+
+    fn load_vector_range(&self, id: u128, start: usize, len: usize) -> Result<Vector, ErrorCode> {
+        let ranges = match self.vectors.get(&id) {
+            Some(PendingVector::Full(vector)) => {
+                let data = slice(&vector.data, start, len)?;
+                return Ok(Vector { data });
+            }
+            Some(PendingVector::Ranges(ranges)) => ranges.as_slice(),
+            None => &[],
+        };
+        let cached = match self.cache.borrow().vectors.get(&id) {
+            Some(vector) => Some(slice(&vector.data, start, len)?),
+            None => None,
+        };
+        let mut vector = match cached {
+            Some(data) => Vector { data },
+            None => self.inner.load_vector_range(id, start, len)?,
+        };
+        apply_ranges(&mut vector.data, start, ranges);
+        Ok(vector)
+    }
+
+    fn store_vector_range(
+        &mut self,
+        id: u128,
+        start: usize,
+        input: Vector,
+    ) -> Result<(), ErrorCode> {
+        // Range must be within bounds of the vector
+        self.load_vector_range(id, start, input.data.len())?;
+        match self.vectors.get_mut(&id) {
+            Some(PendingVector::Full(vector)) => {
+                apply_ranges(&mut vector.data, 0, &[(start, input)]);
+            }
+            Some(PendingVector::Ranges(ranges)) => {
+                ranges.push((start, input));
+            }
+            None => {
+                self.vectors
+                    .insert(id, PendingVector::Ranges(vec![(start, input)]));
+            }
+        }
+        Ok(())
+    }
+}
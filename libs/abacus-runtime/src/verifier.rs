@@ -0,0 +1,312 @@
+use common::abacus::{instruction_set::*, program_error::*};
+
+use crate::decoder::{Decoder, Instruction};
+
+/// Static properties of a program established by `verify()`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramInfo {
+    /// Number of instructions in the program
+    pub num_instructions: usize,
+
+    /// Maximum stack depth reached by the program, or `None` when depth
+    /// depends on lengths of vectors known only at run-time (`UNPK`, `T`).
+    pub max_stack_depth: Option<usize>,
+
+    /// Highest register index used by `LDR`, `LDM` or `STR`, or `None` when
+    /// program does not use registers.
+    pub max_register: Option<usize>,
+
+    /// Number of arguments declared by `ARGS`, i.e. zero unless program is a
+    /// template.
+    pub num_args: usize,
+}
+
+impl ProgramInfo {
+    /// Minimum number of registers program must be executed with.
+    pub fn num_registers(&self) -> usize {
+        self.max_register.map_or(0, |r| r + 1)
+    }
+}
+
+/// Bounds of stack depth during static simulation
+///
+/// Upper bound is lost once instruction pushes number of operands, which
+/// depends on run-time length of a vector. From that point only definite
+/// stack underflow can be detected, i.e. when lower bound is certain.
+#[derive(Clone, Copy)]
+struct Depth {
+    min: usize,
+    max: Option<usize>,
+}
+
+impl Depth {
+    fn new(num_inputs: usize) -> Self {
+        Self {
+            min: num_inputs,
+            max: Some(num_inputs),
+        }
+    }
+
+    fn require(&mut self, count: usize) -> Result<(), ErrorCode> {
+        match self.max {
+            Some(max) if max < count => Err(ErrorCode::StackUnderflow),
+            _ => {
+                // Once instruction succeeds, stack had at least that many
+                self.min = self.min.max(count);
+                Ok(())
+            }
+        }
+    }
+
+    fn pop(&mut self, count: usize) -> Result<(), ErrorCode> {
+        self.require(count)?;
+        self.min = self.min.saturating_sub(count);
+        self.max = self.max.map(|max| max - count);
+        Ok(())
+    }
+
+    fn push(&mut self, count: usize) {
+        self.min += count;
+        self.max = self.max.map(|max| max + count);
+    }
+
+    fn unbound(&mut self) {
+        self.max = None;
+    }
+}
+
+/// Verify program before execution
+///
+/// Decodes every instruction rejecting unknown op-codes and truncated
+/// arguments, and simulates stack depth to catch stack underflow without
+/// executing the program. Program is assumed to start with empty stack.
+///
+/// Note that verification does not load any data from `VectorIO`, and so it
+/// cannot check types or lengths of operands.
+///
+pub fn verify(code: &[u8]) -> Result<ProgramInfo, ProgramError> {
+    verify_with_inputs(code, 0)
+}
+
+/// Verify sub-routine before execution
+///
+/// Same as `verify()`, except program is assumed to start with `num_inputs`
+/// operands on the stack, as it is the case with `B` and `FOLD` sub-routines.
+///
+pub fn verify_with_inputs(code: &[u8], num_inputs: usize) -> Result<ProgramInfo, ProgramError> {
+    let mut depth = Depth::new(num_inputs);
+    let mut info = ProgramInfo {
+        num_instructions: 0,
+        max_stack_depth: Some(num_inputs),
+        max_register: None,
+        num_args: 0,
+    };
+
+    let mut decoder = Decoder::new(code);
+    loop {
+        let pc = decoder.program_counter();
+        let Some(next) = decoder.next() else {
+            break;
+        };
+        let next_op_code = code.get(decoder.program_counter()).copied();
+        next.and_then(|instruction| {
+            check_modifier(&instruction, next_op_code)?;
+            simulate(&instruction, &mut depth, &mut info)
+        })
+        .map_err(|error_code| ProgramError {
+            op_code: code.get(pc).copied(),
+            ..ProgramError::new(error_code, pc, depth.min)
+        })?;
+        info.num_instructions += 1;
+        info.max_stack_depth = match (info.max_stack_depth, depth.max) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            _ => None,
+        };
+    }
+
+    Ok(info)
+}
+
+fn simulate(
+    instruction: &Instruction,
+    depth: &mut Depth,
+    info: &mut ProgramInfo,
+) -> Result<(), ErrorCode> {
+    let arg = |i| instruction.arg_usize(i);
+    match instruction.op_code {
+        OP_LDL | OP_LDV | OP_LDLR | OP_LDVR | OP_IMMS | OP_IMML => {
+            depth.push(1);
+        }
+        OP_LDD | OP_ZEROS | OP_ONES => {
+            depth.require(arg(0) + 1)?;
+            depth.push(1);
+        }
+        OP_LDR | OP_LDM => {
+            use_register(info, arg(0));
+            depth.push(1);
+        }
+        OP_STR => {
+            use_register(info, arg(0));
+            depth.pop(1)?;
+        }
+        OP_STL | OP_STV | OP_STVR => {
+            depth.pop(1)?;
+        }
+        OP_PKV | OP_PKL => {
+            depth.pop(arg(0).max(1))?;
+            depth.push(1);
+        }
+        OP_POPN => {
+            depth.pop(arg(0).max(1))?;
+        }
+        OP_UNPK => {
+            depth.pop(1)?;
+            depth.unbound();
+        }
+        OP_T => match arg(0) {
+            0 => Err(ErrorCode::InvalidOperand)?,
+            count => {
+                depth.pop(count)?;
+                depth.unbound();
+            }
+        },
+        OP_VPUSH | OP_LPUSH | OP_SQRT | OP_EXP | OP_LN | OP_VSUM | OP_VMIN | OP_VMAX
+        | OP_VCUMSUM | OP_VNORM => {
+            depth.require(1)?;
+        }
+        OP_VPOP | OP_LPOP | OP_SUNPK => {
+            depth.require(1)?;
+            depth.push(1);
+        }
+        OP_ADD | OP_SUB | OP_SSB | OP_MUL | OP_DIV | OP_POW | OP_VDOT => {
+            depth.require(arg(0) + 1)?;
+        }
+        OP_MIN | OP_MAX | OP_EQ | OP_GT | OP_LT | OP_NET | OP_LUNION | OP_SWAP | OP_SPK => {
+            if arg(0) == 0 {
+                Err(ErrorCode::OutOfRange)?;
+            }
+            depth.require(arg(0) + 1)?;
+        }
+        OP_CLAMP | OP_SEL | OP_SGET | OP_SADD | OP_SUPD => {
+            if arg(0) == 0 || arg(1) == 0 {
+                Err(ErrorCode::OutOfRange)?;
+            }
+            depth.require(arg(0).max(arg(1)) + 1)?;
+        }
+        OP_JUPD | OP_JADD => {
+            let (pos_b, pos_a, lab_b) = (arg(0), arg(1), arg(2));
+            if pos_a == lab_b {
+                // Same labels, and it's just normal vector add
+                depth.require(2)?;
+            } else {
+                if pos_b == 0 {
+                    Err(ErrorCode::OutOfRange)?;
+                }
+                if pos_a == 0 || lab_b == 0 {
+                    Err(ErrorCode::InvalidOperand)?;
+                }
+                depth.require(pos_b.max(pos_a).max(lab_b) + 1)?;
+            }
+        }
+        OP_JFLT => {
+            let (lab_a, lab_b) = (arg(0), arg(1));
+            if lab_a != lab_b {
+                if lab_a == 0 || lab_b == 0 {
+                    Err(ErrorCode::InvalidOperand)?;
+                }
+                depth.require(lab_a.max(lab_b) + 1)?;
+            }
+        }
+        OP_B => {
+            let (num_inputs, num_outputs) = (arg(1), arg(2));
+            depth.pop(num_inputs)?;
+            depth.push(num_outputs);
+        }
+        OP_FOLD => {
+            let (num_inputs, num_outputs) = (arg(1), arg(2));
+            depth.pop(num_inputs + 1)?;
+            depth.push(num_outputs);
+        }
+        OP_BZ => {
+            let (num_inputs, num_outputs) = (arg(1), arg(2));
+            if num_inputs != num_outputs {
+                // When not taken, inputs are left on stack as outputs
+                Err(ErrorCode::InvalidInstruction)?;
+            }
+            depth.pop(num_inputs + 1)?;
+            depth.push(num_outputs);
+        }
+        OP_NEG | OP_ABS | OP_POS | OP_NEGPART => {
+            depth.require(1)?;
+        }
+        OP_ASSERT_ZERO => {
+            depth.pop(1)?;
+        }
+        OP_ARGS => {
+            // Arguments must be declared before any of them is used
+            if info.num_instructions != 0 {
+                Err(ErrorCode::InvalidInstruction)?;
+            }
+            info.num_args = arg(0);
+        }
+        OP_LDA | OP_LDLA | OP_LDVA => {
+            use_arg(info, arg(0))?;
+            depth.push(1);
+        }
+        OP_STLA | OP_STVA => {
+            use_arg(info, arg(0))?;
+            depth.pop(1)?;
+        }
+        OP_ASSERT_LE => {
+            if arg(0) == 0 {
+                Err(ErrorCode::OutOfRange)?;
+            }
+            depth.require(arg(0) + 1)?;
+            depth.pop(1)?;
+        }
+        OP_RNDU | OP_RNDE => {}
+        _ => Err(ErrorCode::InvalidInstruction)?,
+    }
+    Ok(())
+}
+
+/// Lower bound of stack depth after instruction succeeds given lower bound
+/// before it, or zero if instruction always fails
+pub(crate) fn min_depth_after(instruction: &Instruction, min_depth: usize) -> usize {
+    let mut depth = Depth {
+        min: min_depth,
+        max: None,
+    };
+    // Arguments are unknown, and so any of them may be used
+    let mut info = ProgramInfo {
+        num_instructions: 0,
+        max_stack_depth: None,
+        max_register: None,
+        num_args: usize::MAX,
+    };
+    match simulate(instruction, &mut depth, &mut info) {
+        Ok(()) => depth.min,
+        Err(_) => 0,
+    }
+}
+
+/// Rounding modifiers must be immediately followed by `MUL` or `DIV`
+fn check_modifier(instruction: &Instruction, next_op_code: Option<u8>) -> Result<(), ErrorCode> {
+    match (instruction.op_code, next_op_code) {
+        (OP_RNDU | OP_RNDE, Some(OP_MUL | OP_DIV)) => Ok(()),
+        (OP_RNDU | OP_RNDE, _) => Err(ErrorCode::InvalidInstruction),
+        _ => Ok(()),
+    }
+}
+
+fn use_register(info: &mut ProgramInfo, reg: usize) {
+    info.max_register = Some(info.max_register.map_or(reg, |r| r.max(reg)));
+}
+
+fn use_arg(info: &ProgramInfo, arg: usize) -> Result<(), ErrorCode> {
+    if arg < info.num_args {
+        Ok(())
+    } else {
+        Err(ErrorCode::OutOfRange)
+    }
+}
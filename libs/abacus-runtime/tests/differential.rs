@@ -0,0 +1,105 @@
+//! Differential tests of `VectorVM` against reference interpreter
+//!
+//! Run with `cargo test -p abacus-runtime --test differential`, and set
+//! `PROPTEST_CASES` to run more cases than the default 256.
+
+mod reference;
+
+use abacus_runtime::{
+    decoder::Encoding, metering::ExecutionLimits, optimizer::optimize, runtime::VectorVM,
+    verifier::verify,
+};
+use common::{abacus::program_error::ErrorCode, amount::Amount};
+use proptest::prelude::*;
+
+use reference::{Outcome, Step};
+
+/// Values near zero, around one, large and anywhere in range
+fn raw_amount() -> impl Strategy<Value = u128> {
+    prop_oneof![
+        4 => 0..=10 * Amount::SCALE,
+        2 => 0..=1_000_000 * Amount::SCALE,
+        1 => Just(0u128),
+        1 => 0..=1_000u128,
+        1 => any::<u128>(),
+    ]
+}
+
+fn steps() -> impl Strategy<Value = Vec<Step>> {
+    let step = (any::<u8>(), any::<u8>(), raw_amount()).prop_map(|(selector, pos, value)| Step {
+        selector,
+        pos,
+        value,
+    });
+    prop::collection::vec(step, 1..32)
+}
+
+/// Between one and three input vectors, all of the same length
+fn inputs() -> impl Strategy<Value = Vec<Vec<Amount>>> {
+    (1..4usize, 1..6usize).prop_flat_map(|(count, len)| {
+        let vector = prop::collection::vec(raw_amount().prop_map(Amount), len);
+        prop::collection::vec(vector, count)
+    })
+}
+
+fn encoding() -> impl Strategy<Value = Encoding> {
+    prop_oneof![Just(Encoding::Fixed), Just(Encoding::Compact)]
+}
+
+proptest! {
+    #[test]
+    fn vector_vm_agrees_with_reference(
+        inputs in inputs(),
+        steps in steps(),
+        encoding in encoding(),
+    ) {
+        let program = reference::build(inputs, &steps, encoding);
+        prop_assert!(verify(&program.code).is_ok());
+
+        let mut vio = reference::MemoryVectorIO::new(&program);
+        let result = VectorVM::new(&mut vio).execute(program.code.clone(), 0);
+
+        match (reference::execute(&program), result) {
+            (Outcome::Success(expected), Ok(())) => {
+                let outputs = vio.outputs(&program);
+                for (computed, expected) in outputs.iter().zip(&expected) {
+                    prop_assert_eq!(computed.len(), expected.len());
+                    for (x, bounds) in computed.iter().zip(expected) {
+                        prop_assert!(
+                            reference::is_within(*x, bounds),
+                            "Computed {} is not within {} +/- {}",
+                            x.0,
+                            bounds.value,
+                            bounds.error
+                        );
+                    }
+                }
+            }
+            (Outcome::Overflow, Err(err)) => {
+                prop_assert!(matches!(err.error_code, ErrorCode::MathOverflow), "{:?}", err);
+            }
+            (Outcome::Ambiguous, _) => {}
+            (expected, result) => {
+                prop_assert!(false, "Expected {:?}, but VM returned {:?}", expected, result);
+            }
+        }
+    }
+
+    /// Arbitrary bytes either fail verification or execution, or they run to
+    /// completion, but they never panic, and neither does optimizer
+    #[test]
+    fn arbitrary_code_never_panics(code in prop::collection::vec(any::<u8>(), 0..64)) {
+        let _ = verify(&code);
+        let _ = optimize(&code);
+
+        let limits = ExecutionLimits {
+            max_instructions: 1_000,
+            max_stack_depth: 64,
+            max_call_depth: 4,
+            max_vector_len: 1_000,
+        };
+        let mut vio = reference::MemoryVectorIO::default();
+        vio.vectors.insert(1, vec![Amount::ONE, Amount::TWO]);
+        let _ = VectorVM::with_limits(&mut vio, limits).execute(code, 4);
+    }
+}
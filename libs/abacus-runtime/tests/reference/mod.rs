@@ -0,0 +1,523 @@
+//! Reference interpreter of VIL arithmetic over big rationals
+//!
+//! Programs are built from a list of `Step`s, which can come from any source
+//! of randomness (proptest strategies or fuzzer input). Every step is mapped
+//! to an instruction, which is valid for the types of operands on the stack
+//! at that point, or skipped if there is no such instruction. The resulting
+//! program is executed by `VectorVM` and by this interpreter, which computes
+//! exact results together with bounds of rounding errors:
+//!
+//! - `ADD`, `SUB`, `SSB`, `MIN`, `MAX`, `VSUM`, `VMIN`, `VMAX` and `VCUMSUM`
+//!   are exact, and they only accumulate errors of their operands,
+//! - `MUL`, `DIV`, `VDOT` and `VNORM` round down each result by less than
+//!   `Amount::EPSILON`, i.e. 1e-18, and they also amplify errors of their
+//!   operands by magnitude of the other operand.
+//!
+//! Where the bounds cannot tell whether `VectorVM` succeeds or fails, e.g.
+//! when `SUB` result is within error bound of zero, the outcome is ambiguous
+//! and it is not compared.
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use abacus_runtime::{decoder::arg_types, decoder::Encoding, runtime::VectorIO};
+use common::{
+    abacus::{instruction_set::*, program_error::ErrorCode},
+    amount::Amount,
+    labels::Labels,
+    vector::Vector,
+};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{Signed, Zero};
+
+/// Maximum number of operands generated programs keep on the stack
+pub const MAX_DEPTH: usize = 8;
+
+/// Storage id of the first input vector, others follow
+pub const FIRST_INPUT_ID: u128 = 1;
+
+/// Storage id of the first output vector, i.e. of former TOS
+pub const FIRST_OUTPUT_ID: u128 = 100;
+
+/// One step of generated program
+#[derive(Debug, Clone)]
+pub struct Step {
+    /// Selects instruction from `STEP_OPS`
+    pub selector: u8,
+
+    /// Selects stack position or input vector
+    pub pos: u8,
+
+    /// Raw value of immediate scalar
+    pub value: u128,
+}
+
+/// Instructions generated from steps
+const STEP_OPS: [u8; 18] = [
+    OP_IMMS, OP_LDV, OP_LDD, OP_SWAP, OP_POPN, OP_ADD, OP_SUB, OP_SSB, OP_MUL, OP_DIV, OP_MIN,
+    OP_MAX, OP_VSUM, OP_VMIN, OP_VMAX, OP_VDOT, OP_VCUMSUM, OP_VNORM,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Type {
+    Scalar,
+    Vector,
+}
+
+/// Instruction of generated program with its arguments
+#[derive(Debug, Clone, Copy)]
+pub struct Op {
+    pub op_code: u8,
+    pub arg: u128,
+}
+
+/// Generated program with its inputs
+pub struct Program {
+    pub code: Vec<u8>,
+    pub ops: Vec<Op>,
+    pub inputs: Vec<Vec<Amount>>,
+    pub num_outputs: usize,
+}
+
+/// Position of an operand compatible with TOS for binary operation,
+/// searching from `pos` towards the bottom of the stack and wrapping around
+fn find_operand(
+    types: &[Type],
+    pos: usize,
+    min_pos: usize,
+    accept: impl Fn(Type) -> bool,
+) -> Option<usize> {
+    let depth = types.len();
+    if depth <= min_pos {
+        return None;
+    }
+    let count = depth - min_pos;
+    (0..count)
+        .map(|i| min_pos + (pos + i) % count)
+        .find(|&pos| accept(types[depth - 1 - pos]))
+}
+
+/// Map step to instruction valid for operand types on the stack, and update
+/// the types as the instruction would
+fn select(step: &Step, types: &mut Vec<Type>, num_inputs: usize) -> Option<Op> {
+    let op_code = STEP_OPS[step.selector as usize % STEP_OPS.len()];
+    let pos = step.pos as usize;
+    let depth = types.len();
+    let tos = types.last().copied();
+    let op = |arg: usize| Op {
+        op_code,
+        arg: arg as u128,
+    };
+    match op_code {
+        OP_IMMS | OP_LDV | OP_LDD if depth >= MAX_DEPTH => None,
+        OP_IMMS => {
+            types.push(Type::Scalar);
+            Some(Op {
+                op_code,
+                arg: step.value,
+            })
+        }
+        OP_LDV => {
+            types.push(Type::Vector);
+            Some(Op {
+                op_code,
+                arg: FIRST_INPUT_ID + (pos % num_inputs) as u128,
+            })
+        }
+        OP_LDD => {
+            let pos = pos % depth.max(1);
+            let t = *types.get(depth.checked_sub(1 + pos)?)?;
+            types.push(t);
+            Some(op(pos))
+        }
+        OP_SWAP => {
+            let pos = find_operand(types, pos, 1, |_| true)?;
+            types.swap(depth - 1, depth - 1 - pos);
+            Some(op(pos))
+        }
+        OP_POPN if depth > 1 => {
+            types.pop();
+            Some(op(1))
+        }
+        OP_ADD | OP_SUB | OP_SSB | OP_MUL | OP_DIV => {
+            let tos = tos?;
+            let pos = find_operand(types, pos, 0, |t| t == tos || t == Type::Scalar)?;
+            Some(op(pos))
+        }
+        OP_MIN | OP_MAX => {
+            let tos = tos?;
+            let pos = find_operand(types, pos, 1, |t| t == tos)?;
+            Some(op(pos))
+        }
+        OP_VSUM | OP_VMIN | OP_VMAX if tos == Some(Type::Vector) => {
+            *types.last_mut()? = Type::Scalar;
+            Some(op(0))
+        }
+        OP_VDOT if tos == Some(Type::Vector) => {
+            let pos = find_operand(types, pos, 0, |t| t == Type::Vector)?;
+            *types.last_mut()? = Type::Scalar;
+            Some(op(pos))
+        }
+        OP_VCUMSUM | OP_VNORM if tos == Some(Type::Vector) => Some(op(0)),
+        _ => None,
+    }
+}
+
+fn write_instruction(op_code: u8, args: &[u128], encoding: Encoding, code: &mut Vec<u8>) {
+    code.push(op_code);
+    let types = arg_types(op_code).expect("Unknown op-code");
+    for (arg_type, &arg) in types.iter().zip(args) {
+        if arg_type.is_wide() {
+            encoding.write(arg, code);
+        } else {
+            code.push(arg as u8);
+        }
+    }
+}
+
+/// Build program from steps
+///
+/// Program loads `inputs` vectors of equal length, performs the steps, and
+/// stores every operand left on the stack starting from TOS as vectors with
+/// ids from `FIRST_OUTPUT_ID`, where scalars are packed into vectors of one.
+pub fn build(inputs: Vec<Vec<Amount>>, steps: &[Step], encoding: Encoding) -> Program {
+    assert!(!inputs.is_empty());
+    let mut types = Vec::new();
+    let mut ops = Vec::new();
+    for step in steps {
+        if let Some(op) = select(step, &mut types, inputs.len()) {
+            ops.push(op);
+        }
+    }
+    for (i, t) in types.iter().rev().enumerate() {
+        if *t == Type::Scalar {
+            ops.push(Op {
+                op_code: OP_PKV,
+                arg: 1,
+            });
+        }
+        ops.push(Op {
+            op_code: OP_STV,
+            arg: FIRST_OUTPUT_ID + i as u128,
+        });
+    }
+
+    let mut code = Vec::new();
+    encoding.write_header(&mut code);
+    for op in &ops {
+        let args: &[u128] = match op.op_code {
+            OP_VSUM | OP_VMIN | OP_VMAX | OP_VCUMSUM | OP_VNORM => &[],
+            _ => &[op.arg],
+        };
+        write_instruction(op.op_code, args, encoding, &mut code);
+    }
+
+    Program {
+        code,
+        ops,
+        inputs,
+        num_outputs: types.len(),
+    }
+}
+
+/// Exact value and bound of error of the value computed by `VectorVM`
+#[derive(Debug, Clone)]
+pub struct Bounded {
+    pub value: BigRational,
+    pub error: BigRational,
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Scalar(Bounded),
+    Vector(Vec<Bounded>),
+}
+
+/// Outcome of reference execution
+#[derive(Debug)]
+pub enum Outcome {
+    /// Program succeeds storing these vectors
+    Success(Vec<Vec<Bounded>>),
+
+    /// Program fails with `MathOverflow`
+    Overflow,
+
+    /// Bounds cannot tell whether program succeeds or fails
+    Ambiguous,
+}
+
+fn rational(raw: u128) -> BigRational {
+    BigRational::new(BigInt::from(raw), BigInt::from(Amount::SCALE))
+}
+
+fn epsilon() -> BigRational {
+    rational(1)
+}
+
+fn max_value() -> BigRational {
+    rational(u128::MAX)
+}
+
+fn exact(raw: u128) -> Bounded {
+    Bounded {
+        value: rational(raw),
+        error: BigRational::zero(),
+    }
+}
+
+/// Result of an instruction on single components
+type Checked = Result<Bounded, Outcome>;
+
+/// Check that every value within bounds fits into `Amount`
+fn fits(value: BigRational, error: BigRational) -> Checked {
+    if &value + &error <= max_value() {
+        Ok(Bounded { value, error })
+    } else if &value - &error > max_value() {
+        Err(Outcome::Overflow)
+    } else {
+        Err(Outcome::Ambiguous)
+    }
+}
+
+fn add(a: &Bounded, b: &Bounded) -> Checked {
+    fits(&a.value + &b.value, &a.error + &b.error)
+}
+
+fn sub(a: &Bounded, b: &Bounded) -> Checked {
+    let value = &a.value - &b.value;
+    let error = &a.error + &b.error;
+    if value.is_negative() && error < value.abs() {
+        Err(Outcome::Overflow)
+    } else if value < error {
+        Err(Outcome::Ambiguous)
+    } else {
+        Ok(Bounded { value, error })
+    }
+}
+
+fn ssb(a: &Bounded, b: &Bounded) -> Checked {
+    let value = (&a.value - &b.value).max(BigRational::zero());
+    Ok(Bounded {
+        value,
+        error: &a.error + &b.error,
+    })
+}
+
+/// Error of product of bounded values, before it is rounded
+fn product_error(a: &Bounded, b: &Bounded) -> BigRational {
+    &a.value * &b.error + &b.value * &a.error + &a.error * &b.error
+}
+
+fn mul(a: &Bounded, b: &Bounded) -> Checked {
+    fits(&a.value * &b.value, product_error(a, b) + epsilon())
+}
+
+fn div(a: &Bounded, b: &Bounded) -> Checked {
+    if b.value.is_zero() && b.error.is_zero() {
+        return Err(Outcome::Overflow);
+    }
+    if b.value <= b.error {
+        return Err(Outcome::Ambiguous);
+    }
+    // |a'/b' - a/b| <= (a |b - b'| + b |a - a'|) / (b b')
+    let error = (&a.value * &b.error + &b.value * &a.error) / (&b.value * (&b.value - &b.error));
+    fits(&a.value / &b.value, error + epsilon())
+}
+
+fn min(a: &Bounded, b: &Bounded) -> Checked {
+    Ok(Bounded {
+        value: a.value.clone().min(b.value.clone()),
+        error: a.error.clone().max(b.error.clone()),
+    })
+}
+
+fn max(a: &Bounded, b: &Bounded) -> Checked {
+    Ok(Bounded {
+        value: a.value.clone().max(b.value.clone()),
+        error: a.error.clone().max(b.error.clone()),
+    })
+}
+
+fn sum(v: &[Bounded]) -> Checked {
+    let value = v.iter().map(|x| &x.value).sum();
+    let error = v.iter().map(|x| &x.error).sum();
+    fits(value, error)
+}
+
+fn dot(a: &[Bounded], b: &[Bounded]) -> Checked {
+    let value = a.iter().zip(b).map(|(x, y)| &x.value * &y.value).sum();
+    let error: BigRational = a.iter().zip(b).map(|(x, y)| product_error(x, y)).sum();
+    fits(value, error + epsilon())
+}
+
+fn binary(v1: &Value, v2: &Value, f: fn(&Bounded, &Bounded) -> Checked) -> Result<Value, Outcome> {
+    let result = match (v1, v2) {
+        (Value::Scalar(x1), Value::Scalar(x2)) => Value::Scalar(f(x1, x2)?),
+        (Value::Vector(v1), Value::Scalar(x2)) => {
+            Value::Vector(collect(v1.iter().map(|x1| f(x1, x2)))?)
+        }
+        (Value::Vector(v1), Value::Vector(v2)) => {
+            Value::Vector(collect(v1.iter().zip(v2).map(|(x1, x2)| f(x1, x2)))?)
+        }
+        _ => unreachable!("Generated program is well-typed"),
+    };
+    Ok(result)
+}
+
+/// Collect results of components, where any overflow makes whole instruction
+/// overflow, as `VectorVM` fails on it whichever component comes first
+fn collect(results: impl Iterator<Item = Checked>) -> Result<Vec<Bounded>, Outcome> {
+    let mut values = Vec::new();
+    let mut ambiguous = false;
+    for result in results {
+        match result {
+            Ok(x) => values.push(x),
+            Err(Outcome::Overflow) => return Err(Outcome::Overflow),
+            Err(_) => ambiguous = true,
+        }
+    }
+    if ambiguous {
+        return Err(Outcome::Ambiguous);
+    }
+    Ok(values)
+}
+
+fn vector(value: &Value) -> &[Bounded] {
+    match value {
+        Value::Vector(v) => v,
+        Value::Scalar(_) => unreachable!("Generated program is well-typed"),
+    }
+}
+
+fn execute_op(op: &Op, stack: &mut Vec<Value>, program: &Program) -> Result<(), Outcome> {
+    let depth = stack.len();
+    let pos = op.arg as usize;
+    let tos = depth.wrapping_sub(1);
+    let binary_op: Option<fn(&Bounded, &Bounded) -> Checked> = match op.op_code {
+        OP_ADD => Some(add),
+        OP_SUB => Some(sub),
+        OP_SSB => Some(ssb),
+        OP_MUL => Some(mul),
+        OP_DIV => Some(div),
+        OP_MIN => Some(min),
+        OP_MAX => Some(max),
+        _ => None,
+    };
+    if let Some(f) = binary_op {
+        stack[tos] = binary(&stack[tos], &stack[tos - pos], f)?;
+        return Ok(());
+    }
+    match op.op_code {
+        OP_IMMS => stack.push(Value::Scalar(exact(op.arg))),
+        OP_LDV => {
+            let input = &program.inputs[(op.arg - FIRST_INPUT_ID) as usize];
+            stack.push(Value::Vector(input.iter().map(|x| exact(x.0)).collect()));
+        }
+        OP_LDD => stack.push(stack[tos - pos].clone()),
+        OP_SWAP => stack.swap(tos, tos - pos),
+        OP_POPN => stack.truncate(depth - pos),
+        OP_VSUM => stack[tos] = Value::Scalar(sum(vector(&stack[tos]))?),
+        OP_VMIN | OP_VMAX => {
+            let v = vector(&stack[tos]);
+            let pick = if op.op_code == OP_VMIN { min } else { max };
+            let first = v.first().cloned().expect("Inputs are not empty");
+            let result = v.iter().try_fold(first, |acc, x| pick(&acc, x))?;
+            stack[tos] = Value::Scalar(result);
+        }
+        OP_VDOT => {
+            let result = dot(vector(&stack[tos]), vector(&stack[tos - pos]))?;
+            stack[tos] = Value::Scalar(result);
+        }
+        OP_VCUMSUM => {
+            let v = vector(&stack[tos]);
+            let results = (1..=v.len()).map(|n| sum(&v[..n]));
+            stack[tos] = Value::Vector(collect(results)?);
+        }
+        OP_VNORM => {
+            let v = vector(&stack[tos]);
+            let total = sum(v)?;
+            let results = v.iter().map(|x| div(x, &total));
+            stack[tos] = Value::Vector(collect(results)?);
+        }
+        OP_PKV => {
+            let Some(Value::Scalar(x)) = stack.pop() else {
+                unreachable!("Generated program is well-typed")
+            };
+            stack.push(Value::Vector(vec![x]));
+        }
+        OP_STV => {
+            stack.pop();
+        }
+        _ => unreachable!("Not generated: {}", op.op_code),
+    }
+    Ok(())
+}
+
+/// Execute program computing exact results and their error bounds
+pub fn execute(program: &Program) -> Outcome {
+    let mut stack = Vec::new();
+    let mut outputs = Vec::new();
+    for op in &program.ops {
+        if op.op_code == OP_STV {
+            outputs.push(vector(stack.last().expect("Stack is not empty")).to_vec());
+        }
+        if let Err(outcome) = execute_op(op, &mut stack, program) {
+            return outcome;
+        }
+    }
+    Outcome::Success(outputs)
+}
+
+/// Check that value computed by `VectorVM` is within bounds
+pub fn is_within(computed: Amount, expected: &Bounded) -> bool {
+    (rational(computed.0) - &expected.value).abs() <= expected.error
+}
+
+/// Storage for inputs and outputs of generated programs
+#[derive(Default)]
+pub struct MemoryVectorIO {
+    pub vectors: HashMap<u128, Vec<Amount>>,
+}
+
+impl MemoryVectorIO {
+    pub fn new(program: &Program) -> Self {
+        let mut vio = Self::default();
+        for (i, input) in program.inputs.iter().enumerate() {
+            vio.vectors
+                .insert(FIRST_INPUT_ID + i as u128, input.clone());
+        }
+        vio
+    }
+
+    /// Vectors stored by program, i.e. its operands left on the stack
+    pub fn outputs(&self, program: &Program) -> Vec<Vec<Amount>> {
+        (0..program.num_outputs)
+            .map(|i| self.vectors[&(FIRST_OUTPUT_ID + i as u128)].clone())
+            .collect()
+    }
+}
+
+impl VectorIO for MemoryVectorIO {
+    fn load_labels(&self, _id: u128) -> Result<Labels, ErrorCode> {
+        Err(ErrorCode::NotFound)
+    }
+
+    fn load_vector(&self, id: u128) -> Result<Vector, ErrorCode> {
+        let data = self.vectors.get(&id).ok_or(ErrorCode::NotFound)?.clone();
+        Ok(Vector { data })
+    }
+
+    fn load_code(&self, _id: u128) -> Result<Vec<u8>, ErrorCode> {
+        Err(ErrorCode::NotFound)
+    }
+
+    fn store_labels(&mut self, _id: u128, _input: Labels) -> Result<(), ErrorCode> {
+        Err(ErrorCode::InvalidOperand)
+    }
+
+    fn store_vector(&mut self, id: u128, input: Vector) -> Result<(), ErrorCode> {
+        self.vectors.insert(id, input.data);
+        Ok(())
+    }
+}
@@ -1,11 +1,11 @@
 use alloc::{vec, vec::Vec};
 
-use alloy_primitives::{uint, U128, U256};
+use alloy_primitives::{uint, FixedBytes, U128, U256, U64};
 use common::vector::Vector;
 use stylus_sdk::{
     keccak_const,
     prelude::*,
-    storage::{StorageBool, StorageBytes, StorageMap, StorageU128},
+    storage::{StorageBool, StorageBytes, StorageFixedBytes, StorageMap, StorageU128, StorageU64},
 };
 
 use crate::contracts::storage::StorageSlot;
@@ -17,11 +17,30 @@ pub const SCRATCH_4: U128 = uint!(4_U128);
 
 pub const FIRST_DYNAMIC_ID: U128 = uint!(100_U128);
 
+/// Size of the storage chunk in bytes, i.e. two vector components or labels
+pub const CHUNK_SIZE: usize = 32;
+
+/// Size of vector component or label in bytes
+const ITEM_SIZE: usize = size_of::<u128>();
+
 #[storage]
 pub struct ClerkStorage {
+    // Vectors stored before chunked storage was introduced. These are read
+    // as they are, and migrated into chunks on first write.
     vectors: StorageMap<U128, StorageBytes>,
     presence: StorageMap<U128, StorageBool>,
     last_vector: StorageU128,
+    // Bytes of each vector are stored in fixed-size chunks, so that ranges of
+    // very long vectors can be loaded and stored without touching the rest.
+    // Each chunk costs its own keccak and SLOAD, and so loading whole vector
+    // costs more than loading legacy vector, which hashed its key once.
+    chunks: StorageMap<U128, StorageMap<u64, StorageFixedBytes<CHUNK_SIZE>>>,
+    lengths: StorageMap<U128, StorageU64>,
+}
+
+#[inline]
+fn num_chunks(len_bytes: usize) -> usize {
+    len_bytes.div_ceil(CHUNK_SIZE)
 }
 
 pub const CLERK_STORAGE_SLOT: U256 = {
@@ -57,26 +76,126 @@ impl ClerkStorage {
         value
     }
 
+    pub fn contains(&self, id: U128) -> bool {
+        self.presence.get(id)
+    }
+
     pub fn len_bytes(&self, id: U128) -> usize {
-        self.vectors.get(id).len()
+        match self.lengths.get(id).to() {
+            0 => self.vectors.getter(id).len(),
+            len => len,
+        }
+    }
+
+    /// Tells whether vector was stored before chunked storage was introduced
+    fn is_legacy(&self, id: U128) -> bool {
+        self.lengths.get(id).is_zero() && !self.vectors.getter(id).is_empty()
+    }
+
+    /// Move bytes of legacy vector into chunks
+    fn migrate(&mut self, id: U128) {
+        if self.is_legacy(id) {
+            let data = self.vectors.getter(id).get_bytes();
+            self.store_bytes(id, data);
+        }
     }
 
     pub fn len_vector(&self, id: U128) -> usize {
-        self.len_bytes(id) / size_of::<u128>()
+        self.len_bytes(id) / ITEM_SIZE
     }
 
     pub fn store_bytes(&mut self, id: U128, data: impl AsRef<[u8]>) {
-        let mut vector = self.vectors.setter(id);
-        vector.set_bytes(data);
+        let data = data.as_ref();
+        let old_num_chunks = num_chunks(self.lengths.get(id).to());
+        if self.is_legacy(id) {
+            self.vectors.setter(id).erase();
+        }
+        let mut chunks = self.chunks.setter(id);
+        for (index, chunk) in data.chunks(CHUNK_SIZE).enumerate() {
+            let mut value = FixedBytes::<CHUNK_SIZE>::ZERO;
+            value[..chunk.len()].copy_from_slice(chunk);
+            chunks.setter(index as u64).set(value);
+        }
+        for index in num_chunks(data.len())..old_num_chunks {
+            chunks.delete(index as u64);
+        }
+        self.lengths.setter(id).set(U64::from(data.len()));
         self.presence.setter(id).set(true);
     }
 
+    /// Fetch all bytes of the vector
+    ///
+    /// Costs one keccak and one SLOAD per 32 bytes chunk, so use
+    /// `fetch_bytes_range()` when only part of very long vector is needed.
     pub fn fetch_bytes(&self, id: U128) -> Option<Vec<u8>> {
+        self.fetch_bytes_range(id, 0, self.len_bytes(id))
+    }
+
+    /// Fetch `len` bytes starting at `start` touching only chunks in range
+    ///
+    /// Returns `None` if there is no such vector, or range is out of bounds.
+    pub fn fetch_bytes_range(&self, id: U128, start: usize, len: usize) -> Option<Vec<u8>> {
+        if !self.presence.get(id) {
+            return None;
+        }
+        let end = start.checked_add(len)?;
+        if self.len_bytes(id) < end {
+            return None;
+        }
+        if self.is_legacy(id) {
+            let vector = self.vectors.getter(id);
+            return Some(vector.get_bytes()[start..end].to_vec());
+        }
+        let chunks = self.chunks.getter(id);
+        let mut result = Vec::with_capacity(len);
+        let mut offset = start;
+        while offset < end {
+            let value = chunks.get((offset / CHUNK_SIZE) as u64);
+            let from = offset % CHUNK_SIZE;
+            let to = CHUNK_SIZE.min(from + end - offset);
+            result.extend_from_slice(&value[from..to]);
+            offset += to - from;
+        }
+        Some(result)
+    }
+
+    /// Overwrite bytes starting at `start` touching only chunks in range
+    ///
+    /// Range must be within bounds of existing vector, i.e. length of the
+    /// vector doesn't change. Returns `None` if there is no such vector, or
+    /// range is out of bounds.
+    pub fn store_bytes_range(
+        &mut self,
+        id: U128,
+        start: usize,
+        data: impl AsRef<[u8]>,
+    ) -> Option<()> {
+        let data = data.as_ref();
         if !self.presence.get(id) {
             return None;
         }
-        let vector = self.vectors.getter(id);
-        Some(vector.get_bytes())
+        let end = start.checked_add(data.len())?;
+        if self.len_bytes(id) < end {
+            return None;
+        }
+        self.migrate(id);
+        let mut chunks = self.chunks.setter(id);
+        let mut offset = start;
+        while offset < end {
+            let index = (offset / CHUNK_SIZE) as u64;
+            let from = offset % CHUNK_SIZE;
+            let to = CHUNK_SIZE.min(from + end - offset);
+            // Partially overwritten chunk needs to preserve remaining bytes
+            let mut value = if to - from < CHUNK_SIZE {
+                chunks.get(index)
+            } else {
+                FixedBytes::ZERO
+            };
+            value[from..to].copy_from_slice(&data[offset - start..offset - start + to - from]);
+            chunks.setter(index).set(value);
+            offset += to - from;
+        }
+        Some(())
     }
 
     pub fn store_vector(&mut self, vector_id: U128, vector: Vector) {
@@ -88,4 +207,144 @@ impl ClerkStorage {
         Some(Vector::from_vec(data))
     }
 
+    pub fn store_vector_range(
+        &mut self,
+        vector_id: U128,
+        start: usize,
+        vector: Vector,
+    ) -> Option<()> {
+        self.store_bytes_range(vector_id, start.checked_mul(ITEM_SIZE)?, vector.to_vec())
+    }
+
+    pub fn fetch_vector_range(&self, vector_id: U128, start: usize, len: usize) -> Option<Vector> {
+        let data = self.fetch_bytes_range(
+            vector_id,
+            start.checked_mul(ITEM_SIZE)?,
+            len.checked_mul(ITEM_SIZE)?,
+        )?;
+        Some(Vector::from_vec(data))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use stylus_sdk::testing::TestVM;
+    use vector_macros::amount_vec;
+
+    use super::*;
+
+    fn bytes(len: usize) -> Vec<u8> {
+        (0..len).map(|x| x as u8).collect()
+    }
+
+    fn store_legacy(clerk: &mut ClerkStorage, id: U128, data: &[u8]) {
+        clerk.vectors.setter(id).set_bytes(data);
+        clerk.presence.setter(id).set(true);
+    }
+
+    #[test]
+    fn test_chunks() {
+        let vm = TestVM::default();
+        let mut clerk = ClerkStorage::from(&vm);
+        let id = uint!(7_U128);
+        let missing_id = uint!(8_U128);
+
+        // Three full chunks and a partial one
+        let data = bytes(100);
+        clerk.store_bytes(id, &data);
+        assert_eq!(clerk.len_bytes(id), 100);
+        assert_eq!(clerk.fetch_bytes(id), Some(data.clone()));
+
+        // Unaligned ranges
+        assert_eq!(
+            clerk.fetch_bytes_range(id, 5, 40),
+            Some(data[5..45].to_vec())
+        );
+        assert_eq!(
+            clerk.fetch_bytes_range(id, 96, 4),
+            Some(data[96..].to_vec())
+        );
+        assert_eq!(clerk.fetch_bytes_range(id, 100, 0), Some(vec![]));
+
+        // Out of range
+        assert_eq!(clerk.fetch_bytes_range(id, 90, 11), None);
+        assert_eq!(clerk.fetch_bytes_range(id, usize::MAX, 1), None);
+        assert_eq!(clerk.fetch_bytes_range(missing_id, 0, 0), None);
+        assert_eq!(clerk.store_bytes_range(id, 90, [0; 11]), None);
+        assert_eq!(clerk.store_bytes_range(missing_id, 0, []), None);
+        assert_eq!(clerk.fetch_bytes(id), Some(data.clone()));
+
+        // Partially overwritten chunks keep remaining bytes
+        let mut expected = data.clone();
+        expected[30..70].fill(0xff);
+        clerk.store_bytes_range(id, 30, [0xff; 40]).unwrap();
+        assert_eq!(clerk.fetch_bytes(id), Some(expected));
+
+        // Chunks no longer used are deleted
+        clerk.store_bytes(id, &data[..10]);
+        assert_eq!(clerk.fetch_bytes(id), Some(data[..10].to_vec()));
+        assert_eq!(clerk.chunks.getter(id).get(1), FixedBytes::ZERO);
+    }
+
+    #[test]
+    fn test_vector_range() {
+        let vm = TestVM::default();
+        let mut clerk = ClerkStorage::from(&vm);
+        let id = uint!(7_U128);
+
+        clerk.store_vector(id, amount_vec![1, 2, 3, 4, 5]);
+        assert_eq!(clerk.len_vector(id), 5);
+
+        // Components span chunk boundaries
+        let range = clerk.fetch_vector_range(id, 1, 3).unwrap();
+        assert_eq!(range.data, amount_vec![2, 3, 4].data);
+
+        clerk
+            .store_vector_range(id, 3, amount_vec![10, 11])
+            .unwrap();
+        let vector = clerk.fetch_vector(id).unwrap();
+        assert_eq!(vector.data, amount_vec![1, 2, 3, 10, 11].data);
+        assert!(clerk.fetch_vector_range(id, 4, 2).is_none());
+        assert!(clerk
+            .store_vector_range(id, usize::MAX, Vector::new())
+            .is_none());
+    }
+
+    #[test]
+    fn test_legacy() {
+        let vm = TestVM::default();
+        let mut clerk = ClerkStorage::from(&vm);
+        let id = uint!(7_U128);
+        let other_id = uint!(8_U128);
+        let data = bytes(100);
+
+        // Legacy vectors are read as they are
+        store_legacy(&mut clerk, id, &data);
+        assert!(clerk.is_legacy(id));
+        assert_eq!(clerk.len_bytes(id), 100);
+        assert_eq!(clerk.fetch_bytes(id), Some(data.clone()));
+        assert_eq!(
+            clerk.fetch_bytes_range(id, 5, 40),
+            Some(data[5..45].to_vec())
+        );
+        assert_eq!(clerk.fetch_bytes_range(id, 90, 11), None);
+        assert_eq!(clerk.store_bytes_range(id, 90, [0; 11]), None);
+        assert!(clerk.is_legacy(id));
+
+        // Range write migrates legacy vector into chunks
+        let mut expected = data.clone();
+        expected[30..70].fill(0xff);
+        clerk.store_bytes_range(id, 30, [0xff; 40]).unwrap();
+        assert!(!clerk.is_legacy(id));
+        assert!(clerk.vectors.getter(id).is_empty());
+        assert_eq!(clerk.len_bytes(id), 100);
+        assert_eq!(clerk.fetch_bytes(id), Some(expected));
+
+        // Full write replaces legacy vector
+        store_legacy(&mut clerk, other_id, &data);
+        clerk.store_bytes(other_id, &data[..10]);
+        assert!(!clerk.is_legacy(other_id));
+        assert!(clerk.vectors.getter(other_id).is_empty());
+        assert_eq!(clerk.fetch_bytes(other_id), Some(data[..10].to_vec()));
+    }
 }
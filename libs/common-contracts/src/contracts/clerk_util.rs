@@ -1,8 +1,12 @@
 use alloc::{vec, vec::Vec};
-use common::{amount::Amount, labels::Labels, vector::Vector};
+use common::{amount::Amount, labels::Labels, uint::read_u128, vector::Vector};
 
-use super::{clerk::ClerkStorage, keep::Vault};
-use alloy_primitives::{Address, U128};
+use super::{
+    clerk::ClerkStorage,
+    keep::{Keep, Vault},
+};
+use alloy_primitives::{Address, B256, U128, U32, U8};
+use stylus_sdk::crypto::keccak;
 
 pub fn new_vector_bytes(clerk_storage: &mut ClerkStorage, data: impl AsRef<[u8]>) -> U128 {
     let vector_id = clerk_storage.next_vector();
@@ -25,6 +29,55 @@ pub fn new_labels_empty(clerk_storage: &mut ClerkStorage) -> U128 {
     new_labels(clerk_storage, Labels::new())
 }
 
+/// Position of the first label for which `pred` is false
+///
+/// Labels must be partitioned by `pred`, e.g. sorted. Labels are fetched one
+/// at a time, so that only few chunks of very long labels are touched.
+fn partition_point_labels(
+    clerk_storage: &ClerkStorage,
+    labels_id: U128,
+    pred: impl Fn(u128) -> bool,
+) -> Option<usize> {
+    let label_size = size_of::<u128>();
+    let mut low = 0;
+    let mut high = clerk_storage.len_vector(labels_id);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let data = clerk_storage.fetch_bytes_range(labels_id, mid * label_size, label_size)?;
+        if pred(read_u128(&data)) {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    Some(low)
+}
+
+/// Find range of positions spanning labels from `first` to `last` inclusive
+///
+/// Labels must be sorted. Returns `(start, len)`, which can be used with
+/// `LDLR`, `LDVR` and `STVR` to only touch that part of very long vectors,
+/// or `None` unless range starts with `first` and ends with `last`, e.g. when
+/// labels are missing, or aren't sorted after all.
+pub fn find_labels_range(
+    clerk_storage: &ClerkStorage,
+    labels_id: U128,
+    first: u128,
+    last: u128,
+) -> Option<(usize, usize)> {
+    let label_size = size_of::<u128>();
+    let label_at = |pos: usize| {
+        let data = clerk_storage.fetch_bytes_range(labels_id, pos * label_size, label_size)?;
+        Some(read_u128(&data))
+    };
+    let start = partition_point_labels(clerk_storage, labels_id, |label| label < first)?;
+    let end = partition_point_labels(clerk_storage, labels_id, |label| label <= last)?;
+    if end <= start || label_at(start)? != first || label_at(end - 1)? != last {
+        return None;
+    }
+    Some((start, end - start))
+}
+
 pub fn new_vector(clerk_storage: &mut ClerkStorage, data: Vector) -> U128 {
     let vector_id = clerk_storage.next_vector();
 
@@ -166,3 +219,30 @@ pub fn lazy_init_vendor_quote(
 
     quote_id
 }
+
+/// Install program into the registry unless it is already installed
+///
+/// Returns hash of the program, and id of its code if program was installed
+/// by this call, so that caller can tell whether to emit an event.
+pub fn lazy_install_program(
+    storage: &mut Keep,
+    clerk_storage: &mut ClerkStorage,
+    code: impl AsRef<[u8]>,
+    version: u32,
+    num_args: u8,
+) -> (B256, Option<U128>) {
+    let program_hash = keccak(code.as_ref());
+    if storage.programs.get(program_hash).is_installed() {
+        return (program_hash, None);
+    }
+
+    let code_id = new_vector_bytes(clerk_storage, code);
+
+    let mut program = storage.programs.setter(program_hash);
+    program.code_id.set(code_id);
+    program.version.set(U32::from(version));
+    program.num_args.set(U8::from(num_args));
+    storage.program_hashes.push(program_hash);
+
+    (program_hash, Some(code_id))
+}
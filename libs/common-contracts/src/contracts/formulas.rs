@@ -358,3 +358,117 @@ impl Report {
         self.report.data[REPORT_RECEIVED_OFFSET]
     }
 }
+
+/// Orders, quotes and reports are serialized with named fields, e.g.
+/// `{"capacity": "10.0", "price": "1.5", "slope": "0.01"}`, rather than as
+/// vectors, so that consumers don't need to know offsets.
+#[cfg(feature = "with-serde")]
+mod serde_impl {
+    use alloc::vec;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct OrderFields {
+        collateral_remaining: Amount,
+        collateral_spent: Amount,
+        itp_minted: Amount,
+        itp_locked: Amount,
+        itp_burned: Amount,
+        collateral_withdrawn: Amount,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct QuoteFields {
+        capacity: Amount,
+        price: Amount,
+        slope: Amount,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct ReportFields {
+        delivered: Amount,
+        received: Amount,
+    }
+
+    impl Serialize for Order {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            OrderFields {
+                collateral_remaining: self.collateral_remaining(),
+                collateral_spent: self.collateral_spent(),
+                itp_minted: self.itp_minted(),
+                itp_locked: self.itp_locked(),
+                itp_burned: self.itp_burned(),
+                collateral_withdrawn: self.collateral_withdrawn(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Order {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let fields = OrderFields::deserialize(deserializer)?;
+            Ok(Self {
+                bid: Vector {
+                    data: vec![
+                        fields.collateral_remaining,
+                        fields.collateral_spent,
+                        fields.itp_minted,
+                    ],
+                },
+                ask: Vector {
+                    data: vec![
+                        fields.itp_locked,
+                        fields.itp_burned,
+                        fields.collateral_withdrawn,
+                    ],
+                },
+            })
+        }
+    }
+
+    impl Serialize for Quote {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            QuoteFields {
+                capacity: self.capacity(),
+                price: self.price(),
+                slope: self.slope(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Quote {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let fields = QuoteFields::deserialize(deserializer)?;
+            Ok(Self {
+                quote: Vector {
+                    data: vec![fields.capacity, fields.price, fields.slope],
+                },
+            })
+        }
+    }
+
+    impl Serialize for Report {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            ReportFields {
+                delivered: self.delivered(),
+                received: self.received(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Report {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let fields = ReportFields::deserialize(deserializer)?;
+            Ok(Self {
+                report: Vector {
+                    data: vec![fields.delivered, fields.received],
+                },
+            })
+        }
+    }
+}
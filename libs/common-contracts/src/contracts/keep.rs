@@ -1,10 +1,13 @@
 use alloc::{vec, vec::Vec};
 
-use alloy_primitives::{uint, Address, U128, U256, U32, U8};
+use alloy_primitives::{uint, Address, B256, U128, U256, U32, U8};
 use stylus_sdk::{
     keccak_const,
     prelude::*,
-    storage::{StorageAddress, StorageMap, StorageU128, StorageU32, StorageU8, StorageVec},
+    storage::{
+        StorageAddress, StorageB256, StorageBool, StorageMap, StorageU128, StorageU32, StorageU8,
+        StorageVec,
+    },
 };
 
 use crate::contracts::storage::StorageSlot;
@@ -106,9 +109,9 @@ impl Vault {
 pub struct Account {
     owner: StorageAddress,
 
-    // TODO: These will be very long vectors, e.g. 2M components.
-    // We will optimise Clerk and Abacus to provide partial load/store
-    // and we'll store chunks in mapping.
+    // These will be very long vectors, e.g. 2M components. Clerk stores
+    // them in chunks, and formulas should use LDLR, LDVR and STVR to only
+    // load and store the range they need.
     pub assets: StorageU128, // Vector = [Name; num_assets]
     pub margin: StorageU128, // Vector = [Margin; num_assets]
 
@@ -155,6 +158,35 @@ impl Account {
     }
 }
 
+#[storage]
+pub struct StoredProgram {
+    pub code_id: StorageU128, // Code = [u8; code_len]
+    pub version: StorageU32,
+    pub num_args: StorageU8,
+    pub retired: StorageBool,
+}
+
+impl StoredProgram {
+    pub fn is_installed(&self) -> bool {
+        !self.code_id.get().is_zero()
+    }
+
+    pub fn only_installed(&self) -> Result<(), Vec<u8>> {
+        if !self.is_installed() {
+            Err(b"Program not installed")?;
+        }
+        Ok(())
+    }
+
+    pub fn only_active(&self) -> Result<(), Vec<u8>> {
+        self.only_installed()?;
+        if self.retired.get() {
+            Err(b"Program retired")?;
+        }
+        Ok(())
+    }
+}
+
 #[storage]
 pub struct Keep {
     // Integrity Protection
@@ -172,6 +204,11 @@ pub struct Keep {
     pub clerk: StorageAddress,
     pub scribe: StorageAddress,
     pub worksman: StorageAddress,
+
+    // Program Registry
+    pub programs: StorageMap<B256, StoredProgram>, // Mapping = {Keccak256(Code) => Stored Program}
+    pub program_hashes: StorageVec<StorageB256>, // List of program hashes in order of installation
+    pub update_quote_hash: StorageB256, // Keccak256(Code) of update quote template installed by Banker
 }
 
 impl Keep {
@@ -1,6 +1,6 @@
 use alloc::vec::Vec;
 
-use alloy_primitives::{Address, Bytes};
+use alloy_primitives::{Address, Bytes, B256, U128};
 
 use crate::{
     contracts::calls::InnerCall,
@@ -17,6 +17,14 @@ pub trait KeepCalls {
         num_registry: u128,
     ) -> Result<(), Vec<u8>>;
 
+    fn execute_program(
+        &mut self,
+        clerk: Address,
+        program_hash: B256,
+        args: Vec<U128>,
+        num_registry: u128,
+    ) -> Result<(), Vec<u8>>;
+
     fn build_vault(&mut self, worksman: Address) -> Result<Address, Vec<u8>>;
 
     fn verify_signature(
@@ -48,6 +56,22 @@ where
         Ok(())
     }
 
+    fn execute_program(
+        &mut self,
+        clerk: Address,
+        program_hash: B256,
+        args: Vec<U128>,
+        num_registry: u128,
+    ) -> Result<(), Vec<u8>> {
+        let call = IClerk::executeProgramCall {
+            program_hash,
+            args: args.into_iter().map(|arg| arg.to()).collect(),
+            num_registry,
+        };
+        self.inner_call(clerk, call)?;
+        Ok(())
+    }
+
     fn build_vault(&mut self, worksman: Address) -> Result<Address, Vec<u8>> {
         let IWorksman::buildVaultReturn { _0: result } =
             self.inner_call_ret(worksman, IWorksman::buildVaultCall {})?;
@@ -8,6 +8,9 @@ sol! {
 
         function submitSupply(uint128 vendor_id, bytes calldata asset_names, bytes calldata asset_quantities_short, bytes calldata asset_quantities_long) external;
 
+        /// Asset names should be sorted, so that only the range of market
+        /// vectors spanning them is updated, and otherwise whole market
+        /// vectors are loaded and stored.
         function submitMarketData(uint128 vendor_id, bytes calldata asset_names, bytes calldata asset_liquidity, bytes calldata asset_prices, bytes calldata asset_slopes) external;
         
         function updateIndexQuote(uint128 vendor_id, uint128 index_id) external;
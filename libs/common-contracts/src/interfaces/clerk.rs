@@ -1,7 +1,95 @@
+use alloc::vec::Vec;
+
 use alloy_sol_types::sol;
+use common::abacus::program_error::{ErrorCode, Frame, OperandType, ProgramError};
 
 sol! {
     interface IClerk  {
         function updateRecords(bytes calldata code, uint128 num_registry) external;
+
+        function executeProgram(bytes32 program_hash, uint128[] memory args, uint128 num_registry) external;
+
+        function installProgram(bytes calldata code, uint32 version, uint8 num_args) external returns (bytes32);
+
+        function retireProgram(bytes32 program_hash) external;
+
+        function getProgram(bytes32 program_hash) external view returns (uint128, uint32, uint8, bool);
+
+        function getProgramCount() external view returns (uint256);
+
+        function getPrograms(uint256 start_from, uint256 max_len) external view returns (bytes32[] memory);
+
+        event ProgramInstalled(bytes32 program_hash, uint128 code_id, uint32 version);
+
+        event ProgramRetired(bytes32 program_hash);
+
+        /// Program failed, see `ProgramError` for meaning of the fields
+        ///
+        /// Frame iteration is `type(uint64).max` for `B` frames, and op-code
+        /// is zero when failure wasn't at an instruction.
+        error ProgramFailed(
+            uint8 error_code,
+            uint128[] error_args,
+            uint32 program_counter,
+            uint32 stack_depth,
+            uint8 op_code,
+            uint128[] frame_programs,
+            uint64[] frame_iterations,
+            uint8[] operands
+        );
+    }
+}
+
+/// Frame iteration of `B` frames in `ProgramFailed`
+const NO_ITERATION: u64 = u64::MAX;
+
+impl From<&ProgramError> for IClerk::ProgramFailed {
+    fn from(err: &ProgramError) -> Self {
+        Self {
+            error_code: err.error_code.code(),
+            error_args: err.error_code.args(),
+            program_counter: err.program_counter as u32,
+            stack_depth: err.stack_depth as u32,
+            op_code: err.op_code.unwrap_or_default(),
+            frame_programs: err.frames.iter().map(|frame| frame.program_id).collect(),
+            frame_iterations: err
+                .frames
+                .iter()
+                .map(|frame| frame.iteration.map_or(NO_ITERATION, |x| x as u64))
+                .collect(),
+            operands: err.operands.iter().map(OperandType::code).collect(),
+        }
+    }
+}
+
+impl TryFrom<IClerk::ProgramFailed> for ProgramError {
+    type Error = ();
+
+    /// Decode error returned by Clerk, failing on unknown error code or
+    /// operand type
+    fn try_from(err: IClerk::ProgramFailed) -> Result<Self, Self::Error> {
+        let frames = err
+            .frame_programs
+            .into_iter()
+            .zip(err.frame_iterations)
+            .map(|(program_id, iteration)| Frame {
+                program_id,
+                iteration: (iteration != NO_ITERATION).then_some(iteration as usize),
+            })
+            .collect();
+        let operands = err
+            .operands
+            .into_iter()
+            .map(OperandType::from_code)
+            .collect::<Option<Vec<_>>>()
+            .ok_or(())?;
+        Ok(ProgramError {
+            error_code: ErrorCode::from_code(err.error_code, &err.error_args).ok_or(())?,
+            program_counter: err.program_counter as usize,
+            stack_depth: err.stack_depth as usize,
+            op_code: (err.op_code != 0).then_some(err.op_code),
+            frames,
+            operands,
+        })
     }
 }
\ No newline at end of file
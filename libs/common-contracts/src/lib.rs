@@ -3,20 +3,31 @@
 //#[macro_use]
 extern crate alloc;
 
-#[cfg(feature = "stylus")]
 pub mod contracts {
+    #[cfg(feature = "stylus")]
     pub mod acl;
+    #[cfg(feature = "stylus")]
     pub mod calls;
+    #[cfg(feature = "stylus")]
     pub mod castle;
+    #[cfg(feature = "stylus")]
     pub mod clerk;
+    #[cfg(feature = "stylus")]
     pub mod delegate;
     pub mod formulas;
+    #[cfg(feature = "stylus")]
     pub mod gate;
+    #[cfg(feature = "stylus")]
     pub mod keep;
+    #[cfg(feature = "stylus")]
     pub mod keep_calls;
+    #[cfg(feature = "stylus")]
     pub mod clerk_util;
+    #[cfg(feature = "stylus")]
     pub mod storage;
+    #[cfg(feature = "stylus")]
     pub mod vault;
+    #[cfg(feature = "stylus")]
     pub mod vault_native;
 }
 
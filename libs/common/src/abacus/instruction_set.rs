@@ -1,17 +1,20 @@
 // Vector Instruction Set (VIS) for Vector IL (VIL) Virtual Machine
 
-// 1. Data Loading & Stack Access (10-14)
+// 1. Data Loading & Stack Access (10-17)
 pub const OP_LDL: u8 = 10; //   LDL <label_id>                ; no stack args ; result = [TOS: Labels]; Load Labels object from VIO by ID. Pushes on TOS.
 pub const OP_LDV: u8 = 11; //   LDV <vector_id>               ; no stack args ; result = [TOS: Vector]; Load Vector object from VIO by ID. Pushes on TOS.
 
 pub const OP_LDD: u8 = 13; //   LDD <pos>                     ; stack args = [TOS - pos] ; result = [TOS]; Load Duplicate (copy) of stack operand at [T-pos]. Pushes on TOS.
 pub const OP_LDR: u8 = 14; //   LDR <reg>                     ; no stack args ; result = [TOS - pos] ; Load value from Registry (R0-Rn). Pushes on TOS.
 pub const OP_LDM: u8 = 15; //   LDM <reg>                     ; no stack args ; result = [TOS - pos] ; Load value moving it out of Registry (R0-Rn). Value is removed from registry. Pushes on TOS.
+pub const OP_LDLR: u8 = 16; //  LDLR <label_id> <start> <len> ; no stack args ; result = [TOS: Labels]; Load Range of `len` labels starting at `start` from Labels object in VIO by ID. Fails if range is out of bounds. Pushes on TOS.
+pub const OP_LDVR: u8 = 17; //  LDVR <vector_id> <start> <len> ; no stack args ; result = [TOS: Vector]; Load Range of `len` components starting at `start` from Vector object in VIO by ID. Fails if range is out of bounds. Pushes on TOS.
 
-// 2. Data Storage & Register Access (20-23)
+// 2. Data Storage & Register Access (20-24)
 pub const OP_STL: u8 = 20; //   STL <label_id>                ; stack args = [TOS: Labels] ; Store Labels object into VIO. Consumes TOS.
 pub const OP_STV: u8 = 21; //   STV <vector_id>               ; stack args = [TOS: Vector] ; Store Vector object into VIO. Consumes TOS.
 pub const OP_STR: u8 = 23; //   STR <reg>                     ; stack args = [TOS - pos] ; stack unchanged, result in registry[reg]; Store into Registry (R0-Rn). Consumes TOS.
+pub const OP_STVR: u8 = 24; //  STVR <vector_id> <start>      ; stack args = [TOS: Vector] ; Store Vector into Range of Vector object in VIO starting at `start`, i.e. overwrite components `start..start + len(TOS)`. Fails if range is out of bounds. Consumes TOS.
 
 // 3. Data Structure Manipulation (30-35)
 pub const OP_PKV: u8 = 30; //   PKV <count>                   ; stack args = [TOS - count, ..., TOS: Scalar] ; result [TOS]; Pack `count` values from stack into a new Vector. Consumes `count` operands from TOS, and replaces them with Vector.
@@ -25,26 +28,37 @@ pub const OP_T: u8 = 35; //     T <count>                     ; stack args = [TO
 pub const OP_LUNION: u8 = 40; // LUNION <pos>                 ; stack args = [TOS - pos, TOS] ; result = [TOS] ; Union of two Labels operands (TOS and T-pos). Pushes on TOS.
 pub const OP_LPUSH: u8 = 41; //  LPUSH <immediate (label)>    ; stack args = [TOS: Labels[0..len]] ; result = [TOS:Labels[0..len + 1]] ; Push a label value onto the Labels object (TOS). In-place updates Labels on TOS, appending new component at the end.
 pub const OP_LPOP: u8 = 42; //   LPOP                         ; stack args = [TOS: Labels[0..len]] ; result = [TOS: Lables[0..len - 1]] ; Pop a label value from the Labels object (TOS). In-place updates Labels on TOS, removing last component.
-pub const OP_JUPD: u8 = 43; //   JUPD <pos_B> <lab_A> <lab_B> ; stack args = [TOS - lab_A: 'LA, TOS - lab_B: Labels 'LB, TOS - pos_B, TOS: Vector: 'A] ; result = [TOS: 'A filtered mapped 'LB to 'LA]; Update using Labels. Expands vector at [TOS - pos_B] using labels at [TOS - lab_B] to match labels of TOS at [TOS - lab_A]. In-place updates TOS. Consumes TOS.
-pub const OP_JADD: u8 = 44; //   JADD <pos_B> <lab_A> <lab_B> ; stack args = [TOS - lab_A: 'LA, TOS - lab_B: Labels 'LB, TOS - pos_B, TOS: Vector: 'A] ; result = [TOS: 'A expaned w/ 0 mapped 'LB to 'LA]; Add using Labels. Expands vector at [TOS - pos_B] using labels at [TOS - lab_B] to match labels of TOS at [TOS - lab_A]. In-place updates TOS. Consumes TOS.
-pub const OP_JFLT: u8 = 45; //   JFLT <lab_A> <lab_B>         ; stack args = [TOS - lab_A: 'LA, TOS - lab_B: Labels 'LB, TOS: Vector: 'A] ; result = [TOS: 'A filtered mapped 'LB to 'LA]; Filter using Labels. Expands vector at [TOS-1] using labels at [T-lab_B] to match labels of TOS at [T-lab_A]. In-place updates TOS. Does not consume other operands.
+pub const OP_JUPD: u8 = 43; //   JUPD <pos_B> <lab_A> <lab_B> ; stack args = [TOS - lab_A: 'LA, TOS - lab_B: Labels 'LB, TOS - pos_B, TOS: Vector: 'A] ; result = [TOS: 'A filtered mapped 'LB to 'LA]; Update using Labels. Expands vector at [TOS - pos_B] using labels at [TOS - lab_B] to match labels of TOS at [TOS - lab_A]. In-place updates TOS. Consumes TOS. Fails if any label of 'LB is missing from 'LA. Labels need not be sorted, however sorted labels are joined faster.
+pub const OP_JADD: u8 = 44; //   JADD <pos_B> <lab_A> <lab_B> ; stack args = [TOS - lab_A: 'LA, TOS - lab_B: Labels 'LB, TOS - pos_B, TOS: Vector: 'A] ; result = [TOS: 'A expaned w/ 0 mapped 'LB to 'LA]; Add using Labels. Expands vector at [TOS - pos_B] using labels at [TOS - lab_B] to match labels of TOS at [TOS - lab_A]. In-place updates TOS. Consumes TOS. Fails if any label of 'LB is missing from 'LA. Labels need not be sorted, however sorted labels are joined faster.
+pub const OP_JFLT: u8 = 45; //   JFLT <lab_A> <lab_B>         ; stack args = [TOS - lab_A: 'LA, TOS - lab_B: Labels 'LB, TOS: Vector: 'A] ; result = [TOS: 'A filtered mapped 'LB to 'LA]; Filter using Labels. Expands vector at [TOS-1] using labels at [T-lab_B] to match labels of TOS at [T-lab_A]. In-place updates TOS. Does not consume other operands. Fails if any label of 'LB is missing from 'LA. Labels need not be sorted, however sorted labels are joined faster.
 
-// 5. Arithmetic & Core Math (50-55)
-pub const OP_ADD: u8 = 50; //    ADD <pos>                    ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS] ; Add TOS by operand at [T-pos]. Works with vectors and scalars. In-place updates operand on TOS. Does not consume the other operand.
-pub const OP_SUB: u8 = 51; //    SUB <pos>                    ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS] ; Subtract TOS by operand at [T-pos]. Works with vectors and scalars. In-place updates operand on TOS. Does not consume the other operand.
+// 5. Arithmetic & Core Math (50-58)
+pub const OP_ADD: u8 = 50; //    ADD <pos>                    ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS] ; Add TOS by operand at [T-pos]. Works with vectors and scalars. Signed TOS works with signed or unsigned operand at [T-pos]. Sparse TOS works with sparse operand at [T-pos], resulting in union of their labels. In-place updates operand on TOS. Does not consume the other operand.
+pub const OP_SUB: u8 = 51; //    SUB <pos>                    ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS] ; Subtract TOS by operand at [T-pos]. Works with vectors and scalars. Signed TOS works with signed or unsigned operand at [T-pos]. In-place updates operand on TOS. Does not consume the other operand.
 pub const OP_SSB: u8 = 52; //    SSB <pos>                    ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS] ; Saturating subtract TOS by operand at [T-pos]. Works with vectors and scalars. In-place updates operand on TOS. Does not consume the other operand.
-pub const OP_MUL: u8 = 53; //    MUL <pos>                    ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS] ; Multiply TOS by operand at [T-pos]. Works with vectors and scalars. In-place updates operand on TOS. Does not consume the other operand.
-pub const OP_DIV: u8 = 54; //    DIV <pos>                    ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS] ; Divide TOS by operand at [T-pos]. Works with vectors and scalars. In-place updates operand on TOS. Does not consume the other operand.
+pub const OP_MUL: u8 = 53; //    MUL <pos>                    ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS] ; Multiply TOS by operand at [T-pos]. Works with vectors and scalars. Signed TOS works with signed or unsigned operand at [T-pos]. Sparse TOS works with sparse operand at [T-pos], resulting in intersection of their labels, or with scalar. In-place updates operand on TOS. Does not consume the other operand.
+pub const OP_DIV: u8 = 54; //    DIV <pos>                    ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS] ; Divide TOS by operand at [T-pos]. Works with vectors and scalars. Signed TOS works with signed or unsigned operand at [T-pos]. In-place updates operand on TOS. Does not consume the other operand.
 pub const OP_SQRT: u8 = 55; //   SQRT                         ; stack args = [TOS: Vector|Scalar]; result = [TOS] ; Square root of TOS (scalar or component-wise vector). Works with vectors and scalars. In-place updates operand on TOS.
+pub const OP_EXP: u8 = 56; //    EXP                          ; stack args = [TOS: Vector|Scalar]; result = [TOS] ; Natural exponent of TOS (scalar or component-wise vector), i.e. e^TOS. Works with vectors and scalars. In-place updates operand on TOS.
+pub const OP_LN: u8 = 57; //     LN                           ; stack args = [TOS: Vector|Scalar]; result = [TOS] ; Natural logarithm of TOS (scalar or component-wise vector). Fails for values below one as their logarithm is negative. Works with vectors and scalars. In-place updates operand on TOS.
+pub const OP_POW: u8 = 58; //    POW <pos>                    ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS] ; Raise TOS to the power of operand at [T-pos]. Works with vectors and scalars. In-place updates operand on TOS. Does not consume the other operand.
 
-// 6. Logic & Comparison (60-61)
+// 6. Logic & Comparison (60-66)
 pub const OP_MIN: u8 = 60; //    MIN <pos>                    ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS: Vector|Scalar] ; Min between TOS and operand at [T-pos]. Works with vectors and scalars. In-place updates operand on TOS. Does not consume the other operand.
 pub const OP_MAX: u8 = 61; //    MAX <pos>                    ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS: Vector|Scalar] ; Max between TOS and operand at [T-pos]. Works with vectors and scalars. In-place updates operand on TOS. Does not consume the other operand.
+pub const OP_EQ: u8 = 62; //     EQ <pos>                     ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS: Vector|Scalar] ; Compare TOS with operand at [T-pos] producing mask, i.e. 1 where components are equal and 0 otherwise. Works with vectors and scalars. In-place updates operand on TOS. Does not consume the other operand.
+pub const OP_GT: u8 = 63; //     GT <pos>                     ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS: Vector|Scalar] ; Compare TOS with operand at [T-pos] producing mask, i.e. 1 where TOS component is greater and 0 otherwise. Works with vectors and scalars. In-place updates operand on TOS. Does not consume the other operand.
+pub const OP_LT: u8 = 64; //     LT <pos>                     ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS: Vector|Scalar] ; Compare TOS with operand at [T-pos] producing mask, i.e. 1 where TOS component is less and 0 otherwise. Works with vectors and scalars. In-place updates operand on TOS. Does not consume the other operand.
+pub const OP_CLAMP: u8 = 65; //  CLAMP <lo_pos> <hi_pos>      ; stack args = [TOS - lo_pos, TOS - hi_pos, TOS: Vector|Scalar] ; result = [TOS: Vector|Scalar] ; Clamp TOS between operands at [T-lo_pos] and [T-hi_pos], i.e. MIN(MAX(TOS, lo), hi). Bounds can be vectors or scalars. In-place updates operand on TOS. Does not consume the other operands.
+pub const OP_SEL: u8 = 66; //    SEL <mask_pos> <other_pos>   ; stack args = [TOS - mask_pos, TOS - other_pos, TOS: Vector|Scalar] ; result = [TOS: Vector|Scalar] ; Select component-wise by mask at [T-mask_pos], i.e. keep TOS component where mask is non-zero, and take component of operand at [T-other_pos] where mask is zero. Mask and other operand can be vectors or scalars. In-place updates operand on TOS. Does not consume the other operands.
 
-// 7. Vector Aggregation (70-72)
-pub const OP_VSUM: u8 = 70; //   VSUM                         ; stack args = [TOS: Vector] ; result = [TOS: Scalar] ; Sum of all vector components. Pushes on TOS. Does not consume the operand.
+// 7. Vector Aggregation (70-75)
+pub const OP_VSUM: u8 = 70; //   VSUM                         ; stack args = [TOS: Vector|SignedVector] ; result = [TOS: Scalar|SignedScalar] ; Sum of all vector components. Pushes on TOS. Does not consume the operand.
 pub const OP_VMIN: u8 = 71; //   VMIN                         ; stack args = [TOS: Vector] ; result = [TOS: Scalar] ; Minimum value found within vector components. Pushes on TOS. Does not consume the operand.
 pub const OP_VMAX: u8 = 72; //   VMAX                         ; stack args = [TOS: Vector] ; result = [TOS: Scalar] ; Maximum value found within vector components. Pushes on TOS. Does not consume the operand.
+pub const OP_VDOT: u8 = 73; //   VDOT <pos>                   ; stack args = [TOS - pos: Vector, TOS: Vector] ; result = [TOS: Scalar] ; Dot product of TOS and vector at [T-pos], i.e. sum of component products computed at 256-bit precision and rounded once. Replaces TOS. Does not consume the other operand.
+pub const OP_VCUMSUM: u8 = 74; // VCUMSUM                      ; stack args = [TOS: Vector] ; result = [TOS: Vector] ; Cumulative sum of vector components, i.e. i-th component is sum of components 0..=i. In-place updates operand on TOS.
+pub const OP_VNORM: u8 = 75; //  VNORM                        ; stack args = [TOS: Vector] ; result = [TOS: Vector] ; Normalize vector so that its components sum to one, i.e. divide each component by sum of all components. In-place updates operand on TOS.
 
 // 8. Immediate Values & Vector Creation (80-83)
 pub const OP_IMMS: u8 = 80; //   IMMS <immediate (scalar)>    ; no stack args ; result = [TOS: Scalar] ; Push immediate Scalar value on stack
@@ -57,3 +71,40 @@ pub const OP_POPN: u8 = 90; //   POPN <count>                 ; stack args = ['B
 pub const OP_SWAP: u8 = 91; //   SWAP <pos>                   ; stack args = [TOS - pos: 'A, TOS: 'B] ; result = [TOS - pos: 'B, TOS: 'A]; Swap TOS with operand at [T-n]
 pub const OP_B: u8 = 92; //      B <prg_id> <N> <M> <R>       ; stack args = [TOS - N] ; result = [TOS - M] ; Call sub-routine stored as Lables at `prg_id`, supplying `N` inputs and taking `M` outputs from stack. `N` inputs are consumed from stack. `M` outputs are moved from sub-routine's TOS to caller's TOS.
 pub const OP_FOLD: u8 = 93; //   FOLD <prg_id> <N> <M> <R>    ; stack args = [(TOS - N - 1, ..., TOS - 1): 'A..., TOS: 'X] ; result = [TOS - M, ..., TOS] ; first iteration = [(TOS - N - 1, ..., TOS - 1): 'A..., TOS: 'X[1]] ; i-th iteration = ['R..., TOS: 'X[i]], where 'R... stack resulting from previous iteration; Fold (iterate) over vector/label operands. Same as `B` except sub-routine is called repeatedly over components of Vector at TOS.
+pub const OP_BZ: u8 = 94; //     BZ <prg_id> <N> <M> <R>      ; stack args = [TOS - N, ..., TOS - 1, TOS: Scalar] ; result = [TOS - M, ..., TOS] ; Branch if zero. Consumes condition from TOS, and if it is zero calls sub-routine same as `B`. Otherwise sub-routine is not called and `N` inputs are left on stack as outputs, therefore `N` must be equal to `M`.
+
+// 10. Assertions (100-101)
+pub const OP_ASSERT_ZERO: u8 = 100; // ASSERT_ZERO <assert_id>   ; stack args = [TOS: Vector|Scalar] ; no result ; Assert that TOS (scalar or all vector components) is zero, otherwise fail with `AssertionFailed(assert_id)`. Consumes TOS.
+pub const OP_ASSERT_LE: u8 = 101; //   ASSERT_LE <pos> <assert_id> ; stack args = [TOS - pos, TOS: Vector|Scalar] ; no result ; Assert that TOS is less or equal to operand at [T-pos] (component-wise for vectors), otherwise fail with `AssertionFailed(assert_id)`. Consumes TOS. Does not consume the other operand.
+
+// 11. Signed Values (110-114)
+pub const OP_NEG: u8 = 110; //   NEG                          ; stack args = [TOS: Vector|Scalar|SignedVector|SignedScalar] ; result = [TOS: SignedVector|SignedScalar] ; Negate TOS. Unsigned operand becomes signed. In-place updates operand on TOS.
+pub const OP_ABS: u8 = 111; //   ABS                          ; stack args = [TOS: SignedVector|SignedScalar] ; result = [TOS: Vector|Scalar] ; Absolute value of TOS, i.e. MAX(TOS, -TOS). In-place updates operand on TOS.
+pub const OP_POS: u8 = 112; //   POS                          ; stack args = [TOS: SignedVector|SignedScalar] ; result = [TOS: Vector|Scalar] ; Positive (long) part of TOS, i.e. MAX(TOS, 0). In-place updates operand on TOS.
+pub const OP_NEGPART: u8 = 113; // NEGPART                    ; stack args = [TOS: SignedVector|SignedScalar] ; result = [TOS: Vector|Scalar] ; Negative (short) part of TOS, i.e. MAX(-TOS, 0). In-place updates operand on TOS.
+pub const OP_NET: u8 = 114; //   NET <pos>                    ; stack args = [TOS - pos: Vector|Scalar, TOS: Vector|Scalar] ; result = [TOS: SignedVector|SignedScalar] ; Net of long (TOS) and short (at [T-pos]) pair, i.e. TOS - [T-pos] as signed value. In-place updates operand on TOS. Does not consume the other operand.
+
+// 12. Sparse Vectors (120-124)
+pub const OP_SPK: u8 = 120; //   SPK <pos>                    ; stack args = [TOS - pos: Labels, TOS: Vector] ; result = [TOS: Sparse] ; Pack vector with labels at [T-pos] into Sparse vector, i.e. sorted label/value pairs. Labels must be sorted and unique. In-place updates operand on TOS. Does not consume the other operand.
+pub const OP_SUNPK: u8 = 121; // SUNPK                        ; stack args = [TOS: Sparse] ; result = [TOS - 1: Labels, TOS: Vector] ; Unpack Sparse vector into its labels and values. Consumes TOS, and replaces it with Labels and Vector.
+pub const OP_SGET: u8 = 122; //  SGET <pos_vector> <pos_labels> ; stack args = [TOS - pos_vector: Vector, TOS - pos_labels: Labels, TOS: Labels] ; result = [TOS: Sparse] ; Gather components of vector at [T-pos_vector] with labels at [T-pos_labels] into Sparse vector with labels of TOS. Fails if any label is not found. In-place updates operand on TOS. Does not consume the other operands.
+pub const OP_SADD: u8 = 123; //  SADD <pos_sparse> <pos_labels> ; stack args = [TOS - pos_sparse: Sparse, TOS - pos_labels: Labels, TOS: Vector] ; result = [TOS: Vector] ; Scatter-add Sparse vector at [T-pos_sparse] into TOS with labels at [T-pos_labels], i.e. add each value to the component with its label. Fails if any label is not found. In-place updates operand on TOS. Does not consume the other operands.
+pub const OP_SUPD: u8 = 124; //  SUPD <pos_sparse> <pos_labels> ; stack args = [TOS - pos_sparse: Sparse, TOS - pos_labels: Labels, TOS: Vector] ; result = [TOS: Vector] ; Scatter-update TOS with Sparse vector at [T-pos_sparse] using labels at [T-pos_labels], i.e. overwrite the component with each label by its value. Fails if any label is not found. In-place updates operand on TOS. Does not consume the other operands.
+
+// 13. Program Arguments (130-135)
+pub const OP_ARGS: u8 = 130; //  ARGS <count>                 ; no stack args ; no result ; Declare that program is a template taking `count` arguments, which are bound by the caller at run-time, e.g. storage ids of a particular vault. Must be the first instruction, and fails otherwise. Fails unless program is executed with exactly `count` arguments.
+pub const OP_LDA: u8 = 131; //   LDA <arg>                    ; no stack args ; result = [TOS: Label] ; Load Argument at index `arg` as immediate Label value. Pushes on TOS. Fails unless program declares its arguments with `ARGS`.
+pub const OP_LDLA: u8 = 132; //  LDLA <arg>                   ; no stack args ; result = [TOS: Labels] ; Load Labels object from VIO by ID given in Argument at index `arg`. Pushes on TOS. Fails unless program declares its arguments with `ARGS`.
+pub const OP_LDVA: u8 = 133; //  LDVA <arg>                   ; no stack args ; result = [TOS: Vector] ; Load Vector object from VIO by ID given in Argument at index `arg`. Pushes on TOS. Fails unless program declares its arguments with `ARGS`.
+pub const OP_STLA: u8 = 134; //  STLA <arg>                   ; stack args = [TOS: Labels] ; Store Labels object into VIO by ID given in Argument at index `arg`. Consumes TOS. Fails unless program declares its arguments with `ARGS`.
+pub const OP_STVA: u8 = 135; //  STVA <arg>                   ; stack args = [TOS: Vector] ; Store Vector object into VIO by ID given in Argument at index `arg`. Consumes TOS. Fails unless program declares its arguments with `ARGS`.
+
+// 14. Rounding Modifiers (140-141)
+pub const OP_RNDU: u8 = 140; //  RNDU                         ; no stack args ; no result ; Round Up result of the following instruction, which must be MUL or DIV, i.e. any remainder adds one EPSILON (1e-18) to each component. Without modifier results are rounded down (truncated). Use for liabilities, so that split executions never charge less than a single one. Supports only Vector and Scalar operands.
+pub const OP_RNDE: u8 = 141; //  RNDE                         ; no stack args ; no result ; Round result of the following instruction, which must be MUL or DIV, to nearest with ties to Even, i.e. unbiased on average. Supports only Vector and Scalar operands.
+
+// Bytecode Encoding
+//
+// Arguments <reg>, <pos>, <count>, <arg>, <N>, <M> and <R> always occupy one byte. Wide arguments, i.e. storage ids, labels and immediate scalars, occupy 16 bytes (little-endian u128) by default.
+// Program starting with COMPACT_HEADER encodes wide arguments as unsigned LEB128, i.e. 7 bits per byte with high bit set on all bytes except the last one, so that ids and labels below 128 occupy one byte. Header is not an op-code, and it applies to the program it starts, but not to the sub-routines it calls.
+pub const COMPACT_HEADER: u8 = 0xff;
@@ -0,0 +1,70 @@
+use alloc::vec::Vec;
+
+use alloy_primitives::keccak256;
+
+/// Bit set in storage id of every procedure
+///
+/// Keeps procedure ids apart from ids of vectors, labels and programs, which
+/// are assigned sequentially.
+pub const PROCEDURE_ID_FLAG: u128 = 1 << 127;
+
+/// Storage id of procedure derived from its bytecode
+///
+/// Id is content-derived, so that same procedure used by several programs is
+/// stored only once, and that changed procedure never overwrites procedure
+/// called by programs already deployed.
+pub fn procedure_id(code: &[u8]) -> u128 {
+    let hash = keccak256(code);
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&hash[..16]);
+    u128::from_be_bytes(bytes) | PROCEDURE_ID_FLAG
+}
+
+/// Sub-routine declared by `PROC name(inputs, outputs, registers) { ... }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Procedure {
+    pub name: &'static str,
+    pub id: u128,
+    pub code: Vec<u8>,
+    pub num_inputs: u8,
+    pub num_outputs: u8,
+    pub num_registers: u8,
+}
+
+impl Procedure {
+    pub fn new(
+        name: &'static str,
+        code: Vec<u8>,
+        num_inputs: u8,
+        num_outputs: u8,
+        num_registers: u8,
+    ) -> Self {
+        Self {
+            name,
+            id: procedure_id(&code),
+            code,
+            num_inputs,
+            num_outputs,
+            num_registers,
+        }
+    }
+}
+
+/// Program compiled by `abacus!` together with procedures it calls
+///
+/// Procedures must be stored under their ids before program is executed, and
+/// they are listed in order of declaration, i.e. each procedure is listed
+/// after any procedure it calls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    pub code: Vec<u8>,
+    pub procedures: Vec<Procedure>,
+}
+
+impl Program {
+    pub fn procedure(&self, name: &str) -> Option<&Procedure> {
+        self.procedures
+            .iter()
+            .find(|procedure| procedure.name == name)
+    }
+}
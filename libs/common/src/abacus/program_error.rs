@@ -1,22 +1,167 @@
+use alloc::{vec, vec::Vec};
 use core::fmt::Debug;
 
 pub enum ErrorCode {
     StackUnderflow,
     StackOverflow,
     InvalidInstruction,
+    TruncatedInstruction,
     InvalidOperand,
     NotFound,
     OutOfRange,
-    NotAligned,
+    /// Lengths of operands, which were expected to be equal
+    NotAligned(usize, usize),
     MathUnderflow,
     MathOverflow,
-    SubroutineError(alloc::boxed::Box<ProgramError>),
+    LimitExceeded(LimitKind),
+    AssertionFailed(u128),
 }
 
+/// Kind of execution limit that was exceeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitKind {
+    Instructions,
+    StackDepth,
+    CallDepth,
+    VectorLength,
+}
+
+/// Sub-routine frame entered with `B` or `FOLD`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    /// Storage id of the program executed in this frame
+    pub program_id: u128,
+
+    /// Index of the item being folded, or `None` for `B`
+    pub iteration: Option<usize>,
+}
+
+/// Type of operand, i.e. `Operand` without its value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperandType {
+    None,
+    Labels,
+    Vector,
+    Scalar,
+    Label,
+    SignedVector,
+    SignedScalar,
+    Sparse,
+}
+
+/// Maximum number of operand types recorded in `ProgramError::operands`
+pub const MAX_ERROR_OPERANDS: usize = 3;
+
 pub struct ProgramError {
     pub error_code: ErrorCode,
     pub program_counter: usize,
     pub stack_depth: usize,
+
+    /// Op-code of failed instruction, or `None` when program failed outside
+    /// of any instruction, e.g. when its writes could not be committed
+    pub op_code: Option<u8>,
+
+    /// Path of sub-routine frames to failed instruction, empty when it failed
+    /// in main program. Program counter and stack depth are of the last frame.
+    pub frames: Vec<Frame>,
+
+    /// Types of operands on top of the stack before failed instruction, the
+    /// last one is TOS, at most `MAX_ERROR_OPERANDS` of them
+    pub operands: Vec<OperandType>,
+}
+
+impl ErrorCode {
+    /// Number identifying error code, e.g. in ABI-encoded errors
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::StackUnderflow => 1,
+            Self::StackOverflow => 2,
+            Self::InvalidInstruction => 3,
+            Self::TruncatedInstruction => 4,
+            Self::InvalidOperand => 5,
+            Self::NotFound => 6,
+            Self::OutOfRange => 7,
+            Self::NotAligned(..) => 8,
+            Self::MathUnderflow => 9,
+            Self::MathOverflow => 10,
+            Self::LimitExceeded(_) => 11,
+            Self::AssertionFailed(_) => 12,
+        }
+    }
+
+    /// Values carried by error code, e.g. assertion id
+    pub fn args(&self) -> Vec<u128> {
+        match self {
+            Self::NotAligned(a, b) => vec![*a as u128, *b as u128],
+            Self::LimitExceeded(kind) => vec![*kind as u128],
+            Self::AssertionFailed(id) => vec![*id],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Error code from its number and values as given by `code()` and
+    /// `args()`, or `None` if they don't match any
+    pub fn from_code(code: u8, args: &[u128]) -> Option<Self> {
+        let error_code = match (code, args) {
+            (1, []) => Self::StackUnderflow,
+            (2, []) => Self::StackOverflow,
+            (3, []) => Self::InvalidInstruction,
+            (4, []) => Self::TruncatedInstruction,
+            (5, []) => Self::InvalidOperand,
+            (6, []) => Self::NotFound,
+            (7, []) => Self::OutOfRange,
+            (8, [a, b]) => Self::NotAligned(usize::try_from(*a).ok()?, usize::try_from(*b).ok()?),
+            (9, []) => Self::MathUnderflow,
+            (10, []) => Self::MathOverflow,
+            (11, [kind]) => Self::LimitExceeded(match kind {
+                0 => LimitKind::Instructions,
+                1 => LimitKind::StackDepth,
+                2 => LimitKind::CallDepth,
+                3 => LimitKind::VectorLength,
+                _ => return None,
+            }),
+            (12, [id]) => Self::AssertionFailed(*id),
+            _ => return None,
+        };
+        Some(error_code)
+    }
+}
+
+impl OperandType {
+    /// Number identifying operand type, e.g. in ABI-encoded errors
+    pub fn code(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn from_code(code: u8) -> Option<Self> {
+        let operand_type = match code {
+            0 => Self::None,
+            1 => Self::Labels,
+            2 => Self::Vector,
+            3 => Self::Scalar,
+            4 => Self::Label,
+            5 => Self::SignedVector,
+            6 => Self::SignedScalar,
+            7 => Self::Sparse,
+            _ => return None,
+        };
+        Some(operand_type)
+    }
+}
+
+impl ProgramError {
+    /// Error with no instruction, frames or operands, e.g. reported by
+    /// static verification
+    pub fn new(error_code: ErrorCode, program_counter: usize, stack_depth: usize) -> Self {
+        Self {
+            error_code,
+            program_counter,
+            stack_depth,
+            op_code: None,
+            frames: Vec::new(),
+            operands: Vec::new(),
+        }
+    }
 }
 
 impl Debug for ErrorCode {
@@ -25,13 +170,15 @@ impl Debug for ErrorCode {
             Self::StackUnderflow => write!(f, "StackUnderflow"),
             Self::StackOverflow => write!(f, "StackOverflow"),
             Self::InvalidInstruction => write!(f, "InvalidInstruction"),
+            Self::TruncatedInstruction => write!(f, "TruncatedInstruction"),
             Self::InvalidOperand => write!(f, "InvalidOperand"),
             Self::NotFound => write!(f, "NotFound"),
             Self::OutOfRange => write!(f, "OutOfRange"),
-            Self::NotAligned => write!(f, "NotAligned"),
+            Self::NotAligned(a, b) => write!(f, "NotAligned({}, {})", a, b),
             Self::MathUnderflow => write!(f, "MathUnderflow"),
             Self::MathOverflow => write!(f, "MathOverflow"),
-            Self::SubroutineError(inner) => write!(f, "SubroutineError({:?})", *inner),
+            Self::LimitExceeded(kind) => write!(f, "LimitExceeded({:?})", kind),
+            Self::AssertionFailed(id) => write!(f, "AssertionFailed({})", id),
         }
     }
 }
@@ -42,6 +189,9 @@ impl Debug for ProgramError {
             .field("error_code", &self.error_code)
             .field("program_counter", &self.program_counter)
             .field("stack_depth", &self.stack_depth)
+            .field("op_code", &self.op_code)
+            .field("frames", &self.frames)
+            .field("operands", &self.operands)
             .finish()
     }
 }
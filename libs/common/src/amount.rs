@@ -2,6 +2,9 @@ use alloc::vec::Vec;
 
 use alloy_primitives::{ruint::UintTryTo, U128, U256};
 
+#[cfg(feature = "amount-exp")]
+use alloy_primitives::U512;
+
 #[cfg(feature = "with-ethers")]
 use ethers::types::U256 as EthersU256;
 
@@ -60,6 +63,144 @@ fn sqrt_u256(n: U256) -> Option<U256> {
     Some(current)
 }
 
+/// Scale of intermediate results of `exp`, `ln` and `pow`, i.e. 36 decimals
+#[cfg(feature = "amount-exp")]
+const EXP_SCALE: u128 = 1_000_000_000_000_000_000_000_000_000_000_000_000;
+
+/// Natural logarithm of two scaled by `EXP_SCALE` (rounded down)
+#[cfg(feature = "amount-exp")]
+const EXP_LN2: u128 = 693_147_180_559_945_309_417_232_121_458_176_568;
+
+/// Natural exponent of `x`, where both `x` and result are scaled by `EXP_SCALE`.
+/// Returns `None` if result does not fit into U256.
+#[cfg(feature = "amount-exp")]
+fn exp_u256(x: U256) -> Option<U256> {
+    let scale = convert_from_u128(EXP_SCALE);
+    let ln2 = convert_from_u128(EXP_LN2);
+
+    // Range reduction:
+    //  x = k ln(2) + r, where 0 <= r < ln(2)
+    //  e^x = 2^k e^r
+    //
+    let k = try_convert_to_u128(x / ln2)?;
+    if k >= 256 {
+        return None;
+    }
+    let r = x - convert_from_u128(k) * ln2;
+
+    // Taylor series:
+    //  e^r = 1 + r + r^2 / 2! + r^3 / 3! + ...
+    //
+    // Terms decrease monotonically as r < 1, and we stop once they vanish.
+    //
+    let mut sum = scale;
+    let mut term = scale;
+    let mut n = 1;
+    loop {
+        term = term * r / (scale * convert_from_u128(n));
+        if term.is_zero() {
+            break;
+        }
+        sum += term;
+        n += 1;
+    }
+    sum.checked_shl(k as usize)
+}
+
+/// Natural logarithm of `x >= 1`, where both `x` and result are scaled by
+/// `EXP_SCALE`. Returns `None` if `x < 1`.
+#[cfg(feature = "amount-exp")]
+fn ln_u256(x: U256) -> Option<U256> {
+    let scale = convert_from_u128(EXP_SCALE);
+    let ln2 = convert_from_u128(EXP_LN2);
+    if x < scale {
+        return None;
+    }
+
+    // Range reduction:
+    //  x = 2^k m, where 1 <= m < 2
+    //  ln(x) = k ln(2) + ln(m)
+    //
+    let k = (x / scale).bit_len() - 1;
+    let m = x >> k;
+
+    // Series of inverse hyperbolic tangent:
+    //  ln(m) = 2 atanh(z), where z = (m - 1) / (m + 1) and 0 <= z < 1/3
+    //  atanh(z) = z + z^3 / 3 + z^5 / 5 + ...
+    //
+    let z = (m - scale) * scale / (m + scale);
+    let z2 = z * z / scale;
+    let mut sum = z;
+    let mut power = z;
+    let mut n = 3;
+    loop {
+        power = power * z2 / scale;
+        let term = power / convert_from_u128(n);
+        if term.is_zero() {
+            break;
+        }
+        sum += term;
+        n += 2;
+    }
+    Some(sum * convert_from_u128(2) + convert_from_u128(k as u128) * ln2)
+}
+
+/// Multiply values scaled by `EXP_SCALE` using 512-bit intermediate product
+#[cfg(feature = "amount-exp")]
+fn mul_exp_scale(a: U256, b: U256) -> Option<U256> {
+    let product: U512 = a.widening_mul(b);
+    let result =
+        product / U512::from_limbs([EXP_SCALE as u64, (EXP_SCALE >> 64) as u64, 0, 0, 0, 0, 0, 0]);
+    result.uint_try_to().ok()
+}
+
+/// Convert value scaled by `EXP_SCALE` into `Amount` rounding to nearest
+#[cfg(feature = "amount-exp")]
+fn round_from_exp_scale(value: U256) -> Option<Amount> {
+    let factor = convert_from_u128(EXP_SCALE / Amount::SCALE);
+    let half = convert_from_u128(EXP_SCALE / Amount::SCALE / 2);
+    let result = value.checked_add(half)? / factor;
+    Some(Amount(try_convert_to_u128(result)?))
+}
+
+/// Rounding of results of fixed-point multiplication and division
+///
+/// Operations without explicit rounding round down (towards zero), which
+/// favours the payer. Liabilities should be rounded up, so that split
+/// executions never charge less in total than a single execution would.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Rounding {
+    /// Towards zero, i.e. truncate
+    #[default]
+    Down,
+
+    /// Away from zero, i.e. any remainder adds one `EPSILON`
+    Up,
+
+    /// To nearest, and ties to even, i.e. unbiased on average
+    HalfEven,
+}
+
+/// Divide rounding quotient as requested, or `None` if divisor is zero
+fn div_rounded_u256(numerator: U256, denominator: U256, rounding: Rounding) -> Option<U256> {
+    let quotient = numerator.checked_div(denominator)?;
+    let remainder = numerator % denominator;
+    let round_up = match rounding {
+        Rounding::Down => false,
+        Rounding::Up => !remainder.is_zero(),
+        Rounding::HalfEven => {
+            // Compare remainder with half of denominator without overflow
+            let rest = denominator - remainder;
+            remainder > rest || (remainder == rest && quotient.bit(0))
+        }
+    };
+    if round_up {
+        quotient.checked_add(U256::ONE)
+    } else {
+        Some(quotient)
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Amount(pub u128);
 
@@ -80,7 +221,7 @@ impl Amount {
     }
 
     pub fn checked_sub(self, rhs: Self) -> Option<Self> {
-        let result = self.to_u256() - rhs.to_u256();
+        let result = self.to_u256().checked_sub(rhs.to_u256())?;
         Some(Self(try_convert_to_u128(result)?))
     }
 
@@ -97,12 +238,36 @@ impl Amount {
     }
 
     pub fn checked_div(self, rhs: Self) -> Option<Self> {
-        let result = (self.to_u256() * Self::u256_scale()) / rhs.to_u256();
+        let result = (self.to_u256() * Self::u256_scale()).checked_div(rhs.to_u256())?;
+        Some(Self(try_convert_to_u128(result)?))
+    }
+
+    /// Product rounded as requested, where `Rounding::Down` is the same as
+    /// `checked_mul()`
+    pub fn checked_mul_rounded(self, rhs: Self, rounding: Rounding) -> Option<Self> {
+        let product = self.to_u256() * rhs.to_u256();
+        let result = div_rounded_u256(product, Self::u256_scale(), rounding)?;
         Some(Self(try_convert_to_u128(result)?))
     }
 
+    /// Quotient rounded as requested, where `Rounding::Down` is the same as
+    /// `checked_div()`
+    pub fn checked_div_rounded(self, rhs: Self, rounding: Rounding) -> Option<Self> {
+        let numerator = self.to_u256() * Self::u256_scale();
+        let result = div_rounded_u256(numerator, rhs.to_u256(), rounding)?;
+        Some(Self(try_convert_to_u128(result)?))
+    }
+
+    pub fn checked_mul_round_up(self, rhs: Self) -> Option<Self> {
+        self.checked_mul_rounded(rhs, Rounding::Up)
+    }
+
+    pub fn checked_div_round_up(self, rhs: Self) -> Option<Self> {
+        self.checked_div_rounded(rhs, Rounding::Up)
+    }
+
     pub fn checked_idiv(self, rhs: Self) -> Option<Self> {
-        let result = (self.to_u256() / rhs.to_u256()) * Self::u256_scale();
+        let result = self.to_u256().checked_div(rhs.to_u256())? * Self::u256_scale();
         Some(Self(try_convert_to_u128(result)?))
     }
 
@@ -112,12 +277,108 @@ impl Amount {
         Some(Self(try_convert_to_u128(result)?))
     }
 
+    /// Dot product of two slices of equal length
+    ///
+    /// Products are summed at 256-bit precision, and the result is rounded
+    /// once, which is more accurate than summing rounded products.
+    pub fn checked_dot(lhs: &[Self], rhs: &[Self]) -> Option<Self> {
+        if lhs.len() != rhs.len() {
+            return None;
+        }
+        let mut result = U256::ZERO;
+        for (a, b) in lhs.iter().zip(rhs.iter()) {
+            result = result.checked_add(a.to_u256() * b.to_u256())?;
+        }
+        Some(Self(try_convert_to_u128(result / Self::u256_scale())?))
+    }
+
     #[cfg(feature = "amount-sqrt")]
     pub fn checked_sqrt(self) -> Option<Self> {
         let result = sqrt_u256(self.to_u256())? * convert_from_u128(Self::SCALE_SQRT);
         Some(Self(try_convert_to_u128(result)?))
     }
 
+    /// Natural exponent, i.e. `e^self`
+    ///
+    /// Computed at 36 decimal places with relative error below 1e-33, and
+    /// then rounded to nearest. Returns `None` if result does not fit, i.e.
+    /// for values above approx. 47.27. For negative exponents use `1 / e^x`.
+    #[cfg(feature = "amount-exp")]
+    pub fn checked_exp(self) -> Option<Self> {
+        let x = self.to_u256() * Self::u256_scale();
+        round_from_exp_scale(exp_u256(x)?)
+    }
+
+    /// Natural logarithm, i.e. `ln(self)`
+    ///
+    /// Computed at 36 decimal places with absolute error below 1e-33, and
+    /// then rounded to nearest. Returns `None` for values less than one, as
+    /// their logarithm is negative. For those use `ln(x) = -ln(1 / x)`.
+    #[cfg(feature = "amount-exp")]
+    pub fn checked_ln(self) -> Option<Self> {
+        let x = self.to_u256() * Self::u256_scale();
+        round_from_exp_scale(ln_u256(x)?)
+    }
+
+    /// Integer power, i.e. `self^n`
+    ///
+    /// Computed by repeated squaring at 36 decimal places, i.e. each of at
+    /// most `2 log2(n)` multiplications is rounded down to 1e-36, and then
+    /// result is rounded to nearest. Returns `None` if result does not fit.
+    #[cfg(feature = "amount-exp")]
+    pub fn checked_powi(self, n: u32) -> Option<Self> {
+        let scale = convert_from_u128(EXP_SCALE);
+        let mut base = self.to_u256() * Self::u256_scale();
+        let mut result = scale;
+        let mut n = n;
+        while n != 0 {
+            if n & 1 != 0 {
+                result = mul_exp_scale(result, base)?;
+            }
+            n >>= 1;
+            if n != 0 {
+                // Overflow here means that result would overflow too
+                base = mul_exp_scale(base, base)?;
+            }
+        }
+        round_from_exp_scale(result)
+    }
+
+    /// Power with fractional exponent, i.e. `self^exponent`
+    ///
+    /// Integer exponents are computed with `checked_powi()`. Otherwise it is
+    /// computed as `e^(exponent ln(self))` at 36 decimal places with relative
+    /// error below `(1 + exponent) 1e-33`, and then rounded to nearest.
+    /// Results smaller than `EPSILON / 2` are rounded to zero, and `None` is
+    /// returned if result does not fit.
+    #[cfg(feature = "amount-exp")]
+    pub fn checked_pow(self, exponent: Self) -> Option<Self> {
+        if exponent.0 % Self::SCALE == 0 {
+            if let Ok(n) = u32::try_from(exponent.0 / Self::SCALE) {
+                return self.checked_powi(n);
+            }
+        }
+        if self.is_zero() {
+            return Some(Self::ZERO);
+        }
+        let scale = convert_from_u128(EXP_SCALE);
+        let x = self.to_u256() * Self::u256_scale();
+        if scale <= x {
+            let y = ln_u256(x)?.checked_mul(exponent.to_u256())? / Self::u256_scale();
+            round_from_exp_scale(exp_u256(y)?)
+        } else {
+            // Base is less than one, and so:
+            //  x^e = 1 / (1 / x)^e = e^-(e ln(1 / x))
+            let y =
+                ln_u256(scale * scale / x)?.checked_mul(exponent.to_u256())? / Self::u256_scale();
+            if convert_from_u128(50) * scale < y {
+                // e^-50 is way below EPSILON
+                return Some(Self::ZERO);
+            }
+            round_from_exp_scale(scale * scale / exp_u256(y)?)
+        }
+    }
+
     #[inline]
     pub fn is_less_than(&self, other: &Self) -> bool {
         self.0 < other.0
@@ -201,7 +462,12 @@ impl Amount {
     }
 }
 
-#[cfg(any(not(feature = "stylus"), feature = "debug", feature = "stylus-test"))]
+#[cfg(any(
+    not(feature = "stylus"),
+    feature = "debug",
+    feature = "stylus-test",
+    feature = "with-serde"
+))]
 impl core::fmt::Display for Amount {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         #[cfg(feature = "stylus")]
@@ -248,6 +514,121 @@ impl core::fmt::Debug for Amount {
     }
 }
 
+/// Error parsing decimal string into [`Amount`]
+#[cfg(any(
+    not(feature = "stylus"),
+    feature = "debug",
+    feature = "stylus-test",
+    feature = "with-serde"
+))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseAmountError {
+    /// String is empty or is not of the form `123` or `123.456`
+    InvalidFormat,
+    /// More than 18 digits after decimal point
+    TooManyDecimals,
+    /// Value doesn't fit into 128-bit fixed-point representation
+    Overflow,
+}
+
+#[cfg(any(
+    not(feature = "stylus"),
+    feature = "debug",
+    feature = "stylus-test",
+    feature = "with-serde"
+))]
+impl core::fmt::Display for ParseAmountError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::InvalidFormat => write!(f, "Invalid decimal amount"),
+            Self::TooManyDecimals => {
+                write!(f, "Amount has more than {} decimals", Amount::DECIMALS)
+            }
+            Self::Overflow => write!(f, "Amount is too large"),
+        }
+    }
+}
+
+/// Parse decimal string, e.g. `1.5`, into [`Amount`]
+///
+/// This is the inverse of `Display`, and parsing is exact, i.e. strings with
+/// more than 18 decimals are rejected rather than rounded.
+#[cfg(any(
+    not(feature = "stylus"),
+    feature = "debug",
+    feature = "stylus-test",
+    feature = "with-serde"
+))]
+impl core::str::FromStr for Amount {
+    type Err = ParseAmountError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (integral, fraction) = s.split_once('.').unwrap_or((s, ""));
+
+        let is_digits = |part: &str| part.bytes().all(|c| c.is_ascii_digit());
+        if integral.is_empty() || !is_digits(integral) || !is_digits(fraction) {
+            return Err(ParseAmountError::InvalidFormat);
+        }
+        if s.ends_with('.') {
+            return Err(ParseAmountError::InvalidFormat);
+        }
+        if fraction.len() > Self::DECIMALS {
+            return Err(ParseAmountError::TooManyDecimals);
+        }
+
+        let mut value = 0u128;
+        let digits = integral.bytes().chain(fraction.bytes());
+        let padding = core::iter::repeat_n(b'0', Self::DECIMALS - fraction.len());
+        for c in digits.chain(padding) {
+            value = value
+                .checked_mul(10)
+                .and_then(|x| x.checked_add((c - b'0') as u128))
+                .ok_or(ParseAmountError::Overflow)?;
+        }
+        Ok(Self(value))
+    }
+}
+
+/// Amounts are serialized as decimal strings, e.g. `"1.5"`, so that no
+/// precision is lost in formats such as JSON.
+#[cfg(feature = "with-serde")]
+impl serde::Serialize for Amount {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "with-serde")]
+impl<'de> serde::Deserialize<'de> for Amount {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct AmountVisitor;
+
+        impl serde::de::Visitor<'_> for AmountVisitor {
+            type Value = Amount;
+
+            fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                write!(f, "decimal amount string or whole number")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Amount, E> {
+                v.parse().map_err(E::custom)
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Amount, E> {
+                self.visit_u128(v as u128)
+            }
+
+            fn visit_u128<E: serde::de::Error>(self, v: u128) -> Result<Amount, E> {
+                v.checked_mul(Amount::SCALE)
+                    .map(Amount)
+                    .ok_or_else(|| E::custom(ParseAmountError::Overflow))
+            }
+        }
+
+        deserializer.deserialize_any(AmountVisitor)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -312,6 +693,29 @@ mod test {
             Amount::from_u128_with_scale(2, 0),
         );
 
+        do_test_amount(
+            Amount::checked_dot(
+                &[
+                    Amount::from_u128_with_scale(1_5, 1),
+                    Amount::from_u128_with_scale(3, 18),
+                ],
+                &[
+                    Amount::from_u128_with_scale(2, 0),
+                    Amount::from_u128_with_scale(1, 18),
+                ],
+            )
+            .unwrap(),
+            Amount::from_u128_with_scale(3, 0),
+        );
+        do_test_amount(
+            Amount::checked_dot(
+                &[Amount::from_u128_with_scale(3, 18); 2],
+                &[Amount::from_u128_with_scale(5, 1); 2],
+            )
+            .unwrap(),
+            Amount::from_u128_with_scale(3, 18),
+        );
+
         assert!(
             Amount::from_u128_with_scale(1, 0).is_less_than(&Amount::from_u128_with_scale(2, 0))
         );
@@ -319,4 +723,370 @@ mod test {
             Amount::from_u128_with_scale(2, 1).is_less_than(&Amount::from_u128_with_scale(1, 0))
         );
     }
+
+    #[test]
+    fn test_amount_parse() {
+        let parse = |s: &str| s.parse::<Amount>();
+
+        assert_eq!(parse("0"), Ok(Amount::ZERO));
+        assert_eq!(parse("1"), Ok(Amount::ONE));
+        assert_eq!(parse("1.0"), Ok(Amount::ONE));
+        assert_eq!(parse("0.005"), Ok(Amount::from_u128_with_scale(5, 3)));
+        assert_eq!(parse("000.000000000000000001"), Ok(Amount::EPSILON));
+        assert_eq!(parse("12.345"), Ok(Amount::from_u128_with_scale(12_345, 3)));
+        assert_eq!(parse(&Amount::MAX.to_string()), Ok(Amount::MAX));
+
+        for s in [
+            "", ".", "1.", ".5", "-1", "+1", "1.2.3", "1e3", " 1", "0x10",
+        ] {
+            assert_eq!(parse(s), Err(ParseAmountError::InvalidFormat), "{s:?}");
+        }
+        assert_eq!(
+            parse("0.0000000000000000001"),
+            Err(ParseAmountError::TooManyDecimals)
+        );
+        assert_eq!(
+            parse("340282366920938463464"),
+            Err(ParseAmountError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_amount_rounding() {
+        let amount = |value: u128, scale: u8| Amount::from_u128_with_scale(value, scale);
+        let third = amount(1, 0).checked_div(amount(3, 0)).unwrap();
+
+        // 1 / 3 = 0.333...3 (18 decimals) rounded down and up
+        do_test_amount(third, Amount(333_333_333_333_333_333));
+        do_test_amount(
+            Amount::ONE.checked_div_round_up(amount(3, 0)).unwrap(),
+            Amount(333_333_333_333_333_334),
+        );
+        do_test_amount(
+            Amount::TWO
+                .checked_div_rounded(amount(3, 0), Rounding::HalfEven)
+                .unwrap(),
+            Amount(666_666_666_666_666_667),
+        );
+
+        // Exact results are never rounded
+        do_test_amount(
+            amount(1_5, 1).checked_mul_round_up(Amount::TWO).unwrap(),
+            amount(3, 0),
+        );
+
+        // Ties round to even: 0.5e-18 -> 0, and 1.5e-18 -> 2e-18
+        let half = amount(5, 1);
+        do_test_amount(
+            Amount::EPSILON
+                .checked_mul_rounded(half, Rounding::HalfEven)
+                .unwrap(),
+            Amount::ZERO,
+        );
+        do_test_amount(
+            Amount(3)
+                .checked_mul_rounded(half, Rounding::HalfEven)
+                .unwrap(),
+            Amount(2),
+        );
+        do_test_amount(
+            Amount::EPSILON.checked_mul_round_up(half).unwrap(),
+            Amount::EPSILON,
+        );
+
+        assert!(Amount::ONE.checked_div_round_up(Amount::ZERO).is_none());
+        assert!(Amount::MAX.checked_mul_round_up(Amount::TWO).is_none());
+        assert!(Amount::MAX
+            .checked_mul_rounded(Amount(Amount::SCALE - 1), Rounding::Up)
+            .is_some());
+    }
+
+    #[cfg(feature = "amount-exp")]
+    #[test]
+    fn test_amount_exp() {
+        let amount = |value: u128, scale: u8| Amount::from_u128_with_scale(value, scale);
+
+        do_test_amount(Amount::ZERO.checked_exp().unwrap(), Amount::ONE);
+        do_test_amount(
+            Amount::ONE.checked_exp().unwrap(),
+            amount(2_718281828459045235, 18),
+        );
+        do_test_amount(
+            amount(10, 0).checked_exp().unwrap(),
+            amount(22026_465794806716516958, 18),
+        );
+        assert!(amount(47, 0).checked_exp().is_some());
+        assert!(amount(48, 0).checked_exp().is_none());
+        assert!(Amount::MAX.checked_exp().is_none());
+
+        do_test_amount(Amount::ONE.checked_ln().unwrap(), Amount::ZERO);
+        do_test_amount(
+            amount(2, 0).checked_ln().unwrap(),
+            amount(693147180559945309, 18),
+        );
+        do_test_amount(
+            amount(10, 0).checked_ln().unwrap(),
+            amount(2_302585092994045684, 18),
+        );
+        do_test_amount(
+            Amount::MAX.checked_ln().unwrap(),
+            amount(47_276307437780177293, 18),
+        );
+        assert!(amount(5, 1).checked_ln().is_none());
+        assert!(Amount::ZERO.checked_ln().is_none());
+
+        do_test_amount(amount(1_5, 1).checked_powi(3).unwrap(), amount(3_375, 3));
+        do_test_amount(amount(7, 0).checked_powi(0).unwrap(), Amount::ONE);
+        do_test_amount(
+            amount(10, 0).checked_powi(20).unwrap(),
+            amount(100_000_000_000_000_000_000, 0),
+        );
+        assert!(amount(10, 0).checked_powi(21).is_none());
+        do_test_amount(amount(1, 9).checked_powi(3).unwrap(), Amount::ZERO);
+
+        do_test_amount(
+            amount(2, 0).checked_pow(amount(5, 1)).unwrap(),
+            amount(1_414213562373095049, 18),
+        );
+        do_test_amount(amount(4, 0).checked_pow(amount(5, 1)).unwrap(), Amount::TWO);
+        do_test_amount(
+            amount(3, 0).checked_pow(amount(2_5, 1)).unwrap(),
+            amount(15_588457268119895642, 18),
+        );
+        do_test_amount(
+            amount(3, 1).checked_pow(amount(1_7, 1)).unwrap(),
+            amount(129153486074980267, 18),
+        );
+        do_test_amount(
+            amount(5, 1).checked_pow(Amount::TWO).unwrap(),
+            amount(25, 2),
+        );
+        do_test_amount(
+            Amount::ZERO.checked_pow(amount(5, 1)).unwrap(),
+            Amount::ZERO,
+        );
+        do_test_amount(Amount::ZERO.checked_pow(Amount::ZERO).unwrap(), Amount::ONE);
+        do_test_amount(
+            Amount::EPSILON.checked_pow(amount(1_5, 1)).unwrap(),
+            Amount::ZERO,
+        );
+        assert!(amount(10, 0).checked_pow(amount(215, 1)).is_none());
+    }
+
+    mod properties {
+        use num_bigint::BigUint;
+        use proptest::prelude::*;
+
+        use super::*;
+
+        fn big(value: u128) -> BigUint {
+            BigUint::from(value)
+        }
+
+        fn scale() -> BigUint {
+            big(Amount::SCALE)
+        }
+
+        /// Raw value of exact result, or `None` if it doesn't fit
+        fn expected(value: BigUint) -> Option<u128> {
+            u128::try_from(value).ok()
+        }
+
+        /// Values near zero, around one, large and anywhere in range
+        fn raw_amount() -> impl Strategy<Value = u128> {
+            prop_oneof![
+                Just(0u128),
+                Just(u128::MAX),
+                0..=1_000u128,
+                0..=10 * Amount::SCALE,
+                0..=1_000_000_000 * Amount::SCALE,
+                any::<u128>(),
+            ]
+        }
+
+        proptest! {
+            #[test]
+            fn checked_ops_match_exact_results(a in raw_amount(), b in raw_amount()) {
+                let (x, y) = (Amount(a), Amount(b));
+
+                prop_assert_eq!(x.checked_add(y).map(|r| r.0), expected(big(a) + big(b)));
+                prop_assert_eq!(
+                    x.checked_sub(y).map(|r| r.0),
+                    if a < b { None } else { Some(a - b) }
+                );
+                prop_assert_eq!(x.saturating_sub(y).map(|r| r.0), Some(a.saturating_sub(b)));
+                prop_assert_eq!(
+                    x.checked_mul(y).map(|r| r.0),
+                    expected(big(a) * big(b) / scale())
+                );
+                prop_assert_eq!(x.checked_sq().map(|r| r.0), expected(big(a) * big(a) / scale()));
+
+                let (div, idiv) = if b == 0 {
+                    (None, None)
+                } else {
+                    (
+                        expected(big(a) * scale() / big(b)),
+                        expected(big(a / b) * scale()),
+                    )
+                };
+                prop_assert_eq!(x.checked_div(y).map(|r| r.0), div);
+                prop_assert_eq!(x.checked_idiv(y).map(|r| r.0), idiv);
+            }
+
+            #[test]
+            fn checked_dot_matches_exact_result(
+                pairs in prop::collection::vec((raw_amount(), raw_amount()), 0..8)
+            ) {
+                let lhs: Vec<_> = pairs.iter().map(|(a, _)| Amount(*a)).collect();
+                let rhs: Vec<_> = pairs.iter().map(|(_, b)| Amount(*b)).collect();
+                let sum: BigUint = pairs.iter().map(|(a, b)| big(*a) * big(*b)).sum();
+
+                // Sum of products is accumulated in 256 bits
+                let fits = sum.bits() <= 256;
+                let result = if fits { expected(sum / scale()) } else { None };
+                prop_assert_eq!(Amount::checked_dot(&lhs, &rhs).map(|r| r.0), result);
+                if !lhs.is_empty() {
+                    prop_assert!(Amount::checked_dot(&lhs, &rhs[1..]).is_none());
+                }
+            }
+
+            #[test]
+            fn parse_inverts_display(a in raw_amount()) {
+                prop_assert_eq!(Amount(a).to_string().parse::<Amount>(), Ok(Amount(a)));
+            }
+        }
+
+        /// Exact quotient rounded as requested
+        fn rounded(numerator: BigUint, denominator: BigUint, rounding: Rounding) -> BigUint {
+            let quotient = &numerator / &denominator;
+            let remainder = numerator % &denominator;
+            let twice = remainder * 2u32;
+            let round_up = match rounding {
+                Rounding::Down => false,
+                Rounding::Up => twice.bits() != 0,
+                Rounding::HalfEven => {
+                    twice > denominator || (twice == denominator && quotient.bit(0))
+                }
+            };
+            if round_up {
+                quotient + 1u32
+            } else {
+                quotient
+            }
+        }
+
+        fn rounding() -> impl Strategy<Value = Rounding> {
+            prop_oneof![
+                Just(Rounding::Down),
+                Just(Rounding::Up),
+                Just(Rounding::HalfEven)
+            ]
+        }
+
+        proptest! {
+            #[test]
+            fn rounded_ops_match_exact_results(
+                a in raw_amount(),
+                b in raw_amount(),
+                rounding in rounding(),
+            ) {
+                let (x, y) = (Amount(a), Amount(b));
+
+                prop_assert_eq!(
+                    x.checked_mul_rounded(y, rounding).map(|r| r.0),
+                    expected(rounded(big(a) * big(b), scale(), rounding))
+                );
+                let div = if b == 0 {
+                    None
+                } else {
+                    expected(rounded(big(a) * scale(), big(b), rounding))
+                };
+                prop_assert_eq!(x.checked_div_rounded(y, rounding).map(|r| r.0), div);
+                if rounding == Rounding::Down {
+                    prop_assert_eq!(
+                        x.checked_mul_rounded(y, rounding).map(|r| r.0),
+                        x.checked_mul(y).map(|r| r.0)
+                    );
+                    prop_assert_eq!(
+                        x.checked_div_rounded(y, rounding).map(|r| r.0),
+                        x.checked_div(y).map(|r| r.0)
+                    );
+                }
+            }
+
+            /// Collateral is split into executions at the same price, where
+            /// quantity bought is rounded down, and collateral charged for
+            /// it is rounded up. Total charged never exceeds collateral, and
+            /// the rest is never more than one `EPSILON` of quantity per
+            /// execution, i.e. collateral is conserved.
+            #[test]
+            fn split_executions_conserve_collateral(
+                parts in prop::collection::vec(1..=1_000 * Amount::SCALE, 1..16),
+                price in Amount::SCALE / 1_000..=1_000 * Amount::SCALE,
+            ) {
+                let price = Amount(price);
+                let mut charged = Amount::ZERO;
+                let mut bought = Amount::ZERO;
+                let mut collateral = Amount::ZERO;
+                for part in parts.iter().map(|x| Amount(*x)) {
+                    let quantity = part.checked_div(price).unwrap();
+                    let cost = quantity.checked_mul_round_up(price).unwrap();
+                    prop_assert!(cost <= part);
+
+                    collateral = collateral.checked_add(part).unwrap();
+                    charged = charged.checked_add(cost).unwrap();
+                    bought = bought.checked_add(quantity).unwrap();
+                }
+                prop_assert!(charged <= collateral);
+
+                // Single execution buys at least as much as split ones
+                let quantity = collateral.checked_div(price).unwrap();
+                prop_assert!(bought <= quantity);
+                prop_assert!(quantity.0 - bought.0 <= parts.len() as u128);
+
+                // Splitting claims pro-rata and claiming the rest with the
+                // last one pays out exactly what was bought
+                let mut remaining = bought;
+                let mut remaining_collateral = collateral;
+                for part in parts.iter().map(|x| Amount(*x)) {
+                    let claimed = if part == remaining_collateral {
+                        remaining
+                    } else {
+                        part.checked_mul(remaining)
+                            .unwrap()
+                            .checked_div(remaining_collateral)
+                            .unwrap()
+                    };
+                    remaining = remaining.checked_sub(claimed).unwrap();
+                    remaining_collateral = remaining_collateral.checked_sub(part).unwrap();
+                }
+                prop_assert_eq!(remaining.0, 0);
+            }
+        }
+
+        #[cfg(feature = "amount-sqrt")]
+        proptest! {
+            #[test]
+            fn checked_sqrt_matches_exact_result(a in raw_amount()) {
+                let result = expected(big(a).sqrt() * big(Amount::SCALE_SQRT));
+                prop_assert_eq!(Amount(a).checked_sqrt().map(|r| r.0), result);
+            }
+        }
+
+        #[cfg(feature = "amount-exp")]
+        proptest! {
+            #[test]
+            fn checked_exp_ln_pow_never_panic(a in raw_amount(), b in raw_amount(), n in any::<u32>()) {
+                let (x, y) = (Amount(a), Amount(b));
+
+                // Logarithm is defined from one, and its result always fits
+                prop_assert_eq!(x.checked_ln().is_some(), Amount::ONE <= x);
+                if let Some(e) = x.checked_exp() {
+                    prop_assert!(Amount::ONE <= e);
+                }
+                x.checked_powi(n);
+                x.checked_pow(y);
+            }
+        }
+    }
 }
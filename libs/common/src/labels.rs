@@ -1,19 +1,60 @@
 use alloc::vec::Vec;
+use core::ops::Deref;
+
+use alloy_primitives::map::{hash_map::Entry, HashMap};
 
 use crate::uint::{read_u128, write_u128};
 
+/// Labels, e.g. asset names, which vectors are indexed by
+///
+/// Labels track whether they are known to be sorted and unique, so that joins
+/// don't need to check it again. Labels are read as slice, and modified only
+/// using `push()` or `data_mut()`, which keep track of it.
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "with-serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct Labels {
-    pub data: Vec<u128>,
+    data: Vec<u128>,
+
+    /// Whether labels are sorted and unique, or `None` when not known
+    #[cfg_attr(feature = "with-serde", serde(skip))]
+    sorted: Option<bool>,
+}
+
+impl From<Vec<u128>> for Labels {
+    fn from(data: Vec<u128>) -> Self {
+        Self { data, sorted: None }
+    }
+}
+
+impl From<Labels> for Vec<u128> {
+    fn from(labels: Labels) -> Self {
+        labels.data
+    }
+}
+
+impl Deref for Labels {
+    type Target = [u128];
+
+    fn deref(&self) -> &[u128] {
+        &self.data
+    }
 }
 
 impl Labels {
     pub fn new() -> Self {
-        Self { data: Vec::new() }
+        Self {
+            data: Vec::new(),
+            sorted: None,
+        }
     }
 
     #[cfg(feature = "vec-u128")]
     pub fn from_vec_u128(data: Vec<u128>) -> Self {
-        Self { data }
+        Self::from(data)
     }
 
     #[cfg(feature = "vec-u128")]
@@ -26,6 +67,88 @@ impl Labels {
         self.data.clone()
     }
 
+    pub fn as_slice(&self) -> &[u128] {
+        &self.data
+    }
+
+    /// Tells whether labels are sorted and unique, i.e. strictly increasing
+    ///
+    /// Labels are checked only when it is not known already.
+    pub fn is_sorted_unique(&self) -> bool {
+        self.sorted.unwrap_or_else(|| is_sorted_unique(&self.data))
+    }
+
+    /// Check whether labels are sorted and unique, and keep track of it
+    pub fn track_sorted_unique(&mut self) -> bool {
+        let sorted = self.is_sorted_unique();
+        self.sorted = Some(sorted);
+        sorted
+    }
+
+    /// Labels to modify, which forgets whether they are sorted and unique
+    pub fn data_mut(&mut self) -> &mut Vec<u128> {
+        self.sorted = None;
+        &mut self.data
+    }
+
+    /// Append label keeping track of whether labels are sorted and unique
+    pub fn push(&mut self, label: u128) {
+        let last = self.data.last().copied();
+        self.sorted = self
+            .sorted
+            .map(|sorted| sorted && last.is_none_or(|last| last < label));
+        self.data.push(label);
+    }
+
+    /// Remove last label, which keeps labels sorted and unique if they were
+    pub fn pop(&mut self) -> Option<u128> {
+        self.data.pop()
+    }
+
+    /// Union of these and `other` labels, or `None` unless both are sorted
+    /// and unique
+    ///
+    /// Result is sorted and unique too.
+    pub fn union_sorted(&self, other: &Labels) -> Option<Labels> {
+        if !self.is_sorted_unique() || !other.is_sorted_unique() {
+            return None;
+        }
+        let (a, b) = (&self.data, &other.data);
+        let mut data = Vec::with_capacity(a.len().max(b.len()));
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() && j < b.len() {
+            if a[i] <= b[j] {
+                if a[i] == b[j] {
+                    j += 1;
+                }
+                data.push(a[i]);
+                i += 1;
+            } else {
+                data.push(b[j]);
+                j += 1;
+            }
+        }
+        data.extend_from_slice(&a[i..]);
+        data.extend_from_slice(&b[j..]);
+        Some(Labels {
+            data,
+            sorted: Some(true),
+        })
+    }
+
+    /// Positions of `keys` within these labels, i.e. `self.data[p[j]] == keys.data[j]`
+    ///
+    /// Uses merge-join when both labels and keys are sorted, binary search
+    /// when only labels are sorted, and hash index otherwise. Returns `None`
+    /// if any key is missing.
+    pub fn positions_of(&self, keys: &Labels) -> Option<Vec<usize>> {
+        if self.is_sorted_unique() {
+            sorted_positions_of(&self.data, keys)
+        } else {
+            hashed_positions(&self.data, &keys.data)
+        }
+    }
+
     #[cfg(feature = "vec-u8")]
     pub fn is_valid_vec(data_ref: &impl AsRef<[u8]>) -> bool {
         let data = data_ref.as_ref();
@@ -37,7 +160,7 @@ impl Labels {
     pub fn len_from_vec(data_ref: &impl AsRef<[u8]>) -> Option<usize> {
         let data = data_ref.as_ref();
         let len = data.len();
-        
+
         if 0 != len % size_of::<u128>() {
             return None;
         }
@@ -78,6 +201,142 @@ impl Labels {
     }
 }
 
+/// Labels known to be sorted and unique
+///
+/// Validated once when constructed, so that joins can use merge-join or
+/// binary search without checking order again.
+pub struct SortedLabels {
+    labels: Labels,
+}
+
+impl SortedLabels {
+    /// Returns `None` unless labels are sorted and unique
+    pub fn new(mut labels: Labels) -> Option<Self> {
+        if !labels.track_sorted_unique() {
+            return None;
+        }
+        Some(Self { labels })
+    }
+
+    pub fn as_labels(&self) -> &Labels {
+        &self.labels
+    }
+
+    pub fn into_labels(self) -> Labels {
+        self.labels
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.data.is_empty()
+    }
+
+    /// Position of a label, or `None` if it is missing
+    pub fn position(&self, label: u128) -> Option<usize> {
+        self.labels.data.binary_search(&label).ok()
+    }
+
+    /// Positions of `keys` within these labels
+    ///
+    /// Returns `None` if any key is missing.
+    pub fn positions_of(&self, keys: &Labels) -> Option<Vec<usize>> {
+        sorted_positions_of(&self.labels.data, keys)
+    }
+
+    /// Union of both labels, which is sorted and unique too
+    pub fn union(&self, other: &SortedLabels) -> SortedLabels {
+        let labels = self
+            .labels
+            .union_sorted(&other.labels)
+            .expect("Labels are sorted");
+        SortedLabels { labels }
+    }
+}
+
+/// Tells whether labels are strictly increasing
+pub fn is_sorted_unique(labels: &[u128]) -> bool {
+    labels.windows(2).all(|w| w[0] < w[1])
+}
+
+/// Positions of `keys` within `labels` using the best join for their order
+pub fn positions(labels: &[u128], keys: &[u128]) -> Option<Vec<usize>> {
+    if is_sorted_unique(labels) {
+        sorted_positions(labels, keys)
+    } else {
+        hashed_positions(labels, keys)
+    }
+}
+
+/// Positions of `keys` within sorted and unique `labels`
+///
+/// Sorted keys are merge-joined with labels, and other keys are looked up
+/// using binary search.
+pub fn sorted_positions(labels: &[u128], keys: &[u128]) -> Option<Vec<usize>> {
+    if is_sorted_unique(keys) {
+        merged_positions(labels, keys)
+    } else {
+        searched_positions(labels, keys)
+    }
+}
+
+/// Same as `sorted_positions()` using order of `keys` if already known
+fn sorted_positions_of(labels: &[u128], keys: &Labels) -> Option<Vec<usize>> {
+    if keys.is_sorted_unique() {
+        merged_positions(labels, &keys.data)
+    } else {
+        searched_positions(labels, &keys.data)
+    }
+}
+
+/// Positions of `keys` within `labels`, where both are sorted and unique
+///
+/// Keys are merge-joined with labels, galloping over runs of labels between
+/// keys, so that few keys don't visit all labels.
+pub fn merged_positions(labels: &[u128], keys: &[u128]) -> Option<Vec<usize>> {
+    let mut result = Vec::with_capacity(keys.len());
+    let mut start = 0;
+    for key in keys {
+        // Find range of labels containing key doubling the step
+        let mut step = 1;
+        let mut end = start;
+        while end < labels.len() && labels[end] < *key {
+            start = end;
+            end = end.saturating_add(step).min(labels.len());
+            step *= 2;
+        }
+        let end = (end + 1).min(labels.len());
+        let index = start + labels.get(start..end)?.binary_search(key).ok()?;
+        result.push(index);
+        start = index + 1;
+    }
+    Some(result)
+}
+
+/// Positions of `keys` in any order within sorted and unique `labels`
+pub fn searched_positions(labels: &[u128], keys: &[u128]) -> Option<Vec<usize>> {
+    keys.iter()
+        .map(|key| labels.binary_search(key).ok())
+        .collect()
+}
+
+/// Positions of `keys` within `labels` in any order
+///
+/// Builds hash index of labels, and if a label repeats its first position is
+/// used.
+pub fn hashed_positions(labels: &[u128], keys: &[u128]) -> Option<Vec<usize>> {
+    let mut index = HashMap::<u128, usize>::default();
+    index.reserve(labels.len());
+    for (i, label) in labels.iter().enumerate() {
+        if let Entry::Vacant(entry) = index.entry(*label) {
+            entry.insert(i);
+        }
+    }
+    keys.iter().map(|key| index.get(key).copied()).collect()
+}
+
 #[macro_export]
 macro_rules! label {
     () => {
@@ -99,3 +358,81 @@ impl core::fmt::Display for Labels {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn test_positions() {
+        let labels: Vec<u128> = (0..1000).map(|x| x * 2).collect();
+
+        // Merge-join of sorted keys
+        assert_eq!(
+            sorted_positions(&labels, &[0, 2, 500, 1998]),
+            Some(vec![0, 1, 250, 999])
+        );
+        assert_eq!(sorted_positions(&labels, &[]), Some(vec![]));
+        assert_eq!(sorted_positions(&labels, &[2, 3]), None);
+        assert_eq!(sorted_positions(&labels, &[1998, 2000]), None);
+
+        // Binary search of unsorted keys
+        assert_eq!(
+            sorted_positions(&labels, &[500, 0, 500]),
+            Some(vec![250, 0, 250])
+        );
+        assert_eq!(sorted_positions(&labels, &[500, 1]), None);
+
+        // Hash index of unsorted labels
+        let labels = [7, 3, 9, 3];
+        assert_eq!(hashed_positions(&labels, &[3, 9, 7]), Some(vec![1, 2, 0]));
+        assert_eq!(hashed_positions(&labels, &[4]), None);
+
+        let labels = Labels::from(vec![7, 3, 9]);
+        let keys = Labels::from(vec![9, 7]);
+        assert!(!labels.is_sorted_unique());
+        assert_eq!(labels.positions_of(&keys), Some(vec![2, 0]));
+    }
+
+    #[test]
+    fn test_sorted_labels() {
+        assert!(SortedLabels::new(Labels::from(vec![1, 1])).is_none());
+        assert!(SortedLabels::new(Labels::from(vec![2, 1])).is_none());
+
+        let a = SortedLabels::new(Labels::from(vec![1, 3, 5])).unwrap();
+        let b = SortedLabels::new(Labels::from(vec![2, 3, 6])).unwrap();
+        assert_eq!(a.position(5), Some(2));
+        assert_eq!(a.position(4), None);
+
+        let union = a.union(&b);
+        assert_eq!(union.as_labels().as_slice(), [1, 2, 3, 5, 6]);
+        assert_eq!(union.positions_of(b.as_labels()), Some(vec![1, 2, 4]));
+    }
+
+    #[test]
+    fn test_track_sorted() {
+        let mut labels = Labels::from(vec![1, 3]);
+        assert!(labels.track_sorted_unique());
+        labels.push(5);
+        assert!(labels.is_sorted_unique());
+        labels.push(4);
+        assert!(!labels.is_sorted_unique());
+
+        // Modifying labels forgets their order, which is checked again
+        labels.data_mut().sort();
+        assert!(labels.is_sorted_unique());
+        labels.data_mut().reverse();
+        assert!(!labels.is_sorted_unique());
+        labels.data_mut().reverse();
+        assert_eq!(labels.pop(), Some(5));
+        assert!(labels.track_sorted_unique());
+
+        let unsorted = Labels::from(vec![2, 1]);
+        assert!(labels.union_sorted(&unsorted).is_none());
+        let union = labels.union_sorted(&Labels::from(vec![2, 3])).unwrap();
+        assert_eq!(union.as_slice(), [1, 2, 3, 4]);
+        assert!(union.is_sorted_unique());
+    }
+}
@@ -8,11 +8,13 @@ pub mod asset;
 
 pub mod abacus {
     pub mod instruction_set;
+    pub mod program;
     pub mod program_error;
 }
 
 pub mod labels;
 pub mod log;
 pub mod math;
+pub mod signed_amount;
 pub mod uint;
 pub mod vector;
@@ -0,0 +1,298 @@
+use alloc::vec::Vec;
+
+use alloy_primitives::I256;
+
+use crate::{amount::Amount, uint};
+
+#[inline]
+fn convert_from_i128(value: i128) -> I256 {
+    // Conversion from i128 to I256 is lossless
+    I256::try_from(value).unwrap_or_default()
+}
+
+#[inline]
+fn try_convert_to_i128(value: I256) -> Option<i128> {
+    i128::try_from(value).ok()
+}
+
+/// Signed fixed-point value with the same scale as `Amount`
+///
+/// Used in place of long/short pairs of `Amount`, i.e. net value is
+/// `long - short`, and both parts can be recovered with `positive_part()`
+/// and `negative_part()`. Multiplication and division use 256-bit
+/// intermediate precision, and results are rounded towards zero.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SignedAmount(pub i128);
+
+impl SignedAmount {
+    pub const ZERO: SignedAmount = SignedAmount(0);
+    pub const EPSILON: SignedAmount = SignedAmount(1);
+    pub const MIN: SignedAmount = SignedAmount(i128::MIN);
+    pub const MAX: SignedAmount = SignedAmount(i128::MAX);
+    pub const ONE: SignedAmount = SignedAmount(Self::SCALE);
+    pub const SCALE: i128 = Amount::SCALE as i128;
+    pub const DECIMALS: usize = Amount::DECIMALS;
+
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        Some(Self(self.0.checked_add(rhs.0)?))
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        Some(Self(self.0.checked_sub(rhs.0)?))
+    }
+
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        let result = self
+            .to_i256()
+            .checked_mul(rhs.to_i256())?
+            .checked_div(Self::i256_scale())?;
+        Some(Self(try_convert_to_i128(result)?))
+    }
+
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        let result = self
+            .to_i256()
+            .checked_mul(Self::i256_scale())?
+            .checked_div(rhs.to_i256())?;
+        Some(Self(try_convert_to_i128(result)?))
+    }
+
+    pub fn checked_neg(self) -> Option<Self> {
+        Some(Self(self.0.checked_neg()?))
+    }
+
+    /// Magnitude of the value, which always fits into `Amount`
+    #[inline]
+    pub fn abs(&self) -> Amount {
+        Amount(self.0.unsigned_abs())
+    }
+
+    /// Long part, i.e. `MAX(value, 0)`
+    #[inline]
+    pub fn positive_part(&self) -> Amount {
+        if self.0 > 0 {
+            self.abs()
+        } else {
+            Amount::ZERO
+        }
+    }
+
+    /// Short part, i.e. `MAX(-value, 0)`
+    #[inline]
+    pub fn negative_part(&self) -> Amount {
+        if self.0 < 0 {
+            self.abs()
+        } else {
+            Amount::ZERO
+        }
+    }
+
+    /// Net value of long/short pair, i.e. `long - short`
+    pub fn from_parts(long: Amount, short: Amount) -> Option<Self> {
+        let value = if short.0 <= long.0 {
+            0i128.checked_add_unsigned(long.0 - short.0)?
+        } else {
+            0i128.checked_sub_unsigned(short.0 - long.0)?
+        };
+        Some(Self(value))
+    }
+
+    #[inline]
+    pub fn try_from_amount(value: Amount) -> Option<Self> {
+        Some(Self(i128::try_from(value.0).ok()?))
+    }
+
+    #[inline]
+    pub fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    #[inline]
+    pub fn is_negative(&self) -> bool {
+        self.0 < 0
+    }
+
+    #[inline]
+    pub fn from_slice(slice: &[u8]) -> Self {
+        Self(uint::read_u128(slice) as i128)
+    }
+
+    #[inline]
+    pub fn to_vec(&self, output: &mut Vec<u8>) {
+        uint::write_u128(self.0 as u128, output);
+    }
+
+    #[inline]
+    pub fn from_i128_raw(value: i128) -> Self {
+        Self(value)
+    }
+
+    #[inline]
+    pub fn to_i128_raw(&self) -> i128 {
+        self.0
+    }
+
+    #[inline]
+    pub fn to_i256(&self) -> I256 {
+        convert_from_i128(self.0)
+    }
+
+    #[inline]
+    pub fn i256_scale() -> I256 {
+        convert_from_i128(Self::SCALE)
+    }
+}
+
+#[cfg(any(not(feature = "stylus"), feature = "debug", feature = "stylus-test"))]
+impl core::fmt::Display for SignedAmount {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.is_negative() {
+            write!(f, "-")?;
+        }
+        core::fmt::Display::fmt(&self.abs(), f)
+    }
+}
+
+#[cfg(any(not(feature = "stylus"), feature = "debug", feature = "stylus-test"))]
+impl core::fmt::Debug for SignedAmount {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "signed_amount!({})", self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn signed(value: i128, scale: u8) -> SignedAmount {
+        let amount = Amount::from_u128_with_scale(value.unsigned_abs(), scale);
+        let result = SignedAmount::try_from_amount(amount).unwrap();
+        if value < 0 {
+            result.checked_neg().unwrap()
+        } else {
+            result
+        }
+    }
+
+    fn do_test_signed_amount(lhs: SignedAmount, rhs: SignedAmount) {
+        assert_eq!(lhs.0, rhs.0);
+    }
+
+    fn do_test_amount(lhs: Amount, rhs: Amount) {
+        assert_eq!(lhs.0, rhs.0);
+    }
+
+    #[test]
+    fn test_signed_amount() {
+        do_test_signed_amount(signed(1, 0), SignedAmount::ONE);
+        do_test_amount(signed(-1_5, 1).abs(), Amount::from_u128_with_scale(1_5, 1));
+
+        do_test_signed_amount(
+            signed(1_50, 2).checked_sub(signed(2, 0)).unwrap(),
+            signed(-5, 1),
+        );
+        do_test_signed_amount(
+            signed(-1_50, 2).checked_mul(signed(-2, 0)).unwrap(),
+            signed(3, 0),
+        );
+        do_test_signed_amount(
+            signed(3, 0).checked_div(signed(-1_50, 2)).unwrap(),
+            signed(-2, 0),
+        );
+
+        // Rounding is towards zero
+        do_test_signed_amount(
+            SignedAmount(-1).checked_mul(signed(5, 1)).unwrap(),
+            SignedAmount::ZERO,
+        );
+
+        // Intermediate results exceeding 128 bits
+        let big = SignedAmount(i128::MAX / 2);
+        do_test_signed_amount(
+            big.checked_mul(signed(2, 0))
+                .unwrap()
+                .checked_div(signed(4, 0))
+                .unwrap(),
+            SignedAmount(i128::MAX / 4),
+        );
+        assert!(big.checked_mul(signed(3, 0)).is_none());
+        assert!(SignedAmount::ONE.checked_div(SignedAmount::ZERO).is_none());
+        assert!(SignedAmount::MIN.checked_neg().is_none());
+
+        // Long/short pairs
+        let long = Amount::from_u128_with_scale(2, 0);
+        let short = Amount::from_u128_with_scale(5, 0);
+        let net = SignedAmount::from_parts(long, short).unwrap();
+        do_test_signed_amount(net, signed(-3, 0));
+        do_test_amount(net.positive_part(), Amount::ZERO);
+        do_test_amount(net.negative_part(), Amount::from_u128_with_scale(3, 0));
+        do_test_amount(
+            SignedAmount::from_parts(short, long)
+                .unwrap()
+                .positive_part(),
+            Amount::from_u128_with_scale(3, 0),
+        );
+        do_test_signed_amount(
+            SignedAmount::from_parts(Amount::ZERO, Amount(1 << 127)).unwrap(),
+            SignedAmount::MIN,
+        );
+        assert!(SignedAmount::from_parts(Amount::MAX, Amount::ZERO).is_none());
+    }
+
+    #[cfg(any(not(feature = "stylus"), feature = "debug", feature = "stylus-test"))]
+    #[test]
+    fn test_signed_amount_display() {
+        assert_eq!(format!("{}", signed(-3, 0)), "-3.0");
+        assert_eq!(format!("{:0.2}", signed(1_25, 2)), "1.25");
+    }
+
+    mod properties {
+        use num_bigint::BigInt;
+        use proptest::prelude::*;
+
+        use super::*;
+
+        fn big(value: i128) -> BigInt {
+            BigInt::from(value)
+        }
+
+        /// Raw value of exact result, or `None` if it doesn't fit
+        fn expected(value: BigInt) -> Option<i128> {
+            i128::try_from(value).ok()
+        }
+
+        fn raw_signed_amount() -> impl Strategy<Value = i128> {
+            prop_oneof![
+                Just(0i128),
+                Just(i128::MIN),
+                Just(i128::MAX),
+                -1_000..=1_000i128,
+                -10 * SignedAmount::SCALE..=10 * SignedAmount::SCALE,
+                any::<i128>(),
+            ]
+        }
+
+        proptest! {
+            #[test]
+            fn checked_ops_match_exact_results(a in raw_signed_amount(), b in raw_signed_amount()) {
+                let (x, y) = (SignedAmount(a), SignedAmount(b));
+                let scale = big(SignedAmount::SCALE);
+
+                prop_assert_eq!(x.checked_add(y).map(|r| r.0), expected(big(a) + big(b)));
+                prop_assert_eq!(x.checked_sub(y).map(|r| r.0), expected(big(a) - big(b)));
+                prop_assert_eq!(x.checked_neg().map(|r| r.0), expected(-big(a)));
+
+                // Division of big integers rounds towards zero as well
+                prop_assert_eq!(
+                    x.checked_mul(y).map(|r| r.0),
+                    expected(big(a) * big(b) / &scale)
+                );
+                let div = if b == 0 { None } else { expected(big(a) * &scale / big(b)) };
+                prop_assert_eq!(x.checked_div(y).map(|r| r.0), div);
+
+                let net = SignedAmount::from_parts(x.positive_part(), x.negative_part());
+                prop_assert_eq!(net.map(|r| r.0), Some(a));
+            }
+        }
+    }
+}
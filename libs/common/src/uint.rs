@@ -12,3 +12,85 @@ pub fn write_u128(input: u128, output: &mut Vec<u8>) {
     let bytes = input.to_le_bytes();
     output.extend_from_slice(&bytes);
 }
+
+/// Maximum number of bytes of LEB128 encoded u128, i.e. ceil(128 / 7)
+pub const MAX_LEB128_LEN: usize = 19;
+
+/// Write value as unsigned LEB128, i.e. 7 bits per byte starting with least
+/// significant ones, and with high bit set on all bytes except the last one
+#[inline]
+pub fn write_leb128(input: u128, output: &mut Vec<u8>) {
+    let mut value = input;
+    while value >= 0x80 {
+        output.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+/// Read unsigned LEB128 value from the start of input
+///
+/// Returns value and number of bytes it occupies, or `None` if input ends
+/// before the last byte, or if value does not fit into u128.
+#[inline]
+pub fn read_leb128(input: &[u8]) -> Option<(u128, usize)> {
+    let mut value = 0u128;
+    for (index, byte) in input.iter().take(MAX_LEB128_LEN).enumerate() {
+        let bits = (byte & 0x7f) as u128;
+        let shift = 7 * index as u32;
+        if bits.leading_zeros() < shift {
+            return None;
+        }
+        value |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Some((value, index + 1));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_leb128() {
+        for value in [
+            0,
+            1,
+            0x7f,
+            0x80,
+            100,
+            300,
+            1 << 64,
+            u128::MAX - 1,
+            u128::MAX,
+        ] {
+            let mut bytes = Vec::new();
+            write_leb128(value, &mut bytes);
+            assert_eq!(read_leb128(&bytes), Some((value, bytes.len())));
+        }
+
+        let mut bytes = Vec::new();
+        write_leb128(100, &mut bytes);
+        write_leb128(300, &mut bytes);
+        assert_eq!(bytes, vec![0x64, 0xac, 0x02]);
+        assert_eq!(read_leb128(&bytes[1..]), Some((300, 2)));
+
+        let mut bytes = Vec::new();
+        write_leb128(u128::MAX, &mut bytes);
+        assert_eq!(bytes.len(), MAX_LEB128_LEN);
+        assert_eq!(bytes[MAX_LEB128_LEN - 1], 0x03);
+
+        // Truncated
+        assert_eq!(read_leb128(&[]), None);
+        assert_eq!(read_leb128(&[0xac]), None);
+
+        // Exceeding 128 bits
+        bytes[MAX_LEB128_LEN - 1] = 0x04;
+        assert_eq!(read_leb128(&bytes), None);
+        bytes[MAX_LEB128_LEN - 1] = 0x83;
+        bytes.push(0x00);
+        assert_eq!(read_leb128(&bytes), None);
+    }
+}
@@ -1,7 +1,16 @@
 use alloc::vec::Vec;
 
-use crate::amount::Amount;
+use crate::{
+    amount::Amount,
+    labels::{hashed_positions, merged_positions, Labels},
+    signed_amount::SignedAmount,
+};
 
+#[cfg_attr(
+    feature = "with-serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct Vector {
     pub data: Vec<Amount>,
 }
@@ -42,7 +51,7 @@ impl Vector {
     pub fn len_from_vec(data_ref: &impl AsRef<[u8]>) -> Option<usize> {
         let data = data_ref.as_ref();
         let len = data.len();
-        
+
         if 0 != len % size_of::<u128>() {
             return None;
         }
@@ -129,3 +138,219 @@ impl core::fmt::Display for Vector {
         }
     }
 }
+
+/// Vector of signed amounts, e.g. net of long and short vectors
+#[derive(Default)]
+pub struct SignedVector {
+    pub data: Vec<SignedAmount>,
+}
+
+impl SignedVector {
+    pub fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+
+    /// Net of long/short vector pair, i.e. `long - short` component-wise
+    ///
+    /// Returns `None` when vectors are not aligned, or when net value does
+    /// not fit into `SignedAmount`.
+    pub fn from_parts(long: &Vector, short: &Vector) -> Option<Self> {
+        if long.data.len() != short.data.len() {
+            return None;
+        }
+        let mut this = Self::new();
+        this.data.reserve(long.data.len());
+        for (x_long, x_short) in long.data.iter().zip(short.data.iter()) {
+            this.data.push(SignedAmount::from_parts(*x_long, *x_short)?);
+        }
+        Some(this)
+    }
+
+    /// Long vector, i.e. `MAX(value, 0)` component-wise
+    pub fn positive_part(&self) -> Vector {
+        Vector {
+            data: self.data.iter().map(|x| x.positive_part()).collect(),
+        }
+    }
+
+    /// Short vector, i.e. `MAX(-value, 0)` component-wise
+    pub fn negative_part(&self) -> Vector {
+        Vector {
+            data: self.data.iter().map(|x| x.negative_part()).collect(),
+        }
+    }
+}
+
+#[cfg(any(not(feature = "stylus"), feature = "debug", feature = "stylus-test"))]
+impl core::fmt::Display for SignedVector {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let max_scale_len = f.precision().unwrap_or(18).min(18);
+        let separator = if f.alternate() { "\n\t" } else { "," };
+        let mut sepa = "";
+
+        for x in &self.data {
+            write!(
+                f,
+                "{}{:0.max_scale_len$}",
+                sepa,
+                x,
+                max_scale_len = max_scale_len
+            )?;
+            sepa = separator;
+        }
+        Ok(())
+    }
+}
+
+/// Sparse vector, i.e. values of only some labels, and zero for others
+///
+/// Labels are sorted and unique, and each label has corresponding value in
+/// `data`. Used to update few components of long vectors without creating
+/// full-length intermediate vectors.
+#[derive(Default)]
+pub struct SparseVector {
+    pub labels: Vec<u128>,
+    pub data: Vec<Amount>,
+}
+
+impl SparseVector {
+    pub fn new() -> Self {
+        Self {
+            labels: Vec::new(),
+            data: Vec::new(),
+        }
+    }
+
+    /// Sparse vector of values with given labels
+    ///
+    /// Returns `None` when labels and values are not aligned, or when labels
+    /// are not sorted and unique.
+    pub fn from_parts(labels: Labels, values: Vector) -> Option<Self> {
+        if labels.len() != values.data.len() {
+            return None;
+        }
+        if !labels.is_sorted_unique() {
+            return None;
+        }
+        Some(Self {
+            labels: labels.into(),
+            data: values.data,
+        })
+    }
+
+    pub fn into_parts(self) -> (Labels, Vector) {
+        (Labels::from(self.labels), Vector { data: self.data })
+    }
+
+    /// Positions of labels of this vector within `labels`
+    ///
+    /// Returns `None` if any label is missing.
+    pub fn positions(&self, labels: &Labels) -> Option<Vec<usize>> {
+        // Labels of sparse vector are always sorted and unique
+        if labels.is_sorted_unique() {
+            merged_positions(labels, &self.labels)
+        } else {
+            hashed_positions(labels, &self.labels)
+        }
+    }
+
+    /// Values of `vector` with `labels` at given sorted `keys`
+    ///
+    /// Returns `None` if any key is missing, or when labels and vector are
+    /// not aligned.
+    pub fn gather(keys: &Labels, labels: &Labels, vector: &Vector) -> Option<Self> {
+        if labels.len() != vector.data.len() {
+            return None;
+        }
+        if !keys.is_sorted_unique() {
+            return None;
+        }
+        let data = labels
+            .positions_of(keys)?
+            .into_iter()
+            .map(|i| vector.data[i])
+            .collect();
+        Some(Self {
+            labels: keys.as_slice().to_vec(),
+            data,
+        })
+    }
+
+    /// Sum of sparse vectors, i.e. union of their labels
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        let mut result = Self::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.labels.len() || j < other.labels.len() {
+            let lhs = self.labels.get(i);
+            let rhs = other.labels.get(j);
+            match (lhs, rhs) {
+                (Some(a), Some(b)) if a == b => {
+                    result.labels.push(*a);
+                    result.data.push(self.data[i].checked_add(other.data[j])?);
+                    i += 1;
+                    j += 1;
+                }
+                (Some(a), Some(b)) if a < b => {
+                    result.labels.push(*a);
+                    result.data.push(self.data[i]);
+                    i += 1;
+                }
+                (Some(a), None) => {
+                    result.labels.push(*a);
+                    result.data.push(self.data[i]);
+                    i += 1;
+                }
+                (_, Some(b)) => {
+                    result.labels.push(*b);
+                    result.data.push(other.data[j]);
+                    j += 1;
+                }
+                (None, None) => break,
+            }
+        }
+        Some(result)
+    }
+
+    /// Component-wise product of sparse vectors, i.e. intersection of their
+    /// labels
+    pub fn checked_mul(&self, other: &Self) -> Option<Self> {
+        let mut result = Self::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.labels.len() && j < other.labels.len() {
+            let (a, b) = (self.labels[i], other.labels[j]);
+            if a == b {
+                result.labels.push(a);
+                result.data.push(self.data[i].checked_mul(other.data[j])?);
+            }
+            if a <= b {
+                i += 1;
+            }
+            if b <= a {
+                j += 1;
+            }
+        }
+        Some(result)
+    }
+}
+
+#[cfg(any(not(feature = "stylus"), feature = "debug", feature = "stylus-test"))]
+impl core::fmt::Display for SparseVector {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let max_scale_len = f.precision().unwrap_or(18).min(18);
+        let separator = if f.alternate() { "\n\t" } else { "," };
+        let mut sepa = "";
+
+        for (label, x) in self.labels.iter().zip(self.data.iter()) {
+            write!(
+                f,
+                "{}{}:{:0.max_scale_len$}",
+                sepa,
+                label,
+                x,
+                max_scale_len = max_scale_len
+            )?;
+            sepa = separator;
+        }
+        Ok(())
+    }
+}
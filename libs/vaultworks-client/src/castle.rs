@@ -0,0 +1,415 @@
+use alloy_primitives::{Address, Bytes};
+use alloy_provider::{network::Ethereum, PendingTransactionBuilder, Provider};
+use alloy_sol_types::SolCall;
+use common::{amount::Amount, labels::Labels, vector::Vector};
+use common_contracts::{
+    contracts::formulas::{Order, Quote},
+    interfaces::{
+        alchemist::IAlchemist, banker::IBanker, factor::IFactor, guildmaster::IGuildmaster,
+        steward::ISteward,
+    },
+};
+use vaultworks_sdk::{
+    alchemist::AssetWeights,
+    banker::{self, AssetMargin, AssetSupply, MarketData},
+    codec::{decode_labels, decode_order, decode_quote, decode_vector},
+    steward::{LongShort, RebalanceWeights},
+};
+
+use crate::{contract::Contract, ClientError};
+
+type PendingTransaction = PendingTransactionBuilder<Ethereum>;
+
+/// Client of Castle, which routes calls to its facets, i.e. Banker, Factor,
+/// Steward, Guildmaster and Alchemist
+///
+/// Queries decode byte payloads into protocol types, and transactions return
+/// pending transaction, so that caller can choose to wait for receipt.
+pub struct CastleClient<P> {
+    contract: Contract<P>,
+}
+
+impl<P: Provider> CastleClient<P> {
+    pub fn new(address: Address, provider: P) -> Self {
+        Self {
+            contract: Contract { address, provider },
+        }
+    }
+
+    pub fn address(&self) -> Address {
+        self.contract.address
+    }
+
+    pub fn provider(&self) -> &P {
+        &self.contract.provider
+    }
+
+    /// Execute any call of Castle interfaces without sending transaction
+    pub async fn call<C: SolCall>(&self, call: C) -> Result<C::Return, ClientError> {
+        self.contract.call(call).await
+    }
+
+    /// Send transaction executing any call of Castle interfaces
+    pub async fn send<C: SolCall>(&self, call: C) -> Result<PendingTransaction, ClientError> {
+        self.contract.send(call).await
+    }
+
+    //
+    // Steward
+    //
+
+    pub async fn get_vault(&self, index_id: u128) -> Result<Address, ClientError> {
+        let ret = self.call(ISteward::getVaultCall { index_id }).await?;
+        Ok(ret._0)
+    }
+
+    /// Market data of vendor, which includes asset names from `getVendorAssets()`
+    pub async fn get_market_data(&self, vendor_id: u128) -> Result<MarketData, ClientError> {
+        let assets = self
+            .call(ISteward::getVendorAssetsCall { vendor_id })
+            .await?;
+        let ret = self.call(ISteward::getMarketDataCall { vendor_id }).await?;
+        Ok(MarketData::try_from_returns(&assets._0, &ret._0)?)
+    }
+
+    pub async fn get_index_assets_count(&self, index_id: u128) -> Result<u128, ClientError> {
+        let ret = self
+            .call(ISteward::getIndexAssetsCountCall { index_id })
+            .await?;
+        Ok(ret._0)
+    }
+
+    pub async fn get_index_assets(&self, index_id: u128) -> Result<Labels, ClientError> {
+        let ret = self.call(ISteward::getIndexAssetsCall { index_id }).await?;
+        Ok(decode_labels(&ret._0)?)
+    }
+
+    pub async fn get_index_weights(&self, index_id: u128) -> Result<Vector, ClientError> {
+        let ret = self
+            .call(ISteward::getIndexWeightsCall { index_id })
+            .await?;
+        Ok(decode_vector(&ret._0)?)
+    }
+
+    /// Index assets along with their weights
+    pub async fn get_index_asset_weights(
+        &self,
+        index_id: u128,
+    ) -> Result<AssetWeights, ClientError> {
+        let assets = self.call(ISteward::getIndexAssetsCall { index_id }).await?;
+        let weights = self
+            .call(ISteward::getIndexWeightsCall { index_id })
+            .await?;
+        Ok(AssetWeights::try_from_returns(&assets._0, &weights._0)?)
+    }
+
+    pub async fn get_index_rebalance_weights(
+        &self,
+        index_id: u128,
+    ) -> Result<RebalanceWeights, ClientError> {
+        let ret = self
+            .call(ISteward::getIndexRebalanceWeightsCall { index_id })
+            .await?;
+        Ok(RebalanceWeights::try_from_returns(&ret._0)?)
+    }
+
+    pub async fn get_index_quote(
+        &self,
+        index_id: u128,
+        vendor_id: u128,
+    ) -> Result<Quote, ClientError> {
+        let ret = self
+            .call(ISteward::getIndexQuoteCall {
+                index_id,
+                vendor_id,
+            })
+            .await?;
+        Ok(decode_quote(&ret._0)?)
+    }
+
+    pub async fn get_trader_order(
+        &self,
+        index_id: u128,
+        trader: Address,
+    ) -> Result<Order, ClientError> {
+        let ret = self
+            .call(ISteward::getTraderOrderCall { index_id, trader })
+            .await?;
+        Ok(decode_order(&ret._0)?)
+    }
+
+    pub async fn get_trader_count(&self, index_id: u128) -> Result<u128, ClientError> {
+        let ret = self.call(ISteward::getTraderCountCall { index_id }).await?;
+        Ok(ret._0)
+    }
+
+    pub async fn get_trader_at(
+        &self,
+        index_id: u128,
+        offset: u128,
+    ) -> Result<Address, ClientError> {
+        let ret = self
+            .call(ISteward::getTraderAtCall { index_id, offset })
+            .await?;
+        Ok(ret._0)
+    }
+
+    pub async fn get_vendor_order(
+        &self,
+        index_id: u128,
+        vendor_id: u128,
+    ) -> Result<Order, ClientError> {
+        let ret = self
+            .call(ISteward::getVendorOrderCall {
+                index_id,
+                vendor_id,
+            })
+            .await?;
+        Ok(decode_order(&ret._0)?)
+    }
+
+    pub async fn get_vendor_count(&self, index_id: u128) -> Result<u128, ClientError> {
+        let ret = self.call(ISteward::getVendorCountCall { index_id }).await?;
+        Ok(ret._0)
+    }
+
+    pub async fn get_vendor_at(&self, index_id: u128, offset: u128) -> Result<u128, ClientError> {
+        let ret = self
+            .call(ISteward::getVendorAtCall { index_id, offset })
+            .await?;
+        Ok(ret._0)
+    }
+
+    pub async fn get_total_order(&self, index_id: u128) -> Result<Order, ClientError> {
+        let ret = self.call(ISteward::getTotalOrderCall { index_id }).await?;
+        Ok(decode_order(&ret._0)?)
+    }
+
+    pub async fn get_vendor_assets(&self, vendor_id: u128) -> Result<Labels, ClientError> {
+        let ret = self
+            .call(ISteward::getVendorAssetsCall { vendor_id })
+            .await?;
+        Ok(decode_labels(&ret._0)?)
+    }
+
+    pub async fn get_vendor_margin(&self, vendor_id: u128) -> Result<Vector, ClientError> {
+        let ret = self
+            .call(ISteward::getVendorMarginCall { vendor_id })
+            .await?;
+        Ok(decode_vector(&ret._0)?)
+    }
+
+    pub async fn get_vendor_supply(&self, vendor_id: u128) -> Result<LongShort, ClientError> {
+        let ret = self
+            .call(ISteward::getVendorSupplyCall { vendor_id })
+            .await?;
+        Ok(LongShort::try_from_returns(&ret._0)?)
+    }
+
+    pub async fn get_vendor_demand(&self, vendor_id: u128) -> Result<LongShort, ClientError> {
+        let ret = self
+            .call(ISteward::getVendorDemandCall { vendor_id })
+            .await?;
+        Ok(LongShort::try_from_returns(&ret._0)?)
+    }
+
+    pub async fn get_vendor_delta(&self, vendor_id: u128) -> Result<LongShort, ClientError> {
+        let ret = self
+            .call(ISteward::getVendorDeltaCall { vendor_id })
+            .await?;
+        Ok(LongShort::try_from_returns(&ret._0)?)
+    }
+
+    pub async fn fetch_vector(&self, id: u128) -> Result<Vector, ClientError> {
+        let ret = self.call(ISteward::fetchVectorCall { id }).await?;
+        Ok(decode_vector(&ret._0)?)
+    }
+
+    //
+    // Banker
+    //
+
+    pub async fn submit_assets(
+        &self,
+        vendor_id: u128,
+        asset_names: &Labels,
+    ) -> Result<PendingTransaction, ClientError> {
+        self.send(banker::submit_assets(vendor_id, asset_names))
+            .await
+    }
+
+    pub async fn submit_margin(
+        &self,
+        vendor_id: u128,
+        margin: &AssetMargin,
+    ) -> Result<PendingTransaction, ClientError> {
+        self.send(margin.to_call(vendor_id)?).await
+    }
+
+    pub async fn submit_supply(
+        &self,
+        vendor_id: u128,
+        supply: &AssetSupply,
+    ) -> Result<PendingTransaction, ClientError> {
+        self.send(supply.to_call(vendor_id)?).await
+    }
+
+    pub async fn submit_market_data(
+        &self,
+        vendor_id: u128,
+        market_data: &MarketData,
+    ) -> Result<PendingTransaction, ClientError> {
+        self.send(market_data.to_call(vendor_id)?).await
+    }
+
+    pub async fn update_index_quote(
+        &self,
+        vendor_id: u128,
+        index_id: u128,
+    ) -> Result<PendingTransaction, ClientError> {
+        self.send(IBanker::updateIndexQuoteCall {
+            vendor_id,
+            index_id,
+        })
+        .await
+    }
+
+    pub async fn update_multiple_index_quotes(
+        &self,
+        vendor_id: u128,
+        index_ids: Vec<u128>,
+    ) -> Result<PendingTransaction, ClientError> {
+        self.send(IBanker::updateMultipleIndexQuotesCall {
+            vendor_id,
+            index_ids,
+        })
+        .await
+    }
+
+    //
+    // Factor
+    //
+
+    pub async fn submit_buy_order(
+        &self,
+        vendor_id: u128,
+        index_id: u128,
+        trader_address: Address,
+        collateral_added: Amount,
+        collateral_removed: Amount,
+    ) -> Result<PendingTransaction, ClientError> {
+        self.send(IFactor::submitBuyOrderCall {
+            vendor_id,
+            index_id,
+            trader_address,
+            collateral_added: collateral_added.to_u128_raw(),
+            collateral_removed: collateral_removed.to_u128_raw(),
+        })
+        .await
+    }
+
+    pub async fn submit_sell_order(
+        &self,
+        vendor_id: u128,
+        index_id: u128,
+        trader_address: Address,
+        collateral_added: Amount,
+        collateral_removed: Amount,
+    ) -> Result<PendingTransaction, ClientError> {
+        self.send(IFactor::submitSellOrderCall {
+            vendor_id,
+            index_id,
+            trader_address,
+            collateral_added: collateral_added.to_u128_raw(),
+            collateral_removed: collateral_removed.to_u128_raw(),
+        })
+        .await
+    }
+
+    pub async fn process_pending_buy_order(
+        &self,
+        vendor_id: u128,
+        index_id: u128,
+        trader_address: Address,
+        max_order_size: Amount,
+    ) -> Result<PendingTransaction, ClientError> {
+        self.send(IFactor::processPendingBuyOrderCall {
+            vendor_id,
+            index_id,
+            trader_address,
+            max_order_size: max_order_size.to_u128_raw(),
+        })
+        .await
+    }
+
+    pub async fn process_pending_sell_order(
+        &self,
+        vendor_id: u128,
+        index_id: u128,
+        trader_address: Address,
+        max_order_size: Amount,
+    ) -> Result<PendingTransaction, ClientError> {
+        self.send(IFactor::processPendingSellOrderCall {
+            vendor_id,
+            index_id,
+            trader_address,
+            max_order_size: max_order_size.to_u128_raw(),
+        })
+        .await
+    }
+
+    //
+    // Guildmaster
+    //
+
+    pub async fn begin_edit_index(
+        &self,
+        index_id: u128,
+    ) -> Result<PendingTransaction, ClientError> {
+        self.send(IGuildmaster::beginEditIndexCall { index_id })
+            .await
+    }
+
+    pub async fn finish_edit_index(
+        &self,
+        index_id: u128,
+    ) -> Result<PendingTransaction, ClientError> {
+        self.send(IGuildmaster::finishEditIndexCall { index_id })
+            .await
+    }
+
+    pub async fn submit_vote(
+        &self,
+        index_id: u128,
+        vote: Bytes,
+    ) -> Result<PendingTransaction, ClientError> {
+        self.send(IGuildmaster::submitVoteCall { index_id, vote })
+            .await
+    }
+
+    //
+    // Alchemist
+    //
+
+    pub async fn submit_asset_weights(
+        &self,
+        index_id: u128,
+        weights: &AssetWeights,
+    ) -> Result<PendingTransaction, ClientError> {
+        self.send(weights.to_call(index_id)?).await
+    }
+
+    pub async fn process_pending_rebalance(
+        &self,
+        vendor_id: u128,
+        index_id: u128,
+        capacity_factor: Amount,
+    ) -> Result<PendingTransaction, ClientError> {
+        self.send(IAlchemist::processPendingRebalanceCall {
+            vendor_id,
+            index_id,
+            capacity_factor: capacity_factor.to_u128_raw(),
+        })
+        .await
+    }
+}
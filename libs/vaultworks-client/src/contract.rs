@@ -0,0 +1,49 @@
+use alloy_primitives::{Address, U256};
+use alloy_provider::{
+    network::{Ethereum, Network, TransactionBuilder},
+    PendingTransactionBuilder, Provider,
+};
+use alloy_sol_types::SolCall;
+use common::amount::Amount;
+
+use crate::ClientError;
+
+type TransactionRequest = <Ethereum as Network>::TransactionRequest;
+
+/// Contract at given address, which we talk to via provider
+///
+/// Calls are encoded and decoded using `sol!` interfaces, so that any
+/// interface can be used with any contract, e.g. all facets of Castle.
+pub(crate) struct Contract<P> {
+    pub address: Address,
+    pub provider: P,
+}
+
+impl<P: Provider> Contract<P> {
+    fn request<C: SolCall>(&self, call: &C) -> TransactionRequest {
+        TransactionRequest::default()
+            .with_to(self.address)
+            .with_input(call.abi_encode())
+    }
+
+    /// Execute call without sending transaction, i.e. `eth_call`
+    pub async fn call<C: SolCall>(&self, call: C) -> Result<C::Return, ClientError> {
+        let data = self.provider.call(&self.request(&call)).await?;
+        Ok(C::abi_decode_returns(&data, true)?)
+    }
+
+    /// Send transaction executing call, i.e. `eth_sendTransaction`
+    ///
+    /// Transaction is signed by provider's wallet, or by node if provider has
+    /// no wallet.
+    pub async fn send<C: SolCall>(
+        &self,
+        call: C,
+    ) -> Result<PendingTransactionBuilder<Ethereum>, ClientError> {
+        Ok(self.provider.send_transaction(self.request(&call)).await?)
+    }
+}
+
+pub(crate) fn amount_from_u256(value: U256) -> Result<Amount, ClientError> {
+    Amount::try_from_u256(value).ok_or(ClientError::Overflow)
+}
@@ -0,0 +1,56 @@
+use core::fmt;
+
+use alloy_transport::TransportError;
+use vaultworks_sdk::SdkError;
+
+#[derive(Debug)]
+pub enum ClientError {
+    /// Node rejected request, e.g. call reverted, or node is unreachable
+    Transport(TransportError),
+    /// Contract returned data, which doesn't match its interface
+    Abi(alloy_sol_types::Error),
+    /// Contract returned byte payload, which doesn't have expected layout
+    Decode(SdkError),
+    /// Contract returned value, which doesn't fit into `Amount`
+    Overflow,
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Transport(err) => write!(f, "Transport error: {}", err),
+            Self::Abi(err) => write!(f, "ABI error: {}", err),
+            Self::Decode(err) => write!(f, "Decode error: {}", err),
+            Self::Overflow => write!(f, "Value does not fit into Amount"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Transport(err) => Some(err),
+            Self::Abi(err) => Some(err),
+            Self::Decode(err) => Some(err),
+            Self::Overflow => None,
+        }
+    }
+}
+
+impl From<TransportError> for ClientError {
+    fn from(err: TransportError) -> Self {
+        Self::Transport(err)
+    }
+}
+
+impl From<alloy_sol_types::Error> for ClientError {
+    fn from(err: alloy_sol_types::Error) -> Self {
+        Self::Abi(err)
+    }
+}
+
+impl From<SdkError> for ClientError {
+    fn from(err: SdkError) -> Self {
+        Self::Decode(err)
+    }
+}
@@ -0,0 +1,23 @@
+//! Typed clients of Castle and Vault contracts
+//!
+//! Clients wrap `sol!` interfaces of `common-contracts` over any alloy
+//! [`Provider`](alloy_provider::Provider), and decode byte payloads using
+//! `vaultworks-sdk`, so that queries return [`Order`], [`Quote`], [`Vector`]
+//! and [`Labels`] rather than raw bytes.
+//!
+//! [`Order`]: common_contracts::contracts::formulas::Order
+//! [`Quote`]: common_contracts::contracts::formulas::Quote
+//! [`Vector`]: common::vector::Vector
+//! [`Labels`]: common::labels::Labels
+//!
+pub mod castle;
+mod contract;
+pub mod error;
+pub mod vault;
+
+pub use castle::CastleClient;
+pub use error::ClientError;
+pub use vault::VaultClient;
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,269 @@
+use std::{
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use alloy_json_rpc::{ErrorPayload, RequestPacket, Response, ResponsePacket, ResponsePayload};
+use alloy_primitives::{address, b256, Address, Bytes, B256, U256};
+use alloy_provider::{ProviderBuilder, RootProvider};
+use alloy_rpc_client::RpcClient;
+use alloy_sol_types::SolCall;
+use alloy_transport::{TransportError, TransportFut};
+use amount_macros::amount;
+use common::{labels::Labels, vector::Vector};
+use common_contracts::{
+    contracts::formulas::Order,
+    interfaces::{
+        banker::IBanker, steward::ISteward, vault::IVault, vault_native::IVaultNative,
+        vault_native_claims::IVaultNativeClaims, vault_native_orders::IVaultNativeOrders,
+    },
+};
+use labels_macros::label_vec;
+use serde_json::{json, value::RawValue, Value};
+use vaultworks_sdk::banker::MarketData;
+use vector_macros::amount_vec;
+
+use crate::{CastleClient, ClientError, VaultClient};
+
+const CASTLE: Address = address!("0x00000000000000000000000000000000000ca571");
+const VAULT: Address = address!("0x00000000000000000000000000000000000fa017");
+const TRADER: Address = address!("0x0000000000000000000000000000000000007ade");
+const KEEPER: Address = address!("0x000000000000000000000000000000000000bee9");
+const TX_HASH: B256 = b256!("0x1111111111111111111111111111111111111111111111111111111111111111");
+
+/// Result of `eth_call` for given calldata, or revert message
+type CallHandler = dyn Fn(&[u8]) -> Result<Vec<u8>, String> + Send + Sync;
+
+/// Transport answering `eth_call` using handler, and accepting any
+/// `eth_sendTransaction`, which it records so that tests can inspect calldata
+#[derive(Clone)]
+struct MockTransport {
+    on_call: Arc<CallHandler>,
+    sent: Arc<Mutex<Vec<(Address, Bytes)>>>,
+}
+
+impl MockTransport {
+    fn new(on_call: impl Fn(&[u8]) -> Result<Vec<u8>, String> + Send + Sync + 'static) -> Self {
+        Self {
+            on_call: Arc::new(on_call),
+            sent: Arc::default(),
+        }
+    }
+
+    fn provider(&self) -> RootProvider {
+        ProviderBuilder::default().on_client(RpcClient::new(self.clone(), true))
+    }
+
+    fn handle(&self, method: &str, params: Value) -> Result<Value, String> {
+        let tx = &params[0];
+        let to: Address = serde_json::from_value(tx["to"].clone()).unwrap();
+        let input = tx.get("input").or_else(|| tx.get("data")).unwrap();
+        let input: Bytes = serde_json::from_value(input.clone()).unwrap();
+        match method {
+            "eth_call" => {
+                let output = (self.on_call)(&input)?;
+                Ok(json!(Bytes::from(output)))
+            }
+            "eth_sendTransaction" => {
+                self.sent.lock().unwrap().push((to, input));
+                Ok(json!(TX_HASH))
+            }
+            _ => panic!("Unexpected request: {}", method),
+        }
+    }
+}
+
+impl tower::Service<RequestPacket> for MockTransport {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        let RequestPacket::Single(request) = request else {
+            panic!("Batch requests are not supported");
+        };
+        let params = request
+            .params()
+            .map(|params| serde_json::from_str(params.get()).unwrap())
+            .unwrap_or_default();
+
+        let payload = match self.handle(request.method(), params) {
+            Ok(value) => {
+                ResponsePayload::Success(RawValue::from_string(value.to_string()).unwrap())
+            }
+            Err(message) => ResponsePayload::Failure(ErrorPayload {
+                code: 3,
+                message: message.into(),
+                data: None,
+            }),
+        };
+        let response = Response {
+            id: request.id().clone(),
+            payload,
+        };
+        Box::pin(async move { Ok(ResponsePacket::Single(response)) })
+    }
+}
+
+/// Answers Steward queries as if vendor had three assets and trader had an order
+fn steward(input: &[u8]) -> Result<Vec<u8>, String> {
+    let selector: [u8; 4] = input[..4].try_into().unwrap();
+    let bytes = |data: Vec<u8>| (Bytes::from(data),);
+    let output = match selector {
+        ISteward::getVendorAssetsCall::SELECTOR => {
+            ISteward::getVendorAssetsCall::abi_encode_returns(&bytes(
+                label_vec![101, 102, 103].to_vec(),
+            ))
+        }
+        ISteward::getMarketDataCall::SELECTOR => {
+            ISteward::getMarketDataCall::abi_encode_returns(&(vec![
+                Bytes::from(amount_vec![1000, 2000, 500].to_vec()),
+                Bytes::from(amount_vec![1.0, 0.005, 0.1].to_vec()),
+                Bytes::from(amount_vec![0.01, 0.0001, 0.001].to_vec()),
+            ],))
+        }
+        ISteward::getTraderOrderCall::SELECTOR => {
+            let call = ISteward::getTraderOrderCall::abi_decode(input, true).unwrap();
+            assert_eq!((call.index_id, call.trader), (7, TRADER));
+            let order = Order {
+                bid: amount_vec![100, 50, 25],
+                ask: amount_vec![5, 10, 20],
+            };
+            ISteward::getTraderOrderCall::abi_encode_returns(&bytes(order.to_vec()))
+        }
+        ISteward::getIndexQuoteCall::SELECTOR => ISteward::getIndexQuoteCall::abi_encode_returns(
+            &bytes(amount_vec![1000, 1.5, 0.01].to_vec()),
+        ),
+        ISteward::getVendorSupplyCall::SELECTOR => {
+            ISteward::getVendorSupplyCall::abi_encode_returns(&(vec![
+                Bytes::from(amount_vec![1, 2, 3].to_vec()),
+                Bytes::from(amount_vec![0, 0, 1].to_vec()),
+            ],))
+        }
+        ISteward::fetchVectorCall::SELECTOR => {
+            // Corrupted vector, which isn't made of whole 128-bit values
+            ISteward::fetchVectorCall::abi_encode_returns(&bytes(vec![1, 2, 3]))
+        }
+        _ => Err("Function not found")?,
+    };
+    Ok(output)
+}
+
+#[tokio::test]
+async fn test_castle_queries() {
+    let mock = MockTransport::new(steward);
+    let castle = CastleClient::new(CASTLE, mock.provider());
+
+    let order = castle.get_trader_order(7, TRADER).await.unwrap();
+    assert_eq!(order.collateral_remaining(), amount!(100));
+    assert_eq!(order.itp_minted(), amount!(25));
+    assert_eq!(order.tell_available().unwrap(), amount!(10));
+
+    let quote = castle.get_index_quote(7, 1).await.unwrap();
+    assert_eq!(quote.price(), amount!(1.5));
+
+    let market_data = castle.get_market_data(1).await.unwrap();
+    assert_eq!(market_data.asset_names.as_slice(), vec![101, 102, 103]);
+    assert_eq!(market_data.prices.data[1], amount!(0.005));
+
+    let supply = castle.get_vendor_supply(1).await.unwrap();
+    assert_eq!(supply.long.data, amount_vec![1, 2, 3].data);
+    assert_eq!(supply.short.data[2], amount!(1));
+
+    assert!(matches!(
+        castle.fetch_vector(3).await,
+        Err(ClientError::Decode(_))
+    ));
+    assert!(matches!(
+        castle.get_index_weights(7).await,
+        Err(ClientError::Transport(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_castle_transactions() {
+    let mock = MockTransport::new(steward);
+    let castle = CastleClient::new(CASTLE, mock.provider());
+
+    let market_data = castle.get_market_data(1).await.unwrap();
+    let pending = castle.submit_market_data(1, &market_data).await.unwrap();
+    assert_eq!(*pending.tx_hash(), TX_HASH);
+
+    let pending = castle.update_index_quote(1, 7).await.unwrap();
+    assert_eq!(*pending.tx_hash(), TX_HASH);
+
+    let sent = mock.sent.lock().unwrap().clone();
+    assert_eq!(sent.len(), 2);
+    assert_eq!(sent[0].0, CASTLE);
+
+    let call = IBanker::submitMarketDataCall::abi_decode(&sent[0].1, true).unwrap();
+    let submitted = MarketData::try_from_returns(
+        &call.asset_names,
+        &[call.asset_liquidity, call.asset_prices, call.asset_slopes],
+    )
+    .unwrap();
+    assert_eq!(call.vendor_id, 1);
+    assert_eq!(submitted.slopes.data, market_data.slopes.data);
+
+    let call = IBanker::updateIndexQuoteCall::abi_decode(&sent[1].1, true).unwrap();
+    assert_eq!((call.vendor_id, call.index_id), (1, 7));
+
+    // Misaligned market data is rejected before anything is sent
+    let mut misaligned = market_data;
+    misaligned.liquidity.data.pop();
+    assert!(matches!(
+        castle.submit_market_data(1, &misaligned).await,
+        Err(ClientError::Decode(_))
+    ));
+    assert_eq!(mock.sent.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_vault() {
+    let mock = MockTransport::new(|input| {
+        let selector: [u8; 4] = input[..4].try_into().unwrap();
+        let raw = |value: f64| (value * 1e18) as u128;
+        let output = match selector {
+            IVault::totalSupplyCall::SELECTOR => {
+                IVault::totalSupplyCall::abi_encode_returns(&(U256::from(raw(12.5)),))
+            }
+            IVaultNative::getQuoteCall::SELECTOR => {
+                IVaultNative::getQuoteCall::abi_encode_returns(&(raw(1000.0), raw(1.5), raw(0.01)))
+            }
+            IVaultNativeClaims::getPendingOrderCall::SELECTOR => {
+                let call =
+                    IVaultNativeClaims::getPendingOrderCall::abi_decode(input, true).unwrap();
+                assert_eq!((call.keeper, call.trader), (KEEPER, TRADER));
+                IVaultNativeClaims::getPendingOrderCall::abi_encode_returns(&(raw(100.0), 0))
+            }
+            _ => Err("Function not found")?,
+        };
+        Ok(output)
+    });
+    let vault = VaultClient::new(VAULT, mock.provider());
+
+    assert_eq!(vault.total_supply().await.unwrap(), amount!(12.5));
+
+    let quote = vault.get_quote().await.unwrap();
+    assert_eq!(quote.capacity(), amount!(1000));
+    assert_eq!(quote.slope(), amount!(0.01));
+
+    let (bid, ask) = vault.get_pending_order(KEEPER, TRADER).await.unwrap();
+    assert_eq!((bid, ask), (amount!(100), amount!(0)));
+
+    let pending = vault
+        .place_buy_order(amount!(250.5), true, KEEPER, TRADER)
+        .await
+        .unwrap();
+    assert_eq!(*pending.tx_hash(), TX_HASH);
+
+    let sent = mock.sent.lock().unwrap().clone();
+    assert_eq!(sent[0].0, VAULT);
+    let call = IVaultNativeOrders::placeBuyOrderCall::abi_decode(&sent[0].1, true).unwrap();
+    assert_eq!(call.collateral_amount, amount!(250.5).to_u128_raw());
+    assert!(call.instant_fill);
+}
@@ -0,0 +1,307 @@
+use alloy_primitives::Address;
+use alloy_provider::{network::Ethereum, PendingTransactionBuilder, Provider};
+use alloy_sol_types::SolCall;
+use common::{amount::Amount, vector::Vector};
+use common_contracts::{
+    contracts::formulas::Quote,
+    interfaces::{
+        vault::IVault, vault_native::IVaultNative, vault_native_claims::IVaultNativeClaims,
+        vault_native_orders::IVaultNativeOrders,
+    },
+};
+
+use crate::{
+    contract::{amount_from_u256, Contract},
+    ClientError,
+};
+
+type PendingTransaction = PendingTransactionBuilder<Ethereum>;
+
+/// Client of Vault, which routes calls to Vault Native and its Orders and
+/// Claims facets
+///
+/// Amounts of ITP and collateral are passed and returned as [`Amount`].
+pub struct VaultClient<P> {
+    contract: Contract<P>,
+}
+
+impl<P: Provider> VaultClient<P> {
+    pub fn new(address: Address, provider: P) -> Self {
+        Self {
+            contract: Contract { address, provider },
+        }
+    }
+
+    pub fn address(&self) -> Address {
+        self.contract.address
+    }
+
+    pub fn provider(&self) -> &P {
+        &self.contract.provider
+    }
+
+    /// Execute any call of Vault interfaces without sending transaction
+    pub async fn call<C: SolCall>(&self, call: C) -> Result<C::Return, ClientError> {
+        self.contract.call(call).await
+    }
+
+    /// Send transaction executing any call of Vault interfaces
+    pub async fn send<C: SolCall>(&self, call: C) -> Result<PendingTransaction, ClientError> {
+        self.contract.send(call).await
+    }
+
+    //
+    // Vault
+    //
+
+    pub async fn index_id(&self) -> Result<u128, ClientError> {
+        Ok(self.call(IVault::indexIdCall {}).await?._0)
+    }
+
+    pub async fn castle(&self) -> Result<Address, ClientError> {
+        Ok(self.call(IVault::castleCall {}).await?._0)
+    }
+
+    pub async fn name(&self) -> Result<String, ClientError> {
+        Ok(self.call(IVault::nameCall {}).await?._0)
+    }
+
+    pub async fn symbol(&self) -> Result<String, ClientError> {
+        Ok(self.call(IVault::symbolCall {}).await?._0)
+    }
+
+    pub async fn total_supply(&self) -> Result<Amount, ClientError> {
+        amount_from_u256(self.call(IVault::totalSupplyCall {}).await?._0)
+    }
+
+    pub async fn balance_of(&self, account: Address) -> Result<Amount, ClientError> {
+        amount_from_u256(self.call(IVault::balanceOfCall { account }).await?._0)
+    }
+
+    //
+    // Vault Native
+    //
+
+    pub async fn vendor_id(&self) -> Result<u128, ClientError> {
+        Ok(self.call(IVaultNative::vendorIdCall {}).await?._0)
+    }
+
+    pub async fn collateral_asset(&self) -> Result<Address, ClientError> {
+        Ok(self.call(IVaultNative::collateralAssetCall {}).await?._0)
+    }
+
+    pub async fn custody_address(&self) -> Result<Address, ClientError> {
+        Ok(self.call(IVaultNative::custodyAddressCall {}).await?._0)
+    }
+
+    pub async fn is_operator(
+        &self,
+        owner: Address,
+        operator: Address,
+    ) -> Result<bool, ClientError> {
+        let ret = self
+            .call(IVaultNative::isOperatorCall { owner, operator })
+            .await?;
+        Ok(ret._0)
+    }
+
+    pub async fn set_operator(
+        &self,
+        operator: Address,
+        approved: bool,
+    ) -> Result<PendingTransaction, ClientError> {
+        self.send(IVaultNative::setOperatorCall { operator, approved })
+            .await
+    }
+
+    pub async fn assets_value(&self, account: Address) -> Result<Amount, ClientError> {
+        let ret = self.call(IVaultNative::assetsValueCall { account }).await?;
+        Ok(Amount::from_u128_raw(ret._0))
+    }
+
+    pub async fn total_assets_value(&self) -> Result<Amount, ClientError> {
+        let ret = self.call(IVaultNative::totalAssetsValueCall {}).await?;
+        Ok(Amount::from_u128_raw(ret._0))
+    }
+
+    pub async fn convert_assets_value(&self, shares: Amount) -> Result<Amount, ClientError> {
+        let shares = shares.to_u128_raw();
+        let ret = self
+            .call(IVaultNative::convertAssetsValueCall { shares })
+            .await?;
+        Ok(Amount::from_u128_raw(ret._0))
+    }
+
+    pub async fn convert_itp_amount(&self, assets: Amount) -> Result<Amount, ClientError> {
+        let assets = assets.to_u128_raw();
+        let ret = self
+            .call(IVaultNative::convertItpAmountCall { assets })
+            .await?;
+        Ok(Amount::from_u128_raw(ret._0))
+    }
+
+    pub async fn estimate_acquisition_cost(&self, shares: Amount) -> Result<Amount, ClientError> {
+        let shares = shares.to_u128_raw();
+        let ret = self
+            .call(IVaultNative::estimateAcquisitionCostCall { shares })
+            .await?;
+        Ok(Amount::from_u128_raw(ret._0))
+    }
+
+    pub async fn estimate_acquisition_itp(&self, assets: Amount) -> Result<Amount, ClientError> {
+        let assets = assets.to_u128_raw();
+        let ret = self
+            .call(IVaultNative::estimateAcquisitionItpCall { assets })
+            .await?;
+        Ok(Amount::from_u128_raw(ret._0))
+    }
+
+    pub async fn estimate_disposal_gains(&self, shares: Amount) -> Result<Amount, ClientError> {
+        let shares = shares.to_u128_raw();
+        let ret = self
+            .call(IVaultNative::estimateDisposalGainsCall { shares })
+            .await?;
+        Ok(Amount::from_u128_raw(ret._0))
+    }
+
+    pub async fn estimate_disposal_itp_cost(&self, assets: Amount) -> Result<Amount, ClientError> {
+        let assets = assets.to_u128_raw();
+        let ret = self
+            .call(IVaultNative::estimateDisposalItpCostCall { assets })
+            .await?;
+        Ok(Amount::from_u128_raw(ret._0))
+    }
+
+    pub async fn get_max_order_size(&self) -> Result<Amount, ClientError> {
+        let ret = self.call(IVaultNative::getMaxOrderSizeCall {}).await?;
+        Ok(Amount::from_u128_raw(ret._0))
+    }
+
+    pub async fn get_quote(&self) -> Result<Quote, ClientError> {
+        let ret = self.call(IVaultNative::getQuoteCall {}).await?;
+        let quote = [ret._0, ret._1, ret._2].map(Amount::from_u128_raw);
+        Ok(Quote {
+            quote: Vector {
+                data: quote.to_vec(),
+            },
+        })
+    }
+
+    //
+    // Vault Native Orders
+    //
+
+    pub async fn place_buy_order(
+        &self,
+        collateral_amount: Amount,
+        instant_fill: bool,
+        keeper: Address,
+        trader: Address,
+    ) -> Result<PendingTransaction, ClientError> {
+        self.send(IVaultNativeOrders::placeBuyOrderCall {
+            collateral_amount: collateral_amount.to_u128_raw(),
+            instant_fill,
+            keeper,
+            trader,
+        })
+        .await
+    }
+
+    pub async fn place_sell_order(
+        &self,
+        itp_amount: Amount,
+        instant_fill: bool,
+        keeper: Address,
+        trader: Address,
+    ) -> Result<PendingTransaction, ClientError> {
+        self.send(IVaultNativeOrders::placeSellOrderCall {
+            itp_amount: itp_amount.to_u128_raw(),
+            instant_fill,
+            keeper,
+            trader,
+        })
+        .await
+    }
+
+    pub async fn process_pending_buy_order(
+        &self,
+        keeper: Address,
+    ) -> Result<PendingTransaction, ClientError> {
+        self.send(IVaultNativeOrders::processPendingBuyOrderCall { keeper })
+            .await
+    }
+
+    pub async fn process_pending_sell_order(
+        &self,
+        keeper: Address,
+    ) -> Result<PendingTransaction, ClientError> {
+        self.send(IVaultNativeOrders::processPendingSellOrderCall { keeper })
+            .await
+    }
+
+    //
+    // Vault Native Claims
+    //
+
+    /// Pending bid (collateral) and ask (ITP) of trader, i.e. keeper's liability
+    pub async fn get_pending_order(
+        &self,
+        keeper: Address,
+        trader: Address,
+    ) -> Result<(Amount, Amount), ClientError> {
+        let ret = self
+            .call(IVaultNativeClaims::getPendingOrderCall { keeper, trader })
+            .await?;
+        Ok((Amount::from_u128_raw(ret._0), Amount::from_u128_raw(ret._1)))
+    }
+
+    /// Claimable ITP and its cost
+    pub async fn get_claimable_acquisition(
+        &self,
+        keeper: Address,
+    ) -> Result<(Amount, Amount), ClientError> {
+        let ret = self
+            .call(IVaultNativeClaims::getClaimableAcquisitionCall { keeper })
+            .await?;
+        Ok((Amount::from_u128_raw(ret._0), Amount::from_u128_raw(ret._1)))
+    }
+
+    /// Claimable gains and amount of ITP burned
+    pub async fn get_claimable_disposal(
+        &self,
+        keeper: Address,
+    ) -> Result<(Amount, Amount), ClientError> {
+        let ret = self
+            .call(IVaultNativeClaims::getClaimableDisposalCall { keeper })
+            .await?;
+        Ok((Amount::from_u128_raw(ret._0), Amount::from_u128_raw(ret._1)))
+    }
+
+    pub async fn claim_acquisition(
+        &self,
+        collateral_amount: Amount,
+        keeper: Address,
+        trader: Address,
+    ) -> Result<PendingTransaction, ClientError> {
+        self.send(IVaultNativeClaims::claimAcquisitionCall {
+            collateral_amount: collateral_amount.to_u128_raw(),
+            keeper,
+            trader,
+        })
+        .await
+    }
+
+    pub async fn claim_disposal(
+        &self,
+        itp_amount: Amount,
+        keeper: Address,
+        trader: Address,
+    ) -> Result<PendingTransaction, ClientError> {
+        self.send(IVaultNativeClaims::claimDisposalCall {
+            itp_amount: itp_amount.to_u128_raw(),
+            keeper,
+            trader,
+        })
+        .await
+    }
+}
@@ -0,0 +1,43 @@
+use common::{labels::Labels, vector::Vector};
+use common_contracts::interfaces::alchemist::IAlchemist;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    codec::{check_len, decode_labels, decode_vector, encode_labels, encode_vector},
+    SdkError,
+};
+
+/// Index asset weights
+#[derive(Serialize, Deserialize)]
+pub struct AssetWeights {
+    pub asset_names: Labels,
+    pub weights: Vector,
+}
+
+impl AssetWeights {
+    /// Build `IAlchemist::submitAssetWeights()` call
+    pub fn to_call(&self, index_id: u128) -> Result<IAlchemist::submitAssetWeightsCall, SdkError> {
+        let num_assets = self.asset_names.len();
+        check_len("asset weights", num_assets, self.weights.data.len())?;
+
+        Ok(IAlchemist::submitAssetWeightsCall {
+            index_id,
+            asset_names: encode_labels(&self.asset_names),
+            asset_weights: encode_vector(&self.weights),
+        })
+    }
+
+    /// Decode results of `ISteward::getIndexAssets()` and `ISteward::getIndexWeights()`
+    pub fn try_from_returns(asset_names: &[u8], weights: &[u8]) -> Result<Self, SdkError> {
+        let this = Self {
+            asset_names: decode_labels(asset_names)?,
+            weights: decode_vector(weights)?,
+        };
+        check_len(
+            "asset weights",
+            this.asset_names.len(),
+            this.weights.data.len(),
+        )?;
+        Ok(this)
+    }
+}
@@ -0,0 +1,197 @@
+use alloy_primitives::Bytes;
+use common::{labels::Labels, vector::Vector};
+use common_contracts::interfaces::banker::IBanker;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    codec::{
+        check_len, decode_labels, decode_vector, encode_labels, encode_vector, expect_payloads,
+    },
+    SdkError,
+};
+
+/// Build `IBanker::submitAssets()` call
+pub fn submit_assets(vendor_id: u128, asset_names: &Labels) -> IBanker::submitAssetsCall {
+    IBanker::submitAssetsCall {
+        vendor_id,
+        market_asset_names: encode_labels(asset_names),
+    }
+}
+
+/// Margin per asset, as submitted by vendor
+#[derive(Serialize, Deserialize)]
+pub struct AssetMargin {
+    pub asset_names: Labels,
+    pub margin: Vector,
+}
+
+impl AssetMargin {
+    /// Build `IBanker::submitMargin()` call
+    pub fn to_call(&self, vendor_id: u128) -> Result<IBanker::submitMarginCall, SdkError> {
+        let num_assets = self.asset_names.len();
+        check_len("asset margin", num_assets, self.margin.data.len())?;
+
+        Ok(IBanker::submitMarginCall {
+            vendor_id,
+            asset_names: encode_labels(&self.asset_names),
+            asset_margin: encode_vector(&self.margin),
+        })
+    }
+}
+
+/// Asset quantities held by vendor, long and short
+#[derive(Serialize, Deserialize)]
+pub struct AssetSupply {
+    pub asset_names: Labels,
+    pub long: Vector,
+    pub short: Vector,
+}
+
+impl AssetSupply {
+    /// Build `IBanker::submitSupply()` call
+    pub fn to_call(&self, vendor_id: u128) -> Result<IBanker::submitSupplyCall, SdkError> {
+        let num_assets = self.asset_names.len();
+        check_len("asset quantities (long)", num_assets, self.long.data.len())?;
+        check_len(
+            "asset quantities (short)",
+            num_assets,
+            self.short.data.len(),
+        )?;
+
+        Ok(IBanker::submitSupplyCall {
+            vendor_id,
+            asset_names: encode_labels(&self.asset_names),
+            asset_quantities_short: encode_vector(&self.short),
+            asset_quantities_long: encode_vector(&self.long),
+        })
+    }
+}
+
+/// Liquidity, prices and slopes per asset, as submitted by vendor
+#[derive(Serialize, Deserialize)]
+pub struct MarketData {
+    pub asset_names: Labels,
+    pub liquidity: Vector,
+    pub prices: Vector,
+    pub slopes: Vector,
+}
+
+impl MarketData {
+    /// Build `IBanker::submitMarketData()` call
+    ///
+    /// Asset names should be sorted, so that Banker only updates the range
+    /// of market vectors spanning them. Unsorted names are accepted, but
+    /// whole market vectors are loaded and stored then, which costs more gas.
+    pub fn to_call(&self, vendor_id: u128) -> Result<IBanker::submitMarketDataCall, SdkError> {
+        let num_assets = self.asset_names.len();
+        check_len("asset liquidity", num_assets, self.liquidity.data.len())?;
+        check_len("asset prices", num_assets, self.prices.data.len())?;
+        check_len("asset slopes", num_assets, self.slopes.data.len())?;
+
+        Ok(IBanker::submitMarketDataCall {
+            vendor_id,
+            asset_names: encode_labels(&self.asset_names),
+            asset_liquidity: encode_vector(&self.liquidity),
+            asset_prices: encode_vector(&self.prices),
+            asset_slopes: encode_vector(&self.slopes),
+        })
+    }
+
+    /// Decode result of `ISteward::getMarketData()`
+    ///
+    /// Steward doesn't return asset names along with market data, so these
+    /// should be obtained from `ISteward::getVendorAssets()`.
+    pub fn try_from_returns(asset_names: &[u8], data: &[Bytes]) -> Result<Self, SdkError> {
+        let [liquidity, prices, slopes] = expect_payloads(data)?;
+        let this = Self {
+            asset_names: decode_labels(asset_names)?,
+            liquidity: decode_vector(liquidity)?,
+            prices: decode_vector(prices)?,
+            slopes: decode_vector(slopes)?,
+        };
+        let num_assets = this.asset_names.len();
+        check_len("asset liquidity", num_assets, this.liquidity.data.len())?;
+        check_len("asset prices", num_assets, this.prices.data.len())?;
+        check_len("asset slopes", num_assets, this.slopes.data.len())?;
+        Ok(this)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloy_sol_types::SolCall;
+    use amount_macros::amount;
+    use labels_macros::label_vec;
+    use vector_macros::amount_vec;
+
+    use super::*;
+
+    fn market_data() -> MarketData {
+        MarketData {
+            asset_names: label_vec![101, 102, 103],
+            liquidity: amount_vec![1000, 2000, 500],
+            prices: amount_vec![1.0, 0.005, 0.1],
+            slopes: amount_vec![0.01, 0.0001, 0.001],
+        }
+    }
+
+    #[test]
+    fn test_submit_market_data() {
+        let call = market_data().to_call(7).unwrap();
+        let encoded = call.abi_encode();
+        let decoded = IBanker::submitMarketDataCall::abi_decode(&encoded, true).unwrap();
+        assert_eq!(decoded.vendor_id, 7);
+
+        let returns = [
+            decoded.asset_liquidity,
+            decoded.asset_prices,
+            decoded.asset_slopes,
+        ];
+        let result = MarketData::try_from_returns(&decoded.asset_names, &returns).unwrap();
+        assert_eq!(result.asset_names.as_slice(), vec![101, 102, 103]);
+        assert_eq!(result.prices.data[1], amount!(0.005));
+        assert_eq!(result.slopes.data, market_data().slopes.data);
+
+        assert!(MarketData::try_from_returns(&decoded.asset_names, &returns[..2]).is_err());
+
+        let mut unaligned = market_data();
+        unaligned.prices.data.pop();
+        assert_eq!(
+            unaligned.to_call(7).err(),
+            Some(SdkError::NotAligned {
+                what: "asset prices",
+                expected: 3,
+                actual: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_market_data_json() {
+        let json = serde_json::to_string(&market_data()).unwrap();
+        assert_eq!(
+            json,
+            concat!(
+                r#"{"asset_names":[101,102,103],"#,
+                r#""liquidity":["1000.0","2000.0","500.0"],"#,
+                r#""prices":["1.0","0.005","0.1"],"#,
+                r#""slopes":["0.01","0.0001","0.001"]}"#
+            )
+        );
+
+        let result: MarketData = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            result.to_call(1).unwrap().abi_encode(),
+            market_data().to_call(1).unwrap().abi_encode()
+        );
+
+        let supply: AssetSupply =
+            serde_json::from_str(r#"{"asset_names":[101],"long":["1.5"],"short":[2]}"#).unwrap();
+        assert_eq!(supply.short.data, vec![amount!(2)]);
+        assert!(supply.to_call(1).is_ok());
+
+        assert!(
+            serde_json::from_str::<AssetMargin>(r#"{"asset_names":[101],"margin":[0.5]}"#).is_err()
+        );
+    }
+}
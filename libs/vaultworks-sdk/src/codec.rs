@@ -0,0 +1,162 @@
+use alloy_primitives::Bytes;
+use common::{labels::Labels, vector::Vector};
+use common_contracts::contracts::formulas::{
+    Order, Quote, Report, ORDER_LAST_OFFSET, QUOTE_LAST_OFFSET, REPORT_LAST_OFFSET,
+};
+
+use crate::SdkError;
+
+pub fn encode_vector(vector: &Vector) -> Bytes {
+    vector.to_vec().into()
+}
+
+pub fn encode_labels(labels: &Labels) -> Bytes {
+    labels.to_vec().into()
+}
+
+/// Decode packed vector, e.g. result of `ISteward::fetchVector()`
+pub fn decode_vector(data: &[u8]) -> Result<Vector, SdkError> {
+    check_aligned("vector", data)?;
+    Ok(Vector::from_vec(data))
+}
+
+/// Decode packed labels, e.g. result of `ISteward::getIndexAssets()`
+pub fn decode_labels(data: &[u8]) -> Result<Labels, SdkError> {
+    check_aligned("labels", data)?;
+    Ok(Labels::from_vec(data))
+}
+
+/// Decode both sides of an order, e.g. result of `ISteward::getTraderOrder()`
+pub fn decode_order(data: &[u8]) -> Result<Order, SdkError> {
+    let vector = decode_vector_len("order", data, 2 * ORDER_LAST_OFFSET)?;
+    let (bid, ask) = vector.data.split_at(ORDER_LAST_OFFSET);
+    Ok(Order {
+        bid: Vector { data: bid.to_vec() },
+        ask: Vector { data: ask.to_vec() },
+    })
+}
+
+/// Decode index quote, i.e. result of `ISteward::getIndexQuote()`
+pub fn decode_quote(data: &[u8]) -> Result<Quote, SdkError> {
+    let quote = decode_vector_len("quote", data, QUOTE_LAST_OFFSET)?;
+    Ok(Quote { quote })
+}
+
+/// Decode execution report, e.g. executed index quantities from `IFactor`
+pub fn decode_report(data: &[u8]) -> Result<Report, SdkError> {
+    let report = decode_vector_len("report", data, REPORT_LAST_OFFSET)?;
+    Ok(Report { report })
+}
+
+pub(crate) fn decode_vector_len(
+    what: &'static str,
+    data: &[u8],
+    expected: usize,
+) -> Result<Vector, SdkError> {
+    check_aligned(what, data)?;
+    let vector = Vector::from_vec(data);
+    check_len(what, expected, vector.data.len())?;
+    Ok(vector)
+}
+
+/// Take exactly `N` payloads from `bytes[]` returned by contract
+pub(crate) fn expect_payloads<const N: usize>(data: &[Bytes]) -> Result<&[Bytes; N], SdkError> {
+    data.try_into().map_err(|_| SdkError::PayloadCount {
+        expected: N,
+        actual: data.len(),
+    })
+}
+
+pub(crate) fn check_len(
+    what: &'static str,
+    expected: usize,
+    actual: usize,
+) -> Result<(), SdkError> {
+    if expected != actual {
+        return Err(SdkError::NotAligned {
+            what,
+            expected,
+            actual,
+        });
+    }
+    Ok(())
+}
+
+fn check_aligned(what: &'static str, data: &[u8]) -> Result<(), SdkError> {
+    if !Vector::is_valid_vec(&data) {
+        return Err(SdkError::Misaligned {
+            what,
+            len: data.len(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use amount_macros::amount;
+    use labels_macros::label_vec;
+    use vector_macros::amount_vec;
+
+    use super::*;
+
+    #[test]
+    fn test_vector_bytes() {
+        let vector = amount_vec![1.0, 0.005, 0.1];
+        let data = encode_vector(&vector);
+
+        // Same layout as produced by scripts/vector_to_bytes.py '[1.0, 0.005, 0.1]'
+        assert_eq!(
+            alloy_primitives::hex::encode(&data),
+            concat!(
+                "000064a7b3b6e00d0000000000000000",
+                "0080e03779c311000000000000000000",
+                "00008a5d784563010000000000000000",
+            )
+        );
+        assert_eq!(decode_vector(&data).unwrap().data, vector.data);
+
+        let labels = label_vec![1, 2, 0xffff_ffff_ffff_ffff_ffff];
+        let data = encode_labels(&labels);
+        assert_eq!(data.len(), 48);
+        assert_eq!(decode_labels(&data).unwrap().as_slice(), labels.as_slice());
+
+        assert_eq!(
+            decode_vector(&data[1..]).err(),
+            Some(SdkError::Misaligned {
+                what: "vector",
+                len: 47
+            })
+        );
+        assert!(decode_labels(&data[..17]).is_err());
+    }
+
+    #[test]
+    fn test_order_bytes() {
+        let order = Order {
+            bid: amount_vec![100, 50, 25],
+            ask: amount_vec![5, 10, 20],
+        };
+        let order = decode_order(&order.to_vec()).unwrap();
+        assert_eq!(order.collateral_remaining(), amount!(100));
+        assert_eq!(order.itp_minted(), amount!(25));
+        assert_eq!(order.itp_locked(), amount!(5));
+        assert_eq!(order.collateral_withdrawn(), amount!(20));
+
+        let quote = decode_quote(&amount_vec![1000, 1.5, 0.01].to_vec()).unwrap();
+        assert_eq!(quote.price(), amount!(1.5));
+
+        let report = decode_report(&amount_vec![3, 2].to_vec()).unwrap();
+        assert_eq!(report.received(), amount!(2));
+
+        assert_eq!(
+            decode_quote(&amount_vec![1, 2].to_vec()).err(),
+            Some(SdkError::NotAligned {
+                what: "quote",
+                expected: 3,
+                actual: 2
+            })
+        );
+        assert!(decode_order(&amount_vec![1, 2, 3].to_vec()).is_err());
+    }
+}
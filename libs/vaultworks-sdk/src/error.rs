@@ -0,0 +1,39 @@
+use core::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SdkError {
+    /// Byte payload is not made of whole 128-bit values
+    Misaligned { what: &'static str, len: usize },
+    /// Vector or labels don't have expected number of elements
+    NotAligned {
+        what: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    /// Contract returned unexpected number of byte payloads
+    PayloadCount { expected: usize, actual: usize },
+}
+
+impl fmt::Display for SdkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Misaligned { what, len } => {
+                write!(f, "Invalid {}: {} bytes is not multiple of 16", what, len)
+            }
+            Self::NotAligned {
+                what,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Invalid {}: expected {} elements, got {}",
+                what, expected, actual
+            ),
+            Self::PayloadCount { expected, actual } => {
+                write!(f, "Expected {} payloads, got {}", expected, actual)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SdkError {}
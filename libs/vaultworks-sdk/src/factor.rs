@@ -0,0 +1,77 @@
+use alloy_primitives::Bytes;
+use common::vector::Vector;
+use common_contracts::contracts::formulas::{Report, ORDER_LAST_OFFSET};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    codec::{decode_report, decode_vector, decode_vector_len, expect_payloads},
+    SdkError,
+};
+
+/// Outcome of index order execution
+///
+/// Returned by `IFactor::processPendingBuyOrder()`,
+/// `IFactor::processPendingSellOrder()`, `IFactor::executeBuyOrder()` and
+/// `IFactor::executeSellOrder()`.
+#[derive(Serialize, Deserialize)]
+pub struct Execution {
+    /// Updated side of trader's order, i.e. bid for buy and ask for sell
+    pub order: Vector,
+    /// For buy: collateral spent and index quantity minted, and for sell:
+    /// index quantity burned and collateral withdrawn
+    pub report: Report,
+    /// Executed quantity of each index asset
+    pub asset_quantities: Vector,
+}
+
+impl Execution {
+    pub fn try_from_returns(data: &[Bytes]) -> Result<Self, SdkError> {
+        let [order, report, asset_quantities] = expect_payloads(data)?;
+        Ok(Self {
+            order: decode_vector_len("order", order, ORDER_LAST_OFFSET)?,
+            report: decode_report(report)?,
+            asset_quantities: decode_vector(asset_quantities)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amount_macros::amount;
+    use vector_macros::amount_vec;
+
+    use super::*;
+
+    #[test]
+    fn test_execution() {
+        let returns = [
+            amount_vec![40, 60, 30].to_vec().into(),
+            amount_vec![60, 30].to_vec().into(),
+            amount_vec![0.5, 1.25].to_vec().into(),
+        ];
+        let execution = Execution::try_from_returns(&returns).unwrap();
+        assert_eq!(execution.report.delivered(), amount!(60));
+        assert_eq!(execution.report.received(), amount!(30));
+        assert_eq!(execution.asset_quantities.data[1], amount!(1.25));
+
+        let json = serde_json::to_string(&execution).unwrap();
+        assert_eq!(
+            json,
+            concat!(
+                r#"{"order":["40.0","60.0","30.0"],"#,
+                r#""report":{"delivered":"60.0","received":"30.0"},"#,
+                r#""asset_quantities":["0.5","1.25"]}"#
+            )
+        );
+        let execution: Execution = serde_json::from_str(&json).unwrap();
+        assert_eq!(execution.order.data, amount_vec![40, 60, 30].data);
+
+        assert!(Execution::try_from_returns(&returns[1..]).is_err());
+        assert!(Execution::try_from_returns(&[
+            returns[1].clone(),
+            returns[1].clone(),
+            returns[2].clone()
+        ])
+        .is_err());
+    }
+}
@@ -0,0 +1,19 @@
+//! Off-chain encoding and decoding of protocol vectors and orders
+//!
+//! Contracts exchange vectors as `Bytes` of packed 128-bit little-endian
+//! fixed-point values (18 decimals), and labels as packed 128-bit
+//! little-endian integers. This crate builds typed contract calls from
+//! [`Vector`](common::vector::Vector) and [`Labels`](common::labels::Labels),
+//! and decodes byte payloads returned by contracts, so that services don't
+//! need to know binary layout.
+//!
+//! Modules are named after contracts whose calls they build or decode.
+//!
+pub mod alchemist;
+pub mod banker;
+pub mod codec;
+pub mod error;
+pub mod factor;
+pub mod steward;
+
+pub use error::SdkError;
@@ -0,0 +1,59 @@
+use alloy_primitives::Bytes;
+use common::{labels::Labels, vector::Vector};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    codec::{check_len, decode_labels, decode_vector, expect_payloads},
+    SdkError,
+};
+
+/// Pair of long and short vectors
+///
+/// Returned by `ISteward::getVendorSupply()`, `ISteward::getVendorDemand()`
+/// and `ISteward::getVendorDelta()`, and also by
+/// `IAlchemist::processPendingRebalance()` as executed asset quantities.
+#[derive(Serialize, Deserialize)]
+pub struct LongShort {
+    pub long: Vector,
+    pub short: Vector,
+}
+
+impl LongShort {
+    pub fn try_from_returns(data: &[Bytes]) -> Result<Self, SdkError> {
+        let [long, short] = expect_payloads(data)?;
+        let this = Self {
+            long: decode_vector(long)?,
+            short: decode_vector(short)?,
+        };
+        check_len("short vector", this.long.data.len(), this.short.data.len())?;
+        Ok(this)
+    }
+}
+
+/// Target weights of pending index rebalance
+#[derive(Serialize, Deserialize)]
+pub struct RebalanceWeights {
+    pub asset_names: Labels,
+    pub long: Vector,
+    pub short: Vector,
+}
+
+impl RebalanceWeights {
+    /// Decode result of `ISteward::getIndexRebalanceWeights()`
+    pub fn try_from_returns(data: &[Bytes]) -> Result<Self, SdkError> {
+        let [asset_names, long, short] = expect_payloads(data)?;
+        let this = Self {
+            asset_names: decode_labels(asset_names)?,
+            long: decode_vector(long)?,
+            short: decode_vector(short)?,
+        };
+        let num_assets = this.asset_names.len();
+        check_len("rebalance weights (long)", num_assets, this.long.data.len())?;
+        check_len(
+            "rebalance weights (short)",
+            num_assets,
+            this.short.data.len(),
+        )?;
+        Ok(this)
+    }
+}
@@ -3,10 +3,14 @@ use proc_macro2::{Span, TokenStream as TokenStream2, TokenTree};
 use quote::quote;
 use std::collections::HashMap;
 use syn::{
+    buffer::Cursor,
     parse::{Parse, ParseStream},
-    Expr, Ident, Lit, Token,
+    Attribute, Expr, Ident, Lit, LitInt, Token,
 };
 
+mod stack;
+mod vil;
+
 // --- 1. Argument Type Enum ---
 
 #[derive(Debug, PartialEq, Eq, Hash)]
@@ -15,8 +19,9 @@ enum ArgType {
     Amount,     // <immediate (scalar)> for IMMS/VPUSH
     StackPos,   // <pos>, <pos_A>, <pos_B>
     StorageId,  // <label_id>, <vector_id>, <scalar_id>, <prg_id>
-    Label,      // <immediate (label)>
+    Label,      // <immediate (label)>, <assert_id>, <start>, <len>
     Size,       // <count>, <N>, <M>, <R>
+    Argument,   // <arg>
 }
 
 // --- 2. Static Argument Type Map (Grouped by vis.rs Layout) ---
@@ -27,17 +32,20 @@ lazy_static::lazy_static! {
         use ArgType::*;
         let mut m = HashMap::new();
 
-        // 1. Data Loading & Stack Access (10-14)
+        // 1. Data Loading & Stack Access (10-17)
         m.insert("LDL", vec![StorageId]);
         m.insert("LDV", vec![StorageId]);
         m.insert("LDD", vec![StackPos]);
         m.insert("LDR", vec![RegisterId]);
         m.insert("LDM", vec![RegisterId]);
+        m.insert("LDLR", vec![StorageId, Label, Label]); // <label_id> <start> <len>
+        m.insert("LDVR", vec![StorageId, Label, Label]); // <vector_id> <start> <len>
 
-        // 2. Data Storage & Register Access (20-23)
+        // 2. Data Storage & Register Access (20-24)
         m.insert("STL", vec![StorageId]);
         m.insert("STV", vec![StorageId]);
         m.insert("STR", vec![RegisterId]);
+        m.insert("STVR", vec![StorageId, Label]); // <vector_id> <start>
 
         // 3. Data Structure Manipulation (30-35)
         m.insert("PKV", vec![Size]);       // <count>
@@ -55,22 +63,33 @@ lazy_static::lazy_static! {
         m.insert("JADD", vec![StackPos, StackPos, StackPos]);
         m.insert("JFLT", vec![StackPos, StackPos]);
 
-        // 5. Arithmetic & Core Math (50-55)
+        // 5. Arithmetic & Core Math (50-58)
         m.insert("ADD", vec![StackPos]);
         m.insert("SUB", vec![StackPos]);
         m.insert("SSB", vec![StackPos]);
         m.insert("MUL", vec![StackPos]);
         m.insert("DIV", vec![StackPos]);
         m.insert("SQRT", vec![]);
+        m.insert("EXP", vec![]);
+        m.insert("LN", vec![]);
+        m.insert("POW", vec![StackPos]);
 
-        // 6. Logic & Comparison (60-61)
+        // 6. Logic & Comparison (60-66)
         m.insert("MIN", vec![StackPos]);
         m.insert("MAX", vec![StackPos]);
+        m.insert("EQ", vec![StackPos]);
+        m.insert("GT", vec![StackPos]);
+        m.insert("LT", vec![StackPos]);
+        m.insert("CLAMP", vec![StackPos, StackPos]); // <lo_pos> <hi_pos>
+        m.insert("SEL", vec![StackPos, StackPos]);   // <mask_pos> <other_pos>
 
-        // 7. Vector Aggregation (70-72)
+        // 7. Vector Aggregation (70-75)
         m.insert("VSUM", vec![]);
         m.insert("VMIN", vec![]);
         m.insert("VMAX", vec![]);
+        m.insert("VDOT", vec![StackPos]);
+        m.insert("VCUMSUM", vec![]);
+        m.insert("VNORM", vec![]);
 
         // 8. Immediate Values & Vector Creation (80-83)
         m.insert("IMMS", vec![Amount]);    // <immediate (scalar)>
@@ -83,6 +102,37 @@ lazy_static::lazy_static! {
         m.insert("SWAP", vec![StackPos]);  // <pos>
         m.insert("B", vec![StorageId, Size, Size, Size]); // <prg_id> <N> <M> <R>
         m.insert("FOLD", vec![StorageId, Size, Size, Size]); // <prg_id> <N> <M> <R>
+        m.insert("BZ", vec![StorageId, Size, Size, Size]); // <prg_id> <N> <M> <R>
+
+        // 10. Assertions (100-101)
+        m.insert("ASSERT_ZERO", vec![Label]);          // <assert_id>
+        m.insert("ASSERT_LE", vec![StackPos, Label]);  // <pos> <assert_id>
+
+        // 11. Signed Values (110-114)
+        m.insert("NEG", vec![]);
+        m.insert("ABS", vec![]);
+        m.insert("POS", vec![]);
+        m.insert("NEGPART", vec![]);
+        m.insert("NET", vec![StackPos]);   // <pos> (short)
+
+        // 12. Sparse Vectors (120-124)
+        m.insert("SPK", vec![StackPos]);   // <pos> (labels)
+        m.insert("SUNPK", vec![]);
+        m.insert("SGET", vec![StackPos, StackPos]); // <pos_vector> <pos_labels>
+        m.insert("SADD", vec![StackPos, StackPos]); // <pos_sparse> <pos_labels>
+        m.insert("SUPD", vec![StackPos, StackPos]); // <pos_sparse> <pos_labels>
+
+        // 13. Program Arguments (130-135)
+        m.insert("ARGS", vec![Size]);      // <count>
+        m.insert("LDA", vec![Argument]);
+        m.insert("LDLA", vec![Argument]);
+        m.insert("LDVA", vec![Argument]);
+        m.insert("STLA", vec![Argument]);
+        m.insert("STVA", vec![Argument]);
+
+        // 14. Rounding Modifiers (140-141)
+        m.insert("RNDU", vec![]);
+        m.insert("RNDE", vec![]);
 
         m
     };
@@ -94,24 +144,108 @@ lazy_static::lazy_static! {
 /// Holds the arguments and is what the ArgType is mapped to.
 enum InstructionArg {
     Literal(Expr),
-    Register(String), // e.g., "_weights"
+    Register(Ident),  // e.g., "_weights"
     Constant(Ident),  // e.g., "POS_OFFSET"
+    Slot(u8),         // e.g., "asset_names_id" listed in ARGS [...]
+    Procedure(usize), // e.g., "solve" declared by PROC solve(3, 1, 4) { ... }
 }
 
 /// Holds the structure of a single assembly instruction.
 struct Instruction {
     mnemonic: Ident,
     args: Vec<InstructionArg>,
+    num_tokens: usize, // tokens of source text, e.g. 6 for ARGS [a, b]
+}
+
+/// Sub-routine declared by PROC name(inputs, outputs, registers) { ... }
+struct Procedure {
+    name: Ident,
+    num_inputs: usize,
+    num_outputs: usize,
+    num_registers: usize,
+    instructions: Vec<Instruction>,
+    position: usize, // number of instructions of main program preceding it
 }
 
 /// Holds the entire list of instructions from the macro invocation.
 struct InstructionList {
     instructions: Vec<Instruction>,
+    procedures: Vec<Procedure>,
+    num_inputs: usize,    // #![inputs(N)] for sub-routines
+    stack_comments: bool, // #![stack_comments] to validate comments
+    unchecked: bool,      // #![unchecked] to skip stack simulation
+}
+
+/// Instruction using argument slot instead of storage id or label
+fn slot_mnemonic(mnemonic: &str) -> Option<&'static str> {
+    match mnemonic {
+        "LDL" => Some("LDLA"),
+        "LDV" => Some("LDVA"),
+        "STL" => Some("STLA"),
+        "STV" => Some("STVA"),
+        "IMML" => Some("LDA"),
+        _ => None,
+    }
+}
+
+/// Arguments <N> <M> <R> of B, FOLD or BZ calling procedure
+///
+/// FOLD pushes item onto the stack of sub-routine, and so it is the last of
+/// the procedure inputs.
+fn call_sizes(
+    mnemonic: &Ident,
+    mnemonic_str: &str,
+    procedure: &Procedure,
+) -> syn::Result<[usize; 3]> {
+    let num_inputs = if mnemonic_str == "FOLD" {
+        procedure.num_inputs.checked_sub(1).ok_or_else(|| {
+            syn::Error::new(
+                mnemonic.span(),
+                format!(
+                    "PROC {} must take at least one input to be called by FOLD",
+                    procedure.name
+                ),
+            )
+        })?
+    } else {
+        procedure.num_inputs
+    };
+    Ok([num_inputs, procedure.num_outputs, procedure.num_registers])
+}
+
+fn is_keyword(input: ParseStream, keyword: &str) -> bool {
+    input
+        .fork()
+        .parse::<Ident>()
+        .is_ok_and(|ident| ident.to_string().to_uppercase() == keyword)
 }
 
 impl Parse for InstructionList {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let mut instructions = Vec::new();
+        let mut procedures: Vec<Procedure> = Vec::new();
+        let mut slots: Vec<String> = Vec::new();
+        let mut num_inputs = 0;
+        let mut stack_comments = false;
+        let mut unchecked = false;
+
+        // Options of stack simulation, e.g. #![inputs(3)]
+        for attr in input.call(Attribute::parse_inner)? {
+            if attr.path().is_ident("inputs") {
+                num_inputs = attr.parse_args::<LitInt>()?.base10_parse()?;
+            } else if attr.path().is_ident("stack_comments") {
+                attr.meta.require_path_only()?;
+                stack_comments = true;
+            } else if attr.path().is_ident("unchecked") {
+                attr.meta.require_path_only()?;
+                unchecked = true;
+            } else {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "Unknown option, expected inputs(N), stack_comments or unchecked",
+                ));
+            }
+        }
 
         while !input.is_empty() {
             // Consume comments
@@ -122,106 +256,480 @@ impl Parse for InstructionList {
                 break;
             }
 
+            // Sub-routines, e.g. PROC solve(3, 1, 4) { ... }
+            if is_keyword(input, "PROC") {
+                let mut procedure = parse_procedure(input, &procedures)?;
+                procedure.position = instructions.len();
+                procedures.push(procedure);
+                continue;
+            }
+
+            let begin = input.cursor();
             let mnemonic: Ident = input.parse()?;
             let mnemonic_str = mnemonic.to_string().to_uppercase();
 
-            // 1. Look up expected argument types
-            let expected_types = ARG_TYPES
-                .get(mnemonic_str.as_str())
-                .ok_or_else(|| input.error(format!("Unknown VIL mnemonic: {}", mnemonic_str)))?;
+            // Template arguments, e.g. ARGS [asset_names_id, weights_id]
+            if mnemonic_str == "ARGS" && input.peek(syn::token::Bracket) {
+                if !instructions.is_empty() {
+                    return Err(syn::Error::new(
+                        mnemonic.span(),
+                        "ARGS must be the first instruction",
+                    ));
+                }
+                let content;
+                syn::bracketed!(content in input);
+                let names = content.parse_terminated(Ident::parse, Token![,])?;
+                slots = names.iter().map(|ident| ident.to_string()).collect();
+                if slots.len() > u8::MAX as usize {
+                    return Err(syn::Error::new(mnemonic.span(), "Too many arguments"));
+                }
+                let count = syn::LitInt::new(&slots.len().to_string(), mnemonic.span());
+                instructions.push(Instruction {
+                    mnemonic,
+                    args: vec![InstructionArg::Literal(Expr::Lit(syn::ExprLit {
+                        attrs: Vec::new(),
+                        lit: Lit::Int(count),
+                    }))],
+                    num_tokens: count_tokens(begin, input.cursor()),
+                });
+                continue;
+            }
 
-            let mut args = Vec::new();
+            let mut instruction = parse_instruction(input, mnemonic, &slots, &procedures)?;
+            instruction.num_tokens = count_tokens(begin, input.cursor());
+            instructions.push(instruction);
 
-            // 2. Consume exactly the expected arguments with validation
-            for (i, expected_type) in expected_types.iter().enumerate() {
-                // Ignore commas
-                while input.peek(Token![,]) {
-                    let _: Token![,] = input.parse()?;
+            // Consume remaining inline comments
+            if input.peek(Token![/]) && input.peek2(Token![/]) {
+                while !input.is_empty() {
+                    let _: TokenTree = input.parse()?;
                 }
+            }
+        }
 
-                if input.is_empty() {
-                    return Err(input.error(format!(
-                        "Missing argument {} of {} for instruction {}",
-                        i + 1,
-                        expected_types.len(),
-                        mnemonic_str
-                    )));
-                }
+        Ok(InstructionList {
+            instructions,
+            procedures,
+            num_inputs,
+            stack_comments,
+            unchecked,
+        })
+    }
+}
 
-                let (arg, is_register) = if input.peek(Lit) {
-                    let lit: Lit = input.parse()?;
-                    (
-                        InstructionArg::Literal(Expr::Lit(syn::ExprLit {
-                            attrs: Vec::new(),
-                            lit,
-                        })),
-                        false,
-                    )
-                } else if input.peek(Ident) {
-                    let ident: Ident = input.parse()?;
-                    let ident_str = ident.to_string();
-
-                    if ident_str.starts_with('_') {
-                        (InstructionArg::Register(ident_str), true)
-                    } else {
-                        (InstructionArg::Constant(ident), false)
-                    }
-                } else {
-                    return Err(input.error(format!(
-                        "Argument {} of {} for {} must be a literal or identifier, found unexpected token.", 
-                        i + 1, expected_types.len(), mnemonic_str
-                    )));
-                };
+/// Parse PROC name(inputs, outputs, registers) { ... }
+///
+/// Procedure may call procedures declared before it, but it cannot declare
+/// procedures of its own, and it cannot use template arguments, as these are
+/// not passed on to sub-routines.
+fn parse_procedure(input: ParseStream, procedures: &[Procedure]) -> syn::Result<Procedure> {
+    let keyword: Ident = input.parse()?;
+    let name: Ident = input.parse()?;
+    if procedures.iter().any(|procedure| procedure.name == name) {
+        return Err(syn::Error::new(
+            name.span(),
+            format!("PROC {} is already declared", name),
+        ));
+    }
 
-                // 3. Type Validation Check
-                match expected_type {
-                    ArgType::RegisterId if !is_register => {
-                        return Err(input.error(format!(
-                            "Argument {} for {} must be a register (e.g., _name).",
-                            i + 1,
-                            mnemonic_str
-                        )));
-                    }
-                    ArgType::RegisterId if is_register => {} // OK
-                    _ if is_register => {
-                        // All other types (Amount, StackPos, StorageId, Label, Size) must NOT be a register
-                        return Err(input.error(format!(
-                            "Argument {} for {} cannot be a register (_name). Expected a literal or constant.", 
-                            i + 1, mnemonic_str
-                        )));
-                    }
-                    _ => {} // OK for non-register types receiving Literal/Constant
-                }
+    let signature;
+    syn::parenthesized!(signature in input);
+    let sizes = signature.parse_terminated(LitInt::parse, Token![,])?;
+    let sizes = sizes
+        .iter()
+        .map(|size| {
+            let value: usize = size.base10_parse()?;
+            if value > u8::MAX as usize {
+                return Err(syn::Error::new(size.span(), "Size must fit in one byte"));
+            }
+            Ok(value)
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    let [num_inputs, num_outputs, num_registers] = sizes[..] else {
+        return Err(syn::Error::new(
+            keyword.span(),
+            "PROC must declare (inputs, outputs, registers)",
+        ));
+    };
 
-                args.push(arg);
+    let body;
+    syn::braced!(body in input);
+    let mut instructions = Vec::new();
+    while !body.is_empty() {
+        let begin = body.cursor();
+        let mnemonic: Ident = body.parse()?;
+        match mnemonic.to_string().to_uppercase().as_str() {
+            "PROC" => {
+                return Err(syn::Error::new(
+                    mnemonic.span(),
+                    "PROC cannot be declared inside PROC",
+                ));
+            }
+            "ARGS" => {
+                return Err(syn::Error::new(
+                    mnemonic.span(),
+                    "ARGS cannot be used inside PROC, as sub-routines take no arguments",
+                ));
             }
+            _ => {}
+        }
+        let mut instruction = parse_instruction(&body, mnemonic, &[], procedures)?;
+        instruction.num_tokens = count_tokens(begin, body.cursor());
+        instructions.push(instruction);
+    }
 
-            instructions.push(Instruction { mnemonic, args });
+    Ok(Procedure {
+        name,
+        num_inputs,
+        num_outputs,
+        num_registers,
+        instructions,
+        position: 0,
+    })
+}
 
-            // Consume remaining inline comments
-            if input.peek(Token![/]) && input.peek2(Token![/]) {
-                while !input.is_empty() {
-                    let _: TokenTree = input.parse()?;
+/// Parse arguments of single instruction following its mnemonic
+fn parse_instruction(
+    input: ParseStream,
+    mnemonic: Ident,
+    slots: &[String],
+    procedures: &[Procedure],
+) -> syn::Result<Instruction> {
+    let mnemonic_str = mnemonic.to_string().to_uppercase();
+
+    // 1. Look up expected argument types
+    let expected_types = ARG_TYPES
+        .get(mnemonic_str.as_str())
+        .ok_or_else(|| input.error(format!("Unknown VIL mnemonic: {}", mnemonic_str)))?;
+
+    let mut args = Vec::new();
+    let is_call = matches!(mnemonic_str.as_str(), "B" | "FOLD" | "BZ");
+
+    // 2. Consume exactly the expected arguments with validation
+    for (i, expected_type) in expected_types.iter().enumerate() {
+        // Ignore commas
+        while input.peek(Token![,]) {
+            let _: Token![,] = input.parse()?;
+        }
+
+        // Call of procedure may omit <N> <M> <R> given by its declaration
+        if let Some(InstructionArg::Procedure(index)) = args.first() {
+            if i == 1 && !input.peek(Lit) {
+                let sizes = call_sizes(&mnemonic, &mnemonic_str, &procedures[*index])?;
+                for size in sizes {
+                    let size = syn::LitInt::new(&size.to_string(), mnemonic.span());
+                    args.push(InstructionArg::Literal(Expr::Lit(syn::ExprLit {
+                        attrs: Vec::new(),
+                        lit: Lit::Int(size),
+                    })));
                 }
+                break;
+            }
+        }
+
+        if input.is_empty() {
+            return Err(input.error(format!(
+                "Missing argument {} of {} for instruction {}",
+                i + 1,
+                expected_types.len(),
+                mnemonic_str
+            )));
+        }
+
+        let (arg, is_register) = if input.peek(Lit) {
+            let lit: Lit = input.parse()?;
+            (
+                InstructionArg::Literal(Expr::Lit(syn::ExprLit {
+                    attrs: Vec::new(),
+                    lit,
+                })),
+                false,
+            )
+        } else if input.peek(Ident) {
+            let ident: Ident = input.parse()?;
+            let ident_str = ident.to_string();
+
+            if ident_str.starts_with('_') {
+                (InstructionArg::Register(ident), true)
+            } else if let Some(slot) = slots.iter().position(|x| *x == ident_str) {
+                (InstructionArg::Slot(slot as u8), false)
+            } else if let Some(index) = procedures
+                .iter()
+                .position(|procedure| i == 0 && is_call && procedure.name == ident)
+            {
+                (InstructionArg::Procedure(index), false)
+            } else {
+                (InstructionArg::Constant(ident), false)
+            }
+        } else {
+            return Err(input.error(format!(
+                "Argument {} of {} for {} must be a literal or identifier, found unexpected token.", 
+                i + 1, expected_types.len(), mnemonic_str
+            )));
+        };
+
+        // 3. Type Validation Check
+        match expected_type {
+            ArgType::RegisterId if !is_register => {
+                return Err(input.error(format!(
+                    "Argument {} for {} must be a register (e.g., _name).",
+                    i + 1,
+                    mnemonic_str
+                )));
+            }
+            ArgType::RegisterId if is_register => {} // OK
+            _ if is_register => {
+                // All other types (Amount, StackPos, StorageId, Label, Size) must NOT be a register
+                return Err(input.error(format!(
+                    "Argument {} for {} cannot be a register (_name). Expected a literal or constant.", 
+                    i + 1, mnemonic_str
+                )));
+            }
+            _ => {} // OK for non-register types receiving Literal/Constant
+        }
+
+        args.push(arg);
+    }
+
+    // Explicit <N> <M> <R> must match declaration of called procedure
+    if let Some(InstructionArg::Procedure(index)) = args.first() {
+        let procedure = &procedures[*index];
+        let expected = call_sizes(&mnemonic, &mnemonic_str, procedure)?;
+        let matches = args[1..]
+            .iter()
+            .zip(expected)
+            .all(|(arg, size)| stack::literal_usize(arg) == Some(size));
+        if !matches {
+            return Err(syn::Error::new(
+                mnemonic.span(),
+                format!(
+                    "{} {} must be given {} {} {} as declared by PROC",
+                    mnemonic_str, procedure.name, expected[0], expected[1], expected[2]
+                ),
+            ));
+        }
+    }
+
+    // Argument slot replaces storage id or label of the instruction
+    let mut mnemonic = mnemonic;
+    if args
+        .iter()
+        .any(|arg| matches!(arg, InstructionArg::Slot(_)))
+    {
+        match slot_mnemonic(&mnemonic_str) {
+            Some(slot_mnemonic) => {
+                mnemonic = Ident::new(slot_mnemonic, mnemonic.span());
+            }
+            None => {
+                return Err(syn::Error::new(
+                    mnemonic.span(),
+                    format!(
+                        "Argument slot cannot be used with {}, only with LDL, LDV, STL, STV or IMML",
+                        mnemonic_str
+                    ),
+                ));
+            }
+        }
+    }
+
+    Ok(Instruction {
+        mnemonic,
+        args,
+        num_tokens: 0,
+    })
+}
+
+/// Number of tokens between cursors, where delimiters of group are counted
+/// as tokens of their own same as in source text
+fn count_tokens(mut begin: Cursor, end: Cursor) -> usize {
+    fn flat_len(token: &TokenTree) -> usize {
+        match token {
+            TokenTree::Group(group) => {
+                let delimiters = match group.delimiter() {
+                    proc_macro2::Delimiter::None => 0,
+                    _ => 2,
+                };
+                let inner: usize = group.stream().into_iter().map(|t| flat_len(&t)).sum();
+                delimiters + inner
             }
+            _ => 1,
         }
+    }
 
-        Ok(InstructionList { instructions })
+    let mut count = 0;
+    while begin != end {
+        let Some((token, next)) = begin.token_tree() else {
+            break;
+        };
+        count += flat_len(&token);
+        begin = next;
     }
+    count
 }
 
 #[proc_macro]
 pub fn abacus(input: TokenStream) -> TokenStream {
+    expand(input, false)
+}
+
+/// Same as `abacus!`, but produces program with compact encoding
+///
+/// Program starts with `COMPACT_HEADER`, and storage ids, labels and
+/// immediate scalars are encoded as LEB128 instead of 16 bytes each.
+#[proc_macro]
+pub fn abacus_compact(input: TokenStream) -> TokenStream {
+    expand(input, true)
+}
+
+fn expand(input: TokenStream, compact: bool) -> TokenStream {
     let instruction_list = match syn::parse::<InstructionList>(input) {
         Ok(list) => list,
         Err(e) => return e.to_compile_error().into(),
     };
 
+    // Comments are recovered from source text, as they are not passed to
+    // procedural macros
+    let source = if instruction_list.stack_comments {
+        proc_macro::Span::call_site().source_text()
+    } else {
+        None
+    };
+    let warnings = match check_stack(&instruction_list, source.as_deref()) {
+        Ok(warnings) => warnings,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    let mut warning_tokens = TokenStream2::new();
+    for (span, message) in warnings {
+        // Procedural macros cannot emit warnings on stable, and so warning is
+        // reported as use of deprecated item spanned at the offending token
+        let item = Ident::new("AbacusWarning", span);
+        warning_tokens.extend(quote! {
+            {
+                #[deprecated(note = #message)]
+                struct #item;
+                let _ = #item;
+            }
+        });
+    }
+
+    let main_tokens = expand_instructions(&instruction_list.instructions, compact);
+
+    // --- Final Output Wrapper (Result return type) ---
+    let output = if instruction_list.procedures.is_empty() {
+        quote! {
+            (|| -> Result<Vec<u8>, Vec<u8>> {
+                #warning_tokens
+                let mut bytecode: Vec<u8> = Vec::new();
+                #main_tokens;
+                Ok(bytecode)
+            })()
+        }
+    } else {
+        // Procedures are compiled in order of declaration, so that id of each
+        // procedure is known before any procedure declared later calls it
+        let procedure_tokens = instruction_list.procedures.iter().map(|procedure| {
+            let name = procedure.name.to_string();
+            let body_tokens = expand_instructions(&procedure.instructions, compact);
+            let num_inputs = procedure.num_inputs as u8;
+            let num_outputs = procedure.num_outputs as u8;
+            let num_registers = procedure.num_registers as u8;
+            quote! {
+                {
+                    let mut bytecode: Vec<u8> = Vec::new();
+                    #body_tokens;
+                    procedures.push(common::abacus::program::Procedure::new(
+                        #name,
+                        bytecode,
+                        #num_inputs,
+                        #num_outputs,
+                        #num_registers,
+                    ));
+                }
+            }
+        });
+        quote! {
+            (|| -> Result<common::abacus::program::Program, Vec<u8>> {
+                #warning_tokens
+                let mut procedures: Vec<common::abacus::program::Procedure> = Vec::new();
+                #(#procedure_tokens)*
+                let mut bytecode: Vec<u8> = Vec::new();
+                #main_tokens;
+                Ok(common::abacus::program::Program {
+                    code: bytecode,
+                    procedures,
+                })
+            })()
+        }
+    };
+
+    output.into()
+}
+
+/// Compile expression language into Vector IL program
+///
+/// Vectors and labels stored in VIO are declared with their storage ids, and
+/// new values are computed from expressions and stored back, e.g.
+///
+/// ```ignore
+/// vil! {
+///     labels assets = asset_names_id;
+///     vector weights[assets] = weights_id;
+///     vector quote = quote_id;
+///     let price = vdot(prices[assets], weights);
+///     store quote = [capacity, price, slope];
+/// }
+/// ```
+#[proc_macro]
+pub fn vil(input: TokenStream) -> TokenStream {
+    expand_vil(input, false)
+}
+
+/// Same as `vil!`, but produces program with compact encoding
+#[proc_macro]
+pub fn vil_compact(input: TokenStream) -> TokenStream {
+    expand_vil(input, true)
+}
+
+fn expand_vil(input: TokenStream, compact: bool) -> TokenStream {
+    let program = match syn::parse::<vil::Program>(input) {
+        Ok(program) => program,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    // Generated program is simulated same as abacus! to catch any
+    // instruction, which would underflow the stack
+    let instructions = match vil::compile(&program)
+        .and_then(|instructions| stack::simulate(&instructions, 0).map(|_| instructions))
+    {
+        Ok(instructions) => instructions,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    let tokens = expand_instructions(&instructions, compact);
+    let output = quote! {
+        (|| -> Result<Vec<u8>, Vec<u8>> {
+            let mut bytecode: Vec<u8> = Vec::new();
+            #tokens;
+            Ok(bytecode)
+        })()
+    };
+
+    output.into()
+}
+
+/// Generate code pushing instructions into `bytecode`
+fn expand_instructions(instructions: &[Instruction], compact: bool) -> TokenStream2 {
     let mut final_tokens = TokenStream2::new();
+    if compact {
+        final_tokens.extend(quote! {
+            bytecode.push(common::abacus::instruction_set::COMPACT_HEADER);
+        });
+    }
     let mut reg_map: HashMap<String, u128> = HashMap::new();
     let mut next_reg_index: u128 = 0;
 
-    for instruction in instruction_list.instructions {
+    for instruction in instructions {
         let mnemonic_str = instruction.mnemonic.to_string().to_uppercase();
         let expected_types = ARG_TYPES.get(mnemonic_str.as_str()).unwrap();
 
@@ -232,13 +740,13 @@ pub fn abacus(input: TokenStream) -> TokenStream {
             bytecode.push(common::abacus::instruction_set::#op_code_ident);
         });
 
-        for (i, arg) in instruction.args.into_iter().enumerate() {
+        for (i, arg) in instruction.args.iter().enumerate() {
             let expected_type = &expected_types[i];
 
             // 1. Resolve the argument token stream
-            let arg_value = match &arg {
+            let arg_value = match arg {
                 InstructionArg::Register(reg_name) => {
-                    let reg_index = *reg_map.entry(reg_name.clone()).or_insert_with(|| {
+                    let reg_index = *reg_map.entry(reg_name.to_string()).or_insert_with(|| {
                         let index = next_reg_index;
                         next_reg_index += 1;
                         index
@@ -255,11 +763,19 @@ pub fn abacus(input: TokenStream) -> TokenStream {
                 InstructionArg::Constant(ident) => {
                     quote! { #ident }
                 }
+                InstructionArg::Slot(slot) => {
+                    quote! { #slot }
+                }
+                InstructionArg::Procedure(index) => {
+                    quote! { procedures[#index].id }
+                }
             };
 
             // 2. Inject Validation Check for StorageId
-            if matches!(expected_type, ArgType::StorageId) {
-                let display_name = match &arg {
+            if matches!(expected_type, ArgType::StorageId)
+                && !matches!(arg, InstructionArg::Procedure(_))
+            {
+                let display_name = match arg {
                     InstructionArg::Constant(ident) => format_error_name(&ident.to_string()),
                     _ => "Storage ID".to_string(),
                 };
@@ -275,9 +791,12 @@ pub fn abacus(input: TokenStream) -> TokenStream {
 
             // 3. Determine size and conversion
             let conversion_tokens = match expected_type {
-                ArgType::RegisterId | ArgType::StackPos | ArgType::Size => {
+                ArgType::RegisterId | ArgType::StackPos | ArgType::Size | ArgType::Argument => {
                     quote! { bytecode.push(#arg_value as u8); }
                 }
+                ArgType::StorageId | ArgType::Amount | ArgType::Label if compact => {
+                    quote! { common::uint::write_leb128(#arg_value, &mut bytecode); }
+                }
                 ArgType::StorageId | ArgType::Amount | ArgType::Label => {
                     quote! { common::uint::write_u128(#arg_value, &mut bytecode); }
                 }
@@ -287,16 +806,76 @@ pub fn abacus(input: TokenStream) -> TokenStream {
         }
     }
 
-    // --- Final Output Wrapper (Result return type) ---
-    let output = quote! {
-        (|| -> Result<Vec<u8>, Vec<u8>> {
-            let mut bytecode: Vec<u8> = Vec::new();
-            #final_tokens;
-            Ok(bytecode)
-        })()
-    };
+    final_tokens
+}
 
-    output.into()
+/// Simulate stack depth and register use, and validate stack comments
+///
+/// Procedures start with their inputs on the stack, and they must leave at
+/// least their outputs on the stack using at most registers they declare.
+fn check_stack(
+    instruction_list: &InstructionList,
+    source: Option<&str>,
+) -> syn::Result<Vec<(Span, String)>> {
+    if instruction_list.unchecked {
+        return Ok(Vec::new());
+    }
+    let mut warnings = Vec::new();
+    let mut procedures = Vec::new();
+    for procedure in &instruction_list.procedures {
+        let simulation = stack::simulate(&procedure.instructions, procedure.num_inputs)?;
+        let depth = match simulation.depths.last() {
+            Some(depth) => depth.and_then(|depth| depth.max),
+            None => Some(procedure.num_inputs),
+        };
+        if let Some(depth) = depth.filter(|depth| *depth < procedure.num_outputs) {
+            return Err(syn::Error::new(
+                procedure.name.span(),
+                format!(
+                    "PROC {} must leave {} outputs, but stack has {}",
+                    procedure.name, procedure.num_outputs, depth
+                ),
+            ));
+        }
+        let num_registers = stack::num_registers(&procedure.instructions);
+        if num_registers > procedure.num_registers {
+            return Err(syn::Error::new(
+                procedure.name.span(),
+                format!(
+                    "PROC {} uses {} registers, but declares {}",
+                    procedure.name, num_registers, procedure.num_registers
+                ),
+            ));
+        }
+        warnings.extend(simulation.warnings.iter().cloned());
+        procedures.push(simulation);
+    }
+
+    let simulation = stack::simulate(&instruction_list.instructions, instruction_list.num_inputs)?;
+    warnings.extend(simulation.warnings.iter().cloned());
+
+    if let Some(source) = source.filter(|_| instruction_list.stack_comments) {
+        // Instructions with simulated depths in order of source text
+        let mut items = Vec::new();
+        for index in 0..=instruction_list.instructions.len() {
+            for (procedure, simulation) in instruction_list.procedures.iter().zip(&procedures) {
+                if procedure.position == index {
+                    items.extend(
+                        procedure
+                            .instructions
+                            .iter()
+                            .zip(simulation.depths.iter().copied()),
+                    );
+                }
+            }
+            if let Some(instruction) = instruction_list.instructions.get(index) {
+                items.push((instruction, simulation.depths[index]));
+            }
+        }
+
+        stack::check_comments(source, &items)?;
+    }
+    Ok(warnings)
 }
 
 /// Helper to convert "asset_weights_id" -> "Asset Weights"
@@ -0,0 +1,814 @@
+//! Simulation of stack depth and register use at macro-expansion time
+//!
+//! Mirrors `verifier::simulate()` of `abacus-runtime`, so that wrong stack
+//! positions are reported at the offending mnemonic when program is compiled
+//! rather than as `StackUnderflow` when program is verified or executed.
+
+use std::collections::HashSet;
+
+use proc_macro2::Span;
+use syn::{Expr, Lit};
+
+use crate::{Instruction, InstructionArg};
+
+/// Bounds of stack depth, where upper bound is lost after `UNPK` or `T`
+#[derive(Clone, Copy)]
+pub(crate) struct Depth {
+    pub(crate) min: usize,
+    pub(crate) max: Option<usize>,
+}
+
+impl Depth {
+    fn require(&self, count: usize) -> Result<(), usize> {
+        match self.max {
+            Some(max) if max < count => Err(max),
+            _ => Ok(()),
+        }
+    }
+
+    fn pop(&mut self, count: usize) -> Result<(), usize> {
+        self.require(count)?;
+        self.min = self.min.saturating_sub(count);
+        self.max = self.max.map(|max| max - count);
+        Ok(())
+    }
+
+    fn push(&mut self, count: usize) {
+        self.min += count;
+        self.max = self.max.map(|max| max + count);
+    }
+}
+
+/// Outcome of simulation
+pub(crate) struct Simulation {
+    /// Stack depth after each instruction, or `None` once depth depends on
+    /// argument, which is not an integer literal.
+    pub(crate) depths: Vec<Option<Depth>>,
+
+    /// Warnings, which do not stop compilation
+    pub(crate) warnings: Vec<(Span, String)>,
+}
+
+/// Simulate program starting with `num_inputs` operands on the stack
+pub(crate) fn simulate(instructions: &[Instruction], num_inputs: usize) -> syn::Result<Simulation> {
+    let mut depth = Some(Depth {
+        min: num_inputs,
+        max: Some(num_inputs),
+    });
+    let mut registers = Registers::default();
+    let mut simulation = Simulation {
+        depths: Vec::with_capacity(instructions.len()),
+        warnings: Vec::new(),
+    };
+
+    for (index, instruction) in instructions.iter().enumerate() {
+        let mnemonic = instruction.mnemonic.to_string().to_uppercase();
+        registers.check(instruction, &mnemonic, &mut simulation.warnings);
+
+        if matches!(mnemonic.as_str(), "RNDU" | "RNDE") {
+            let next = instructions
+                .get(index + 1)
+                .map(|next| next.mnemonic.to_string().to_uppercase());
+            if !matches!(next.as_deref(), Some("MUL") | Some("DIV")) {
+                return Err(syn::Error::new(
+                    instruction.mnemonic.span(),
+                    format!("{} must be followed by MUL or DIV", mnemonic),
+                ));
+            }
+        }
+
+        if let Some(d) = depth.as_mut() {
+            let args: Vec<Option<usize>> = instruction.args.iter().map(literal_usize).collect();
+            if args.contains(&None) && affects_stack(&mnemonic) {
+                depth = None;
+            } else {
+                let args: Vec<usize> = args.into_iter().map(Option::unwrap_or_default).collect();
+                step(d, &mnemonic, &args).map_err(|message| {
+                    syn::Error::new(
+                        instruction.mnemonic.span(),
+                        format!("{} {}", mnemonic, message),
+                    )
+                })?;
+            }
+        }
+        simulation.depths.push(depth);
+    }
+
+    Ok(simulation)
+}
+
+/// Value of integer literal argument, or zero for arguments, which are not
+/// stack positions or sizes, e.g. storage ids given by constants
+pub(crate) fn literal_usize(arg: &InstructionArg) -> Option<usize> {
+    match arg {
+        InstructionArg::Literal(Expr::Lit(lit)) => match &lit.lit {
+            Lit::Int(int) => int.base10_parse().ok(),
+            _ => Some(0),
+        },
+        InstructionArg::Literal(_) => None,
+        InstructionArg::Constant(_) => None,
+        InstructionArg::Register(_) | InstructionArg::Slot(_) | InstructionArg::Procedure(_) => {
+            Some(0)
+        }
+    }
+}
+
+/// Instructions, whose stack effect depends on their arguments, as opposed to
+/// instructions taking only storage ids, labels, scalars or registers
+fn affects_stack(mnemonic: &str) -> bool {
+    !matches!(
+        mnemonic,
+        "LDL"
+            | "LDV"
+            | "LDLR"
+            | "LDVR"
+            | "IMMS"
+            | "IMML"
+            | "STL"
+            | "STV"
+            | "STVR"
+            | "VPUSH"
+            | "LPUSH"
+            | "ASSERT_ZERO"
+            | "ARGS"
+            | "LDA"
+            | "LDLA"
+            | "LDVA"
+            | "STLA"
+            | "STVA"
+            | "LDR"
+            | "LDM"
+            | "STR"
+    )
+}
+
+/// Apply stack effect of single instruction
+fn step(depth: &mut Depth, mnemonic: &str, args: &[usize]) -> Result<(), String> {
+    let arg = |i: usize| args.get(i).copied().unwrap_or(0);
+    let underflow = |count: usize| {
+        move |available: usize| {
+            format!(
+                "causes stack underflow: it needs {} operands, but stack has {}",
+                count, available
+            )
+        }
+    };
+    let require = |depth: &Depth, count: usize| depth.require(count).map_err(underflow(count));
+    let nonzero = |value: usize| {
+        if value == 0 {
+            Err("position must not be zero".to_string())
+        } else {
+            Ok(())
+        }
+    };
+
+    match mnemonic {
+        "LDL" | "LDV" | "LDLR" | "LDVR" | "IMMS" | "IMML" | "LDR" | "LDM" | "LDA" | "LDLA"
+        | "LDVA" => {
+            depth.push(1);
+        }
+        "LDD" | "ZEROS" | "ONES" => {
+            require(depth, arg(0) + 1)?;
+            depth.push(1);
+        }
+        "STR" | "STL" | "STV" | "STVR" | "STLA" | "STVA" | "ASSERT_ZERO" => {
+            depth.pop(1).map_err(underflow(1))?;
+        }
+        "PKV" | "PKL" => {
+            let count = arg(0).max(1);
+            depth.pop(count).map_err(underflow(count))?;
+            depth.push(1);
+        }
+        "POPN" => {
+            let count = arg(0).max(1);
+            depth.pop(count).map_err(underflow(count))?;
+        }
+        "UNPK" => {
+            depth.pop(1).map_err(underflow(1))?;
+            depth.max = None;
+        }
+        "T" => {
+            if arg(0) == 0 {
+                return Err("count must not be zero".to_string());
+            }
+            depth.pop(arg(0)).map_err(underflow(arg(0)))?;
+            depth.max = None;
+        }
+        "VPUSH" | "LPUSH" | "SQRT" | "EXP" | "LN" | "VSUM" | "VMIN" | "VMAX" | "VCUMSUM"
+        | "VNORM" | "NEG" | "ABS" | "POS" | "NEGPART" => {
+            require(depth, 1)?;
+        }
+        "VPOP" | "LPOP" | "SUNPK" => {
+            require(depth, 1)?;
+            depth.push(1);
+        }
+        "ADD" | "SUB" | "SSB" | "MUL" | "DIV" | "POW" | "VDOT" => {
+            require(depth, arg(0) + 1)?;
+        }
+        "MIN" | "MAX" | "EQ" | "GT" | "LT" | "NET" | "LUNION" | "SWAP" | "SPK" => {
+            nonzero(arg(0))?;
+            require(depth, arg(0) + 1)?;
+        }
+        "CLAMP" | "SEL" | "SGET" | "SADD" | "SUPD" => {
+            nonzero(arg(0))?;
+            nonzero(arg(1))?;
+            require(depth, arg(0).max(arg(1)) + 1)?;
+        }
+        "JUPD" | "JADD" => {
+            let (pos_b, pos_a, lab_b) = (arg(0), arg(1), arg(2));
+            if pos_a == lab_b {
+                require(depth, 2)?;
+            } else {
+                nonzero(pos_b)?;
+                nonzero(pos_a)?;
+                nonzero(lab_b)?;
+                require(depth, pos_b.max(pos_a).max(lab_b) + 1)?;
+            }
+        }
+        "JFLT" => {
+            let (lab_a, lab_b) = (arg(0), arg(1));
+            if lab_a != lab_b {
+                nonzero(lab_a)?;
+                nonzero(lab_b)?;
+                require(depth, lab_a.max(lab_b) + 1)?;
+            }
+        }
+        "B" | "FOLD" | "BZ" => {
+            let (num_inputs, num_outputs) = (arg(1), arg(2));
+            let count = if mnemonic == "B" {
+                num_inputs
+            } else {
+                num_inputs + 1
+            };
+            if mnemonic == "BZ" && num_inputs != num_outputs {
+                return Err("must have same number of inputs and outputs".to_string());
+            }
+            depth.pop(count).map_err(underflow(count))?;
+            depth.push(num_outputs);
+        }
+        "ASSERT_LE" => {
+            nonzero(arg(0))?;
+            require(depth, arg(0) + 1)?;
+            depth.pop(1).map_err(underflow(1))?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Registers holding a value, and registers whose value was moved by `LDM`
+#[derive(Default)]
+struct Registers {
+    written: HashSet<String>,
+    moved: HashSet<String>,
+}
+
+impl Registers {
+    /// Warn when register is read before it is written, or after it is moved
+    fn check(
+        &mut self,
+        instruction: &Instruction,
+        mnemonic: &str,
+        warnings: &mut Vec<(Span, String)>,
+    ) {
+        let Some(InstructionArg::Register(register)) = instruction.args.first() else {
+            return;
+        };
+        let name = register.to_string();
+        match mnemonic {
+            "STR" => {
+                self.moved.remove(&name);
+                self.written.insert(name);
+            }
+            "LDR" | "LDM" if self.moved.contains(&name) => {
+                warnings.push((
+                    register.span(),
+                    format!(
+                        "Register {} is read by {} after it was moved by LDM",
+                        name, mnemonic
+                    ),
+                ));
+            }
+            "LDR" | "LDM" if !self.written.contains(&name) => {
+                warnings.push((
+                    register.span(),
+                    format!(
+                        "Register {} is read by {} before it is written by STR",
+                        name, mnemonic
+                    ),
+                ));
+            }
+            "LDM" => {
+                self.written.remove(&name);
+                self.moved.insert(name);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Number of distinct registers used by instructions
+pub(crate) fn num_registers(instructions: &[Instruction]) -> usize {
+    instructions
+        .iter()
+        .flat_map(|instruction| &instruction.args)
+        .filter_map(|arg| match arg {
+            InstructionArg::Register(register) => Some(register.to_string()),
+            _ => None,
+        })
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+/// Operand count annotated in `// Stack: [..]` comment
+struct Annotation {
+    count: usize,
+    open_ended: bool,
+}
+
+impl Annotation {
+    /// Parse comments like `Stack: [A, B]`, `Stack [A, B]` or `[..., A, B]`
+    fn parse(comment: &str) -> Option<Self> {
+        let text = comment.trim_start();
+        let text = text.strip_prefix("Stack").unwrap_or(text).trim_start();
+        let text = text.strip_prefix(':').unwrap_or(text).trim_start();
+        let text = text.strip_prefix('[')?;
+
+        let mut nesting = 0usize;
+        let mut elements = vec![String::new()];
+        for c in text.chars() {
+            match c {
+                ']' if nesting == 0 => {
+                    let mut annotation = Self {
+                        count: 0,
+                        open_ended: false,
+                    };
+                    for element in &elements {
+                        match element.trim() {
+                            "" => {}
+                            "..." | "…" => annotation.open_ended = true,
+                            _ => annotation.count += 1,
+                        }
+                    }
+                    return Some(annotation);
+                }
+                '(' | '[' | '{' => nesting += 1,
+                ')' | ']' | '}' => nesting = nesting.saturating_sub(1),
+                ',' if nesting == 0 => {
+                    elements.push(String::new());
+                    continue;
+                }
+                _ => {}
+            }
+            if let Some(element) = elements.last_mut() {
+                element.push(c);
+            }
+        }
+        None
+    }
+
+    fn matches(&self, depth: &Depth) -> bool {
+        if self.open_ended {
+            depth.max.is_none_or(|max| self.count <= max)
+        } else {
+            depth.min <= self.count && depth.max.is_none_or(|max| self.count == max)
+        }
+    }
+}
+
+/// Token of source text, where delimiters of groups are tokens of their own
+enum SourceToken<'a> {
+    Ident(&'a str),
+    Punct(char),
+    Literal,
+    Open,
+    Close,
+}
+
+/// Source text split into tokens and comments, each with its line number
+///
+/// Tokens follow the same rules as tokens passed to procedural macros, so
+/// that `IMMS - 1` is three tokens regardless of white space, while comments
+/// are either line comments or block comments starting on that line.
+struct SourceText<'a> {
+    tokens: Vec<(usize, SourceToken<'a>)>,
+    comments: Vec<(usize, &'a str)>,
+}
+
+impl<'a> SourceText<'a> {
+    fn tokenize(source: &'a str) -> Self {
+        let mut this = Self {
+            tokens: Vec::new(),
+            comments: Vec::new(),
+        };
+        let mut line = 0;
+        let mut pos = 0;
+        while let Some(c) = source[pos..].chars().next() {
+            let rest = &source[pos..];
+            let start = pos;
+            pos += c.len_utf8();
+            match c {
+                '\n' => line += 1,
+                _ if c.is_whitespace() => {}
+                '/' if rest.starts_with("//") => {
+                    let len = rest.find('\n').unwrap_or(rest.len());
+                    this.comments.push((line, &rest[2..len]));
+                    pos = start + len;
+                }
+                '/' if rest.starts_with("/*") => {
+                    let mut nesting = 0usize;
+                    let mut len = rest.len();
+                    let mut i = 0;
+                    while i < rest.len() {
+                        if rest[i..].starts_with("/*") {
+                            nesting += 1;
+                            i += 2;
+                        } else if rest[i..].starts_with("*/") {
+                            nesting -= 1;
+                            i += 2;
+                            if nesting == 0 {
+                                len = i;
+                                break;
+                            }
+                        } else {
+                            i += rest[i..].chars().next().map_or(1, char::len_utf8);
+                        }
+                    }
+                    let text = &rest[..len];
+                    this.comments
+                        .push((line, text.trim_start_matches("/*").trim_end_matches("*/")));
+                    line += text.matches('\n').count();
+                    pos = start + len;
+                }
+                '"' => {
+                    let mut escaped = false;
+                    let len = rest[1..]
+                        .find(|c| {
+                            let end = c == '"' && !escaped;
+                            escaped = c == '\\' && !escaped;
+                            end
+                        })
+                        .map_or(rest.len(), |i| i + 2);
+                    line += rest[..len].matches('\n').count();
+                    this.tokens.push((line, SourceToken::Literal));
+                    pos = start + len;
+                }
+                '\'' => {
+                    // Character literal, as opposed to lifetime
+                    let len = rest[1..]
+                        .char_indices()
+                        .skip(1)
+                        .find(|(_, c)| *c == '\'')
+                        .map(|(i, _)| i + 2)
+                        .filter(|len| *len <= 4 || rest[1..].starts_with('\\'));
+                    match len {
+                        Some(len) => {
+                            this.tokens.push((line, SourceToken::Literal));
+                            pos = start + len;
+                        }
+                        None => this.tokens.push((line, SourceToken::Punct(c))),
+                    }
+                }
+                _ if c.is_alphabetic() || c == '_' => {
+                    let len = rest
+                        .find(|c: char| !c.is_alphanumeric() && c != '_')
+                        .unwrap_or(rest.len());
+                    this.tokens.push((line, SourceToken::Ident(&rest[..len])));
+                    pos = start + len;
+                }
+                _ if c.is_ascii_digit() => {
+                    // Number with optional fraction, exponent and suffix, e.g.
+                    // 1.5, 1e-5 or 10u8
+                    let mut len = 0;
+                    for (i, c) in rest.char_indices() {
+                        let fraction = c == '.'
+                            && !rest[..i].contains('.')
+                            && rest[i + 1..].starts_with(|c: char| c.is_ascii_digit());
+                        let exponent = matches!(c, '+' | '-')
+                            && rest[..i].ends_with(['e', 'E'])
+                            && !rest.starts_with("0x");
+                        if !c.is_alphanumeric() && c != '_' && !fraction && !exponent {
+                            break;
+                        }
+                        len = i + c.len_utf8();
+                    }
+                    this.tokens.push((line, SourceToken::Literal));
+                    pos = start + len;
+                }
+                '(' | '[' | '{' => this.tokens.push((line, SourceToken::Open)),
+                ')' | ']' | '}' => this.tokens.push((line, SourceToken::Close)),
+                _ => this.tokens.push((line, SourceToken::Punct(c))),
+            }
+        }
+        this
+    }
+}
+
+/// Skip tokens up to and including the one closing group just opened
+fn skip_group<'a>(tokens: &mut impl Iterator<Item = &'a (usize, SourceToken<'a>)>) {
+    let mut nesting = 1usize;
+    for (_, token) in tokens {
+        match token {
+            SourceToken::Open => nesting += 1,
+            SourceToken::Close => nesting -= 1,
+            _ => {}
+        }
+        if nesting == 0 {
+            return;
+        }
+    }
+}
+
+/// Validate stack comments against simulated depth
+///
+/// Comments are not passed to procedural macros, and so they are recovered
+/// from source text of the invocation. Source text is split into tokens,
+/// which are matched with tokens consumed by each instruction, and each line
+/// or block comment is matched with the last instruction ending on the line,
+/// where the comment starts. Nothing is validated when source text is not
+/// available, e.g. when invoked by another macro.
+///
+/// Instructions of main program and of procedures are given in order of
+/// source text together with their simulated depths.
+pub(crate) fn check_comments(
+    source: &str,
+    instructions: &[(&Instruction, Option<Depth>)],
+) -> syn::Result<()> {
+    let Some(body) = source
+        .find(['{', '(', '['])
+        .and_then(|start| source.get(start + 1..source.len().saturating_sub(1)))
+    else {
+        return Ok(());
+    };
+    let text = SourceText::tokenize(body);
+
+    // Line of last token of each instruction
+    let mut lines = Vec::with_capacity(instructions.len());
+    let mut tokens = text.tokens.iter();
+    while let Some((line, token)) = tokens.next() {
+        match token {
+            // Options, e.g. #![inputs(3)]
+            SourceToken::Punct('#') => {
+                for (_, token) in tokens.by_ref() {
+                    if let SourceToken::Open = token {
+                        skip_group(&mut tokens);
+                        break;
+                    }
+                }
+            }
+            // Declaration PROC name(inputs, outputs, registers) {
+            SourceToken::Ident(word) if word.eq_ignore_ascii_case("PROC") => {
+                tokens.next();
+                if let Some((_, SourceToken::Open)) = tokens.next() {
+                    skip_group(&mut tokens);
+                }
+                tokens.next();
+            }
+            // End of procedure body
+            SourceToken::Close => {}
+            _ => {
+                let (instruction, _) = instructions.get(lines.len()).ok_or_else(mismatch)?;
+                let last = match instruction.num_tokens {
+                    0 | 1 => *line,
+                    count => tokens.nth(count - 2).ok_or_else(mismatch)?.0,
+                };
+                lines.push(last);
+            }
+        }
+    }
+    if lines.len() != instructions.len() {
+        return Err(mismatch());
+    }
+
+    let mut annotated = Vec::new();
+    for (line, comment) in text.comments {
+        let Some(annotation) = Annotation::parse(comment) else {
+            continue;
+        };
+        if let Some(index) = lines.iter().rposition(|last| *last == line) {
+            annotated.push((index, annotation));
+        }
+    }
+
+    let mut errors: Option<syn::Error> = None;
+    for (index, annotation) in annotated {
+        let (instruction, depth) = &instructions[index];
+        let Some(depth) = depth else {
+            continue;
+        };
+        if annotation.matches(depth) {
+            continue;
+        }
+        let simulated = match depth.max {
+            Some(max) => max.to_string(),
+            None => format!("at least {}", depth.min),
+        };
+        let error = syn::Error::new(
+            instruction.mnemonic.span(),
+            format!(
+                "Stack comment lists {}{} operands, but simulated stack has {}",
+                if annotation.open_ended {
+                    "at least "
+                } else {
+                    ""
+                },
+                annotation.count,
+                simulated
+            ),
+        );
+        match errors.as_mut() {
+            Some(errors) => errors.combine(error),
+            None => errors = Some(error),
+        }
+    }
+    errors.map_or(Ok(()), Err)
+}
+
+fn mismatch() -> syn::Error {
+    syn::Error::new(
+        Span::call_site(),
+        "Cannot match stack comments with instructions",
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use crate::InstructionList;
+
+    /// Check invocation as `abacus!` would, and return messages of warnings
+    fn check(source: &str) -> syn::Result<Vec<String>> {
+        let body = &source[source.find('{').unwrap() + 1..source.rfind('}').unwrap()];
+        let instruction_list: InstructionList = syn::parse_str(body)?;
+        let warnings = crate::check_stack(&instruction_list, Some(source))?;
+        Ok(warnings.into_iter().map(|(_, message)| message).collect())
+    }
+
+    fn error(source: &str) -> String {
+        match check(source) {
+            Ok(_) => panic!("Expected error: {}", source),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_underflow() {
+        assert_eq!(
+            error("abacus! { IMMS 1 ADD 1 }"),
+            "ADD causes stack underflow: it needs 2 operands, but stack has 1"
+        );
+        assert_eq!(
+            error("abacus! { LDD 0 }"),
+            "LDD causes stack underflow: it needs 1 operands, but stack has 0"
+        );
+        assert_eq!(
+            error("abacus! { IMMS 1 IMMS 2 SWAP 0 }"),
+            "SWAP position must not be zero"
+        );
+        assert!(check("abacus! { #![inputs(1)] IMMS 1 ADD 1 }").is_ok());
+
+        // Depth is unknown once it depends on constant
+        assert!(check("abacus! { IMMS 1 POPN COUNT LDD 5 }").is_ok());
+    }
+
+    #[test]
+    fn test_rounding() {
+        assert_eq!(
+            error("abacus! { IMMS 1 IMMS 2 RNDU ADD 1 }"),
+            "RNDU must be followed by MUL or DIV"
+        );
+        assert_eq!(
+            error("abacus! { IMMS 1 IMMS 2 RNDE }"),
+            "RNDE must be followed by MUL or DIV"
+        );
+        assert!(check("abacus! { IMMS 1 IMMS 2 RNDU MUL 1 RNDE DIV 1 }").is_ok());
+    }
+
+    #[test]
+    fn test_registers() {
+        let warnings = check(
+            "abacus! {
+                LDR _A
+                IMMS 1 STR _B
+                LDM _B LDR _B
+                IMMS 2 STR _B LDR _B
+            }",
+        )
+        .unwrap();
+        assert_eq!(
+            warnings,
+            vec![
+                "Register _A is read by LDR before it is written by STR",
+                "Register _B is read by LDR after it was moved by LDM",
+            ]
+        );
+
+        // Unchecked program is not simulated at all
+        assert!(check("abacus! { #![unchecked] LDR _A ADD 5 }")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_procedures() {
+        assert_eq!(
+            error("abacus! { PROC f(1, 1) { } }"),
+            "PROC must declare (inputs, outputs, registers)"
+        );
+        assert_eq!(
+            error("abacus! { PROC f(1, 1, 256) { } }"),
+            "Size must fit in one byte"
+        );
+        assert_eq!(
+            error("abacus! { PROC f(1, 1, 0) { PROC g(1, 1, 0) { } } }"),
+            "PROC cannot be declared inside PROC"
+        );
+        assert_eq!(
+            error("abacus! { PROC f(1, 1, 0) { ARGS 1 } }"),
+            "ARGS cannot be used inside PROC, as sub-routines take no arguments"
+        );
+        assert_eq!(
+            error("abacus! { PROC f(1, 1, 0) { } PROC f(1, 1, 0) { } }"),
+            "PROC f is already declared"
+        );
+        assert_eq!(
+            error("abacus! { PROC f(1, 2, 0) { } }"),
+            "PROC f must leave 2 outputs, but stack has 1"
+        );
+        assert_eq!(
+            error("abacus! { PROC f(1, 1, 0) { STR _X LDR _X } }"),
+            "PROC f uses 1 registers, but declares 0"
+        );
+        assert_eq!(
+            error("abacus! { PROC f(2, 1, 0) { ADD 2 } }"),
+            "ADD causes stack underflow: it needs 3 operands, but stack has 2"
+        );
+        assert_eq!(
+            error("abacus! { PROC f(0, 1, 0) { IMMS 1 } IMML 1 FOLD f }"),
+            "PROC f must take at least one input to be called by FOLD"
+        );
+        assert_eq!(
+            error("abacus! { PROC f(1, 1, 0) { } IMMS 1 B f 2 1 0 }"),
+            "B f must be given 1 1 0 as declared by PROC"
+        );
+        assert_eq!(
+            error("abacus! { PROC f(2, 1, 0) { } IMMS 1 B f }"),
+            "B causes stack underflow: it needs 2 operands, but stack has 1"
+        );
+        assert!(check(
+            "abacus! {
+                PROC f(2, 1, 0) { ADD 1 SWAP 1 POPN 1 }
+                IMMS 1 IMMS 2 B f
+                IMMS 3 B f 2 1 0
+            }"
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_stack_comments() {
+        let warnings = check(
+            "abacus! {
+                #![stack_comments]
+                IMMS 1              // Stack: [A]
+                IMMS - 1            // Stack: [A, B]
+                ADD 1 /* Stack: [A, A + B] */
+                PROC f(1, 1, 0) {
+                    IMMS 2          // Stack: [X, 2]
+                    MUL 1 POPN 1    // [..., X * 2]
+                }
+                B f                 // Stack: [A, F(A + B)]
+                LDD
+                    1               // Stack: [A, F, A]
+                POPN 3 IMMS 1.5e-3  // Stack [C]
+            }",
+        )
+        .unwrap();
+        assert!(warnings.is_empty());
+
+        assert_eq!(
+            error(
+                "abacus! {
+                    #![stack_comments]
+                    IMMS 1          // Stack: [A]
+                    IMMS 2          // Stack: [A, B, C]
+                }"
+            ),
+            "Stack comment lists 3 operands, but simulated stack has 2"
+        );
+        assert_eq!(
+            error(
+                "abacus! {
+                    #![stack_comments]
+                    PROC f(1, 1, 0) {
+                        IMMS 2 /* Stack: [..., X, Y, 2] */
+                    }
+                    IMMS 1 B f      // Stack: [A]
+                }"
+            ),
+            "Stack comment lists at least 3 operands, but simulated stack has 2"
+        );
+
+        // Comments are not checked, unless enabled
+        assert!(check("abacus! { IMMS 1 // Stack: [A, B] \n }").is_ok());
+    }
+}
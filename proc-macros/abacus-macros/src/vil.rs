@@ -0,0 +1,1555 @@
+//! Expression language compiled to Vector IL
+//!
+//! Statements bind vectors and labels stored in VIO, compute new values from
+//! expressions, and store them back:
+//!
+//! ```text
+//! labels assets = index_asset_names_id;
+//! labels market = market_asset_names_id;
+//! vector weights[assets] = weights_id;
+//! vector prices[market] = asset_prices_id;
+//! vector quote = quote_id;
+//!
+//! let price = vdot(prices[assets], weights);
+//! store quote = [capacity, price, slope];
+//! ```
+//!
+//! Vectors are typed by the labels they are indexed by, and `prices[assets]`
+//! re-indexes vector by other labels using `JFLT`, while `x[assets] = y` and
+//! `x[assets] += y` update components with those labels using `JUPD` and
+//! `JADD`. Variables are kept in registers, which are reused once variable is
+//! read for the last time, and expressions are evaluated on the stack, where
+//! each operation leaves its other operand in place until end of statement.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use proc_macro2::Span;
+use syn::{
+    parse::{Parse, ParseStream},
+    spanned::Spanned,
+    Attribute, BinOp, Expr, Ident, Lit, LitInt, Token, UnOp,
+};
+
+use crate::{Instruction, InstructionArg};
+
+/// Registers available to programs executed by NPCs
+const DEFAULT_NUM_REGISTERS: usize = 16;
+
+// --- Parsing ---
+
+/// Type of value, where vectors carry name of labels they are indexed by
+#[derive(Clone, PartialEq, Eq)]
+enum Type {
+    Scalar {
+        signed: bool,
+    },
+    Vector {
+        signed: bool,
+        labels: Option<String>,
+    },
+    Labels,
+}
+
+impl Type {
+    fn is_signed(&self) -> bool {
+        matches!(
+            self,
+            Type::Scalar { signed: true } | Type::Vector { signed: true, .. }
+        )
+    }
+
+    fn is_vector(&self) -> bool {
+        matches!(self, Type::Vector { .. })
+    }
+
+    fn is_unsigned_scalar(&self) -> bool {
+        matches!(self, Type::Scalar { signed: false })
+    }
+
+    fn is_unsigned_vector(&self) -> bool {
+        matches!(self, Type::Vector { signed: false, .. })
+    }
+
+    fn labels(&self) -> Option<&str> {
+        match self {
+            Type::Vector { labels, .. } => labels.as_deref(),
+            _ => None,
+        }
+    }
+
+    fn with_signed(&self, signed: bool) -> Type {
+        match self {
+            Type::Scalar { .. } => Type::Scalar { signed },
+            Type::Vector { labels, .. } => Type::Vector {
+                signed,
+                labels: labels.clone(),
+            },
+            Type::Labels => Type::Labels,
+        }
+    }
+
+    /// Value of this type can be assigned to variable of `other` type, where
+    /// vectors conform unless they are indexed by different labels
+    fn conforms_to(&self, other: &Type) -> bool {
+        match (self, other) {
+            (
+                Type::Vector { signed, labels },
+                Type::Vector {
+                    signed: other_signed,
+                    labels: other_labels,
+                },
+            ) => {
+                signed == other_signed
+                    && (labels.is_none() || other_labels.is_none() || labels == other_labels)
+            }
+            _ => self == other,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_signed() {
+            write!(f, "signed ")?;
+        }
+        match self {
+            Type::Scalar { .. } => write!(f, "scalar"),
+            Type::Vector {
+                labels: Some(labels),
+                ..
+            } => write!(f, "vector[{}]", labels),
+            Type::Vector { labels: None, .. } => write!(f, "vector"),
+            Type::Labels => write!(f, "labels"),
+        }
+    }
+}
+
+/// Type annotation, e.g. `scalar`, `signed vector`, `vector[assets]` or `labels`
+struct Annotation {
+    ty: Type,
+    span: Span,
+}
+
+impl Parse for Annotation {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut ident: Ident = input.parse()?;
+        let span = ident.span();
+        let signed = ident == "signed";
+        if signed {
+            ident = input.parse()?;
+        }
+        let ty = match ident.to_string().as_str() {
+            "scalar" => Type::Scalar { signed },
+            "vector" => Type::Vector {
+                signed,
+                labels: parse_labels_index(input)?.map(|labels| labels.to_string()),
+            },
+            "labels" if !signed => Type::Labels,
+            _ => {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "Expected scalar, vector, vector[labels] or labels",
+                ));
+            }
+        };
+        Ok(Self { ty, span })
+    }
+}
+
+/// Parse optional `[labels]` following vector name
+fn parse_labels_index(input: ParseStream) -> syn::Result<Option<Ident>> {
+    if !input.peek(syn::token::Bracket) {
+        return Ok(None);
+    }
+    let content;
+    syn::bracketed!(content in input);
+    Ok(Some(content.parse()?))
+}
+
+enum AssignOp {
+    Set,
+    Add,
+}
+
+enum Statement {
+    /// `labels name = id;` or `vector name[labels] = id;`
+    Declare { name: Ident, ty: Type, id: Expr },
+    /// `let name: type = expr;`
+    Let {
+        name: Ident,
+        annotation: Option<Annotation>,
+        value: Expr,
+    },
+    /// `name[labels] = expr;`, `name += expr;` or `store name = expr;`
+    Assign {
+        store: bool,
+        name: Ident,
+        index: Option<Ident>,
+        op: AssignOp,
+        value: Expr,
+    },
+}
+
+impl Parse for Statement {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Token![let]) {
+            let _: Token![let] = input.parse()?;
+            let name: Ident = input.parse()?;
+            let annotation = if input.peek(Token![:]) {
+                let _: Token![:] = input.parse()?;
+                Some(input.parse()?)
+            } else {
+                None
+            };
+            let _: Token![=] = input.parse()?;
+            let value = input.parse()?;
+            return Ok(Statement::Let {
+                name,
+                annotation,
+                value,
+            });
+        }
+
+        let keyword: Ident = input.parse()?;
+        let (store, name) = match keyword.to_string().as_str() {
+            "labels" | "vector" => {
+                let name: Ident = input.parse()?;
+                let ty = if keyword == "labels" {
+                    Type::Labels
+                } else {
+                    Type::Vector {
+                        signed: false,
+                        labels: parse_labels_index(input)?.map(|labels| labels.to_string()),
+                    }
+                };
+                let _: Token![=] = input.parse()?;
+                let id = input.parse()?;
+                return Ok(Statement::Declare { name, ty, id });
+            }
+            "store" => (true, input.parse()?),
+            _ => (false, keyword),
+        };
+        let index = parse_labels_index(input)?;
+        let op = if input.peek(Token![+=]) {
+            let _: Token![+=] = input.parse()?;
+            AssignOp::Add
+        } else {
+            let _: Token![=] = input.parse()?;
+            AssignOp::Set
+        };
+        let value = input.parse()?;
+        Ok(Statement::Assign {
+            store,
+            name,
+            index,
+            op,
+            value,
+        })
+    }
+}
+
+/// Holds the entire list of statements from the macro invocation.
+pub(crate) struct Program {
+    statements: Vec<Statement>,
+    num_registers: usize, // #![registers(N)] available to program
+}
+
+impl Parse for Program {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut num_registers = DEFAULT_NUM_REGISTERS;
+        for attr in input.call(Attribute::parse_inner)? {
+            if attr.path().is_ident("registers") {
+                num_registers = attr.parse_args::<LitInt>()?.base10_parse()?;
+            } else {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "Unknown option, expected registers(N)",
+                ));
+            }
+        }
+
+        let mut statements = Vec::new();
+        while !input.is_empty() {
+            statements.push(input.parse()?);
+            let _: Token![;] = input.parse()?;
+        }
+        Ok(Self {
+            statements,
+            num_registers,
+        })
+    }
+}
+
+// --- Type Rules ---
+
+/// Operation on TOS using operand at [T-pos]
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Operation {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    SaturatingSub,
+    Pow,
+    Min,
+    Max,
+    Eq,
+    Gt,
+    Lt,
+    Net,
+}
+
+impl Operation {
+    fn mnemonic(self) -> &'static str {
+        match self {
+            Operation::Add => "ADD",
+            Operation::Sub => "SUB",
+            Operation::Mul => "MUL",
+            Operation::Div => "DIV",
+            Operation::SaturatingSub => "SSB",
+            Operation::Pow => "POW",
+            Operation::Min => "MIN",
+            Operation::Max => "MAX",
+            Operation::Eq => "EQ",
+            Operation::Gt => "GT",
+            Operation::Lt => "LT",
+            Operation::Net => "NET",
+        }
+    }
+
+    /// Same operation with operands swapped, if there is one
+    fn swapped(self) -> Option<Operation> {
+        match self {
+            Operation::Add | Operation::Mul | Operation::Min | Operation::Max | Operation::Eq => {
+                Some(self)
+            }
+            Operation::Gt => Some(Operation::Lt),
+            Operation::Lt => Some(Operation::Gt),
+            _ => None,
+        }
+    }
+
+    /// Signed TOS works with signed or unsigned operand
+    fn supports_signed(self) -> bool {
+        matches!(
+            self,
+            Operation::Add | Operation::Sub | Operation::Mul | Operation::Div
+        )
+    }
+
+    /// Vector TOS works with scalar operand
+    fn supports_scalar_operand(self) -> bool {
+        !matches!(self, Operation::Min | Operation::Max | Operation::Net)
+    }
+}
+
+/// How binary operation is lowered
+struct Plan {
+    operation: Operation,
+    swap: bool,            // right-hand side is evaluated on TOS
+    broadcast_tos: bool,   // scalar on TOS is broadcast to vector operand
+    broadcast_other: bool, // scalar operand is broadcast to vector on TOS
+    negate: bool,          // a - b is evaluated as -(b - a)
+    result: Type,
+}
+
+fn plan_binary(operation: Operation, lhs: &Type, rhs: &Type, span: Span) -> syn::Result<Plan> {
+    let mismatch = || {
+        syn::Error::new(
+            span,
+            format!(
+                "{} cannot be applied to {} and {}",
+                operation.mnemonic(),
+                lhs,
+                rhs
+            ),
+        )
+    };
+    let labels = merge_labels(lhs, rhs, span)?;
+
+    // Returns broadcasts needed, or None if TOS cannot take the operand
+    let orient = |tos: &Type, other: &Type| -> Option<(bool, bool)> {
+        match (tos, other) {
+            (Type::Labels, _) | (_, Type::Labels) => None,
+            (Type::Vector { signed: true, .. }, _) if operation.supports_signed() => {
+                Some((false, false))
+            }
+            (Type::Scalar { signed: true }, Type::Scalar { .. }) if operation.supports_signed() => {
+                Some((false, false))
+            }
+            _ if tos.is_signed() || other.is_signed() => None,
+            (Type::Vector { .. }, Type::Scalar { .. }) => {
+                Some((false, !operation.supports_scalar_operand()))
+            }
+            (Type::Scalar { .. }, Type::Vector { .. }) => Some((true, false)),
+            _ => Some((false, false)),
+        }
+    };
+
+    let mut candidates = vec![(operation, false, false)];
+    if let Some(swapped) = operation.swapped() {
+        candidates.push((swapped, true, false));
+    }
+
+    // Prefer orientation needing no broadcast, and only evaluate a - b as
+    // -(b - a) when unsigned a cannot take signed b
+    let mut best: Option<(Operation, bool, bool, bool, bool)> = None;
+    for (candidate, swap, negate) in candidates {
+        let (tos, other) = if swap { (rhs, lhs) } else { (lhs, rhs) };
+        if let Some((broadcast_tos, broadcast_other)) = orient(tos, other) {
+            let is_better = match best {
+                None => true,
+                Some((_, _, _, best_tos, best_other)) => {
+                    (best_tos || best_other) && !(broadcast_tos || broadcast_other)
+                }
+            };
+            if is_better {
+                best = Some((candidate, swap, negate, broadcast_tos, broadcast_other));
+            }
+        }
+    }
+    if best.is_none() && operation == Operation::Sub {
+        best = orient(rhs, lhs).map(|(broadcast_tos, broadcast_other)| {
+            (operation, true, true, broadcast_tos, broadcast_other)
+        });
+    }
+    let (operation, swap, negate, broadcast_tos, broadcast_other) = best.ok_or_else(mismatch)?;
+
+    let tos = if swap { rhs } else { lhs };
+    let signed = match operation {
+        Operation::Net => true,
+        Operation::Eq | Operation::Gt | Operation::Lt => false,
+        _ => tos.is_signed() || negate,
+    };
+    let result = if lhs.is_vector() || rhs.is_vector() {
+        Type::Vector { signed, labels }
+    } else {
+        Type::Scalar { signed }
+    };
+    Ok(Plan {
+        operation,
+        swap,
+        broadcast_tos,
+        broadcast_other,
+        negate,
+        result,
+    })
+}
+
+/// Labels of vector combining operands, which must be indexed by same labels
+fn merge_labels(lhs: &Type, rhs: &Type, span: Span) -> syn::Result<Option<String>> {
+    match (lhs.labels(), rhs.labels()) {
+        (Some(a), Some(b)) if a != b => Err(syn::Error::new(
+            span,
+            format!(
+                "Vector indexed by {} cannot be combined with vector indexed by {}, re-index it with [{}]",
+                a, b, a
+            ),
+        )),
+        (Some(labels), _) | (_, Some(labels)) => Ok(Some(labels.to_string())),
+        _ => Ok(None),
+    }
+}
+
+fn binary_operation(op: &BinOp) -> Option<Operation> {
+    match op {
+        BinOp::Add(_) => Some(Operation::Add),
+        BinOp::Sub(_) => Some(Operation::Sub),
+        BinOp::Mul(_) => Some(Operation::Mul),
+        BinOp::Div(_) => Some(Operation::Div),
+        BinOp::Eq(_) => Some(Operation::Eq),
+        BinOp::Gt(_) => Some(Operation::Gt),
+        BinOp::Lt(_) => Some(Operation::Lt),
+        _ => None,
+    }
+}
+
+fn call_operation(name: &str) -> Option<Operation> {
+    match name {
+        "min" => Some(Operation::Min),
+        "max" => Some(Operation::Max),
+        "pow" => Some(Operation::Pow),
+        "ssub" => Some(Operation::SaturatingSub),
+        "net" => Some(Operation::Net),
+        _ => None,
+    }
+}
+
+/// Strip parentheses
+fn unparen(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Paren(paren) => unparen(&paren.expr),
+        Expr::Group(group) => unparen(&group.expr),
+        _ => expr,
+    }
+}
+
+fn call_name(call: &syn::ExprCall) -> syn::Result<Ident> {
+    match &*call.func {
+        Expr::Path(path) => path.path.require_ident().cloned(),
+        func => Err(syn::Error::new(func.span(), "Expected function name")),
+    }
+}
+
+fn var_name(expr: &Expr) -> Option<&Ident> {
+    match unparen(expr) {
+        Expr::Path(path) => path.path.get_ident(),
+        _ => None,
+    }
+}
+
+fn expect_args<'a>(
+    call: &'a syn::ExprCall,
+    name: &Ident,
+    count: usize,
+) -> syn::Result<Vec<&'a Expr>> {
+    if call.args.len() != count {
+        return Err(syn::Error::new(
+            name.span(),
+            format!("{} takes {} arguments", name, count),
+        ));
+    }
+    Ok(call.args.iter().collect())
+}
+
+// --- Code Generation ---
+
+/// Variable bound to storage or register
+enum Binding {
+    /// Vector or labels in VIO, and register caching its value
+    Storage {
+        ty: Type,
+        id: Expr,
+        cache: Option<usize>,
+    },
+    /// Value in register, or in no register once it is no longer read
+    Local { ty: Type, register: Option<usize> },
+}
+
+impl Binding {
+    fn ty(&self) -> &Type {
+        match self {
+            Binding::Storage { ty, .. } | Binding::Local { ty, .. } => ty,
+        }
+    }
+}
+
+struct Compiler {
+    bindings: HashMap<String, Binding>,
+
+    /// Number of reads of each variable, counted by the first pass, and then
+    /// remaining reads, so that register is released after the last one
+    reads: HashMap<String, usize>,
+    counting: bool,
+
+    registers: Vec<bool>,
+    num_registers: usize,
+
+    /// Stack depth, where operands below TOS are dropped at end of statement
+    depth: usize,
+    instructions: Vec<Instruction>,
+}
+
+fn literal(value: usize, span: Span) -> InstructionArg {
+    InstructionArg::Literal(Expr::Lit(syn::ExprLit {
+        attrs: Vec::new(),
+        lit: Lit::Int(LitInt::new(&value.to_string(), span)),
+    }))
+}
+
+fn register(index: usize, span: Span) -> InstructionArg {
+    InstructionArg::Register(Ident::new(&format!("_r{}", index), span))
+}
+
+fn storage_id(id: &Expr) -> InstructionArg {
+    match var_name(id) {
+        Some(ident) => InstructionArg::Constant(ident.clone()),
+        None => InstructionArg::Literal(id.clone()),
+    }
+}
+
+impl Compiler {
+    fn new(num_registers: usize, reads: Option<HashMap<String, usize>>) -> Self {
+        Self {
+            bindings: HashMap::new(),
+            counting: reads.is_none(),
+            reads: reads.unwrap_or_default(),
+            registers: Vec::new(),
+            num_registers,
+            depth: 0,
+            instructions: Vec::new(),
+        }
+    }
+
+    fn emit(&mut self, mnemonic: &str, args: Vec<InstructionArg>, span: Span) {
+        self.instructions.push(Instruction {
+            mnemonic: Ident::new(mnemonic, span),
+            args,
+            num_tokens: 0,
+        });
+    }
+
+    /// Position relative to TOS of operand at stack `index`
+    fn pos(&self, index: usize, span: Span) -> syn::Result<InstructionArg> {
+        let pos = self.depth - 1 - index;
+        if pos > u8::MAX as usize {
+            return Err(syn::Error::new(span, "Expression is too deep"));
+        }
+        Ok(literal(pos, span))
+    }
+
+    fn top(&self) -> usize {
+        self.depth - 1
+    }
+
+    fn allocate(&mut self, span: Span) -> syn::Result<usize> {
+        let index = match self.registers.iter().position(|used| !used) {
+            Some(index) => index,
+            None => {
+                self.registers.push(false);
+                self.registers.len() - 1
+            }
+        };
+        if index >= self.num_registers {
+            return Err(syn::Error::new(
+                span,
+                format!(
+                    "Program needs more than {} registers, set #![registers(N)]",
+                    self.num_registers
+                ),
+            ));
+        }
+        self.registers[index] = true;
+        Ok(index)
+    }
+
+    fn binding(&self, name: &Ident) -> syn::Result<&Binding> {
+        self.bindings
+            .get(&name.to_string())
+            .ok_or_else(|| syn::Error::new(name.span(), format!("Unknown variable {}", name)))
+    }
+
+    fn declare(&mut self, name: &Ident, binding: Binding) -> syn::Result<()> {
+        let key = name.to_string();
+        if self.bindings.contains_key(&key) {
+            return Err(syn::Error::new(
+                name.span(),
+                format!("Variable {} is already declared", name),
+            ));
+        }
+        self.bindings.insert(key, binding);
+        Ok(())
+    }
+
+    /// Labels variable indexing vectors
+    fn labels_binding(&self, name: &Ident) -> syn::Result<()> {
+        match self.binding(name)?.ty() {
+            Type::Labels => Ok(()),
+            ty => Err(syn::Error::new(
+                name.span(),
+                format!("Vector must be indexed by labels, but {} is {}", name, ty),
+            )),
+        }
+    }
+
+    /// Count read, and tell whether more reads follow
+    fn read(&mut self, name: &str) -> bool {
+        let count = self.reads.entry(name.to_string()).or_default();
+        if self.counting {
+            *count += 1;
+            true
+        } else {
+            *count = count.saturating_sub(1);
+            *count > 0
+        }
+    }
+
+    fn is_read_later(&self, name: &str) -> bool {
+        self.counting || self.reads.get(name).is_some_and(|count| *count > 0)
+    }
+
+    /// Push value of variable
+    fn load(&mut self, name: &Ident) -> syn::Result<Type> {
+        let span = name.span();
+        let key = name.to_string();
+        let is_read_later = self.read(&key);
+        let (ty, source) = match self.binding(name)? {
+            Binding::Storage { ty, id, cache } => (ty.clone(), Ok((id.clone(), *cache))),
+            Binding::Local { ty, register } => (ty.clone(), Err(*register)),
+        };
+        match source {
+            Ok((_, Some(cache))) | Err(Some(cache)) => {
+                if is_read_later {
+                    self.emit("LDR", vec![register(cache, span)], span);
+                } else {
+                    self.emit("LDM", vec![register(cache, span)], span);
+                    self.registers[cache] = false;
+                    self.set_register(&key, None);
+                }
+            }
+            Ok((id, None)) => {
+                let mnemonic = if ty == Type::Labels { "LDL" } else { "LDV" };
+                self.emit(mnemonic, vec![storage_id(&id)], span);
+                if is_read_later {
+                    let cache = self.allocate(span)?;
+                    self.emit("STR", vec![register(cache, span)], span);
+                    self.emit("LDR", vec![register(cache, span)], span);
+                    self.set_register(&key, Some(cache));
+                }
+            }
+            Err(None) => {
+                return Err(syn::Error::new(
+                    span,
+                    format!("Variable {} has no value", name),
+                ));
+            }
+        }
+        self.depth += 1;
+        Ok(ty)
+    }
+
+    fn set_register(&mut self, key: &str, value: Option<usize>) {
+        match self.bindings.get_mut(key) {
+            Some(Binding::Storage { cache, .. }) => *cache = value,
+            Some(Binding::Local { register, .. }) => *register = value,
+            None => {}
+        }
+    }
+
+    /// Drop operands below TOS pushed since stack had `base` operands
+    fn compact(&mut self, base: usize, span: Span) {
+        let count = self.depth - 1 - base;
+        if count > 0 {
+            self.emit("SWAP", vec![literal(count, span)], span);
+            self.emit("POPN", vec![literal(count, span)], span);
+            self.depth -= count;
+        }
+    }
+
+    /// Drop all operands at end of statement
+    fn finish(&mut self, span: Span) {
+        if self.depth > 0 {
+            self.emit("POPN", vec![literal(self.depth, span)], span);
+            self.depth = 0;
+        }
+    }
+
+    /// Broadcast scalar on TOS to vector or labels at stack `index`
+    fn broadcast(&mut self, index: usize, span: Span) -> syn::Result<()> {
+        let pos = self.pos(index, span)?;
+        self.emit("ONES", vec![pos], span);
+        self.depth += 1;
+        self.emit("MUL", vec![literal(1, span)], span);
+        Ok(())
+    }
+
+    /// Type of expression without generating any code
+    fn infer(&self, expr: &Expr) -> syn::Result<Type> {
+        let span = expr.span();
+        match unparen(expr) {
+            Expr::Lit(lit) => match lit.lit {
+                Lit::Int(_) | Lit::Float(_) => Ok(Type::Scalar { signed: false }),
+                _ => Err(syn::Error::new(span, "Expected number")),
+            },
+            Expr::Path(_) => {
+                let name =
+                    var_name(expr).ok_or_else(|| syn::Error::new(span, "Expected variable"))?;
+                Ok(self.binding(name)?.ty().clone())
+            }
+            Expr::Unary(unary) => self.infer_neg(unary),
+            Expr::Binary(binary) => {
+                let operation = binary_operation(&binary.op)
+                    .ok_or_else(|| syn::Error::new(binary.op.span(), "Unsupported operator"))?;
+                let lhs = self.infer(&binary.left)?;
+                let rhs = self.infer(&binary.right)?;
+                Ok(plan_binary(operation, &lhs, &rhs, binary.op.span())?.result)
+            }
+            Expr::Index(index) => self.infer_index(index),
+            Expr::Array(array) => {
+                for item in &array.elems {
+                    self.infer_item(item)?;
+                }
+                if array.elems.is_empty() {
+                    return Err(syn::Error::new(span, "Vector must have components"));
+                }
+                Ok(Type::Vector {
+                    signed: false,
+                    labels: None,
+                })
+            }
+            Expr::Call(call) => self.infer_call(call),
+            _ => Err(syn::Error::new(span, "Unsupported expression")),
+        }
+    }
+
+    fn infer_neg(&self, unary: &syn::ExprUnary) -> syn::Result<Type> {
+        if !matches!(unary.op, UnOp::Neg(_)) {
+            return Err(syn::Error::new(unary.op.span(), "Unsupported operator"));
+        }
+        match self.infer(&unary.expr)? {
+            Type::Labels => Err(syn::Error::new(unary.span(), "Labels cannot be negated")),
+            ty => Ok(ty.with_signed(true)),
+        }
+    }
+
+    fn infer_item(&self, item: &Expr) -> syn::Result<()> {
+        match self.infer(item)? {
+            Type::Scalar { signed: false } => Ok(()),
+            ty => Err(syn::Error::new(
+                item.span(),
+                format!("Vector components must be scalars, found {}", ty),
+            )),
+        }
+    }
+
+    fn infer_index(&self, index: &syn::ExprIndex) -> syn::Result<Type> {
+        let labels = var_name(&index.index)
+            .ok_or_else(|| syn::Error::new(index.index.span(), "Expected labels variable"))?;
+        self.labels_binding(labels)?;
+        match self.infer(&index.expr)? {
+            Type::Vector {
+                signed: false,
+                labels: Some(_),
+            } => Ok(Type::Vector {
+                signed: false,
+                labels: Some(labels.to_string()),
+            }),
+            ty => Err(syn::Error::new(
+                index.expr.span(),
+                format!(
+                    "Only unsigned vector indexed by labels can be re-indexed, found {}",
+                    ty
+                ),
+            )),
+        }
+    }
+
+    fn infer_call(&self, call: &syn::ExprCall) -> syn::Result<Type> {
+        let name = call_name(call)?;
+        let name_str = name.to_string();
+        let span = name.span();
+        let require = |valid: bool, expected: &str, found: &Type| {
+            if valid {
+                Ok(())
+            } else {
+                Err(syn::Error::new(
+                    span,
+                    format!("{} expects {}, found {}", name, expected, found),
+                ))
+            }
+        };
+
+        if let Some(operation) = call_operation(&name_str) {
+            let args = expect_args(call, &name, 2)?;
+            let lhs = self.infer(args[0])?;
+            let rhs = self.infer(args[1])?;
+            return Ok(plan_binary(operation, &lhs, &rhs, span)?.result);
+        }
+
+        match name_str.as_str() {
+            "sqrt" | "exp" | "ln" => {
+                let ty = self.infer(expect_args(call, &name, 1)?[0])?;
+                let valid = ty.is_unsigned_scalar() || ty.is_unsigned_vector();
+                require(valid, "unsigned scalar or vector", &ty)?;
+                Ok(ty)
+            }
+            "abs" | "pos" | "negpart" => {
+                let ty = self.infer(expect_args(call, &name, 1)?[0])?;
+                require(ty.is_signed(), "signed scalar or vector", &ty)?;
+                Ok(ty.with_signed(false))
+            }
+            "vsum" => {
+                let ty = self.infer(expect_args(call, &name, 1)?[0])?;
+                require(ty.is_vector(), "vector", &ty)?;
+                Ok(Type::Scalar {
+                    signed: ty.is_signed(),
+                })
+            }
+            "vmin" | "vmax" => {
+                let ty = self.infer(expect_args(call, &name, 1)?[0])?;
+                require(ty.is_unsigned_vector(), "unsigned vector", &ty)?;
+                Ok(Type::Scalar { signed: false })
+            }
+            "cumsum" | "norm" => {
+                let ty = self.infer(expect_args(call, &name, 1)?[0])?;
+                require(ty.is_unsigned_vector(), "unsigned vector", &ty)?;
+                Ok(ty)
+            }
+            "vdot" => {
+                let args = expect_args(call, &name, 2)?;
+                let lhs = self.infer(args[0])?;
+                let rhs = self.infer(args[1])?;
+                merge_labels(&lhs, &rhs, span)?;
+                require(lhs.is_unsigned_vector(), "unsigned vectors", &lhs)?;
+                require(rhs.is_unsigned_vector(), "unsigned vectors", &rhs)?;
+                Ok(Type::Scalar { signed: false })
+            }
+            "clamp" => {
+                let args = expect_args(call, &name, 3)?;
+                let value = self.infer(args[0])?;
+                let lo = self.infer(args[1])?;
+                let hi = self.infer(args[2])?;
+                let labels = merge_labels(&value, &lo, span)?;
+                let labels = merge_labels(
+                    &Type::Vector {
+                        signed: false,
+                        labels,
+                    },
+                    &hi,
+                    span,
+                )?;
+                let bounds = |ty: &Type| ty.is_unsigned_scalar() || ty.is_unsigned_vector();
+                if value.is_unsigned_vector() && bounds(&lo) && bounds(&hi) {
+                    Ok(Type::Vector {
+                        signed: false,
+                        labels,
+                    })
+                } else if value.is_unsigned_scalar()
+                    && lo.is_unsigned_scalar()
+                    && hi.is_unsigned_scalar()
+                {
+                    Ok(value)
+                } else {
+                    Err(syn::Error::new(
+                        span,
+                        format!(
+                            "clamp cannot be applied to {} between {} and {}",
+                            value, lo, hi
+                        ),
+                    ))
+                }
+            }
+            "select" => {
+                let args = expect_args(call, &name, 3)?;
+                let mask = self.infer(args[0])?;
+                let value = self.infer(args[1])?;
+                let other = self.infer(args[2])?;
+                let labels = merge_labels(&mask, &value, span)?;
+                let labels = merge_labels(
+                    &Type::Vector {
+                        signed: false,
+                        labels,
+                    },
+                    &other,
+                    span,
+                )?;
+                let operand = |ty: &Type| ty.is_unsigned_scalar() || ty.is_unsigned_vector();
+                if !(operand(&mask) && operand(&value) && operand(&other)) {
+                    return Err(syn::Error::new(
+                        span,
+                        format!(
+                            "select cannot be applied to {}, {} and {}",
+                            mask, value, other
+                        ),
+                    ));
+                }
+                if mask.is_vector() || value.is_vector() || other.is_vector() {
+                    Ok(Type::Vector {
+                        signed: false,
+                        labels,
+                    })
+                } else {
+                    Ok(value)
+                }
+            }
+            "zeros" | "ones" => {
+                let arg = expect_args(call, &name, 1)?[0];
+                match self.infer(arg)? {
+                    Type::Labels => Ok(Type::Vector {
+                        signed: false,
+                        labels: var_name(arg).map(|labels| labels.to_string()),
+                    }),
+                    Type::Vector {
+                        signed: false,
+                        labels,
+                    } => Ok(Type::Vector {
+                        signed: false,
+                        labels,
+                    }),
+                    ty => {
+                        require(false, "labels or unsigned vector", &ty)?;
+                        Ok(ty)
+                    }
+                }
+            }
+            "union" => {
+                let args = expect_args(call, &name, 2)?;
+                let lhs = self.infer(args[0])?;
+                let rhs = self.infer(args[1])?;
+                require(lhs == Type::Labels, "labels", &lhs)?;
+                require(rhs == Type::Labels, "labels", &rhs)?;
+                Ok(Type::Labels)
+            }
+            "round_up" | "round_even" => {
+                let arg = expect_args(call, &name, 1)?[0];
+                let ty = self.infer(arg)?;
+                match unparen(arg) {
+                    Expr::Binary(binary) if matches!(binary.op, BinOp::Mul(_) | BinOp::Div(_)) => {
+                        require(!ty.is_signed(), "unsigned product or quotient", &ty)?;
+                        Ok(ty)
+                    }
+                    _ => Err(syn::Error::new(
+                        arg.span(),
+                        format!("{} applies to product or quotient", name),
+                    )),
+                }
+            }
+            _ => Err(syn::Error::new(span, format!("Unknown function {}", name))),
+        }
+    }
+
+    /// Generate code pushing value of expression on TOS
+    fn gen(&mut self, expr: &Expr) -> syn::Result<Type> {
+        let ty = self.infer(expr)?;
+        let span = expr.span();
+        match unparen(expr) {
+            Expr::Lit(lit) => {
+                self.emit(
+                    "IMMS",
+                    vec![InstructionArg::Literal(Expr::Lit(lit.clone()))],
+                    span,
+                );
+                self.depth += 1;
+            }
+            Expr::Path(_) => {
+                if let Some(name) = var_name(expr) {
+                    self.load(&name.clone())?;
+                }
+            }
+            Expr::Unary(unary) => {
+                self.gen(&unary.expr)?;
+                self.emit("NEG", vec![], unary.op.span());
+            }
+            Expr::Binary(binary) => {
+                let operation = binary_operation(&binary.op)
+                    .ok_or_else(|| syn::Error::new(binary.op.span(), "Unsupported operator"))?;
+                self.gen_binary(
+                    operation,
+                    &binary.left,
+                    &binary.right,
+                    None,
+                    binary.op.span(),
+                )?;
+            }
+            Expr::Index(index) => {
+                if let Some(labels) = var_name(&index.index) {
+                    self.gen_index(&index.expr, &labels.clone(), span)?;
+                }
+            }
+            Expr::Array(array) => {
+                for item in &array.elems {
+                    let base = self.depth;
+                    self.gen(item)?;
+                    self.compact(base, item.span());
+                }
+                let count = array.elems.len();
+                self.emit("PKV", vec![literal(count, span)], span);
+                self.depth -= count - 1;
+            }
+            Expr::Call(call) => self.gen_call(call)?,
+            _ => return Err(syn::Error::new(span, "Unsupported expression")),
+        }
+        Ok(ty)
+    }
+
+    fn gen_binary(
+        &mut self,
+        operation: Operation,
+        lhs: &Expr,
+        rhs: &Expr,
+        rounding: Option<&str>,
+        span: Span,
+    ) -> syn::Result<()> {
+        let plan = plan_binary(operation, &self.infer(lhs)?, &self.infer(rhs)?, span)?;
+        let (tos, other) = if plan.swap { (rhs, lhs) } else { (lhs, rhs) };
+        self.gen(other)?;
+        let mut other_index = self.top();
+        self.gen(tos)?;
+        if plan.broadcast_tos {
+            self.broadcast(other_index, span)?;
+        }
+        if plan.broadcast_other {
+            self.emit("ONES", vec![literal(0, span)], span);
+            self.depth += 1;
+            let pos = self.pos(other_index, span)?;
+            self.emit("MUL", vec![pos], span);
+            self.emit("SWAP", vec![literal(1, span)], span);
+            other_index = self.top() - 1;
+        }
+        if let Some(rounding) = rounding {
+            self.emit(rounding, vec![], span);
+        }
+        let pos = self.pos(other_index, span)?;
+        self.emit(plan.operation.mnemonic(), vec![pos], span);
+        if plan.negate {
+            self.emit("NEG", vec![], span);
+        }
+        Ok(())
+    }
+
+    /// Re-index vector by other labels, i.e. `vector[labels]`
+    fn gen_index(&mut self, vector: &Expr, labels: &Ident, span: Span) -> syn::Result<()> {
+        let from = self.infer(vector)?.labels().map(str::to_string);
+        let Some(from) = from.filter(|from| *labels != from) else {
+            self.gen(vector)?;
+            return Ok(());
+        };
+        self.load(labels)?;
+        let to_index = self.top();
+        self.load(&Ident::new(&from, span))?;
+        let from_index = self.top();
+        self.gen(vector)?;
+        let args = vec![self.pos(from_index, span)?, self.pos(to_index, span)?];
+        self.emit("JFLT", args, span);
+        Ok(())
+    }
+
+    fn gen_call(&mut self, call: &syn::ExprCall) -> syn::Result<()> {
+        let name = call_name(call)?;
+        let name_str = name.to_string();
+        let span = name.span();
+        let args: Vec<&Expr> = call.args.iter().collect();
+
+        if let Some(operation) = call_operation(&name_str) {
+            return self.gen_binary(operation, args[0], args[1], None, span);
+        }
+
+        match name_str.as_str() {
+            "sqrt" | "exp" | "ln" | "abs" | "pos" | "negpart" | "vsum" | "vmin" | "vmax" => {
+                self.gen(args[0])?;
+                let mnemonic = name_str.to_uppercase();
+                self.emit(&mnemonic, vec![], span);
+            }
+            "cumsum" | "norm" => {
+                self.gen(args[0])?;
+                let mnemonic = format!("V{}", name_str.to_uppercase());
+                self.emit(&mnemonic, vec![], span);
+            }
+            "vdot" => {
+                self.gen(args[1])?;
+                let other_index = self.top();
+                self.gen(args[0])?;
+                let pos = self.pos(other_index, span)?;
+                self.emit("VDOT", vec![pos], span);
+            }
+            "union" => {
+                self.gen(args[1])?;
+                let other_index = self.top();
+                self.gen(args[0])?;
+                let pos = self.pos(other_index, span)?;
+                self.emit("LUNION", vec![pos], span);
+            }
+            "zeros" | "ones" => {
+                self.gen(args[0])?;
+                let mnemonic = name_str.to_uppercase();
+                self.emit(&mnemonic, vec![literal(0, span)], span);
+                self.depth += 1;
+            }
+            "clamp" | "select" => {
+                // clamp(value, lo, hi) and select(mask, value, other)
+                let (value, first, second) = if name_str == "clamp" {
+                    (args[0], args[1], args[2])
+                } else {
+                    (args[1], args[0], args[2])
+                };
+                let first_ty = self.gen(first)?;
+                let first_index = self.top();
+                let second_ty = self.gen(second)?;
+                let second_index = self.top();
+                let value_ty = self.gen(value)?;
+                if !value_ty.is_vector() {
+                    if second_ty.is_vector() {
+                        self.broadcast(second_index, span)?;
+                    } else if first_ty.is_vector() {
+                        self.broadcast(first_index, span)?;
+                    }
+                }
+                let args = vec![self.pos(first_index, span)?, self.pos(second_index, span)?];
+                let mnemonic = if name_str == "clamp" { "CLAMP" } else { "SEL" };
+                self.emit(mnemonic, args, span);
+            }
+            "round_up" | "round_even" => {
+                let rounding = if name_str == "round_up" {
+                    "RNDU"
+                } else {
+                    "RNDE"
+                };
+                if let Expr::Binary(binary) = unparen(args[0]) {
+                    if let Some(operation) = binary_operation(&binary.op) {
+                        let span = binary.op.span();
+                        let (lhs, rhs) = (&binary.left, &binary.right);
+                        self.gen_binary(operation, lhs, rhs, Some(rounding), span)?;
+                    }
+                }
+            }
+            _ => return Err(syn::Error::new(span, format!("Unknown function {}", name))),
+        }
+        Ok(())
+    }
+
+    /// Store value on TOS in register of local variable
+    fn assign_local(&mut self, name: &Ident) -> syn::Result<()> {
+        let span = name.span();
+        let key = name.to_string();
+        if self.is_read_later(&key) {
+            let current = match self.bindings.get(&key) {
+                Some(Binding::Local { register, .. }) => *register,
+                _ => None,
+            };
+            let index = match current {
+                Some(index) => index,
+                None => self.allocate(span)?,
+            };
+            self.emit("STR", vec![register(index, span)], span);
+            self.set_register(&key, Some(index));
+        } else {
+            self.emit("POPN", vec![literal(1, span)], span);
+        }
+        self.depth -= 1;
+        Ok(())
+    }
+
+    fn compile(&mut self, statements: &[Statement]) -> syn::Result<()> {
+        for statement in statements {
+            match statement {
+                Statement::Declare { name, ty, id } => {
+                    if let Some(labels) = ty.labels() {
+                        self.labels_binding(&Ident::new(labels, name.span()))?;
+                    }
+                    let binding = Binding::Storage {
+                        ty: ty.clone(),
+                        id: id.clone(),
+                        cache: None,
+                    };
+                    self.declare(name, binding)?;
+                }
+                Statement::Let {
+                    name,
+                    annotation,
+                    value,
+                } => {
+                    let mut ty = self.gen(value)?;
+                    if let Some(annotation) = annotation {
+                        if !ty.conforms_to(&annotation.ty) {
+                            return Err(syn::Error::new(
+                                annotation.span,
+                                format!("Expected {}, but value is {}", annotation.ty, ty),
+                            ));
+                        }
+                        ty = annotation.ty.clone();
+                    }
+                    if let Some(labels) = ty.labels() {
+                        self.labels_binding(&Ident::new(labels, name.span()))?;
+                    }
+                    self.declare(name, Binding::Local { ty, register: None })?;
+                    self.assign_local(name)?;
+                    self.finish(name.span());
+                }
+                Statement::Assign {
+                    store,
+                    name,
+                    index,
+                    op,
+                    value,
+                } => {
+                    self.compile_assign(*store, name, index.as_ref(), op, value)?;
+                    self.finish(name.span());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn compile_assign(
+        &mut self,
+        store: bool,
+        name: &Ident,
+        index: Option<&Ident>,
+        op: &AssignOp,
+        value: &Expr,
+    ) -> syn::Result<()> {
+        let span = name.span();
+        let target = self.binding(name)?.ty().clone();
+        match (store, self.binding(name)?) {
+            (true, Binding::Local { .. }) => {
+                return Err(syn::Error::new(
+                    span,
+                    format!("{} is not declared as labels or vector in storage", name),
+                ));
+            }
+            (false, Binding::Storage { .. }) => {
+                return Err(syn::Error::new(
+                    span,
+                    format!("{} is stored in VIO, use store {} = ...", name, name),
+                ));
+            }
+            (false, _) if target == Type::Labels => {
+                return Err(syn::Error::new(
+                    span,
+                    format!(
+                        "Labels {} cannot be assigned, as vectors are indexed by them",
+                        name
+                    ),
+                ));
+            }
+            _ => {}
+        }
+
+        // Components with labels are updated using JUPD or JADD, unless those
+        // are labels of the whole vector
+        let index = index.filter(|index| target.labels() != Some(index.to_string().as_str()));
+        let value_ty = match index {
+            None => {
+                let value_ty = match op {
+                    AssignOp::Set => self.gen(value)?,
+                    AssignOp::Add => {
+                        let lhs = Expr::Path(syn::ExprPath {
+                            attrs: Vec::new(),
+                            qself: None,
+                            path: name.clone().into(),
+                        });
+                        self.gen_binary(Operation::Add, &lhs, value, None, span)?;
+                        plan_binary(Operation::Add, &target, &self.infer(value)?, span)?.result
+                    }
+                };
+                if !value_ty.conforms_to(&target) {
+                    return Err(syn::Error::new(
+                        value.span(),
+                        format!("Expected {}, but value is {}", target, value_ty),
+                    ));
+                }
+                value_ty
+            }
+            Some(index) => {
+                self.labels_binding(index)?;
+                let Some(labels) = target.labels().map(str::to_string) else {
+                    return Err(syn::Error::new(
+                        span,
+                        format!(
+                            "{} must be vector indexed by labels, found {}",
+                            name, target
+                        ),
+                    ));
+                };
+                if target.is_signed() {
+                    return Err(syn::Error::new(
+                        span,
+                        format!("{} must be unsigned vector, found {}", name, target),
+                    ));
+                }
+                self.load(&Ident::new(&labels, span))?;
+                let labels_index = self.top();
+                self.load(index)?;
+                let index_index = self.top();
+                let value_ty = self.gen(value)?;
+                let expected = Type::Vector {
+                    signed: false,
+                    labels: Some(index.to_string()),
+                };
+                if value_ty.is_unsigned_scalar() {
+                    self.broadcast(index_index, value.span())?;
+                } else if !value_ty.conforms_to(&expected) {
+                    return Err(syn::Error::new(
+                        value.span(),
+                        format!("Expected {} or scalar, but value is {}", expected, value_ty),
+                    ));
+                }
+                let value_index = self.top();
+                self.load(name)?;
+                let mnemonic = match op {
+                    AssignOp::Set => "JUPD",
+                    AssignOp::Add => "JADD",
+                };
+                let args = vec![
+                    self.pos(value_index, span)?,
+                    self.pos(labels_index, span)?,
+                    self.pos(index_index, span)?,
+                ];
+                self.emit(mnemonic, args, span);
+                target.clone()
+            }
+        };
+
+        if !store {
+            return self.assign_local(name);
+        }
+        if value_ty.is_signed() {
+            return Err(syn::Error::new(
+                value.span(),
+                format!("Signed values cannot be stored, found {}", value_ty),
+            ));
+        }
+        let key = name.to_string();
+        let Some(Binding::Storage { id, cache, .. }) = self.bindings.get_mut(&key) else {
+            return Ok(());
+        };
+        let id = storage_id(id);
+        // Value cached in register is no longer current
+        if let Some(cache) = cache.take() {
+            self.registers[cache] = false;
+        }
+        let mnemonic = if target == Type::Labels { "STL" } else { "STV" };
+        self.emit(mnemonic, vec![id], span);
+        self.depth -= 1;
+        Ok(())
+    }
+}
+
+/// Compile statements into instructions
+///
+/// First pass counts reads of each variable, and second pass uses the count
+/// to move value out of register on the last read, so that register can be
+/// reused by other variables.
+pub(crate) fn compile(program: &Program) -> syn::Result<Vec<Instruction>> {
+    let mut counter = Compiler::new(program.num_registers, None);
+    counter.compile(&program.statements)?;
+
+    let mut compiler = Compiler::new(program.num_registers, Some(counter.reads));
+    compiler.compile(&program.statements)?;
+    Ok(compiler.instructions)
+}
+
+#[cfg(test)]
+mod test {
+    use quote::ToTokens;
+
+    use super::*;
+
+    /// Compile program as `vil!` would, and list its instructions
+    fn compile_str(source: &str) -> syn::Result<Vec<String>> {
+        let program: Program = syn::parse_str(source)?;
+        let instructions = compile(&program)?;
+        crate::stack::simulate(&instructions, 0)?;
+        Ok(instructions
+            .iter()
+            .map(|instruction| {
+                let mut text = instruction.mnemonic.to_string();
+                for arg in &instruction.args {
+                    let arg = match arg {
+                        InstructionArg::Literal(expr) => expr.to_token_stream().to_string(),
+                        InstructionArg::Register(ident) | InstructionArg::Constant(ident) => {
+                            ident.to_string()
+                        }
+                        InstructionArg::Slot(slot) => slot.to_string(),
+                        InstructionArg::Procedure(index) => index.to_string(),
+                    };
+                    text = format!("{} {}", text, arg);
+                }
+                text
+            })
+            .collect())
+    }
+
+    fn error(source: &str) -> String {
+        match compile_str(source) {
+            Ok(_) => panic!("Expected error: {}", source),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_compile() {
+        let instructions = compile_str(
+            "labels assets = assets_id;
+            vector weights[assets] = weights_id;
+            vector quote = quote_id;
+            let price = vdot(weights, weights);
+            store quote = [price, 1];",
+        )
+        .unwrap();
+        assert_eq!(
+            instructions,
+            vec![
+                "LDV weights_id",
+                "STR _r0",
+                "LDR _r0",
+                "LDM _r0",
+                "VDOT 1",
+                "STR _r0",
+                "POPN 1",
+                "LDM _r0",
+                "IMMS 1",
+                "PKV 2",
+                "STV quote_id",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_type_mismatch() {
+        assert_eq!(
+            error("let x: scalar = [1, 2];"),
+            "Expected scalar, but value is vector"
+        );
+        assert_eq!(
+            error("labels assets = 1; vector v[assets] = 2; let x = v + assets;"),
+            "ADD cannot be applied to vector[assets] and labels"
+        );
+        assert_eq!(
+            error("labels a = 1; labels b = 2; vector x[a] = 3; vector y[b] = 4; let z = x * y;"),
+            "Vector indexed by a cannot be combined with vector indexed by b, re-index it with [a]"
+        );
+        assert_eq!(
+            error("vector v = 1; let x = abs(v);"),
+            "abs expects signed scalar or vector, found vector"
+        );
+        assert_eq!(
+            error("vector v = 1; store v = -v;"),
+            "Expected vector, but value is signed vector"
+        );
+        assert_eq!(
+            error("labels assets = 1; store assets = [1];"),
+            "Expected labels, but value is vector"
+        );
+        assert_eq!(
+            error("let x: matrix = 1;"),
+            "Expected scalar, vector, vector[labels] or labels"
+        );
+    }
+
+    #[test]
+    fn test_unknown_names() {
+        assert_eq!(error("vector v[assets] = 1;"), "Unknown variable assets");
+        assert_eq!(
+            error("vector v = 1; vector w[v] = 2;"),
+            "Vector must be indexed by labels, but v is vector"
+        );
+        assert_eq!(
+            error("labels a = 1; vector v[a] = 2; let x = v[market];"),
+            "Unknown variable market"
+        );
+        assert_eq!(error("let x = y + 1;"), "Unknown variable y");
+        assert_eq!(error("let x = foo(1);"), "Unknown function foo");
+        assert_eq!(
+            error("labels a = 1; labels a = 2;"),
+            "Variable a is already declared"
+        );
+        assert_eq!(
+            error("#![stack(3)] let x = 1;"),
+            "Unknown option, expected registers(N)"
+        );
+    }
+
+    #[test]
+    fn test_argument_count() {
+        assert_eq!(
+            error("vector v = 1; let x = vdot(v);"),
+            "vdot takes 2 arguments"
+        );
+        assert_eq!(error("let x = sqrt(1, 2);"), "sqrt takes 1 arguments");
+        assert_eq!(error("let x = clamp(1, 2);"), "clamp takes 3 arguments");
+        assert_eq!(error("let x = min(1, 2, 3);"), "min takes 2 arguments");
+        assert_eq!(error("let x = [];"), "Vector must have components");
+    }
+
+    #[test]
+    fn test_registers() {
+        let source = "vector v = 1; let x = vsum(v); store v = [x, x];";
+        assert!(compile_str(source).is_ok());
+        assert_eq!(
+            error(&format!("#![registers(0)] {}", source)),
+            "Program needs more than 0 registers, set #![registers(N)]"
+        );
+    }
+}
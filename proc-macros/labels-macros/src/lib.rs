@@ -39,11 +39,9 @@ pub fn label_vec(input: TokenStream) -> TokenStream {
 
     // 3. Generate the final output code wrapped in the Labels and vec! structure.
     let output = quote! {
-        Labels {
-            data: vec![
-                #(#label_tokens),*
-            ],
-        }
+        Labels::from(vec![
+            #(#label_tokens),*
+        ])
     };
 
     output.into()
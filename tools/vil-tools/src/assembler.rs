@@ -0,0 +1,445 @@
+use std::{collections::HashMap, fmt::Display};
+
+use abacus_runtime::decoder::{arg_types, op_code, ArgType, Encoding};
+use common::{abacus::instruction_set::*, amount::Amount};
+
+/// Program assembled from VIL source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assembly {
+    pub code: Vec<u8>,
+
+    /// Register names in order of their indices
+    pub registers: Vec<String>,
+}
+
+impl Assembly {
+    /// Number of registers program must be executed with
+    pub fn num_registers(&self) -> usize {
+        self.registers.len()
+    }
+}
+
+/// Error with location in VIL source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssemblyError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for AssemblyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AssemblyError {}
+
+struct Token<'a> {
+    text: &'a str,
+    line: usize,
+}
+
+/// Split source into tokens skipping `//` comments
+///
+/// Commas are treated as whitespace, same as `abacus!` ignores them, and
+/// brackets are tokens of their own. Options of `abacus!` stack checking,
+/// e.g. `#![inputs(3)]`, are skipped too.
+fn tokenize<'a>(source: &'a str) -> Vec<Token<'a>> {
+    let mut tokens = Vec::new();
+    for (index, line) in source.lines().enumerate() {
+        let code = match line.find("//") {
+            Some(pos) => &line[..pos],
+            None => line,
+        };
+        if code.trim_start().starts_with("#!") {
+            continue;
+        }
+        let mut start = 0;
+        let mut push = |text: &'a str| {
+            if !text.is_empty() {
+                tokens.push(Token {
+                    text,
+                    line: index + 1,
+                });
+            }
+        };
+        for (pos, c) in code.char_indices() {
+            if c.is_whitespace() || c == ',' || c == '[' || c == ']' {
+                push(&code[start..pos]);
+                if c == '[' || c == ']' {
+                    push(&code[pos..pos + 1]);
+                }
+                start = pos + c.len_utf8();
+            }
+        }
+        push(&code[start..]);
+    }
+    tokens
+}
+
+/// Parse Rust-style integer literal, e.g. `100`, `1_000`, `0x64` or `100u128`
+fn parse_int(text: &str) -> Option<u128> {
+    let text = text.replace('_', "");
+    let text = ["u8", "u16", "u32", "u64", "u128", "usize"]
+        .iter()
+        .find_map(|suffix| text.strip_suffix(suffix))
+        .unwrap_or(&text);
+    let (digits, radix) = if let Some(hex) = text.strip_prefix("0x") {
+        (hex, 16)
+    } else if let Some(oct) = text.strip_prefix("0o") {
+        (oct, 8)
+    } else if let Some(bin) = text.strip_prefix("0b") {
+        (bin, 2)
+    } else {
+        (text, 10)
+    };
+    u128::from_str_radix(digits, radix).ok()
+}
+
+/// Parse literal the same way as `amount!` does, e.g. `1`, `0.5` or `1_000.25`
+fn parse_amount(text: &str) -> Option<Amount> {
+    let text = text.replace('_', "");
+    let (integral, fraction) = text.split_once('.').unwrap_or((&text, ""));
+    if !integral
+        .chars()
+        .chain(fraction.chars())
+        .all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    let value: u128 = format!("{}{}", integral, fraction).parse().ok()?;
+    let scale = fraction.len();
+    let raw = if scale <= Amount::DECIMALS {
+        value.checked_mul(10u128.pow((Amount::DECIMALS - scale) as u32))?
+    } else {
+        value / 10u128.checked_pow((scale - Amount::DECIMALS) as u32)?
+    };
+    Some(Amount(raw))
+}
+
+/// Convert "asset_weights_id" -> "Asset Weights"
+///
+/// Same as used by `abacus!` for errors reported when storage id is zero.
+fn format_error_name(input: &str) -> String {
+    input
+        .trim_end_matches("_id")
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                None => String::new(),
+                Some(f) => f.to_uppercase().collect::<String>() + chars.as_str(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Instruction using argument slot instead of storage id or label
+///
+/// Same as `abacus!` replaces instructions using identifiers listed in
+/// `ARGS [...]`.
+fn slot_op_code(op_code: u8) -> Option<u8> {
+    match op_code {
+        OP_LDL => Some(OP_LDLA),
+        OP_LDV => Some(OP_LDVA),
+        OP_STL => Some(OP_STLA),
+        OP_STV => Some(OP_STVA),
+        OP_IMML => Some(OP_LDA),
+        _ => None,
+    }
+}
+
+/// Assemble VIL source into bytecode
+///
+/// Accepts the same syntax as `abacus!` macro, and produces identical
+/// bytecode:
+/// - registers are identifiers starting with `_`, and they are numbered in
+///   order of first use,
+/// - identifiers listed in `ARGS [...]` are argument slots of a template,
+/// - other identifiers are constants looked up in `params`, and they are
+///   encoded as-is, i.e. constant used with `IMMS` is raw `Amount`,
+/// - literals used with `IMMS` and `VPUSH` are decimal amounts.
+///
+/// Unlike `abacus!`, which fails at run-time, zero storage id is reported as
+/// an assembly error.
+pub fn assemble(source: &str, params: &HashMap<String, u128>) -> Result<Assembly, AssemblyError> {
+    assemble_with_encoding(source, params, Encoding::Fixed)
+}
+
+/// Assemble VIL source into bytecode with given encoding
+///
+/// Produces the same bytecode as `abacus_compact!` for `Encoding::Compact`.
+pub fn assemble_with_encoding(
+    source: &str,
+    params: &HashMap<String, u128>,
+    encoding: Encoding,
+) -> Result<Assembly, AssemblyError> {
+    let mut code = Vec::new();
+    encoding.write_header(&mut code);
+    let mut registers: Vec<String> = Vec::new();
+    let mut slots: Vec<&str> = Vec::new();
+
+    let tokens = tokenize(source);
+    let mut tokens = tokens.iter().peekable();
+    let mut is_first = true;
+
+    while let Some(token) = tokens.next() {
+        let error = |message: String| AssemblyError {
+            line: token.line,
+            message,
+        };
+        let mnemonic = token.text.to_uppercase();
+        let mut op_code = op_code(&mnemonic)
+            .ok_or_else(|| error(format!("Unknown VIL mnemonic: {}", mnemonic)))?;
+
+        // Template arguments, e.g. ARGS [asset_names_id, weights_id]
+        if op_code == OP_ARGS && tokens.next_if(|arg| arg.text == "[").is_some() {
+            if !is_first {
+                Err(error("ARGS must be the first instruction".to_string()))?;
+            }
+            while let Some(arg) = tokens.next_if(|arg| arg.text != "]") {
+                slots.push(arg.text);
+            }
+            tokens
+                .next()
+                .ok_or_else(|| error("Missing ] after ARGS".to_string()))?;
+            let count =
+                u8::try_from(slots.len()).map_err(|_| error("Too many arguments".to_string()))?;
+            code.extend([op_code, count]);
+            is_first = false;
+            continue;
+        }
+        is_first = false;
+
+        // Argument slot replaces storage id or label of the instruction
+        if let Some(slot_op_code) = slot_op_code(op_code) {
+            if tokens.peek().is_some_and(|arg| slots.contains(&arg.text)) {
+                op_code = slot_op_code;
+            }
+        }
+
+        let expected_types = arg_types(op_code).unwrap_or(&[]);
+
+        code.push(op_code);
+
+        for (i, expected_type) in expected_types.iter().enumerate() {
+            let arg = tokens.next().ok_or_else(|| {
+                error(format!(
+                    "Missing argument {} of {} for instruction {}",
+                    i + 1,
+                    expected_types.len(),
+                    mnemonic
+                ))
+            })?;
+            let error = |message: String| AssemblyError {
+                line: arg.line,
+                message,
+            };
+            let text = arg.text;
+            let is_register = text.starts_with('_');
+            let is_literal = text.starts_with(|c: char| c.is_ascii_digit());
+
+            let value = match expected_type {
+                ArgType::RegisterId if !is_register => Err(error(format!(
+                    "Argument {} for {} must be a register (e.g., _name).",
+                    i + 1,
+                    mnemonic
+                )))?,
+                ArgType::RegisterId => {
+                    let index = match registers.iter().position(|r| r == text) {
+                        Some(index) => index,
+                        None => {
+                            registers.push(text.to_string());
+                            registers.len() - 1
+                        }
+                    };
+                    index as u128
+                }
+                _ if is_register => Err(error(format!(
+                    "Argument {} for {} cannot be a register (_name). Expected a literal or constant.",
+                    i + 1,
+                    mnemonic
+                )))?,
+                ArgType::Argument if !is_literal => {
+                    slots.iter().position(|slot| *slot == text).ok_or_else(|| {
+                        error(format!("Unknown argument slot: {}", text))
+                    })? as u128
+                }
+                _ if slots.contains(&text) => Err(error(format!(
+                    "Argument slot cannot be used with {}, only with LDL, LDV, STL, STV or IMML",
+                    mnemonic
+                )))?,
+                ArgType::Amount if is_literal => parse_amount(text)
+                    .ok_or_else(|| error(format!("Invalid amount literal: {}", text)))?
+                    .to_u128_raw(),
+                _ if is_literal => {
+                    parse_int(text).ok_or_else(|| error(format!("Invalid literal: {}", text)))?
+                }
+                _ => *params
+                    .get(text)
+                    .ok_or_else(|| error(format!("Unknown constant: {}", text)))?,
+            };
+
+            match expected_type {
+                ArgType::RegisterId | ArgType::StackPos | ArgType::Size | ArgType::Argument => {
+                    let value = u8::try_from(value).map_err(|_| {
+                        error(format!(
+                            "Argument {} for {} out of range: {}",
+                            i + 1,
+                            mnemonic,
+                            text
+                        ))
+                    })?;
+                    code.push(value);
+                }
+                ArgType::StorageId | ArgType::Amount | ArgType::Label => {
+                    if matches!(expected_type, ArgType::StorageId) && value == 0 {
+                        let name = if is_literal {
+                            "Storage ID".to_string()
+                        } else {
+                            format_error_name(text)
+                        };
+                        Err(error(format!("{} cannot be zero", name)))?;
+                    }
+                    encoding.write(value, &mut code);
+                }
+            }
+        }
+    }
+
+    Ok(Assembly { code, registers })
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::formulas::{self, FORMULA_NAMES};
+
+    use super::*;
+
+    /// Extract body of `abacus_compact! { ... }` from formula source
+    fn abacus_body(source: &str) -> String {
+        let mut lines = source.lines();
+        let start = lines
+            .by_ref()
+            .find(|line| line.trim_start().starts_with("abacus_compact! {"))
+            .unwrap();
+        let indent = start.len() - start.trim_start().len();
+        let end = format!("{:indent$}}}", "", indent = indent);
+        lines
+            .take_while(|line| *line != end)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_assemble_formulas() {
+        let formulas_dir =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("../../libs/abacus-formulas/src");
+
+        for name in FORMULA_NAMES {
+            let formula = formulas::compile(name).unwrap().unwrap();
+            let params = formula
+                .params
+                .iter()
+                .enumerate()
+                .map(|(index, param)| (param.to_string(), index as u128 + 1))
+                .collect();
+
+            let source =
+                std::fs::read_to_string(formulas_dir.join(format!("{}.rs", name))).unwrap();
+            let assembly =
+                assemble_with_encoding(&abacus_body(&source), &params, Encoding::Compact)
+                    .unwrap_or_else(|err| panic!("Failed to assemble {}: {}", name, err));
+
+            assert_eq!(assembly.code, formula.code, "Bytecode of {} differs", name);
+        }
+    }
+
+    #[test]
+    fn test_assemble() {
+        let params = HashMap::from([("vector_id".to_string(), 100), ("zero_id".to_string(), 0)]);
+
+        let assembly = assemble(
+            "
+            // Comments and commas are ignored
+            LDV     vector_id       // Stack: [V]
+            imms    1.5             // Stack: [V, 1.5]
+            STR     _Factor
+            LDR     _Factor
+            LDR     _Other
+            LDR     _Factor
+            JUPD    1, 2, 3
+            VPUSH   1_000
+            B       0x10  2  1  4
+            ",
+            &params,
+        )
+        .unwrap();
+
+        let vector_id = 100;
+        let expected = abacus_macros::abacus! {
+            #![unchecked]
+            LDV     vector_id
+            IMMS    1.5
+            STR     _Factor
+            LDR     _Factor
+            LDR     _Other
+            LDR     _Factor
+            JUPD    1, 2, 3
+            VPUSH   1_000
+            B       0x10  2  1  4
+        };
+        assert_eq!(assembly.code, expected.unwrap());
+        assert_eq!(assembly.registers, vec!["_Factor", "_Other"]);
+
+        let source = "LDV vector_id\nIMMS 1.5\nSTV 300";
+        let assembly = assemble_with_encoding(source, &params, Encoding::Compact).unwrap();
+        let expected = abacus_macros::abacus_compact! {
+            LDV     vector_id
+            IMMS    1.5
+            STV     300
+        };
+        assert_eq!(assembly.code, expected.unwrap());
+
+        let source = "ARGS [names_id, vector_id]\nLDL names_id\nLDV vector_id\nSTV vector_id";
+        let assembly = assemble(source, &params).unwrap();
+        let expected = abacus_macros::abacus! {
+            ARGS [names_id, vector_id]
+            LDL     names_id
+            LDV     vector_id
+            STV     vector_id
+        };
+        assert_eq!(assembly.code, expected.unwrap());
+
+        let error = |source: &str| assemble(source, &params).unwrap_err();
+        assert_eq!(error("LDV 1\n\nFOO 1").message, "Unknown VIL mnemonic: FOO");
+        assert_eq!(error("LDV 1\n\nFOO 1").line, 3);
+        assert_eq!(
+            error("ADD").message,
+            "Missing argument 1 of 1 for instruction ADD"
+        );
+        assert_eq!(
+            error("LDV missing_id").message,
+            "Unknown constant: missing_id"
+        );
+        assert_eq!(error("LDV zero_id").message, "Zero cannot be zero");
+        assert_eq!(error("LDV 0").message, "Storage ID cannot be zero");
+        assert_eq!(
+            error("ADD 256").message,
+            "Argument 1 for ADD out of range: 256"
+        );
+        assert!(error("STR 1").message.contains("must be a register"));
+        assert!(error("ADD _x").message.contains("cannot be a register"));
+        assert_eq!(
+            error("LDV 1\nARGS [x]").message,
+            "ARGS must be the first instruction"
+        );
+        assert!(error("ARGS [x]\nADD x")
+            .message
+            .contains("cannot be used with ADD"));
+        assert_eq!(error("ARGS [x]\nLDA y").message, "Unknown argument slot: y");
+    }
+}
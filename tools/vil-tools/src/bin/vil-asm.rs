@@ -0,0 +1,92 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use abacus_runtime::{decoder::Encoding, optimizer::optimize};
+use clap::Parser;
+use eyre::{eyre, Context, Result};
+use vil_tools::{assembler::assemble_with_encoding, input};
+
+/// Assemble VIL program from text source
+///
+/// Source uses the same syntax as `abacus!` macro. Identifiers not starting
+/// with `_` are constants, which must be defined with `-D` or `--params`.
+#[derive(Parser)]
+#[command(name = "vil-asm")]
+struct Args {
+    /// Source file (.vil), or `-` for standard input
+    file: PathBuf,
+
+    /// Define constant, e.g. `-D asset_weights_id=105`
+    #[arg(short = 'D', value_name = "NAME=VALUE")]
+    define: Vec<String>,
+
+    /// File with constants, one `NAME=VALUE` per line
+    #[arg(long)]
+    params: Option<PathBuf>,
+
+    /// Encode storage ids, labels and immediates as LEB128, which makes
+    /// bytecode much shorter
+    #[arg(long)]
+    compact: bool,
+
+    /// Rewrite redundant instruction sequences, e.g. dead register stores
+    #[arg(long)]
+    optimize: bool,
+
+    /// Write raw bytecode to file instead of printing hex
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+fn parse_param(text: &str) -> Result<(String, u128)> {
+    let (name, value) = text
+        .split_once('=')
+        .ok_or_else(|| eyre!("Expected NAME=VALUE, got: {}", text))?;
+    let value =
+        input::parse_u128(value).with_context(|| format!("Invalid value of {}", name.trim()))?;
+    Ok((name.trim().to_string(), value))
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let mut params = HashMap::new();
+    if let Some(path) = &args.params {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        for line in text.lines() {
+            let line = line.split("//").next().unwrap_or_default().trim();
+            if !line.is_empty() {
+                let (name, value) = parse_param(line)?;
+                params.insert(name, value);
+            }
+        }
+    }
+    for define in &args.define {
+        let (name, value) = parse_param(define)?;
+        params.insert(name, value);
+    }
+
+    let source = String::from_utf8(input::read_file(&args.file)?)?;
+    let encoding = if args.compact {
+        Encoding::Compact
+    } else {
+        Encoding::Fixed
+    };
+    let assembly = assemble_with_encoding(&source, &params, encoding)
+        .map_err(|err| eyre!("{}: {}", args.file.display(), err))?;
+    let num_registers = assembly.num_registers();
+    let code = if args.optimize {
+        optimize(&assembly.code).map_err(|err| eyre!("Failed to optimize: {:?}", err))?
+    } else {
+        assembly.code
+    };
+
+    eprintln!("// {} bytes, {} registers", code.len(), num_registers);
+    match &args.output {
+        Some(path) => {
+            fs::write(path, &code).with_context(|| format!("Failed to write {}", path.display()))?
+        }
+        None => println!("0x{}", hex::encode(&code)),
+    }
+    Ok(())
+}
@@ -0,0 +1,356 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::{self, File},
+    io::{self, BufRead, BufWriter, Write},
+    path::PathBuf,
+};
+
+use abacus_runtime::{
+    decoder::mnemonic,
+    disassembler::decode_args,
+    runtime::VectorVM,
+    tracer::{Step, Tracer},
+    transaction::{TransactionalVectorIO, WriteSet},
+    verifier::verify,
+};
+use clap::Parser;
+use common::abacus::program_error::ErrorCode;
+use eyre::{bail, eyre, Context, Result};
+use vil_tools::{
+    assembler::assemble,
+    formulas, input,
+    storage::FileVectorIO,
+    trace::{format_frames, format_operand, JsonLinesTracer},
+};
+
+/// Run VIL program step by step against state stored in files
+///
+/// Labels, vectors and sub-routines are loaded from and stored to the state
+/// directory as `<id>.labels`, `<id>.vector` and `<id>.code` hex files.
+/// Labels and vectors are stored only if program completes.
+#[derive(Parser)]
+#[command(name = "vil-dbg")]
+struct Args {
+    /// Program file with raw or hex encoded bytecode, or `.vil` source
+    file: Option<PathBuf>,
+
+    /// Hex encoded bytecode
+    #[arg(long, conflicts_with = "file")]
+    hex: Option<String>,
+
+    /// Compile one of abacus-formulas, parameters are given ids 1..N
+    #[arg(long, conflicts_with_all = ["file", "hex"])]
+    formula: Option<String>,
+
+    /// Define constant used by `.vil` source, e.g. `-D asset_weights_id=105`
+    #[arg(short = 'D', value_name = "NAME=VALUE")]
+    define: Vec<String>,
+
+    /// Directory with labels, vectors and sub-routines
+    #[arg(long, default_value = ".")]
+    state: PathBuf,
+
+    /// Number of registers (by default as many as program uses)
+    #[arg(long)]
+    registers: Option<usize>,
+
+    /// Stop at instruction, either `<pc>` in main program or `<program_id>:<pc>`
+    #[arg(short, long = "break", value_name = "BREAKPOINT")]
+    breakpoints: Vec<String>,
+
+    /// Run without stopping unless breakpoint is hit
+    #[arg(long)]
+    run: bool,
+
+    /// Write JSON-lines trace of execution to file
+    #[arg(long)]
+    trace: Option<PathBuf>,
+}
+
+/// Breakpoint at program counter of a program (`None` for main program)
+type Breakpoint = (Option<u128>, usize);
+
+fn parse_breakpoint(text: &str) -> Result<Breakpoint> {
+    let parse_pc = |pc: &str| pc.trim().parse().context("Invalid program counter");
+    match text.split_once(':') {
+        Some((program_id, pc)) => Ok((
+            Some(program_id.trim().parse().context("Invalid program id")?),
+            parse_pc(pc)?,
+        )),
+        None => Ok((None, parse_pc(text)?)),
+    }
+}
+
+const HELP: &str = "\
+Commands:
+  s, step, <enter>      execute next instruction
+  c, continue           run until breakpoint or end of program
+  b, break <bp>         set breakpoint at <pc> or <program_id>:<pc>
+  d, delete <bp>        delete breakpoint
+  bl                    list breakpoints
+  p, stack              print stack
+  r, regs               print registers
+  q, quit               abort execution
+  h, help               print this help";
+
+struct Debugger {
+    stepping: bool,
+    breakpoints: BTreeSet<Breakpoint>,
+    trace: Option<JsonLinesTracer<BufWriter<File>>>,
+}
+
+impl Debugger {
+    fn print_location(&self, step: &Step<'_>) {
+        let instruction = step.instruction;
+        let mut text = mnemonic(instruction.op_code).unwrap_or("???").to_string();
+        for arg in decode_args(instruction) {
+            text += &format!(" {}", arg);
+        }
+        println!(
+            "[{}] {:>6}  {}",
+            format_frames(step.frames),
+            instruction.offset,
+            text
+        );
+    }
+
+    fn print_stack(step: &Step<'_>) {
+        if step.stack.is_empty() {
+            println!("  (empty stack)");
+        }
+        for (pos, operand) in step.stack.iter().rev().enumerate() {
+            println!("  [{}] {}", pos, format_operand(operand));
+        }
+    }
+
+    fn print_registers(step: &Step<'_>) {
+        if step.registers.is_empty() {
+            println!("  (no registers)");
+        }
+        for (index, operand) in step.registers.iter().enumerate() {
+            println!("  _r{} = {}", index, format_operand(operand));
+        }
+    }
+
+    fn prompt(&mut self, step: &Step<'_>) {
+        let stdin = io::stdin();
+        loop {
+            print!("(vil-dbg) ");
+            let _ = io::stdout().flush();
+
+            let mut line = String::new();
+            if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+                // End of input, just run to completion
+                self.stepping = false;
+                return;
+            }
+            let mut words = line.split_whitespace();
+            let command = words.next().unwrap_or("s");
+            let arg = words.next();
+            match (command, arg) {
+                ("s" | "step", _) => {
+                    self.stepping = true;
+                    return;
+                }
+                ("c" | "continue", _) => {
+                    self.stepping = false;
+                    return;
+                }
+                ("b" | "break", Some(bp)) => match parse_breakpoint(bp) {
+                    Ok(bp) => {
+                        self.breakpoints.insert(bp);
+                    }
+                    Err(err) => println!("{}", err),
+                },
+                ("d" | "delete", Some(bp)) => match parse_breakpoint(bp) {
+                    Ok(bp) => {
+                        if !self.breakpoints.remove(&bp) {
+                            println!("No such breakpoint");
+                        }
+                    }
+                    Err(err) => println!("{}", err),
+                },
+                ("bl", _) => {
+                    for (program_id, pc) in &self.breakpoints {
+                        match program_id {
+                            Some(program_id) => println!("  {}:{}", program_id, pc),
+                            None => println!("  {}", pc),
+                        }
+                    }
+                }
+                ("p" | "stack", _) => Self::print_stack(step),
+                ("r" | "regs", _) => Self::print_registers(step),
+                ("q" | "quit", _) => {
+                    println!("Aborted");
+                    std::process::exit(1);
+                }
+                _ => println!("{}", HELP),
+            }
+        }
+    }
+}
+
+impl Tracer for Debugger {
+    fn before_instruction(&mut self, step: &Step<'_>) {
+        if let Some(trace) = &mut self.trace {
+            trace.before_instruction(step);
+        }
+        let program_id = step.frames.last().map(|frame| frame.program_id);
+        let hit = self
+            .breakpoints
+            .contains(&(program_id, step.instruction.offset));
+        if self.stepping || hit {
+            if hit {
+                println!("Breakpoint hit");
+            }
+            self.print_location(step);
+            self.prompt(step);
+        }
+    }
+
+    fn after_instruction(&mut self, step: &Step<'_>, error: Option<&ErrorCode>) {
+        if let Some(trace) = &mut self.trace {
+            trace.after_instruction(step, error);
+        }
+        if let Some(error) = error {
+            print!("Failed with {:?} at ", error);
+            self.print_location(step);
+            Self::print_stack(step);
+        }
+    }
+}
+
+fn print_write_set(write_set: &WriteSet) {
+    for write in &write_set.labels {
+        match &write.before {
+            Some(_) => println!("Stored labels {} ({} labels)", write.id, write.after.len()),
+            None => println!(
+                "Stored labels {} ({} labels, new)",
+                write.id,
+                write.after.len()
+            ),
+        }
+    }
+    for write in &write_set.vectors {
+        let len = write.after.data.len();
+        match &write.before {
+            Some(before) => {
+                let changed = (0..len)
+                    .filter(|&i| before.data.get(i) != Some(&write.after.data[i]))
+                    .count();
+                println!(
+                    "Stored vector {} ({} of {} components changed)",
+                    write.id, changed, len
+                );
+            }
+            None => println!("Stored vector {} ({} components, new)", write.id, len),
+        }
+    }
+}
+
+/// Error of state directory together with the error that caused it
+fn storage_error(
+    vio: &TransactionalVectorIO<FileVectorIO>,
+    context: &str,
+    err: ErrorCode,
+) -> eyre::Report {
+    match vio.inner().take_error() {
+        Some(cause) => cause.wrap_err(format!("{}: {:?}", context, err)),
+        None => eyre!("{}: {:?}", context, err),
+    }
+}
+
+fn load_program(args: &Args) -> Result<(Vec<u8>, Option<usize>)> {
+    if let Some(name) = &args.formula {
+        let formula =
+            formulas::compile(name).ok_or_else(|| eyre!("Unknown formula: {}", name))??;
+        return Ok((formula.code, None));
+    }
+    if let Some(hex) = &args.hex {
+        return Ok((input::decode_hex(hex)?, None));
+    }
+    let Some(file) = &args.file else {
+        bail!("Nothing to run: provide file, --hex or --formula");
+    };
+    if file.extension().is_some_and(|ext| ext == "vil") {
+        let mut params = HashMap::new();
+        for define in &args.define {
+            let (name, value) = define
+                .split_once('=')
+                .ok_or_else(|| eyre!("Expected NAME=VALUE, got: {}", define))?;
+            let value =
+                input::parse_u128(value).with_context(|| format!("Invalid value of {}", name))?;
+            params.insert(name.trim().to_string(), value);
+        }
+        let source = fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let assembly =
+            assemble(&source, &params).map_err(|err| eyre!("{}: {}", file.display(), err))?;
+        let num_registers = assembly.num_registers();
+        return Ok((assembly.code, Some(num_registers)));
+    }
+    Ok((input::read_program(file, false)?, None))
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let (code, num_registers) = load_program(&args)?;
+    let num_registers = match (args.registers, num_registers) {
+        (Some(n), _) | (None, Some(n)) => n,
+        (None, None) => verify(&code)
+            .map_err(|err| eyre!("Program verification failed: {:?}", err))?
+            .num_registers(),
+    };
+
+    let trace = match &args.trace {
+        Some(path) => Some(JsonLinesTracer::new(BufWriter::new(
+            File::create(path).with_context(|| format!("Failed to create {}", path.display()))?,
+        ))),
+        None => None,
+    };
+    let mut debugger = Debugger {
+        stepping: !args.run,
+        breakpoints: args
+            .breakpoints
+            .iter()
+            .map(|bp| parse_breakpoint(bp))
+            .collect::<Result<_>>()?,
+        trace,
+    };
+    if debugger.stepping {
+        println!("Type 'h' for help");
+    }
+
+    let mut vio = TransactionalVectorIO::new(FileVectorIO::new(&args.state));
+    let mut program = VectorVM::new(&mut vio);
+    program.set_tracer(&mut debugger);
+    let result = program.execute(code, num_registers);
+    let metrics = program.metrics().clone();
+
+    if let Some(trace) = debugger.trace.take() {
+        trace.finish()?;
+    }
+    println!(
+        "Executed {} instructions ({} units)",
+        metrics.instructions, metrics.units
+    );
+    match result {
+        Ok(()) => {
+            let write_set = vio
+                .write_set()
+                .map_err(|err| storage_error(&vio, "Failed to compare state", err))?;
+            vio.commit()
+                .map_err(|err| storage_error(&vio, "Failed to store state", err))?;
+            print_write_set(&write_set);
+            println!("Program completed");
+        }
+        Err(err) => {
+            if let Some(cause) = vio.inner().take_error() {
+                eprintln!("{:#}", cause);
+            }
+            bail!("Program failed: {:?}, nothing was stored", err)
+        }
+    }
+    Ok(())
+}
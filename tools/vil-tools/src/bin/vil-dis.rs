@@ -0,0 +1,67 @@
+use std::path::PathBuf;
+
+use abacus_runtime::disassembler::listing;
+use clap::Parser;
+use eyre::{bail, eyre, Result};
+use vil_tools::{formulas, input};
+
+/// Disassemble VIL program into annotated listing
+///
+/// Each line starts with byte offset of the instruction, so that the
+/// instruction can be matched against `ProgramError::program_counter`.
+#[derive(Parser)]
+#[command(name = "vil-dis")]
+struct Args {
+    /// File with raw or hex encoded bytecode, or `-` for standard input
+    file: Option<PathBuf>,
+
+    /// Hex encoded bytecode
+    #[arg(long, conflicts_with = "file")]
+    hex: Option<String>,
+
+    /// Compile one of abacus-formulas instead of reading bytecode
+    #[arg(long, conflicts_with_all = ["file", "hex"])]
+    formula: Option<String>,
+
+    /// Never treat file contents as hex
+    #[arg(long)]
+    raw: bool,
+
+    /// Mark instruction that failed with this program counter
+    #[arg(long)]
+    pc: Option<usize>,
+
+    /// List names of formulas available with --formula
+    #[arg(long)]
+    list_formulas: bool,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    if args.list_formulas {
+        for name in formulas::FORMULA_NAMES {
+            println!("{}", name);
+        }
+        return Ok(());
+    }
+
+    let code = if let Some(name) = &args.formula {
+        let formula = formulas::compile(name)
+            .ok_or_else(|| eyre!("Unknown formula: {} (see --list-formulas)", name))??;
+        println!("// {}: {} bytes", name, formula.code.len());
+        for (index, param) in formula.params.iter().enumerate() {
+            println!("// {} = {}", param, index + 1);
+        }
+        formula.code
+    } else if let Some(hex) = &args.hex {
+        input::decode_hex(hex)?
+    } else if let Some(file) = &args.file {
+        input::read_program(file, args.raw)?
+    } else {
+        bail!("Nothing to disassemble: provide file, --hex or --formula");
+    };
+
+    print!("{}", listing(&code, args.pc));
+    Ok(())
+}
@@ -0,0 +1,223 @@
+use abacus_formulas::*;
+
+/// Program compiled from one of `abacus-formulas`
+pub struct CompiledFormula {
+    /// Names of formula parameters in order, each given id equal to its
+    /// position plus one
+    pub params: Vec<&'static str>,
+    pub code: Vec<u8>,
+}
+
+macro_rules! compile_formula {
+    ($module:ident :: $name:ident ( $($param:ident),* $(,)? )) => {{
+        let mut next_id = 0u128;
+        let mut id = || {
+            next_id += 1;
+            next_id
+        };
+        $(let $param = id();)*
+        let code = $module::$name($($param),*);
+        (vec![$(stringify!($param)),*], code)
+    }};
+}
+
+/// Names of all formulas known to `compile()`
+pub const FORMULA_NAMES: &[&str] = &[
+    "add_market_assets",
+    "create_market",
+    "execute_buy_order",
+    "execute_rebalance",
+    "execute_sell_order",
+    "execute_transfer",
+    "solve_quadratic_ask",
+    "solve_quadratic_bid",
+    "submit_buy_order",
+    "submit_sell_order",
+    "update_margin",
+    "update_market_data",
+    "update_quote",
+    "update_rebalance",
+    "update_supply",
+];
+
+/// Compile formula by name
+///
+/// Formulas take ids of vectors and labels as parameters, and so that
+/// they can be compiled without any storage parameters are given ids
+/// `1..=N`. Returns `None` for unknown formula.
+pub fn compile(name: &str) -> Option<eyre::Result<CompiledFormula>> {
+    let (params, code) = match name {
+        "add_market_assets" => compile_formula!(add_market_assets::add_market_assets(
+            asset_names_added_id,
+            market_asset_names_id,
+            market_asset_prices_id,
+            market_asset_slopes_id,
+            market_asset_liquidity_id,
+            supply_long_id,
+            supply_short_id,
+            demand_long_id,
+            demand_short_id,
+            delta_long_id,
+            delta_short_id,
+            margin_id,
+        )),
+        "create_market" => compile_formula!(create_market::create_market(
+            asset_names_id,
+            market_asset_names_id,
+            market_asset_prices_id,
+            market_asset_slopes_id,
+            market_asset_liquidity_id,
+            supply_long_id,
+            supply_short_id,
+            demand_long_id,
+            demand_short_id,
+            delta_long_id,
+            delta_short_id,
+            margin_id,
+        )),
+        "execute_buy_order" => compile_formula!(execute_buy_order::execute_buy_order(
+            order_id,
+            vendor_order_id,
+            total_order_id,
+            collateral_added,
+            collateral_removed,
+            max_order_size,
+            executed_index_quantities_id,
+            executed_asset_quantities_id,
+            asset_names_id,
+            asset_weights_id,
+            index_quote_id,
+            market_asset_names_id,
+            supply_long_id,
+            supply_short_id,
+            demand_long_id,
+            demand_short_id,
+            delta_long_id,
+            delta_short_id,
+            margin_id,
+            solve_quadratic_id,
+        )),
+        "execute_rebalance" => compile_formula!(execute_rebalance::execute_rebalance(
+            capacity_factor,
+            executed_assets_long_id,
+            executed_assets_short_id,
+            rebalance_asset_names_id,
+            rebalance_weights_long_id,
+            rebalance_weights_short_id,
+            market_asset_names_id,
+            supply_long_id,
+            supply_short_id,
+            demand_long_id,
+            demand_short_id,
+            delta_long_id,
+            delta_short_id,
+            margin_id,
+            asset_liquidity_id,
+        )),
+        "execute_sell_order" => compile_formula!(execute_sell_order::execute_sell_order(
+            order_id,
+            vendor_order_id,
+            total_order_id,
+            collateral_added,
+            collateral_removed,
+            max_order_size,
+            executed_index_quantities_id,
+            executed_asset_quantities_id,
+            asset_names_id,
+            asset_weights_id,
+            index_quote_id,
+            market_asset_names_id,
+            supply_long_id,
+            supply_short_id,
+            demand_long_id,
+            demand_short_id,
+            delta_long_id,
+            delta_short_id,
+            margin_id,
+            solve_quadratic_id,
+        )),
+        "execute_transfer" => compile_formula!(execute_transfer::execute_transfer(
+            sender_bid_id,
+            sender_ask_id,
+            receiver_bid_id,
+            amount,
+        )),
+        "solve_quadratic_ask" => (vec![], solve_quadratic_ask::solve_quadratic_ask()),
+        "solve_quadratic_bid" => (vec![], solve_quadratic_bid::solve_quadratic_bid()),
+        "submit_buy_order" => compile_formula!(submit_buy_order::submit_buy_order(
+            order_id,
+            vendor_order_id,
+            total_order_id,
+            collateral_added,
+            collateral_removed,
+        )),
+        "submit_sell_order" => compile_formula!(submit_sell_order::submit_sell_order(
+            order_id,
+            vendor_order_id,
+            total_order_id,
+            collateral_added,
+            collateral_removed,
+        )),
+        "update_margin" => compile_formula!(update_margin::update_margin(
+            asset_names_id,
+            asset_margin_id,
+            market_asset_names_id,
+            margin_id,
+        )),
+        "update_market_data" => compile_formula!(update_market_data::update_market_data(
+            asset_names_id,
+            asset_prices_id,
+            asset_slopes_id,
+            asset_liquidity_id,
+            market_asset_names_id,
+            market_asset_prices_id,
+            market_asset_slopes_id,
+            market_asset_liquidity_id,
+            market_start,
+            market_len,
+        )),
+        "update_quote" => compile_formula!(update_quote::update_quote(
+            index_asset_names_id,
+            weights_id,
+            quote_id,
+            market_asset_names_id,
+            asset_prices_id,
+            asset_slopes_id,
+            asset_liquidity_id,
+        )),
+        "update_rebalance" => compile_formula!(update_rebalance::update_rebalance(
+            total_bid_id,
+            total_ask_id,
+            asset_names_id,
+            asset_weights_id,
+            new_asset_names_id,
+            new_asset_weights_id,
+            rebalance_asset_names_id,
+            rebalance_weights_long_id,
+            rebalance_weights_short_id,
+        )),
+        "update_supply" => compile_formula!(update_supply::update_supply(
+            asset_names_id,
+            asset_quantities_short_id,
+            asset_quantities_long_id,
+            market_asset_names_id,
+            supply_long_id,
+            supply_short_id,
+            demand_long_id,
+            demand_short_id,
+            delta_long_id,
+            delta_short_id,
+        )),
+        _ => return None,
+    };
+    Some(
+        code.map(|code| CompiledFormula { params, code })
+            .map_err(|err| {
+                eyre::eyre!(
+                    "Failed to compile {}: {}",
+                    name,
+                    String::from_utf8_lossy(&err)
+                )
+            }),
+    )
+}
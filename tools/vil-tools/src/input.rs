@@ -0,0 +1,60 @@
+use std::{
+    fs,
+    io::{self, Read},
+    path::Path,
+};
+
+use eyre::{Context, Result};
+
+/// Decode program from hex string with optional `0x` prefix
+///
+/// Whitespace is ignored so that hex can be pasted from transaction data
+/// split across lines.
+pub fn decode_hex(text: &str) -> Result<Vec<u8>> {
+    let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    let text = text.strip_prefix("0x").unwrap_or(&text);
+    hex::decode(text).context("Invalid hex")
+}
+
+/// Parse value of a constant, either decimal or hex with `0x` prefix
+pub fn parse_u128(text: &str) -> Result<u128> {
+    let text = text.trim().replace('_', "");
+    let value = match text.strip_prefix("0x") {
+        Some(hex) => u128::from_str_radix(hex, 16),
+        None => text.parse(),
+    }?;
+    Ok(value)
+}
+
+/// Read file, or standard input if path is `-`
+pub fn read_file(path: &Path) -> Result<Vec<u8>> {
+    if path == Path::new("-") {
+        let mut bytes = Vec::new();
+        io::stdin().read_to_end(&mut bytes)?;
+        Ok(bytes)
+    } else {
+        fs::read(path).with_context(|| format!("Failed to read {}", path.display()))
+    }
+}
+
+/// Read program from file, or from standard input if path is `-`
+///
+/// File may contain either raw bytecode or its hex encoding, unless `raw` is
+/// set, in which case contents are never treated as hex.
+pub fn read_program(path: &Path, raw: bool) -> Result<Vec<u8>> {
+    let bytes = read_file(path)?;
+
+    match std::str::from_utf8(&bytes) {
+        Ok(text) if !raw && looks_like_hex(text) => decode_hex(text),
+        _ => Ok(bytes),
+    }
+}
+
+fn looks_like_hex(text: &str) -> bool {
+    let text = text.trim();
+    let text = text.strip_prefix("0x").unwrap_or(text);
+    !text.is_empty()
+        && text
+            .chars()
+            .all(|c| c.is_ascii_hexdigit() || c.is_whitespace())
+}
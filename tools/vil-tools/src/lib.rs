@@ -0,0 +1,5 @@
+pub mod assembler;
+pub mod formulas;
+pub mod input;
+pub mod storage;
+pub mod trace;
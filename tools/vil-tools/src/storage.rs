@@ -0,0 +1,167 @@
+use std::{
+    cell::RefCell,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use abacus_runtime::runtime::VectorIO;
+use common::{abacus::program_error::ErrorCode, labels::Labels, vector::Vector};
+use eyre::{eyre, Report};
+
+use crate::input::read_program;
+
+/// `VectorIO` backed by files in a directory
+///
+/// Each stored item is a file named after its id with extension telling its
+/// type: `<id>.labels`, `<id>.vector` and `<id>.code`. Labels and vectors are
+/// hex encoded the same way as they are returned by the contracts, e.g. by
+/// `fetchVector(uint128)(bytes)`, so that on-chain state can be copied into
+/// files as-is. Code may be either raw or hex encoded bytecode.
+///
+/// `VectorIO` can only fail with `ErrorCode`, and so file that is missing is
+/// reported as `NotFound`, file that cannot be read, decoded or written as
+/// `InvalidOperand`, while the error that caused it is kept until taken by
+/// `take_error()`.
+pub struct FileVectorIO {
+    dir: PathBuf,
+    error: RefCell<Option<Report>>,
+}
+
+impl FileVectorIO {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            error: RefCell::new(None),
+        }
+    }
+
+    /// Error behind the last `ErrorCode` returned, if any
+    pub fn take_error(&self) -> Option<Report> {
+        self.error.take()
+    }
+
+    fn fail(&self, error_code: ErrorCode, error: Report) -> ErrorCode {
+        self.error.replace(Some(error));
+        error_code
+    }
+
+    pub fn path(&self, id: u128, kind: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", id, kind))
+    }
+
+    fn load_hex(&self, id: u128, kind: &str) -> Result<Vec<u8>, ErrorCode> {
+        let path = self.path(id, kind);
+        let text = fs::read_to_string(&path).map_err(|err| {
+            let error_code = match err.kind() {
+                io::ErrorKind::NotFound => ErrorCode::NotFound,
+                _ => ErrorCode::InvalidOperand,
+            };
+            self.fail(
+                error_code,
+                eyre!("Failed to read {}: {}", path.display(), err),
+            )
+        })?;
+        crate::input::decode_hex(&text).map_err(|err| {
+            self.fail(
+                ErrorCode::InvalidOperand,
+                err.wrap_err(format!("Failed to decode {}", path.display())),
+            )
+        })
+    }
+
+    fn store_hex(&self, id: u128, kind: &str, data: &[u8]) -> Result<(), ErrorCode> {
+        let path = self.path(id, kind);
+        fs::write(&path, format!("0x{}\n", hex::encode(data))).map_err(|err| {
+            self.fail(
+                ErrorCode::InvalidOperand,
+                eyre!("Failed to write {}: {}", path.display(), err),
+            )
+        })
+    }
+}
+
+/// Error for data, which isn't whole number of labels or vector components
+fn not_aligned(data: &[u8]) -> ErrorCode {
+    ErrorCode::NotAligned(data.len(), data.len().next_multiple_of(size_of::<u128>()))
+}
+
+impl VectorIO for FileVectorIO {
+    fn load_labels(&self, id: u128) -> Result<Labels, ErrorCode> {
+        let data = self.load_hex(id, "labels")?;
+        if !Labels::is_valid_vec(&data) {
+            Err(not_aligned(&data))?;
+        }
+        Ok(Labels::from_vec(data))
+    }
+
+    fn load_vector(&self, id: u128) -> Result<Vector, ErrorCode> {
+        let data = self.load_hex(id, "vector")?;
+        if !Vector::is_valid_vec(&data) {
+            Err(not_aligned(&data))?;
+        }
+        Ok(Vector::from_vec(data))
+    }
+
+    fn load_code(&self, id: u128) -> Result<Vec<u8>, ErrorCode> {
+        let path = self.path(id, "code");
+        read_program(Path::new(&path), false).map_err(|err| {
+            let error_code = match err.root_cause().downcast_ref::<io::Error>() {
+                Some(err) if err.kind() == io::ErrorKind::NotFound => ErrorCode::NotFound,
+                _ => ErrorCode::InvalidOperand,
+            };
+            self.fail(error_code, err)
+        })
+    }
+
+    fn store_labels(&mut self, id: u128, input: Labels) -> Result<(), ErrorCode> {
+        self.store_hex(id, "labels", &input.to_vec())
+    }
+
+    fn store_vector(&mut self, id: u128, input: Vector) -> Result<(), ErrorCode> {
+        self.store_hex(id, "vector", &input.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_errors() {
+        let dir = std::env::temp_dir().join(format!("vil-storage-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let vio = FileVectorIO::new(&dir);
+        assert!(vio.take_error().is_none());
+
+        assert!(matches!(vio.load_vector(1), Err(ErrorCode::NotFound)));
+        let error = vio.take_error().unwrap().to_string();
+        assert!(error.starts_with(&format!(
+            "Failed to read {}",
+            vio.path(1, "vector").display()
+        )));
+        assert!(vio.take_error().is_none());
+
+        fs::write(vio.path(2, "labels"), "0xZZ").unwrap();
+        assert!(matches!(vio.load_labels(2), Err(ErrorCode::InvalidOperand)));
+        assert!(vio
+            .take_error()
+            .unwrap()
+            .to_string()
+            .starts_with("Failed to decode"));
+
+        assert!(matches!(vio.load_code(3), Err(ErrorCode::NotFound)));
+        assert!(vio.take_error().is_some());
+
+        let mut missing = FileVectorIO::new(dir.join("missing"));
+        let result = missing.store_vector(4, Vector::new());
+        assert!(matches!(result, Err(ErrorCode::InvalidOperand)));
+        assert!(missing
+            .take_error()
+            .unwrap()
+            .to_string()
+            .starts_with("Failed to write"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
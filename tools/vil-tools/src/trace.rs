@@ -0,0 +1,276 @@
+use std::io::{self, Write};
+
+use abacus_runtime::{
+    decoder::mnemonic,
+    disassembler::{decode_args, DecodedArg},
+    runtime::Operand,
+    tracer::{Frame, Step, Tracer},
+};
+use common::{abacus::program_error::ErrorCode, amount::Amount, signed_amount::SignedAmount};
+use serde::Serialize;
+
+fn amount_str(value: &Amount) -> String {
+    DecodedArg::Amount(*value).to_string()
+}
+
+fn signed_amount_str(value: &SignedAmount) -> String {
+    let sign = if value.is_negative() { "-" } else { "" };
+    format!("{}{}", sign, amount_str(&value.abs()))
+}
+
+fn join<T>(items: &[T], f: impl Fn(&T) -> String) -> String {
+    join_with(items, ", ", f)
+}
+
+fn join_with<T>(items: &[T], separator: &str, f: impl Fn(&T) -> String) -> String {
+    items.iter().map(f).collect::<Vec<_>>().join(separator)
+}
+
+/// Format operand for humans, e.g. `Vector [1, 2.5]`
+pub fn format_operand(operand: &Operand) -> String {
+    match operand {
+        Operand::None => "None".to_string(),
+        Operand::Labels(x) => format!("Labels [{}]", join(x.as_slice(), |v| v.to_string())),
+        Operand::Vector(x) => format!("Vector [{}]", join(&x.data, amount_str)),
+        Operand::Scalar(x) => format!("Scalar {}", amount_str(x)),
+        Operand::Label(x) => format!("Label {}", x),
+        Operand::SignedVector(x) => format!("SignedVector [{}]", join(&x.data, signed_amount_str)),
+        Operand::SignedScalar(x) => format!("SignedScalar {}", signed_amount_str(x)),
+        Operand::Sparse(x) => {
+            let pairs: Vec<_> = x.labels.iter().zip(x.data.iter()).collect();
+            format!(
+                "Sparse [{}]",
+                join(&pairs, |(label, v)| format!("{}: {}", label, amount_str(v)))
+            )
+        }
+    }
+}
+
+/// Format frame path for humans, e.g. `main > 7[3]`
+pub fn format_frames(frames: &[Frame]) -> String {
+    let mut text = "main".to_string();
+    for frame in frames {
+        text += &match frame.iteration {
+            Some(iteration) => format!(" > {}[{}]", frame.program_id, iteration),
+            None => format!(" > {}", frame.program_id),
+        };
+    }
+    text
+}
+
+/// Operand as written by `JsonLinesTracer`, where `Operand::None` is `null`
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum JsonOperand {
+    Labels(Vec<u128>),
+    Vector(Vec<String>),
+    Scalar(String),
+    Label(u128),
+    SignedVector(Vec<String>),
+    SignedScalar(String),
+    Sparse {
+        labels: Vec<u128>,
+        values: Vec<String>,
+    },
+}
+
+impl JsonOperand {
+    fn new(operand: &Operand) -> Option<Self> {
+        let operand = match operand {
+            Operand::None => return None,
+            Operand::Labels(x) => Self::Labels(x.as_slice().to_vec()),
+            Operand::Vector(x) => Self::Vector(x.data.iter().map(amount_str).collect()),
+            Operand::Scalar(x) => Self::Scalar(amount_str(x)),
+            Operand::Label(x) => Self::Label(*x),
+            Operand::SignedVector(x) => {
+                Self::SignedVector(x.data.iter().map(signed_amount_str).collect())
+            }
+            Operand::SignedScalar(x) => Self::SignedScalar(signed_amount_str(x)),
+            Operand::Sparse(x) => Self::Sparse {
+                labels: x.labels.clone(),
+                values: x.data.iter().map(amount_str).collect(),
+            },
+        };
+        Some(operand)
+    }
+
+    fn list(operands: &[Operand]) -> Vec<Option<Self>> {
+        operands.iter().map(Self::new).collect()
+    }
+}
+
+#[derive(Serialize)]
+struct JsonFrame {
+    program_id: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    iteration: Option<usize>,
+}
+
+/// Line written by `JsonLinesTracer`
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum JsonEvent {
+    Before {
+        frames: Vec<JsonFrame>,
+        pc: usize,
+        op: &'static str,
+        args: Vec<String>,
+        depth: usize,
+    },
+    After {
+        frames: Vec<JsonFrame>,
+        pc: usize,
+        op: &'static str,
+        stack: Vec<Option<JsonOperand>>,
+        registers: Vec<Option<JsonOperand>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+}
+
+/// Tracer writing one JSON object per line for every traced event
+///
+/// Before instruction is executed it writes:
+/// ```json
+/// {"event":"before","frames":[],"pc":17,"op":"IMMS","args":["1.5"],"depth":1}
+/// ```
+/// and after instruction it writes stack and registers of current frame, and
+/// an error if instruction failed:
+/// ```json
+/// {"event":"after","frames":[],"pc":17,"op":"IMMS","stack":[...],"registers":[...]}
+/// ```
+/// Amounts are written as decimal strings so that no precision is lost.
+pub struct JsonLinesTracer<W: Write> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> JsonLinesTracer<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            error: None,
+        }
+    }
+
+    /// Flush the writer and report first error that occurred while tracing
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_event(&mut self, event: &JsonEvent) {
+        if self.error.is_none() {
+            let result = serde_json::to_writer(&mut self.writer, event)
+                .map_err(io::Error::from)
+                .and_then(|()| writeln!(self.writer));
+            if let Err(err) = result {
+                self.error = Some(err);
+            }
+        }
+    }
+}
+
+/// Frames, program counter and mnemonic of traced instruction
+fn location(step: &Step<'_>) -> (Vec<JsonFrame>, usize, &'static str) {
+    let frames = step
+        .frames
+        .iter()
+        .map(|frame| JsonFrame {
+            program_id: frame.program_id,
+            iteration: frame.iteration,
+        })
+        .collect();
+    let instruction = step.instruction;
+    let op = mnemonic(instruction.op_code).unwrap_or("???");
+    (frames, instruction.offset, op)
+}
+
+impl<W: Write> Tracer for JsonLinesTracer<W> {
+    fn before_instruction(&mut self, step: &Step<'_>) {
+        let (frames, pc, op) = location(step);
+        let args = decode_args(step.instruction);
+        self.write_event(&JsonEvent::Before {
+            frames,
+            pc,
+            op,
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            depth: step.stack.len(),
+        });
+    }
+
+    fn after_instruction(&mut self, step: &Step<'_>, error: Option<&ErrorCode>) {
+        let (frames, pc, op) = location(step);
+        self.write_event(&JsonEvent::After {
+            frames,
+            pc,
+            op,
+            stack: JsonOperand::list(step.stack),
+            registers: JsonOperand::list(step.registers),
+            error: error.map(|err| format!("{:?}", err)),
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use abacus_runtime::runtime::{VectorIO, VectorVM};
+    use common::vector::Vector;
+
+    use crate::storage::FileVectorIO;
+
+    use super::*;
+
+    #[test]
+    fn test_json_lines_tracer() {
+        let dir = std::env::temp_dir().join(format!("vil-trace-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut vio = FileVectorIO::new(&dir);
+        let data = vec![Amount::ONE, Amount::TWO];
+        vio.store_vector(1, Vector { data }).unwrap();
+
+        let code = abacus_macros::abacus! {
+            LDV     1
+            STR     _V
+            LDR     _V
+            IMMS    0.5
+            SWAP    1
+            MUL     1
+            STV     2
+            LDR     _V
+            LPOP
+        }
+        .unwrap();
+
+        let mut tracer = JsonLinesTracer::new(Vec::new());
+        let mut program = VectorVM::new(&mut vio);
+        program.set_tracer(&mut tracer);
+        let err = program.execute(code, 1).unwrap_err();
+        assert!(matches!(err.error_code, ErrorCode::InvalidOperand));
+
+        let output = String::from_utf8(tracer.finish().unwrap()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 18);
+        assert_eq!(
+            lines[6],
+            r#"{"event":"before","frames":[],"pc":21,"op":"IMMS","args":["0.5"],"depth":1}"#
+        );
+        assert_eq!(
+            lines[11],
+            r#"{"event":"after","frames":[],"pc":40,"op":"MUL","stack":[{"scalar":"0.5"},{"vector":["0.5","1"]}],"registers":[{"vector":["1","2"]}]}"#
+        );
+        assert_eq!(
+            lines[17],
+            r#"{"event":"after","frames":[],"pc":61,"op":"LPOP","stack":[{"scalar":"0.5"},{"vector":["1","2"]}],"registers":[{"vector":["1","2"]}],"error":"InvalidOperand"}"#
+        );
+
+        let stored = vio.load_vector(2).unwrap();
+        assert_eq!(stored.data, vec![Amount(Amount::SCALE / 2), Amount::ONE]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}